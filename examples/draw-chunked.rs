@@ -0,0 +1,97 @@
+//! # Example: drawing from an async task.
+//!
+//! This example demonstrates drawing a text box chunk by chunk from an async function. On a
+//! microcontroller, the closure would start a DMA transfer of the chunk to the display and return
+//! a future that completes when the transfer is done, letting an executor like embassy run other
+//! tasks in the meantime. Here, the chunks are drawn to the simulator display, and the future is
+//! driven by a minimal executor.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+use std::convert::Infallible;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+use embedded_graphics_simulator::{
+    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
+};
+use embedded_text::TextBox;
+
+/// Polls a future to completion on the current thread.
+fn block_on<T>(future: impl Future<Output = T>) -> T {
+    fn raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn noop(_: *const ()) {}
+
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+
+    loop {
+        if let Poll::Ready(output) = Pin::as_mut(&mut future).poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Simulates sending a chunk of pixels to the display.
+///
+/// The returned future is pending once before it completes, like a transfer that takes time.
+struct Transfer {
+    started: bool,
+}
+
+impl Future for Transfer {
+    type Output = Result<(), Infallible>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.started {
+            Poll::Ready(Ok(()))
+        } else {
+            self.started = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn main() -> Result<(), Infallible> {
+    let text = "Hello, World!\nThis text box is drawn in chunks of pixels, and rendering is \
+        suspended while each chunk is being sent to the display.";
+
+    let character_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let text_box = TextBox::new(
+        text,
+        Rectangle::new(Point::zero(), Size::new(128, 64)),
+        character_style,
+    );
+
+    let mut display = SimulatorDisplay::new(Size::new(128, 64));
+
+    block_on(text_box.draw_chunked(|chunk| {
+        chunk.draw(&mut display).unwrap();
+
+        Transfer { started: false }
+    }))?;
+
+    let output_settings = OutputSettingsBuilder::new()
+        .theme(BinaryColorTheme::OledBlue)
+        .scale(2)
+        .build();
+    Window::new("TextBox drawn in chunks", &output_settings).show_static(&display);
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! # Example: editor widget
+//!
+//! This example demonstrates the `EditorView` widget. The widget handles caret movement, the
+//! selection and scrolling, while the application owns and modifies the text.
+//!
+//! Use the arrow keys, Home and End to move the caret, hold Shift to select text.
+//!
+//! Running this example requires enabling the "widget" feature
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+use embedded_graphics_simulator::{
+    sdl2::MouseButton, BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent,
+    Window,
+};
+use embedded_text::{
+    style::{HeightMode, TextBoxStyleBuilder, VerticalOverdraw},
+    widget::{EditorState, EditorStyle, EditorView, Key},
+};
+use sdl2::keyboard::{Keycode, Mod};
+use std::{thread, time::Duration};
+
+fn main() {
+    let output_settings = OutputSettingsBuilder::new()
+        .theme(BinaryColorTheme::OledBlue)
+        .scale(2)
+        .build();
+    let mut window = Window::new("EditorView demonstration", &output_settings);
+
+    let character_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let editor_style = EditorStyle::new(BinaryColor::On, BinaryColor::Off, BinaryColor::On);
+    let textbox_style = TextBoxStyleBuilder::new()
+        .height_mode(HeightMode::Exact(VerticalOverdraw::Hidden))
+        .leading_spaces(true)
+        .trailing_spaces(true)
+        .build();
+
+    let bounds = Rectangle::new(Point::new(16, 8), Size::new(128, 48));
+
+    let mut text = String::from("Hello, World!\nThis line is long enough to be wrapped.\nline3");
+    let mut state = EditorState::new();
+
+    'demo: loop {
+        let mut editor = EditorView::new(&text, bounds, character_style, editor_style)
+            .with_textbox_style(textbox_style)
+            .with_state(state);
+
+        let mut display = SimulatorDisplay::new(Size::new(160, 64));
+        editor.draw(&mut display).unwrap();
+        window.update(&display);
+
+        let mut insert = None;
+        for event in window.events() {
+            match event {
+                SimulatorEvent::KeyDown {
+                    keycode, keymod, ..
+                } => {
+                    let key = match keycode {
+                        Keycode::Left => Key::Left,
+                        Keycode::Right => Key::Right,
+                        Keycode::Up => Key::Up,
+                        Keycode::Down => Key::Down,
+                        Keycode::Home => Key::Home,
+                        Keycode::End => Key::End,
+                        Keycode::Backspace => {
+                            insert = Some(String::new());
+                            continue;
+                        }
+                        Keycode::Return => {
+                            insert = Some(String::from("\n"));
+                            continue;
+                        }
+                        other => {
+                            // Only a few printable keys are handled to keep the example short.
+                            let name = other.name();
+                            if name.len() == 1 {
+                                insert = Some(name.to_lowercase());
+                            } else if other == Keycode::Space {
+                                insert = Some(String::from(" "));
+                            }
+                            continue;
+                        }
+                    };
+
+                    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        editor.handle_key_selecting(key);
+                    } else {
+                        editor.handle_key(key);
+                    }
+                }
+                SimulatorEvent::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    point,
+                } => editor.click(point),
+                SimulatorEvent::Quit => break 'demo,
+                _ => {}
+            }
+        }
+
+        state = editor.state();
+
+        if let Some(inserted) = insert {
+            // Replace the selection, or delete the character before the caret.
+            let caret = state.caret();
+            let range = match state.selection() {
+                Some(selection) => selection,
+                None if inserted.is_empty() => {
+                    let start = text[..caret]
+                        .char_indices()
+                        .next_back()
+                        .map_or(0, |(idx, _)| idx);
+                    start..caret
+                }
+                None => caret..caret,
+            };
+
+            text.replace_range(range.clone(), &inserted);
+            state.set_caret(range.start + inserted.len());
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,55 @@
+//! # Example: selecting a font that fits
+//!
+//! This example demonstrates selecting the largest font that fits the text into a fixed size text
+//! box.
+
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_6X10, FONT_9X15},
+        MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+use embedded_graphics_simulator::{
+    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
+};
+use embedded_text::{alignment::HorizontalAlignment, style::TextBoxStyle, TextBox};
+
+fn main() {
+    let labels = [
+        "Short label",
+        "A longer label that needs a smaller font to fit into the box",
+    ];
+
+    // The candidate character styles, in order of preference.
+    let candidates = [
+        MonoTextStyle::new(&FONT_9X15, BinaryColor::On),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+    ];
+
+    let textbox_style = TextBoxStyle::with_alignment(HorizontalAlignment::Center);
+
+    let mut display = SimulatorDisplay::new(Size::new(128, 64));
+
+    for (i, label) in labels.iter().enumerate() {
+        let bounds = Rectangle::new(Point::new(0, i as i32 * 32), Size::new(128, 30));
+
+        // Pick the first font that fits the text into the bounding box.
+        let (index, _size) = textbox_style
+            .first_fitting(candidates, label, bounds)
+            .unwrap();
+
+        TextBox::with_textbox_style(label, bounds, candidates[index], textbox_style)
+            .draw(&mut display)
+            .unwrap();
+    }
+
+    // Set up the window and show the display's contents.
+    let output_settings = OutputSettingsBuilder::new()
+        .theme(BinaryColorTheme::OledBlue)
+        .scale(2)
+        .build();
+    Window::new("Selecting a font that fits", &output_settings).show_static(&display);
+}
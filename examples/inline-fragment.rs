@@ -0,0 +1,81 @@
+//! # Example: inline fragments
+//!
+//! This example demonstrates embedding a value, drawn with a large font, into a sentence.
+
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_10X20, FONT_6X10},
+        MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+    text::{Baseline, LineHeight, Text},
+};
+use embedded_graphics_simulator::{
+    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
+};
+use embedded_text::{
+    plugin::inline::{InlineFragment, InlineFragments},
+    style::{HeightMode, TextBoxStyleBuilder},
+    TextBox,
+};
+
+/// A number drawn with a large font.
+struct LargeNumber(&'static str);
+
+impl InlineFragment<BinaryColor> for LargeNumber {
+    fn size(&self) -> Size {
+        let character_size = FONT_10X20.character_size;
+        Size::new(
+            character_size.width * self.0.len() as u32,
+            character_size.height,
+        )
+    }
+
+    fn draw_at<D>(&self, position: Point, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        Text::with_baseline(self.0, position, style, Baseline::Top)
+            .draw(target)
+            .map(|_| ())
+    }
+}
+
+fn main() {
+    let text = "The temperature in the living room is {frag:0} degrees, \
+    and the humidity is {frag:1} percent.";
+
+    let fragments = [LargeNumber("21.5"), LargeNumber("40")];
+
+    // Specify the styling options:
+    // * Use the 6x10 MonoFont from embedded-graphics.
+    // * Make the lines as tall as the large font, so the fragments are not clipped.
+    // * Use `FitToText` height mode to stretch the text box to the exact height of the text.
+    let character_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let textbox_style = TextBoxStyleBuilder::new()
+        .height_mode(HeightMode::FitToText)
+        .line_height(LineHeight::Pixels(20))
+        .build();
+
+    let bounds = Rectangle::new(Point::zero(), Size::new(128, 0));
+
+    // Create the text box and add the plugin that draws the fragments.
+    let text_box = TextBox::with_textbox_style(text, bounds, character_style, textbox_style)
+        .add_plugin(InlineFragments::new(&fragments));
+
+    // Create a simulated display with the dimensions of the text box.
+    let mut display = SimulatorDisplay::new(text_box.bounding_box().size);
+
+    // Draw the text box.
+    text_box.draw(&mut display).unwrap();
+
+    // Set up the window and show the display's contents.
+    let output_settings = OutputSettingsBuilder::new()
+        .theme(BinaryColorTheme::OledBlue)
+        .scale(2)
+        .build();
+    Window::new("TextBox example with inline fragments", &output_settings).show_static(&display);
+}
@@ -0,0 +1,62 @@
+//! # Example: the same text box on two displays
+//!
+//! This example demonstrates reusing a text box on displays of different sizes. The layout options
+//! are given once and are adapted to the font used on each display.
+
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_10X20, FONT_6X10},
+        MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+};
+use embedded_graphics_simulator::{
+    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
+};
+use embedded_text::{
+    alignment::HorizontalAlignment,
+    style::{TabSize, TextBoxStyleBuilder},
+    TextBox,
+};
+
+fn main() {
+    let text = "Status\n\nTemp:\t21 C\nFan:\t40 %";
+
+    let textbox_style = TextBoxStyleBuilder::new()
+        .alignment(HorizontalAlignment::Left)
+        .paragraph_spacing(4)
+        .tab_size(TabSize::Pixels(36))
+        .build();
+
+    let mut oled = SimulatorDisplay::new(Size::new(128, 64));
+    let mut lcd = SimulatorDisplay::new(Size::new(320, 240));
+
+    let small = TextBox::with_textbox_style(
+        text,
+        oled.bounding_box(),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+        textbox_style,
+    );
+
+    // The paragraph spacing and the tab size are scaled to the larger font.
+    let large = small.rescaled(
+        lcd.bounding_box(),
+        MonoTextStyle::new(&FONT_10X20, BinaryColor::On),
+    );
+
+    small.draw(&mut oled).unwrap();
+    large.draw(&mut lcd).unwrap();
+
+    // Set up the windows and show the displays' contents.
+    let output_settings = OutputSettingsBuilder::new()
+        .theme(BinaryColorTheme::OledBlue)
+        .scale(2)
+        .build();
+    Window::new("Small display", &output_settings).show_static(&oled);
+
+    let output_settings = OutputSettingsBuilder::new()
+        .theme(BinaryColorTheme::LcdWhite)
+        .build();
+    Window::new("Large display", &output_settings).show_static(&lcd);
+}
@@ -0,0 +1,110 @@
+//! # Example: exporting text to SVG
+//!
+//! This example demonstrates implementing a custom `ElementHandler`. The handler lays out text
+//! using the same rules as `TextBox`, and collects the lines into an SVG `<text>` element.
+
+use std::{convert::Infallible, fmt::Write};
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{renderer::TextRenderer, Baseline},
+};
+use embedded_text::{style::TextBoxStyle, ElementHandler, LineEndType};
+
+struct SvgExporter<'a> {
+    character_style: MonoTextStyle<'a, BinaryColor>,
+    svg: String,
+    x: u32,
+    pending_spaces: u32,
+    line: u32,
+    line_open: bool,
+}
+
+impl SvgExporter<'_> {
+    fn open_line(&mut self) {
+        if !self.line_open {
+            let y = self.line * self.character_style.line_height();
+            write!(self.svg, r#"<tspan x="{}" y="{}">"#, self.x, y).unwrap();
+            self.line_open = true;
+        }
+    }
+}
+
+impl ElementHandler for SvgExporter<'_> {
+    type Error = Infallible;
+    type Color = BinaryColor;
+
+    fn measure(&self, st: &str) -> u32 {
+        self.character_style
+            .measure_string(st, Point::zero(), Baseline::Top)
+            .next_position
+            .x as u32
+    }
+
+    fn whitespace(&mut self, _st: &str, space_count: u32, width: u32) -> Result<(), Self::Error> {
+        // Spaces are only written between words.
+        if self.line_open {
+            self.pending_spaces += space_count;
+        }
+        self.x += width;
+
+        Ok(())
+    }
+
+    fn printed_characters(&mut self, st: &str, width: Option<u32>) -> Result<(), Self::Error> {
+        self.open_line();
+        for _ in 0..self.pending_spaces {
+            self.svg.push(' ');
+        }
+        self.pending_spaces = 0;
+
+        for c in st.chars() {
+            match c {
+                '<' => self.svg.push_str("&lt;"),
+                '>' => self.svg.push_str("&gt;"),
+                '&' => self.svg.push_str("&amp;"),
+                c => self.svg.push(c),
+            }
+        }
+        self.x += width.unwrap_or_else(|| self.measure(st));
+
+        Ok(())
+    }
+
+    fn end_line(&mut self, end: LineEndType) -> Result<(), Self::Error> {
+        if self.line_open {
+            self.svg.push_str("</tspan>\n");
+            self.line_open = false;
+        }
+        if end != LineEndType::CarriageReturn {
+            self.line += 1;
+        }
+        self.x = 0;
+        self.pending_spaces = 0;
+
+        Ok(())
+    }
+}
+
+fn main() {
+    let text = "Hello, World!\nThis text is laid out by embedded-text & exported as SVG.";
+
+    let mut exporter = SvgExporter {
+        character_style: MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+        svg: String::from("<text font-family=\"monospace\" dominant-baseline=\"hanging\">\n"),
+        x: 0,
+        pending_spaces: 0,
+        line: 0,
+        line_open: false,
+    };
+
+    TextBoxStyle::default()
+        .process_elements(text, 128, &mut exporter)
+        .unwrap();
+
+    exporter.svg.push_str("</text>");
+
+    println!("{}", exporter.svg);
+}
@@ -1,10 +1,9 @@
 //! Text alignment options.
+
 use crate::{
     rendering::{cursor::Cursor, space_config::SpaceConfig},
     style::LineMeasurement,
-    utils::str_width,
 };
-use embedded_graphics::text::renderer::TextRenderer;
 
 #[cfg(test)]
 mod test;
@@ -27,14 +26,16 @@ pub enum HorizontalAlignment {
 
 impl HorizontalAlignment {
     /// Calculate offset from the left side and whitespace information.
+    ///
+    /// This function takes the width of a space instead of the text renderer, so that it is not
+    /// duplicated for every character style.
     pub(crate) fn place_line(
         self,
-        renderer: &impl TextRenderer,
+        space_width: u32,
         measurement: LineMeasurement,
     ) -> (i32, SpaceConfig) {
-        let space_width = str_width(renderer, " ");
-        let space_config = SpaceConfig::new(space_width, None);
-        let remaining_space = measurement.max_line_width - measurement.width;
+        let space_config = SpaceConfig::new(space_width);
+        let remaining_space = measurement.max_line_width.saturating_sub(measurement.width);
         match self {
             HorizontalAlignment::Left => (0, space_config),
             HorizontalAlignment::Center => ((remaining_space as i32 + 1) / 2, space_config),
@@ -45,7 +46,7 @@ impl HorizontalAlignment {
                     let space = remaining_space + space_count * space_width;
                     let space_width = space / space_count;
                     let extra_pixels = space % space_count;
-                    SpaceConfig::new(space_width, Some(extra_pixels))
+                    SpaceConfig::justified(space_width, space_count, extra_pixels)
                 } else {
                     space_config
                 };
@@ -70,6 +71,7 @@ impl HorizontalAlignment {
 
 /// Vertical text alignment options.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
 pub enum VerticalAlignment {
     /// Top aligned text.
     ///
@@ -85,6 +87,31 @@ pub enum VerticalAlignment {
     ///
     /// The last line of the text will be aligned to the bottom of the text box.
     Bottom,
+
+    /// Custom aligned text.
+    ///
+    /// The function receives the height of the text and the height of the text box, and returns
+    /// the vertical offset of the first line from the top of the text box. The offset is clamped
+    /// between the offsets used by [`Top`] and [`Bottom`] alignment, so the text never starts
+    /// above the box nor ends below it unless it is taller than the box. In that case the
+    /// [`VerticalOverdraw`] setting decides which part of the text is visible.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_text::{alignment::VerticalAlignment, style::TextBoxStyle};
+    ///
+    /// fn one_third(text_height: i32, box_height: i32) -> i32 {
+    ///     (box_height - text_height) / 3
+    /// }
+    ///
+    /// let style = TextBoxStyle::with_vertical_alignment(VerticalAlignment::Custom(one_third));
+    /// ```
+    ///
+    /// [`Top`]: VerticalAlignment::Top
+    /// [`Bottom`]: VerticalAlignment::Bottom
+    /// [`VerticalOverdraw`]: crate::style::VerticalOverdraw
+    Custom(fn(i32, i32) -> i32),
 }
 
 impl VerticalAlignment {
@@ -103,13 +130,20 @@ impl VerticalAlignment {
             VerticalAlignment::Middle => {
                 let offset = (box_height - text_height) / 2;
 
-                cursor.y += offset;
+                cursor.move_down(offset);
             }
 
             VerticalAlignment::Bottom => {
                 let offset = box_height - text_height;
 
-                cursor.y += offset;
+                cursor.move_down(offset);
+            }
+
+            VerticalAlignment::Custom(place) => {
+                let bottom = box_height - text_height;
+                let offset = place(text_height, box_height).clamp(bottom.min(0), bottom.max(0));
+
+                cursor.move_down(offset);
             }
         }
     }
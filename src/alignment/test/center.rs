@@ -8,9 +8,9 @@ use embedded_graphics::{
 };
 
 use crate::{
-    alignment::HorizontalAlignment,
+    alignment::{test::assert_tab_stops, HorizontalAlignment},
     rendering::test::{assert_rendered, assert_styled_rendered},
-    style::{TextBoxStyle, TextBoxStyleBuilder},
+    style::{TabAnchor, TextBoxStyle, TextBoxStyleBuilder},
     utils::test::size_for,
     TextBox,
 };
@@ -220,3 +220,13 @@ fn soft_hyphen_centering() {
         ],
     );
 }
+
+#[test]
+fn tab_stops_are_anchored() {
+    // "a\tb" is 30px wide, and it is moved right by 15px.
+    assert_tab_stops(HorizontalAlignment::Center, TabAnchor::Content, 15, 39);
+
+    // The tab stops are at 24 and 48px of the box. Moving "a" past 17px would move "b" to the
+    // second tab stop, which leaves more room on the left than on the right.
+    assert_tab_stops(HorizontalAlignment::Center, TabAnchor::Box, 17, 24);
+}
@@ -0,0 +1,85 @@
+use embedded_graphics::{
+    mock_display::MockDisplay,
+    mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::{alignment::VerticalAlignment, style::TextBoxStyle, TextBox};
+
+#[track_caller]
+fn assert_rendered(alignment: VerticalAlignment, pattern: &[&str]) {
+    let mut display = MockDisplay::new();
+
+    let character_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X9)
+        .text_color(BinaryColor::On)
+        .background_color(BinaryColor::Off)
+        .build();
+
+    let style = TextBoxStyle::with_vertical_alignment(alignment);
+
+    TextBox::with_textbox_style(
+        "word",
+        Rectangle::new(Point::zero(), Size::new(55, 15)),
+        character_style,
+        style,
+    )
+    .draw(&mut display)
+    .unwrap();
+
+    display.assert_pattern(pattern);
+}
+
+#[test]
+fn test_custom_alignment() {
+    fn one_third(text_height: i32, box_height: i32) -> i32 {
+        (box_height - text_height) / 3
+    }
+
+    assert_rendered(
+        VerticalAlignment::Custom(one_third),
+        &[
+            "                        ",
+            "                        ",
+            "........................",
+            "......................#.",
+            "......................#.",
+            "#...#...##...#.#....###.",
+            "#.#.#..#..#..##.#..#..#.",
+            "#.#.#..#..#..#.....#..#.",
+            ".#.#....##...#......###.",
+            "........................",
+            "........................",
+            "                        ",
+            "                        ",
+            "                        ",
+            "                        ",
+        ],
+    );
+}
+
+#[test]
+fn test_custom_alignment_is_clamped() {
+    assert_rendered(
+        VerticalAlignment::Custom(|_, box_height| box_height),
+        &[
+            "                        ",
+            "                        ",
+            "                        ",
+            "                        ",
+            "                        ",
+            "                        ",
+            "........................",
+            "......................#.",
+            "......................#.",
+            "#...#...##...#.#....###.",
+            "#.#.#..#..#..##.#..#..#.",
+            "#.#.#..#..#..#.....#..#.",
+            ".#.#....##...#......###.",
+            "........................",
+            "........................",
+        ],
+    );
+}
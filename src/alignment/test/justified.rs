@@ -346,3 +346,33 @@ fn tab_rendering() {
         ],
     );
 }
+
+#[test]
+fn extra_pixels_are_spread_between_spaces() {
+    // 30 pixels of space are distributed between 4 spaces as 8, 7, 8, 7 pixels.
+    assert_rendered(
+        HorizontalAlignment::Justified,
+        "a b c d e fgh",
+        size_for(&FONT_6X9, 10, 2),
+        &[
+            "............................................................",
+            "...............#.............................#..............",
+            "...............#.............................#..............",
+            "..###..........###...........###...........###..........##..",
+            ".#..#..........#..#.........#.............#..#.........#.##.",
+            ".#..#..........#..#.........#.............#..#.........##...",
+            "..###..........###...........###...........###..........###.",
+            "............................................................",
+            "............................................................",
+            "..................                                          ",
+            "...#.........#....                                          ",
+            "..#.#........#....                                          ",
+            "..#.....##...###..                                          ",
+            ".###...#..#..#..#.                                          ",
+            "..#....#..#..#..#.                                          ",
+            "..#.....###..#..#.                                          ",
+            "..........#.......                                          ",
+            "........##........                                          ",
+        ],
+    );
+}
@@ -1,5 +1,52 @@
 mod center;
+mod custom;
 mod justified;
 mod left;
 mod right;
 mod top;
+
+use embedded_graphics::{
+    geometry::Point,
+    mock_display::MockDisplay,
+    mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    text::{Baseline, Text},
+    Drawable,
+};
+
+use crate::{
+    alignment::HorizontalAlignment,
+    style::{TabAnchor, TabSize, TextBoxStyleBuilder},
+    utils::test::size_for,
+    TextBox,
+};
+
+/// Renders "a\tb" into a 10 characters wide line, using 4 characters wide tabs, and checks the
+/// horizontal positions of the two glyphs.
+fn assert_tab_stops(alignment: HorizontalAlignment, anchor: TabAnchor, a_x: i32, b_x: i32) {
+    let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+    let mut display = MockDisplay::new();
+    TextBox::with_textbox_style(
+        "a\tb",
+        Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 1)),
+        character_style,
+        TextBoxStyleBuilder::new()
+            .alignment(alignment)
+            .tab_size(TabSize::Spaces(4))
+            .tab_anchor(anchor)
+            .build(),
+    )
+    .draw(&mut display)
+    .unwrap();
+
+    let mut expected = MockDisplay::new();
+    for (text, x) in [("a", a_x), ("b", b_x)] {
+        Text::with_baseline(text, Point::new(x, 0), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+    }
+
+    display.assert_eq(&expected);
+}
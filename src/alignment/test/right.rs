@@ -8,9 +8,9 @@ use embedded_graphics::{
 };
 
 use crate::{
-    alignment::HorizontalAlignment,
+    alignment::{test::assert_tab_stops, HorizontalAlignment},
     rendering::test::{assert_rendered, assert_styled_rendered},
-    style::{TextBoxStyle, TextBoxStyleBuilder},
+    style::{TabAnchor, TextBoxStyle, TextBoxStyleBuilder},
     utils::test::size_for,
     TextBox,
 };
@@ -220,3 +220,13 @@ fn soft_hyphen_rendering() {
         ],
     );
 }
+
+#[test]
+fn tab_stops_are_anchored() {
+    // "a\tb" is 30px wide, and it is moved right by 30px.
+    assert_tab_stops(HorizontalAlignment::Right, TabAnchor::Content, 30, 54);
+
+    // The tab stops are at 24 and 48px of the box. "b" can't end at the right edge, so it is
+    // placed at the last tab stop it fits after, and "a" is moved right until it reaches it.
+    assert_tab_stops(HorizontalAlignment::Right, TabAnchor::Box, 41, 48);
+}
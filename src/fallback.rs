@@ -0,0 +1,366 @@
+//! Fallback fonts.
+//!
+//! Fonts for small displays usually contain a limited set of characters. [`FallbackTextStyle`]
+//! combines two character styles: characters that are available in the primary style are drawn
+//! using it, and every other character is drawn using the fallback style. The text box measures
+//! the text using the combined style, so wrapping takes the widths of both fonts into account.
+//!
+//! Character styles don't report which characters they contain, so the characters of the primary
+//! style are selected by a function:
+//!
+//! ```rust
+//! use embedded_graphics::{
+//!     mock_display::MockDisplay,
+//!     mono_font::{ascii, iso_8859_1, MonoTextStyle},
+//!     pixelcolor::BinaryColor,
+//!     prelude::*,
+//!     primitives::Rectangle,
+//! };
+//! use embedded_text::{fallback::FallbackTextStyle, TextBox};
+//!
+//! let character_style = FallbackTextStyle::new(
+//!     MonoTextStyle::new(&ascii::FONT_6X9, BinaryColor::On),
+//!     MonoTextStyle::new(&iso_8859_1::FONT_6X9, BinaryColor::On),
+//!     |c| c.is_ascii(),
+//! );
+//!
+//! let mut display = MockDisplay::new();
+//! TextBox::new(
+//!     "Grüße",
+//!     Rectangle::new(Point::zero(), Size::new(30, 9)),
+//!     character_style,
+//! )
+//! .draw(&mut display)
+//! .unwrap();
+//! ```
+//!
+//! Fallback styles can be nested to use more than two fonts. The line height of the combined
+//! style is the larger line height of the two styles. Characters of both styles are drawn on a
+//! shared baseline, which is as far below the top of the line as the lower baseline of the two
+//! styles.
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    primitives::Rectangle,
+    text::{
+        renderer::{CharacterStyle, TextMetrics, TextRenderer},
+        Baseline, DecorationColor,
+    },
+};
+
+/// A character style that draws characters missing from its primary style using a fallback style.
+#[derive(Clone, Copy, Debug)]
+pub struct FallbackTextStyle<P, F> {
+    primary: P,
+    fallback: F,
+    has_glyph: fn(char) -> bool,
+}
+
+impl<P, F> FallbackTextStyle<P, F> {
+    /// Creates a new fallback character style.
+    ///
+    /// Characters for which `has_glyph` returns `true` are drawn using the `primary` style.
+    #[inline]
+    pub const fn new(primary: P, fallback: F, has_glyph: fn(char) -> bool) -> Self {
+        Self {
+            primary,
+            fallback,
+            has_glyph,
+        }
+    }
+
+    /// Splits the longest leading run of characters that are drawn using the same style.
+    ///
+    /// Returns whether the run is drawn using the primary style, the run and the rest of the text.
+    fn split_run<'t>(&self, text: &'t str) -> (bool, &'t str, &'t str) {
+        let primary = text.chars().next().map_or(true, self.has_glyph);
+        let end = text
+            .char_indices()
+            .find(|(_, c)| (self.has_glyph)(*c) != primary)
+            .map_or(text.len(), |(idx, _)| idx);
+        let (run, rest) = text.split_at(end);
+
+        (primary, run, rest)
+    }
+}
+
+impl<P, F> FallbackTextStyle<P, F>
+where
+    P: TextRenderer,
+    F: TextRenderer<Color = P::Color>,
+{
+    /// Returns the distance between the top of a line and the baseline that both styles share.
+    fn ascent(&self) -> i32 {
+        let ascent = |bounding_box: Rectangle| -bounding_box.top_left.y;
+
+        ascent(
+            self.primary
+                .measure_string("", Point::zero(), Baseline::Alphabetic)
+                .bounding_box,
+        )
+        .max(ascent(
+            self.fallback
+                .measure_string("", Point::zero(), Baseline::Alphabetic)
+                .bounding_box,
+        ))
+    }
+
+    /// Returns the position of the shared alphabetic baseline, and the top of the line, of text
+    /// that is drawn at `position`.
+    fn alphabetic(&self, position: Point, baseline: Baseline) -> (Point, i32) {
+        let line_height = self.line_height() as i32;
+        let ascent = self.ascent();
+        let top = position.y
+            - match baseline {
+                Baseline::Top => 0,
+                Baseline::Bottom => line_height - 1,
+                Baseline::Middle => (line_height - 1) / 2,
+                Baseline::Alphabetic => ascent,
+            };
+
+        (Point::new(position.x, top + ascent), top)
+    }
+}
+
+/// Returns the smallest rectangle that contains both rectangles, ignoring empty ones.
+fn envelope(a: Rectangle, b: Rectangle) -> Rectangle {
+    match (a.bottom_right(), b.bottom_right()) {
+        (Some(a_end), Some(b_end)) => Rectangle::with_corners(
+            a.top_left.component_min(b.top_left),
+            a_end.component_max(b_end),
+        ),
+        (Some(_), None) => a,
+        (None, _) => b,
+    }
+}
+
+impl<P, F> TextRenderer for FallbackTextStyle<P, F>
+where
+    P: TextRenderer,
+    F: TextRenderer<Color = P::Color>,
+{
+    type Color = P::Color;
+
+    #[inline]
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        // Runs are drawn on the same alphabetic baseline, even if the fonts have different sizes.
+        let (mut next, _) = self.alphabetic(position, baseline);
+        let mut text = text;
+        while !text.is_empty() {
+            let (primary, run, rest) = self.split_run(text);
+            next = if primary {
+                self.primary
+                    .draw_string(run, next, Baseline::Alphabetic, target)?
+            } else {
+                self.fallback
+                    .draw_string(run, next, Baseline::Alphabetic, target)?
+            };
+            text = rest;
+        }
+
+        Ok(Point::new(next.x, position.y))
+    }
+
+    #[inline]
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.primary
+            .draw_whitespace(width, position, baseline, target)
+    }
+
+    #[inline]
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let (alphabetic, top) = self.alphabetic(position, baseline);
+        let mut metrics = TextMetrics {
+            bounding_box: Rectangle::new(Point::new(position.x, top), Size::zero()),
+            next_position: alphabetic,
+        };
+        let mut text = text;
+        while !text.is_empty() {
+            let (primary, run, rest) = self.split_run(text);
+            let run_metrics = if primary {
+                self.primary
+                    .measure_string(run, metrics.next_position, Baseline::Alphabetic)
+            } else {
+                self.fallback
+                    .measure_string(run, metrics.next_position, Baseline::Alphabetic)
+            };
+
+            metrics = TextMetrics {
+                bounding_box: envelope(metrics.bounding_box, run_metrics.bounding_box),
+                next_position: run_metrics.next_position,
+            };
+            text = rest;
+        }
+
+        metrics.next_position.y = position.y;
+        metrics
+    }
+
+    #[inline]
+    fn line_height(&self) -> u32 {
+        self.primary.line_height().max(self.fallback.line_height())
+    }
+}
+
+impl<P, F> CharacterStyle for FallbackTextStyle<P, F>
+where
+    P: CharacterStyle,
+    F: CharacterStyle<Color = P::Color>,
+{
+    type Color = P::Color;
+
+    #[inline]
+    fn set_text_color(&mut self, text_color: Option<Self::Color>) {
+        self.primary.set_text_color(text_color);
+        self.fallback.set_text_color(text_color);
+    }
+
+    #[inline]
+    fn set_background_color(&mut self, background_color: Option<Self::Color>) {
+        self.primary.set_background_color(background_color);
+        self.fallback.set_background_color(background_color);
+    }
+
+    #[inline]
+    fn set_underline_color(&mut self, underline_color: DecorationColor<Self::Color>) {
+        self.primary.set_underline_color(underline_color);
+        self.fallback.set_underline_color(underline_color);
+    }
+
+    #[inline]
+    fn set_strikethrough_color(&mut self, strikethrough_color: DecorationColor<Self::Color>) {
+        self.primary.set_strikethrough_color(strikethrough_color);
+        self.fallback.set_strikethrough_color(strikethrough_color);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii, iso_8859_1, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+        text::{renderer::TextRenderer, Baseline, Text},
+    };
+
+    use super::FallbackTextStyle;
+    use crate::{style::TextBoxStyle, TextBox};
+
+    #[test]
+    fn missing_glyph_is_drawn_using_fallback_style() {
+        let primary = MonoTextStyle::new(&ascii::FONT_6X9, BinaryColor::On);
+        let fallback = MonoTextStyle::new(&iso_8859_1::FONT_7X13, BinaryColor::On);
+        let character_style = FallbackTextStyle::new(primary, fallback, |c| c.is_ascii());
+
+        // The fallback glyph is 7px wide.
+        assert_eq!(
+            TextBoxStyle::default().natural_width(&character_style, "a\u{e4}b"),
+            6 + 7 + 6
+        );
+
+        let mut display = MockDisplay::new();
+        TextBox::new(
+            "a\u{e4}b",
+            Rectangle::new(Point::zero(), Size::new(19, 13)),
+            character_style,
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        // The glyphs share the baseline of the taller font, 10px below the top of the line.
+        let mut expected = MockDisplay::new();
+        for (text, x, style) in [
+            ("a", 0, primary),
+            ("\u{e4}", 6, fallback),
+            ("b", 13, primary),
+        ] {
+            Text::with_baseline(text, Point::new(x, 10), style, Baseline::Alphabetic)
+                .draw(&mut expected)
+                .unwrap();
+        }
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn mixed_height_fonts_share_the_baseline() {
+        let primary = MonoTextStyle::new(&ascii::FONT_6X9, BinaryColor::On);
+        let fallback = MonoTextStyle::new(&iso_8859_1::FONT_7X13, BinaryColor::On);
+        let character_style = FallbackTextStyle::new(primary, fallback, |c| c.is_ascii());
+
+        // The 6x9 font has its baseline at 6px, the 7x13 font at 10px.
+        let metrics = character_style.measure_string("a\u{e4}", Point::new(1, 2), Baseline::Top);
+        assert_eq!(
+            metrics.bounding_box,
+            Rectangle::new(Point::new(1, 2), Size::new(13, 13))
+        );
+        assert_eq!(metrics.next_position, Point::new(14, 2));
+
+        let metrics = character_style.measure_string("a", Point::new(1, 12), Baseline::Alphabetic);
+        assert_eq!(
+            metrics.bounding_box,
+            Rectangle::new(Point::new(1, 6), Size::new(6, 9))
+        );
+
+        let mut display = MockDisplay::new();
+        let next = character_style
+            .draw_string(
+                "a\u{e4}",
+                Point::new(1, 12),
+                Baseline::Alphabetic,
+                &mut display,
+            )
+            .unwrap();
+        assert_eq!(next, Point::new(14, 12));
+
+        let mut expected = MockDisplay::new();
+        Text::with_baseline("a", Point::new(1, 12), primary, Baseline::Alphabetic)
+            .draw(&mut expected)
+            .unwrap();
+        Text::with_baseline("\u{e4}", Point::new(7, 12), fallback, Baseline::Alphabetic)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn words_are_wrapped_using_combined_width() {
+        let character_style = FallbackTextStyle::new(
+            MonoTextStyle::new(&ascii::FONT_6X9, BinaryColor::On),
+            MonoTextStyle::new(&iso_8859_1::FONT_7X13, BinaryColor::On),
+            |c| c.is_ascii(),
+        );
+
+        // "ab \u{e4}\u{e4}" is 2 * 6 + 6 + 2 * 7 = 32px wide.
+        let style = TextBoxStyle::default();
+        assert_eq!(
+            style.measure_text_height(&character_style, "ab \u{e4}\u{e4}", 32),
+            13
+        );
+        assert_eq!(
+            style.measure_text_height(&character_style, "ab \u{e4}\u{e4}", 31),
+            26
+        );
+    }
+}
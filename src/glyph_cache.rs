@@ -0,0 +1,661 @@
+//! Caching of rendered glyphs.
+//!
+//! Fonts stored in slow memory, like external flash, are expensive to read. The text box draws
+//! every glyph separately, so a small cache of recently drawn glyphs can remove most font reads
+//! when the same characters are displayed repeatedly, like the digits of a numeric display.
+//!
+//! A [`GlyphCache`] is created by the user, which makes its RAM cost explicit, and is attached to a
+//! character style using [`CachedTextStyle`]:
+//!
+//! ```rust
+//! use embedded_graphics::{
+//!     mock_display::MockDisplay,
+//!     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+//!     pixelcolor::BinaryColor,
+//!     prelude::*,
+//!     primitives::Rectangle,
+//! };
+//! use embedded_text::{
+//!     glyph_cache::{CachedTextStyle, GlyphCache},
+//!     TextBox,
+//! };
+//!
+//! // Keep the 10 most recently drawn glyphs.
+//! let cache = GlyphCache::<BinaryColor, 10>::new();
+//! let character_style = CachedTextStyle::new(MonoTextStyle::new(&FONT_6X9, BinaryColor::On), &cache);
+//!
+//! let mut display = MockDisplay::new();
+//! TextBox::new(
+//!     "1010",
+//!     Rectangle::new(Point::zero(), Size::new(24, 9)),
+//!     character_style,
+//! )
+//! .draw(&mut display)
+//! .unwrap();
+//! ```
+//!
+//! Using the cache doesn't change the rendered output of character styles that draw strings as a
+//! sequence of separate glyphs, like `MonoTextStyle`. Glyphs that are larger than
+//! [`MAX_GLYPH_WIDTH`] × [`MAX_GLYPH_HEIGHT`] pixels, or are drawn using more than 3 different
+//! colors, are not cached and are always drawn by the wrapped character style.
+//!
+//! # Memory usage
+//!
+//! Every cached glyph stores its pixels in 32 rows of 64 bits, so a cache of `N` glyphs takes a
+//! little more than `N × 256` bytes. The rest of a glyph, its key and palette, depends on the
+//! color type and on the target platform. Use `core::mem::size_of` to get the exact size.
+use core::{
+    cell::{Cell, RefCell},
+    fmt,
+};
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point, Size},
+    pixelcolor::PixelColor,
+    primitives::Rectangle,
+    text::{
+        renderer::{CharacterStyle, TextMetrics, TextRenderer},
+        Baseline, DecorationColor,
+    },
+    Pixel,
+};
+
+/// The width of the largest glyph that can be cached, in pixels.
+pub const MAX_GLYPH_WIDTH: u32 = 32;
+
+/// The height of the largest glyph that can be cached, in pixels.
+pub const MAX_GLYPH_HEIGHT: u32 = 32;
+
+/// Number of colors a cached glyph can use.
+const PALETTE_SIZE: usize = 3;
+
+/// Properties changed using the [`CharacterStyle`] setters of a [`CachedTextStyle`].
+///
+/// `None` means that the property of the wrapped style is unchanged.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct StyleChanges<C> {
+    text_color: Option<Option<C>>,
+    background_color: Option<Option<C>>,
+    underline_color: Option<DecorationColor<C>>,
+    strikethrough_color: Option<DecorationColor<C>>,
+}
+
+impl<C> StyleChanges<C> {
+    const NONE: Self = Self {
+        text_color: None,
+        background_color: None,
+        underline_color: None,
+        strikethrough_color: None,
+    };
+}
+
+/// Identifies a glyph drawn by a character style.
+#[derive(Clone, Copy, PartialEq)]
+struct GlyphKey<C> {
+    c: char,
+    style_id: u64,
+    changes: StyleChanges<C>,
+}
+
+/// A rendered glyph.
+///
+/// Pixels are stored with 2 bits each: 0 means that the pixel is not drawn, other values select
+/// a color from the palette.
+#[derive(Clone, Copy)]
+struct Glyph<C> {
+    key: Option<GlyphKey<C>>,
+    last_used: u32,
+    advance: Point,
+    rows: [u64; MAX_GLYPH_HEIGHT as usize],
+    palette: [Option<C>; PALETTE_SIZE],
+}
+
+impl<C: PixelColor> Glyph<C> {
+    const EMPTY: Self = Self {
+        key: None,
+        last_used: 0,
+        advance: Point::zero(),
+        rows: [0; MAX_GLYPH_HEIGHT as usize],
+        palette: [None; PALETTE_SIZE],
+    };
+
+    fn pixels(&self, position: Point) -> impl Iterator<Item = Pixel<C>> + '_ {
+        self.rows.iter().enumerate().flat_map(move |(y, row)| {
+            (0..MAX_GLYPH_WIDTH).filter_map(move |x| {
+                let index = (row >> (2 * x)) & 0b11;
+                let color = match index {
+                    0 => None,
+                    _ => self.palette[index as usize - 1],
+                };
+
+                color.map(|color| Pixel(position + Point::new(x as i32, y as i32), color))
+            })
+        })
+    }
+}
+
+/// Draw target that records the pixels of a single glyph.
+struct Recorder<C> {
+    glyph: Glyph<C>,
+    origin: Point,
+    overflow: bool,
+}
+
+impl<C: PixelColor> Recorder<C> {
+    fn set_pixel(&mut self, point: Point, color: C) {
+        let Point { x, y } = point - self.origin;
+        if !(0..MAX_GLYPH_WIDTH as i32).contains(&x) || !(0..MAX_GLYPH_HEIGHT as i32).contains(&y) {
+            self.overflow = true;
+            return;
+        }
+
+        let palette = &mut self.glyph.palette;
+        let index = match palette.iter().position(|c| *c == Some(color)) {
+            Some(index) => index,
+            None => match palette.iter().position(Option::is_none) {
+                Some(index) => {
+                    palette[index] = Some(color);
+                    index
+                }
+                None => {
+                    self.overflow = true;
+                    return;
+                }
+            },
+        };
+
+        let row = &mut self.glyph.rows[y as usize];
+        *row &= !(0b11 << (2 * x));
+        *row |= (index as u64 + 1) << (2 * x);
+    }
+}
+
+impl<C> Dimensions for Recorder<C> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.origin, Size::new(MAX_GLYPH_WIDTH, MAX_GLYPH_HEIGHT))
+    }
+}
+
+impl<C: PixelColor> DrawTarget for Recorder<C> {
+    type Color = C;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(point, color);
+        }
+
+        Ok(())
+    }
+}
+
+/// Cache of the `N` most recently drawn glyphs.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct GlyphCache<C, const N: usize> {
+    glyphs: RefCell<[Glyph<C>; N]>,
+    clock: Cell<u32>,
+    next_style_id: Cell<u64>,
+}
+
+impl<C, const N: usize> GlyphCache<C, N>
+where
+    C: PixelColor,
+{
+    /// Creates a new, empty glyph cache.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            glyphs: RefCell::new([Glyph::EMPTY; N]),
+            clock: Cell::new(0),
+            next_style_id: Cell::new(0),
+        }
+    }
+
+    /// Removes every glyph from the cache.
+    #[inline]
+    pub fn clear(&self) {
+        *self.glyphs.borrow_mut() = [Glyph::EMPTY; N];
+    }
+
+    /// Returns a new identifier for a character style.
+    ///
+    /// Identifiers are never reused: a 64 bit counter doesn't wrap around in practice.
+    fn style_id(&self) -> u64 {
+        let id = self.next_style_id.get();
+        self.next_style_id.set(id.wrapping_add(1));
+
+        id
+    }
+
+    fn tick(&self) -> u32 {
+        let now = self.clock.get().wrapping_add(1);
+        self.clock.set(now);
+
+        now
+    }
+
+    /// Draws a cached glyph. Returns `None` if the glyph is not cached.
+    fn draw<D>(
+        &self,
+        key: GlyphKey<C>,
+        position: Point,
+        target: &mut D,
+    ) -> Option<Result<Point, D::Error>>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let now = self.tick();
+        let mut glyphs = self.glyphs.borrow_mut();
+        let glyph = glyphs.iter_mut().find(|glyph| glyph.key == Some(key))?;
+        glyph.last_used = now;
+
+        Some(
+            target
+                .draw_iter(glyph.pixels(position))
+                .map(|_| position + glyph.advance),
+        )
+    }
+
+    /// Stores a recorded glyph in place of the least recently used one.
+    fn insert(&self, key: GlyphKey<C>, mut glyph: Glyph<C>) {
+        let now = self.clock.get();
+        let mut glyphs = self.glyphs.borrow_mut();
+
+        // Empty slots are used first.
+        let oldest = glyphs.iter_mut().max_by_key(|glyph| match glyph.key {
+            Some(_) => u64::from(now.wrapping_sub(glyph.last_used)),
+            None => u64::MAX,
+        });
+
+        if let Some(oldest) = oldest {
+            glyph.key = Some(key);
+            glyph.last_used = now;
+            *oldest = glyph;
+        }
+    }
+}
+
+impl<C, const N: usize> Default for GlyphCache<C, N>
+where
+    C: PixelColor,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, const N: usize> fmt::Debug for GlyphCache<C, N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlyphCache")
+            .field("capacity", &N)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Character style that draws glyphs using a [`GlyphCache`].
+///
+/// Strings drawn with the top baseline are split into glyphs, which are looked up in the cache
+/// first. Glyphs that are not cached are drawn by the wrapped character style, and are stored in
+/// the cache. The space between two glyphs is drawn as whitespace. Glyphs are cached separately
+/// for every combination of colors set using the [`CharacterStyle`] methods.
+#[derive(Debug)]
+pub struct CachedTextStyle<'c, S, const N: usize>
+where
+    S: TextRenderer,
+{
+    style: S,
+    cache: &'c GlyphCache<S::Color, N>,
+    style_id: u64,
+    changes: StyleChanges<S::Color>,
+}
+
+impl<'c, S, const N: usize> CachedTextStyle<'c, S, N>
+where
+    S: TextRenderer,
+{
+    /// Attaches a glyph cache to a character style.
+    #[inline]
+    pub fn new(style: S, cache: &'c GlyphCache<S::Color, N>) -> Self {
+        Self {
+            style,
+            cache,
+            style_id: cache.style_id(),
+            changes: StyleChanges::NONE,
+        }
+    }
+
+    /// Returns the wrapped character style.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.style
+    }
+
+    fn width(&self, text: &str) -> i32 {
+        self.style
+            .measure_string(text, Point::zero(), Baseline::Top)
+            .next_position
+            .x
+    }
+
+    /// Draws a single glyph using the cache.
+    fn draw_glyph<D>(
+        &self,
+        c: char,
+        glyph: &str,
+        position: Point,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = S::Color>,
+    {
+        let key = GlyphKey {
+            c,
+            style_id: self.style_id,
+            changes: self.changes,
+        };
+        if let Some(result) = self.cache.draw(key, position, target) {
+            return result;
+        }
+
+        let mut recorder = Recorder {
+            glyph: Glyph::EMPTY,
+            origin: position,
+            overflow: false,
+        };
+        let next = match self
+            .style
+            .draw_string(glyph, position, Baseline::Top, &mut recorder)
+        {
+            Ok(next) => next,
+            Err(infallible) => match infallible {},
+        };
+
+        if recorder.overflow {
+            return self
+                .style
+                .draw_string(glyph, position, Baseline::Top, target);
+        }
+
+        recorder.glyph.advance = next - position;
+        target.draw_iter(recorder.glyph.pixels(position))?;
+        self.cache.insert(key, recorder.glyph);
+
+        Ok(next)
+    }
+}
+
+impl<S, const N: usize> Clone for CachedTextStyle<'_, S, N>
+where
+    S: TextRenderer + Clone,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            style: self.style.clone(),
+            cache: self.cache,
+            style_id: self.style_id,
+            changes: self.changes,
+        }
+    }
+}
+
+impl<S, const N: usize> TextRenderer for CachedTextStyle<'_, S, N>
+where
+    S: TextRenderer,
+{
+    type Color = S::Color;
+
+    #[inline]
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        if baseline != Baseline::Top || text.is_empty() {
+            return self.style.draw_string(text, position, baseline, target);
+        }
+
+        let mut pos = position;
+        let mut previous: Option<(usize, i32)> = None;
+        for (idx, c) in text.char_indices() {
+            let end = idx + c.len_utf8();
+            let glyph = &text[idx..end];
+
+            // The glyph is placed after the previous one, including the spacing between them.
+            let glyph_x = match previous {
+                Some((start, x)) => x + self.width(&text[start..end]) - self.width(glyph),
+                None => position.x,
+            };
+            previous = Some((idx, glyph_x));
+
+            // The space between two glyphs is drawn like whitespace.
+            if glyph_x > pos.x {
+                let width = (glyph_x - pos.x) as u32;
+                self.style.draw_whitespace(width, pos, baseline, target)?;
+            }
+
+            pos = self.draw_glyph(c, glyph, Point::new(glyph_x, position.y), target)?;
+        }
+
+        Ok(pos)
+    }
+
+    #[inline]
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.style
+            .draw_whitespace(width, position, baseline, target)
+    }
+
+    #[inline]
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        self.style.measure_string(text, position, baseline)
+    }
+
+    #[inline]
+    fn line_height(&self) -> u32 {
+        self.style.line_height()
+    }
+}
+
+impl<S, const N: usize> CharacterStyle for CachedTextStyle<'_, S, N>
+where
+    S: TextRenderer + CharacterStyle<Color = <S as TextRenderer>::Color>,
+{
+    type Color = <S as TextRenderer>::Color;
+
+    #[inline]
+    fn set_text_color(&mut self, text_color: Option<Self::Color>) {
+        self.style.set_text_color(text_color);
+        self.changes.text_color = Some(text_color);
+    }
+
+    #[inline]
+    fn set_background_color(&mut self, background_color: Option<Self::Color>) {
+        self.style.set_background_color(background_color);
+        self.changes.background_color = Some(background_color);
+    }
+
+    #[inline]
+    fn set_underline_color(&mut self, underline_color: DecorationColor<Self::Color>) {
+        self.style.set_underline_color(underline_color);
+        self.changes.underline_color = Some(underline_color);
+    }
+
+    #[inline]
+    fn set_strikethrough_color(&mut self, strikethrough_color: DecorationColor<Self::Color>) {
+        self.style.set_strikethrough_color(strikethrough_color);
+        self.changes.strikethrough_color = Some(strikethrough_color);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::cell::Cell;
+
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle, MonoTextStyleBuilder},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+        text::{
+            renderer::{CharacterStyle, TextRenderer},
+            DecorationColor,
+        },
+    };
+
+    use super::{CachedTextStyle, GlyphCache};
+    use crate::{
+        utils::test::{size_for, CountingDraws},
+        TextBox,
+    };
+
+    fn render<S>(character_style: S, text: &str) -> MockDisplay<BinaryColor>
+    where
+        S: TextRenderer<Color = BinaryColor> + CharacterStyle<Color = BinaryColor> + Clone,
+    {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+            character_style,
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        display
+    }
+
+    #[test]
+    fn repeated_glyphs_are_drawn_from_the_cache() {
+        let draws = Cell::new(0);
+        let cache = GlyphCache::<BinaryColor, 4>::new();
+        let character_style = CountingDraws {
+            inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            draws: &draws,
+        };
+
+        let display = render(
+            CachedTextStyle::new(character_style.clone(), &cache),
+            "1212 21121",
+        );
+
+        // Only the first "1" and "2" are drawn by the font.
+        assert_eq!(draws.get(), 2);
+        display.assert_eq(&render(character_style, "1212 21121"));
+    }
+
+    #[test]
+    fn least_recently_used_glyph_is_replaced() {
+        let draws = Cell::new(0);
+        let cache = GlyphCache::<BinaryColor, 2>::new();
+        let character_style = CachedTextStyle::new(
+            CountingDraws {
+                inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                draws: &draws,
+            },
+            &cache,
+        );
+
+        // "3" replaces "2", which was used less recently than "1".
+        render(character_style.clone(), "1213");
+        assert_eq!(draws.get(), 3);
+
+        draws.set(0);
+        render(character_style.clone(), "13");
+        assert_eq!(draws.get(), 0);
+
+        render(character_style, "2");
+        assert_eq!(draws.get(), 1);
+    }
+
+    #[test]
+    fn changing_the_style_invalidates_cached_glyphs() {
+        let draws = Cell::new(0);
+        let cache = GlyphCache::<BinaryColor, 4>::new();
+        let mut character_style = CachedTextStyle::new(
+            CountingDraws {
+                inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                draws: &draws,
+            },
+            &cache,
+        );
+
+        render(character_style.clone(), "1");
+        character_style.set_text_color(Some(BinaryColor::Off));
+        let display = render(character_style, "1");
+
+        assert_eq!(draws.get(), 2);
+        display.assert_eq(&render(
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::Off),
+            "1",
+        ));
+    }
+
+    #[test]
+    fn cached_output_is_identical() {
+        let cache = GlyphCache::<BinaryColor, 8>::new();
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .underline()
+            .build();
+
+        let mut underlined = character_style;
+        underlined.set_underline_color(DecorationColor::TextColor);
+
+        for text in ["10 01", "a\tb", "0000000000"] {
+            let expected = render(underlined, text);
+            let cached = CachedTextStyle::new(underlined, &cache);
+
+            // Draw twice, to render from a filled cache.
+            for _ in 0..2 {
+                render(cached.clone(), text).assert_eq(&expected);
+            }
+        }
+    }
+
+    #[test]
+    fn restoring_the_style_reuses_cached_glyphs() {
+        let draws = Cell::new(0);
+        let cache = GlyphCache::<BinaryColor, 4>::new();
+        let mut character_style = CachedTextStyle::new(
+            CountingDraws {
+                inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                draws: &draws,
+            },
+            &cache,
+        );
+
+        for color in [BinaryColor::Off, BinaryColor::On, BinaryColor::Off] {
+            character_style.set_text_color(Some(color));
+            render(character_style.clone(), "1");
+        }
+
+        // The third draw uses the glyph cached by the first one.
+        assert_eq!(draws.get(), 2);
+    }
+}
@@ -16,8 +16,10 @@
 //!
 //! [`TextBox`] also supports some special characters not handled by embedded-graphics' `Text`:
 //!  - non-breaking space (`\u{200b}`)
-//!  - zero-width space (`\u{a0}`)
+//!  - zero-width space (`\u{a0}`), which allows breaking lines even with
+//!    [`LineBreak::SpacesOnly`](style::LineBreak::SpacesOnly)
 //!  - soft hyphen (`\u{ad}`)
+//!  - zero-width no-break space / byte order mark (`\u{feff}`), which is never drawn
 //!  - carriage return (`\r`)
 //!  - tab (`\t`) with configurable tab size
 //!
@@ -93,6 +95,11 @@
 //!
 //! * `plugin` (*experimental*): allows implementing custom plugins.
 //! * `ansi` (default enabled): enables ANSI sequence support using the `Ansi` plugin.
+//! * `widget` (*experimental*): enables the `EditorView` widget. Implies `plugin`.
+//! * `grapheme`: keeps grapheme clusters (e.g. combining accents, emoji sequences) together when
+//!   breaking words and moving the caret.
+//! * `glyph-cache`: enables the `glyph_cache` module, to cache glyphs drawn from slow font memory.
+//! * `async`: enables `TextBox::draw_chunked`, to draw the text box from an async task.
 //!
 //! [embedded-graphics]: https://github.com/embedded-graphics/embedded-graphics/
 //! [the embedded-graphics simulator]: https://github.com/embedded-graphics/embedded-graphics/tree/master/simulator
@@ -106,18 +113,27 @@
 #![deny(missing_docs)]
 #![warn(clippy::all)]
 #![allow(clippy::needless_doctest_main)]
+// Function pointers in style options are compared by address, which is good enough for style
+// equality.
+#![allow(unknown_lints, unpredictable_function_pointer_comparisons)]
 
 pub mod alignment;
+pub mod fallback;
+#[cfg(feature = "glyph-cache")]
+pub mod glyph_cache;
 mod parser;
 pub mod plugin;
 mod rendering;
 pub mod style;
 mod utils;
+#[cfg(feature = "widget")]
+pub mod widget;
 
 use crate::{
     alignment::{HorizontalAlignment, VerticalAlignment},
     plugin::{NoPlugin, PluginMarker as Plugin, PluginWrapper},
-    style::{HeightMode, TabSize, TextBoxStyle},
+    rendering::cursor::UNBOUNDED_LINE_WIDTH,
+    style::{HeightMode, Scale, TabSize, TextBoxStyle},
 };
 use embedded_graphics::{
     geometry::{Dimensions, Point},
@@ -131,10 +147,23 @@ use embedded_graphics::{
 };
 use object_chain::{Chain, ChainElement, Link};
 
-#[cfg(feature = "plugin")]
 pub use crate::{
-    parser::{ChangeTextStyle, Token},
-    rendering::{cursor::Cursor, TextBoxProperties},
+    parser::{ChangeTextStyle, StyleDiff},
+    rendering::{
+        blend::BlendColor,
+        chunks::PixelChunks,
+        dither::DimColor,
+        line_iter::{ElementHandler, LineEndType},
+        ordered::PixelChunk,
+    },
+};
+
+#[cfg(any(feature = "plugin", feature = "ansi"))]
+pub use crate::parser::Token;
+#[cfg(feature = "plugin")]
+pub use crate::rendering::{
+    cursor::{Cursor, CursorState},
+    TextBoxProperties,
 };
 
 /// A text box object.
@@ -205,18 +234,39 @@ pub use crate::{
 /// offset is applied after all vertical measurements and alignments. This can be useful to scroll
 /// text in a fixed text box. Setting a positive value moves the text down.
 ///
+/// To scroll by whole lines, use [`set_first_visible_line`] to skip the first lines of the text.
+///
 /// Residual text
 /// -------------
 ///
 /// If the text does not fit the given bounding box, the [`draw`] method returns the part which was
 /// not processed. The return value can be used to flow text into multiple text boxes.
 ///
+/// Large text boxes
+/// ----------------
+///
+/// Text is laid out in at most 2<sup>24</sup> pixels wide lines, which is much wider than any
+/// display. Wider text boxes, including ones with sizes like `u32::MAX`, lay out their text in a
+/// 2<sup>24</sup> pixels wide area at the left side of the box, so text that fits into that width
+/// is not wrapped.
+///
+/// Drawing performance
+/// -------------------
+///
+/// The backgrounds of whitespace and padding, and text decorations are drawn using
+/// [`DrawTarget::fill_solid`], glyphs are drawn by the character style. Draw targets that
+/// implement accelerated fills benefit from them without further configuration. Only dithered
+/// [`intensity`] and blended drawing fall back to drawing pixel by pixel.
+///
 /// [`draw`]: embedded_graphics::Drawable::draw()
 /// [`set_vertical_offset`]: TextBox::set_vertical_offset()
+/// [`intensity`]: crate::style::TextBoxStyle::intensity
+/// [`DrawTarget::fill_solid`]: embedded_graphics::draw_target::DrawTarget::fill_solid
+/// [`set_first_visible_line`]: TextBox::set_first_visible_line()
 /// [`add_plugin`]: TextBox::add_plugin()
 /// [`take_plugins`]: TextBox::take_plugins()
 /// [`embedded-graphics` documentation]: https://docs.rs/embedded-graphics/0.7.1/embedded_graphics/text/index.html
-#[derive(Clone, Debug, Hash)]
+#[derive(Clone, Debug, Hash, PartialEq)]
 #[must_use]
 pub struct TextBox<'a, S, M = NoPlugin<<S as TextRenderer>::Color>>
 where
@@ -237,6 +287,9 @@ where
     /// Vertical offset applied to the text just before rendering.
     pub vertical_offset: i32,
 
+    /// The index of the first displayed line.
+    pub first_visible_line: u32,
+
     plugin: PluginWrapper<'a, M, S::Color>,
 }
 
@@ -266,6 +319,7 @@ where
             character_style,
             style: textbox_style,
             vertical_offset: 0,
+            first_visible_line: 0,
             plugin: PluginWrapper::new(NoPlugin::new()),
         };
 
@@ -388,6 +442,21 @@ where
         self
     }
 
+    /// Sets the index of the first displayed line.
+    ///
+    /// The first `line` lines of the text are laid out, but not drawn, and the rest of the text is
+    /// displayed from the top of the text box. If the text after `line` would not fill the text
+    /// box, the index is reduced so that the last page of the text is displayed.
+    ///
+    /// Together with [`line_count`], this can be used to scroll the text line by line.
+    ///
+    /// [`line_count`]: TextBox::line_count()
+    #[inline]
+    pub fn set_first_visible_line(&mut self, line: u32) -> &mut Self {
+        self.first_visible_line = line;
+        self
+    }
+
     /// Adds a new plugin to the `TextBox`.
     #[inline]
     pub fn add_plugin<M>(self, plugin: M) -> TextBox<'a, S, Chain<M>>
@@ -400,6 +469,7 @@ where
             character_style: self.character_style,
             style: self.style,
             vertical_offset: self.vertical_offset,
+            first_visible_line: self.first_visible_line,
             plugin: PluginWrapper::new(Chain::new(plugin)),
         };
         styled.style.height_mode.apply(&mut styled);
@@ -427,6 +497,7 @@ where
             character_style: self.character_style,
             style: self.style,
             vertical_offset: self.vertical_offset,
+            first_visible_line: self.first_visible_line,
             plugin: PluginWrapper::new(parent.append(plugin)),
         };
         styled.style.height_mode.apply(&mut styled);
@@ -478,6 +549,106 @@ where
     M: Plugin<'a, S::Color>,
     S::Color: From<Rgb888>,
 {
+    /// Creates a copy of the text box with a different bounding box and character style.
+    ///
+    /// The text, the plugins and the layout options are reused. Layout options that are given in
+    /// pixels are scaled to the new character style, see [`TextBoxStyle::rescaled`] for details.
+    /// The vertical offset is scaled by the ratio of the line heights. The [height mode] is applied
+    /// to the new bounding box.
+    ///
+    /// This can be used to display the same content on displays of different sizes.
+    ///
+    /// [height mode]: crate::style::HeightMode
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     mono_font::{
+    ///         ascii::{FONT_10X20, FONT_6X10},
+    ///         MonoTextStyle,
+    ///     },
+    ///     pixelcolor::BinaryColor,
+    ///     prelude::*,
+    ///     primitives::Rectangle,
+    /// };
+    /// use embedded_text::{style::TextBoxStyleBuilder, TextBox};
+    ///
+    /// let style = TextBoxStyleBuilder::new().paragraph_spacing(3).build();
+    ///
+    /// let oled = TextBox::with_textbox_style(
+    ///     "Hello,\nWorld!",
+    ///     Rectangle::new(Point::zero(), Size::new(128, 64)),
+    ///     MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+    ///     style,
+    /// );
+    ///
+    /// let lcd = oled.rescaled(
+    ///     Rectangle::new(Point::zero(), Size::new(320, 240)),
+    ///     MonoTextStyle::new(&FONT_10X20, BinaryColor::On),
+    /// );
+    ///
+    /// assert_eq!(lcd.style.paragraph_spacing, 6);
+    /// ```
+    #[inline]
+    pub fn rescaled<S2>(&self, bounds: Rectangle, character_style: S2) -> TextBox<'a, S2, M>
+    where
+        S2: TextRenderer<Color = S::Color>,
+        M: Clone,
+    {
+        let vertical_scale = Scale::new(
+            self.character_style.line_height(),
+            character_style.line_height(),
+        );
+        let vertical_offset = self.vertical_offset.unsigned_abs();
+        let vertical_offset = vertical_scale.apply(vertical_offset).min(i32::MAX as u32) as i32;
+
+        let mut styled = TextBox {
+            text: self.text,
+            bounds,
+            style: self.style.rescaled(&self.character_style, &character_style),
+            character_style,
+            vertical_offset: vertical_offset * self.vertical_offset.signum(),
+            first_visible_line: self.first_visible_line,
+            plugin: self.plugin.clone(),
+        };
+        styled.style.height_mode.apply(&mut styled);
+        styled
+    }
+
+    /// Returns the number of lines the text is wrapped into.
+    ///
+    /// Lines overwritten after a carriage return are not counted, and [`max_lines`] is ignored.
+    ///
+    /// [`max_lines`]: TextBoxStyle::max_lines
+    #[inline]
+    pub fn line_count(&self) -> u32 {
+        self.style.measure_line_count_impl(
+            self.plugin.clone(),
+            &self.character_style,
+            self.text,
+            self.bounds.size.width,
+        )
+    }
+
+    /// Lays out the text and passes the resulting elements to a custom [`ElementHandler`].
+    ///
+    /// This works like [`TextBoxStyle::process_elements`] with the width of the text box, but the
+    /// text is processed by the plugins of the text box. Style changes made by plugins, e.g. ANSI
+    /// sequences, are reported using [`ElementHandler::change_text_style`].
+    #[inline]
+    pub fn process_elements<E>(&self, handler: &mut E) -> Result<(), E::Error>
+    where
+        E: ElementHandler<Color = S::Color>,
+    {
+        self.style.process_elements_impl(
+            self.plugin.clone(),
+            self.text,
+            self.bounds.size.width,
+            handler,
+        )
+    }
+
     /// Sets the height of the [`TextBox`] to the height of the text.
     #[inline]
     fn fit_height(&mut self) -> &mut Self {
@@ -493,14 +664,15 @@ where
         // Measure text given the width of the textbox
         let text_height = self
             .style
-            .measure_text_height_impl(
+            .padding
+            .outer_height(self.style.measure_text_height_impl(
                 self.plugin.clone(),
                 &self.character_style,
                 self.text,
                 self.bounding_box().size.width,
-            )
-            .min(max_height)
-            .min(i32::MAX as u32);
+            ));
+        // Keep the bottom edge of the text box representable.
+        let text_height = text_height.min(max_height).min(UNBOUNDED_LINE_WIDTH);
 
         // Apply height
         self.bounds.size.height = text_height;
@@ -17,9 +17,21 @@
 //! );
 //! ```
 use core::{marker::PhantomData, str::Chars};
-use embedded_graphics::{prelude::PixelColor, text::DecorationColor};
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb888},
+    prelude::PixelColor,
+    text::DecorationColor,
+};
+
+use crate::utils::is_zero_width;
 
 /// Change text style.
+///
+/// Style changes don't change the font, so they don't affect the width of the text, and text is
+/// measured the same way with or without them. Text that needs a different font can be embedded
+/// as an inline object, see the [`InlineFragments`] plugin.
+///
+/// [`InlineFragments`]: crate::plugin::inline::InlineFragments
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum ChangeTextStyle<C> {
     /// Reset text style. Disables decoration, removes background color and sets a default text color.
@@ -36,6 +48,80 @@ pub enum ChangeTextStyle<C> {
 
     /// Change color of strikethrough decoration.
     Strikethrough(DecorationColor<C>),
+
+    /// Move the glyphs down by the given number of pixels, or up if negative.
+    ///
+    /// The offset doesn't change the width of the text or the height of the line. Moved glyphs
+    /// may overlap the neighbouring lines, but are clipped to the text area, so large offsets
+    /// can hide parts of them. This can be used to draw superscript and subscript text.
+    VerticalOffset(i8),
+}
+
+/// The text style attributes changed by a [`ChangeTextStyle`] token.
+///
+/// Every field holds the new value of the attribute, or `None` if the attribute was not changed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct StyleDiff<C> {
+    /// New text color. `Some(None)` means transparent.
+    pub text_color: Option<Option<C>>,
+
+    /// New background color. `Some(None)` means transparent.
+    pub background_color: Option<Option<C>>,
+
+    /// New color of underlining.
+    pub underline: Option<DecorationColor<C>>,
+
+    /// New color of strikethrough decoration.
+    pub strikethrough: Option<DecorationColor<C>>,
+
+    /// New vertical offset of the glyphs.
+    pub vertical_offset: Option<i8>,
+}
+
+impl<C> From<ChangeTextStyle<C>> for StyleDiff<C>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    #[inline]
+    fn from(change: ChangeTextStyle<C>) -> Self {
+        let unchanged = Self {
+            text_color: None,
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+            vertical_offset: None,
+        };
+
+        match change {
+            ChangeTextStyle::Reset => Self {
+                text_color: Some(Some(Into::<Rgb888>::into(BinaryColor::On).into())),
+                background_color: Some(None),
+                underline: Some(DecorationColor::None),
+                strikethrough: Some(DecorationColor::None),
+                vertical_offset: Some(0),
+            },
+            ChangeTextStyle::TextColor(color) => Self {
+                text_color: Some(color),
+                ..unchanged
+            },
+            ChangeTextStyle::BackgroundColor(color) => Self {
+                background_color: Some(color),
+                ..unchanged
+            },
+            ChangeTextStyle::Underline(color) => Self {
+                underline: Some(color),
+                ..unchanged
+            },
+            ChangeTextStyle::Strikethrough(color) => Self {
+                strikethrough: Some(color),
+                ..unchanged
+            },
+            ChangeTextStyle::VerticalOffset(offset) => Self {
+                vertical_offset: Some(offset),
+                ..unchanged
+            },
+        }
+    }
 }
 
 /// A text token
@@ -51,6 +137,12 @@ pub enum Token<'a, C> {
     Tab,
 
     /// A number of whitespace characters.
+    ///
+    /// The number doesn't include zero-width spaces. The string is the original whitespace
+    /// sequence, so different kinds of spaces can be told apart. Tabs are separate [`Tab`] tokens,
+    /// and the extra space characters of a [`ParserConfig`] are not mixed with other whitespace.
+    ///
+    /// [`Tab`]: Token::Tab
     Whitespace(u32, &'a str),
 
     /// A word (a sequence of non-whitespace characters).
@@ -59,6 +151,18 @@ pub enum Token<'a, C> {
     /// A possible wrapping point. Contains the separator character(s).
     Break(&'a str),
 
+    /// An inline object that is laid out like a word of the given width.
+    ///
+    /// The object is never broken or hyphenated. The text box doesn't draw anything for it, the
+    /// plugin that produced the token is expected to draw the object in its `post_render`
+    /// callback, which receives `text` and the area of the object.
+    Inline {
+        /// The source text the object replaces.
+        text: &'a str,
+        /// Width of the object in pixels.
+        width: u32,
+    },
+
     /// Change of text style.
     ChangeTextStyle(ChangeTextStyle<C>),
 
@@ -68,7 +172,62 @@ pub enum Token<'a, C> {
         chars: i32,
         /// True to draw over the area of movement with the background color.
         draw_background: bool,
+        /// True to continue a forward movement at the start of the next line, if it doesn't fit
+        /// into the current line. Otherwise, the cursor stops at the end of the line.
+        wrap: bool,
     },
+
+    /// An ANSI escape sequence, including the leading `\x1b` character.
+    ///
+    /// The [`Ansi`] plugin produces these tokens for escape sequences it doesn't support, and
+    /// [`AnsiTokens`] produces them for every escape sequence. Escape sequences are not displayed
+    /// and have no width, so the text box skips them without ending the current word.
+    ///
+    /// [`Ansi`]: crate::plugin::ansi::Ansi
+    /// [`AnsiTokens`]: crate::plugin::ansi::AnsiTokens
+    EscapeSequence(&'a str),
+}
+
+/// Character classes of the text parser.
+///
+/// By default, Unicode whitespace characters separate words. Extra space characters are handled
+/// like spaces: they separate words, lines can be wrapped at them, and they are displayed as blank
+/// space. Unlike spaces, they keep the width of their glyphs and are not stretched by justified
+/// alignment. Extra word characters are handled like letters, even
+/// if they are whitespace characters. Newline, carriage return and tab characters can't be made
+/// word characters.
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_text::style::{ParserConfig, TextBoxStyleBuilder};
+/// #
+/// // Fields are separated by middle dots and vertical bars.
+/// let style = TextBoxStyleBuilder::new()
+///     .parser_config(ParserConfig::new(&['\u{b7}', '|'], &[]))
+///     .build();
+/// ```
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// Characters that are handled like spaces.
+    pub extra_space_chars: &'static [char],
+
+    /// Characters that are handled like letters.
+    pub extra_word_chars: &'static [char],
+}
+
+impl ParserConfig {
+    /// Creates a new parser configuration.
+    #[inline]
+    pub const fn new(
+        extra_space_chars: &'static [char],
+        extra_word_chars: &'static [char],
+    ) -> Self {
+        Self {
+            extra_space_chars,
+            extra_word_chars,
+        }
+    }
 }
 
 /// Text parser. Turns a string into a stream of [`Token`] objects.
@@ -77,24 +236,56 @@ pub(crate) struct Parser<'a, C>
 where
     C: PixelColor,
 {
+    text: &'a str,
     inner: Chars<'a>,
+    max_word_len: usize,
+    max_newlines: usize,
+    config: ParserConfig,
+    break_at_soft_hyphens: bool,
+    /// Number of newlines since the last token that is not whitespace.
+    newlines: usize,
     _marker: PhantomData<C>,
 }
 
 pub(crate) const SPEC_CHAR_NBSP: char = '\u{a0}';
 pub(crate) const SPEC_CHAR_ZWSP: char = '\u{200b}';
 pub(crate) const SPEC_CHAR_SHY: char = '\u{ad}';
+pub(crate) const SPEC_CHAR_LRI: char = '\u{2066}';
+pub(crate) const SPEC_CHAR_RLI: char = '\u{2067}';
+pub(crate) const SPEC_CHAR_FSI: char = '\u{2068}';
+pub(crate) const SPEC_CHAR_PDI: char = '\u{2069}';
+
+/// Characters that keep their meaning regardless of the parser configuration.
+const CONTROL_CHARS: [char; 3] = ['\n', '\r', '\t'];
+
+fn is_word_char(c: char, config: &ParserConfig) -> bool {
+    if config.extra_word_chars.contains(&c) && !CONTROL_CHARS.contains(&c) {
+        return true;
+    }
 
-fn is_word_char(c: char) -> bool {
     // Word tokens are terminated when a whitespace, zwsp or shy character is found. An exception
     // to this rule is the nbsp, which is whitespace but is included in the word.
-    (!c.is_whitespace() || c == SPEC_CHAR_NBSP) && ![SPEC_CHAR_ZWSP, SPEC_CHAR_SHY].contains(&c)
+    (!c.is_whitespace() || c == SPEC_CHAR_NBSP)
+        && ![SPEC_CHAR_ZWSP, SPEC_CHAR_SHY].contains(&c)
+        && !config.extra_space_chars.contains(&c)
 }
 
-fn is_space_char(c: char) -> bool {
+fn is_space_char(c: char, config: &ParserConfig) -> bool {
+    if is_word_char(c, config) || CONTROL_CHARS.contains(&c) {
+        return false;
+    }
+
     // zero-width space breaks whitespace sequences - this works as long as
     // space handling is symmetrical (i.e. starting == ending behaviour)
-    c.is_whitespace() && !['\n', '\r', '\t', SPEC_CHAR_NBSP].contains(&c) || c == SPEC_CHAR_ZWSP
+    c.is_whitespace() && c != SPEC_CHAR_NBSP
+        || c == SPEC_CHAR_ZWSP
+        || config.extra_space_chars.contains(&c)
+}
+
+/// Extra space characters are measured differently than spaces, so they are returned as separate
+/// whitespace tokens.
+fn is_extra_space_char(c: char, config: &ParserConfig) -> bool {
+    config.extra_space_chars.contains(&c) && is_space_char(c, config)
 }
 
 impl<'a, C> Parser<'a, C>
@@ -105,16 +296,66 @@ where
     #[inline]
     #[must_use]
     pub fn parse(text: &'a str) -> Self {
+        Self::parse_max_word_len(text, usize::MAX)
+    }
+
+    /// Create a new parser object that splits words longer than `max_word_len` characters.
+    ///
+    /// Long words are returned as multiple consecutive [`Token::Word`]s, which gives the line
+    /// breaking logic a wrap opportunity at least every `max_word_len` characters. Zero-width
+    /// characters are not counted and are kept with the preceding character.
+    #[inline]
+    #[must_use]
+    pub fn parse_max_word_len(text: &'a str, max_word_len: usize) -> Self {
         Self {
+            text,
             inner: text.chars(),
+            max_word_len: max_word_len.max(1),
+            max_newlines: usize::MAX,
+            config: ParserConfig::new(&[], &[]),
+            break_at_soft_hyphens: true,
+            newlines: 0,
             _marker: PhantomData,
         }
     }
 
+    /// Uses the character classes of `config`.
+    #[inline]
+    #[must_use]
+    pub fn with_config(mut self, config: ParserConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets whether soft hyphens are returned as [`Token::Break`]s. If disabled, soft hyphens are
+    /// part of the words.
+    #[inline]
+    #[must_use]
+    pub fn break_at_soft_hyphens(mut self, break_at_soft_hyphens: bool) -> Self {
+        self.break_at_soft_hyphens = break_at_soft_hyphens;
+        self
+    }
+
+    /// Drops the newlines of a run of blank lines after the first `max_newlines` newlines.
+    ///
+    /// Lines that only contain whitespace are considered blank. The first newline is always kept,
+    /// so a limit of 0 works like a limit of 1.
+    #[inline]
+    #[must_use]
+    pub fn max_consecutive_newlines(mut self, max_newlines: usize) -> Self {
+        self.max_newlines = max_newlines.max(1);
+        self
+    }
+
     pub fn as_str(&self) -> &str {
         self.inner.as_str()
     }
 
+    /// Returns the whole parsed text, including the parts that were already parsed.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
     fn consume_string(&mut self, string: &'a str, c: char) -> &'a str {
         // pointer arithmetic to get the offset of `c` relative to `string`
         let offset = {
@@ -135,6 +376,49 @@ where
     }
 }
 
+/// Returns whether a line (a piece of text without newline characters) contains no words.
+fn is_blank_line(line: &str, config: ParserConfig) -> bool {
+    Parser::<'_, Rgb888>::parse(line)
+        .with_config(config)
+        .all(|token| !matches!(token, Token::Word(_)))
+}
+
+/// Returns the byte offset where the first `n` paragraphs of `text` end.
+///
+/// Paragraphs are separated by one or more blank lines. The returned offset points to the end of
+/// the last line of the `n`th paragraph, before its newline character. If the text contains fewer
+/// than `n` paragraphs, the end of the last paragraph is returned. Words are separated using
+/// `config`.
+pub(crate) fn paragraphs_end(text: &str, n: usize, config: ParserConfig) -> usize {
+    let mut count = 0;
+    let mut in_paragraph = false;
+    let mut end = 0;
+    let mut line_start = 0;
+
+    if n == 0 {
+        return 0;
+    }
+
+    for line in text.split('\n') {
+        if is_blank_line(line, config) {
+            if in_paragraph && count == n {
+                return end;
+            }
+            in_paragraph = false;
+        } else {
+            if !in_paragraph {
+                count += 1;
+                in_paragraph = true;
+            }
+            end = line_start + line.len();
+        }
+
+        line_start += line.len() + 1;
+    }
+
+    end
+}
+
 impl<'a, C> Iterator for Parser<'a, C>
 where
     C: PixelColor,
@@ -143,16 +427,44 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if self.newlines >= self.max_newlines {
+            self.inner = self.inner.as_str().trim_start_matches('\n').chars();
+        }
+
+        let token = self.next_token();
+        match token {
+            Some(Token::NewLine) => self.newlines += 1,
+            Some(Token::Whitespace(..)) | Some(Token::Tab) | Some(Token::CarriageReturn) => {}
+            _ => self.newlines = 0,
+        }
+
+        token
+    }
+}
+
+impl<'a, C> Parser<'a, C>
+where
+    C: PixelColor,
+{
+    fn next_token(&mut self) -> Option<Token<'a, C>> {
         let string = self.inner.as_str();
+        let config = self.config;
+        let shy_in_words = !self.break_at_soft_hyphens;
+        let is_word_char = |c| is_word_char(c, &config) || shy_in_words && c == SPEC_CHAR_SHY;
 
         if let Some(c) = self.inner.next() {
             if is_word_char(c) {
                 // find the longest consecutive slice of text for a Word token
+                let mut len = 1;
                 for c in &mut self.inner {
-                    if !is_word_char(c) {
+                    let zero_width = is_zero_width(c);
+                    if !is_word_char(c) || (len == self.max_word_len && !zero_width) {
                         let consumed = self.consume_string(string, c);
                         return Some(Token::Word(consumed));
                     }
+                    if !zero_width {
+                        len += 1;
+                    }
                 }
 
                 // consumed all the text
@@ -174,9 +486,11 @@ where
 
                     // count consecutive whitespace
                     _ => {
+                        let extra = is_extra_space_char(c, &config);
                         let mut len = 1;
                         for c in &mut self.inner {
-                            if is_space_char(c) {
+                            if is_space_char(c, &config) && is_extra_space_char(c, &config) == extra
+                            {
                                 if c != SPEC_CHAR_ZWSP {
                                     len += 1;
                                 }
@@ -201,7 +515,7 @@ where
 mod test {
     use embedded_graphics::pixelcolor::BinaryColor;
 
-    use super::{Parser, Token};
+    use super::{paragraphs_end, Parser, ParserConfig, Token};
 
     #[track_caller]
     pub fn assert_tokens(text: &str, tokens: std::vec::Vec<Token<BinaryColor>>) {
@@ -256,6 +570,22 @@ mod test {
         assert_tokens("  \u{200B} ", vec![Token::Whitespace(3, "  \u{200B} ")]);
     }
 
+    #[test]
+    fn whitespace_tokens_keep_original_characters() {
+        assert_tokens(
+            " \u{2003}\u{2009} ",
+            vec![Token::Whitespace(4, " \u{2003}\u{2009} ")],
+        );
+        assert_tokens(
+            " \t ",
+            vec![
+                Token::Whitespace(1, " "),
+                Token::Tab,
+                Token::Whitespace(1, " "),
+            ],
+        );
+    }
+
     #[test]
     fn parse_multibyte_last() {
         assert_tokens("test😅", vec![Token::Word("test😅")]);
@@ -278,4 +608,139 @@ mod test {
             vec![Token::Word("foo"), Token::Break("-"), Token::Word("bar")],
         );
     }
+
+    #[test]
+    fn soft_hyphens_can_be_part_of_words() {
+        let tokens = Parser::parse("foo\u{AD}bar \u{AD}")
+            .break_at_soft_hyphens(false)
+            .collect::<Vec<Token<BinaryColor>>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("foo\u{AD}bar"),
+                Token::Whitespace(1, " "),
+                Token::Word("\u{AD}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_max_word_len() {
+        let text = "0123456789".repeat(10);
+        let words = Parser::<BinaryColor>::parse_max_word_len(&text, 10).collect::<Vec<_>>();
+
+        assert_eq!(words.len(), 10);
+        assert!(words
+            .iter()
+            .all(|token| *token == Token::Word("0123456789")));
+
+        assert_tokens_max_len(
+            "abc\u{fe0f}defg hi",
+            3,
+            vec![
+                Token::Word("abc\u{fe0f}"),
+                Token::Word("def"),
+                Token::Word("g"),
+                Token::Whitespace(1, " "),
+                Token::Word("hi"),
+            ],
+        );
+    }
+
+    #[track_caller]
+    fn assert_tokens_max_len(text: &str, n: usize, tokens: std::vec::Vec<Token<BinaryColor>>) {
+        assert_eq!(
+            Parser::parse_max_word_len(text, n).collect::<std::vec::Vec<Token<BinaryColor>>>(),
+            tokens
+        )
+    }
+
+    #[test]
+    fn paragraph_ends() {
+        let text = "first\nparagraph\n\nsecond\n \t\n\nthird";
+        let end = |text, n| paragraphs_end(text, n, ParserConfig::default());
+
+        assert_eq!(end(text, 0), 0);
+        assert_eq!(&text[..end(text, 1)], "first\nparagraph");
+        assert_eq!(&text[..end(text, 2)], "first\nparagraph\n\nsecond");
+        assert_eq!(end(text, 3), text.len());
+        assert_eq!(end(text, 4), text.len());
+
+        // Leading blank lines are not paragraphs.
+        assert_eq!(end("\n\nfoo\n\nbar", 1), 5);
+    }
+
+    #[test]
+    fn paragraph_ends_use_parser_config() {
+        let text = "first\n\u{b7}\nsecond";
+
+        assert_eq!(paragraphs_end(text, 1, ParserConfig::default()), text.len());
+        assert_eq!(
+            paragraphs_end(text, 1, ParserConfig::new(&['\u{b7}'], &[])),
+            "first".len()
+        );
+    }
+
+    #[test]
+    fn consecutive_newlines_are_limited() {
+        let tokens = |text, max| {
+            Parser::parse(text)
+                .max_consecutive_newlines(max)
+                .collect::<std::vec::Vec<Token<BinaryColor>>>()
+        };
+
+        assert_eq!(
+            tokens("a\n\n\n\nb\nc", 2),
+            vec![
+                Token::Word("a"),
+                Token::NewLine,
+                Token::NewLine,
+                Token::Word("b"),
+                Token::NewLine,
+                Token::Word("c"),
+            ]
+        );
+
+        // Whitespace doesn't end a run of blank lines.
+        assert_eq!(
+            tokens("a\n \n\nb", 2),
+            vec![
+                Token::Word("a"),
+                Token::NewLine,
+                Token::Whitespace(1, " "),
+                Token::NewLine,
+                Token::Word("b"),
+            ]
+        );
+
+        // The first newline is always kept, so words are not joined.
+        assert_eq!(
+            tokens("a\n\nb", 0),
+            vec![Token::Word("a"), Token::NewLine, Token::Word("b")]
+        );
+    }
+
+    #[test]
+    fn extra_characters_are_classified_by_config() {
+        let tokens = |text| {
+            Parser::parse(text)
+                .with_config(ParserConfig::new(&['\u{b7}', '\n'], &['\u{2009}', '\t']))
+                .collect::<std::vec::Vec<Token<BinaryColor>>>()
+        };
+
+        assert_eq!(
+            tokens("a\u{b7}\u{b7} b\u{2009}c\td\ne"),
+            vec![
+                Token::Word("a"),
+                Token::Whitespace(2, "\u{b7}\u{b7}"),
+                Token::Whitespace(1, " "),
+                Token::Word("b\u{2009}c"),
+                Token::Tab,
+                Token::Word("d"),
+                Token::NewLine,
+                Token::Word("e"),
+            ]
+        );
+    }
 }
@@ -11,6 +11,9 @@
 //! > *Note:* if `embedded-text` fails to parse an escape sequence, it will ignore the `\x1b` character
 //! and display the rest as normal text.
 //!
+//! Valid escape sequences that are not supported are not displayed, they are passed to other
+//! plugins as `Token::EscapeSequence` tokens.
+//!
 //! All escape sequences start with the `\x1b[` sequence, where `\x1b` is the ASCII `escape`
 //! character. `embedded-text` supports a subset of the `SGR` parameters, which are numeric codes
 //! with specific functions, followed by a number of parameters and end with the `m` character.
@@ -74,7 +77,28 @@
 //!
 //! `embedded-text` supports all color types that are included in `embedded-graphics`.
 //!
-//! If you wish to use a different color type, the types needs to implement `From<Rgb888>`.
+//! If you wish to use a different color type, the types needs to implement `From<Rgb888>`. This
+//! is the only conversion the plugin needs, and the text box uses the same conversion for its own
+//! colors. The conversions of `embedded-graphics` can help implementing it, for example the
+//! luma based conversion to grayscale colors:
+//!
+//! ```rust
+//! use embedded_graphics::pixelcolor::{raw::RawU2, Gray2, GrayColor, PixelColor, Rgb888};
+//!
+//! /// A 2 bit grayscale display color.
+//! #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+//! struct Lcd(u8);
+//!
+//! impl PixelColor for Lcd {
+//!     type Raw = RawU2;
+//! }
+//!
+//! impl From<Rgb888> for Lcd {
+//!     fn from(color: Rgb888) -> Self {
+//!         Lcd(Gray2::from(color).luma())
+//!     }
+//! }
+//! ```
 //!
 //! Other text styling options
 //! --------------------------
@@ -105,37 +129,211 @@
 //! You have the following options:
 //!
 //!  - Move the cursor forward `<n>` characters: `\x1b[<n>C`. This command will stop at the end of
-//!    line, so you can use it to simulate a highlighted line, for example.
+//!    line, so you can use it to simulate a highlighted line, for example. Use
+//!    [`Ansi::cursor_overflow`] to continue the movement in the next line instead.
 //!    *Note:* Moving the cursor *forward* fills the line with the background color. If you want to
 //!    avoid this, make sure to reset the background color before moving the cursor!
 //!  - Move the cursor backward `<n>` characters: `\x1b[<n>D`. This command will stop at the start
 //!    of line.
+//!
+//! Hyperlinks
+//! ----------
+//!
+//! `embedded-text` understands the `OSC 8` hyperlink sequences: `\x1b]8;;<url>\x07` starts a
+//! link and `\x1b]8;;\x07` ends it. The sequences themselves are not displayed, and the text
+//! between them is rendered normally. Use [`Ansi::underline_links`] to underline links.
+//!
+//! To find out where links are displayed, for example to make them tappable, create the plugin
+//! using [`Ansi::with_links`]. After the text box is drawn, [`Ansi::links`] returns the area
+//! covered by each link. A link that is wrapped into multiple lines has one area per line.
+//!
+//! Inspecting escape sequences
+//! ---------------------------
+//!
+//! The [`Ansi`] plugin turns escape sequences into style changes and cursor movements. To look at
+//! the escape sequences themselves, for example to validate text before displaying it, iterate over
+//! the tokens of the text using [`AnsiTokens`]. Unlike the plugin, this iterator yields every
+//! escape sequence as a single `EscapeSequence` token, including the ones `embedded-text` doesn't
+//! support.
 
 use ansi_parser::AnsiSequence;
-use embedded_graphics::{pixelcolor::Rgb888, prelude::PixelColor};
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    pixelcolor::Rgb888,
+    prelude::PixelColor,
+    primitives::Rectangle,
+    text::{
+        renderer::{CharacterStyle, TextRenderer},
+        DecorationColor,
+    },
+};
 
 use crate::{
-    parser::Token,
-    plugin::{ansi::utils::try_parse_sgr, Plugin},
+    parser::{ChangeTextStyle, Parser, Token},
+    plugin::{
+        ansi::utils::{parse_hyperlink, try_parse_sgr},
+        Plugin,
+    },
+    rendering::{cursor::Cursor, TextBoxProperties},
 };
 
 mod utils;
 
 /// Ansi sequence parser plugin.
+///
+/// `LINKS` is the maximum number of link areas the plugin records, see [`Ansi::with_links`].
 #[derive(Clone)]
-pub struct Ansi<'a, C: PixelColor> {
+pub struct Ansi<'a, C: PixelColor, const LINKS: usize = 0> {
     carry: Option<Token<'a, C>>,
+    link_color: Option<C>,
+    underline: DecorationColor<C>,
+    link: Option<&'a str>,
+    links: [(Rectangle, &'a str); LINKS],
+    link_count: usize,
+    cursor_overflow: CursorOverflow,
+}
+
+/// Specifies what happens when the cursor is moved forward past the end of a line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CursorOverflow {
+    /// The cursor stops at the end of the line.
+    Clamp,
+
+    /// The rest of the movement continues at the start of the next line.
+    ///
+    /// The cursor is moved by whole characters, so the movement wraps when the next character
+    /// wouldn't fit into the line.
+    Wrap,
 }
 
 impl<C: PixelColor> Ansi<'_, C> {
     /// Returns a new plugin object.
     #[inline]
     pub const fn new() -> Self {
-        Self { carry: None }
+        Self::with_links()
+    }
+}
+
+impl<'a, C: PixelColor, const LINKS: usize> Ansi<'a, C, LINKS> {
+    /// Returns a new plugin object that records the areas of at most `LINKS` links.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_graphics::{
+    /// #     mock_display::MockDisplay,
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// #     prelude::*,
+    /// #     primitives::Rectangle,
+    /// # };
+    /// use embedded_text::{plugin::ansi::Ansi, TextBox};
+    ///
+    /// let text_box = TextBox::new(
+    ///     "see \x1b]8;;https://example.com\x07example\x1b]8;;\x07",
+    ///     Rectangle::new(Point::zero(), Size::new(72, 9)),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// )
+    /// .add_plugin(Ansi::<_, 4>::with_links());
+    ///
+    /// text_box.draw(&mut MockDisplay::new())?;
+    ///
+    /// let ansi = text_box.take_plugins().object;
+    /// let links = ansi.links().collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     links,
+    ///     [(
+    ///         Rectangle::new(Point::new(24, 0), Size::new(42, 9)),
+    ///         "https://example.com"
+    ///     )]
+    /// );
+    /// # Ok::<(), core::convert::Infallible>(())
+    /// ```
+    #[inline]
+    pub const fn with_links() -> Self {
+        Self {
+            carry: None,
+            link_color: None,
+            underline: DecorationColor::None,
+            link: None,
+            links: [(Rectangle::zero(), ""); LINKS],
+            link_count: 0,
+            cursor_overflow: CursorOverflow::Clamp,
+        }
+    }
+
+    /// Sets what happens when the cursor is moved forward past the end of a line.
+    ///
+    /// The default is [`CursorOverflow::Clamp`].
+    #[inline]
+    pub const fn cursor_overflow(mut self, overflow: CursorOverflow) -> Self {
+        self.cursor_overflow = overflow;
+
+        self
+    }
+
+    /// Underlines links using the given color.
+    #[inline]
+    pub const fn underline_links(mut self, color: C) -> Self {
+        self.link_color = Some(color);
+
+        self
+    }
+
+    /// Returns the areas covered by links, and their URLs.
+    ///
+    /// The areas are recorded while the text box is drawn. If the text contains more link areas
+    /// than the capacity of the plugin, the rest of them are not recorded.
+    #[inline]
+    pub fn links(&self) -> impl Iterator<Item = (Rectangle, &'a str)> + '_ {
+        self.links[..self.link_count].iter().copied()
+    }
+
+    /// Starts or ends a link and returns the style change that applies the link style.
+    fn set_link(&mut self, link: Option<&'a str>) -> Option<ChangeTextStyle<C>> {
+        let was_link = self.link.is_some();
+        self.link = link;
+
+        match (self.link_color, was_link, link.is_some()) {
+            (Some(color), false, true) => {
+                Some(ChangeTextStyle::Underline(DecorationColor::Custom(color)))
+            }
+            (Some(_), true, false) => Some(ChangeTextStyle::Underline(self.underline)),
+            _ => None,
+        }
+    }
+
+    /// Keeps track of the underline style outside of links.
+    fn track_underline(&mut self, change: ChangeTextStyle<C>) {
+        match change {
+            ChangeTextStyle::Reset => self.underline = DecorationColor::None,
+            ChangeTextStyle::Underline(color) => self.underline = color,
+            _ => {}
+        }
+    }
+
+    /// Records the area of a rendered piece of link text.
+    fn record_link(&mut self, url: &'a str, bounds: Rectangle) {
+        if let Some((last_bounds, last_url)) = self.links[..self.link_count].last_mut() {
+            let last_end = last_bounds.top_left.x + last_bounds.size.width as i32;
+            if *last_url == url
+                && last_bounds.top_left.y == bounds.top_left.y
+                && last_end == bounds.top_left.x
+            {
+                // Continuation of the link in the same line.
+                last_bounds.size.width += bounds.size.width;
+                return;
+            }
+        }
+
+        if let Some(slot) = self.links.get_mut(self.link_count) {
+            *slot = (bounds, url);
+            self.link_count += 1;
+        }
     }
 }
 
-impl<'a, C: PixelColor + From<Rgb888>> Plugin<'a, C> for Ansi<'a, C> {
+impl<'a, C: PixelColor + From<Rgb888>, const LINKS: usize> Plugin<'a, C> for Ansi<'a, C, LINKS> {
     fn next_token(
         &mut self,
         mut next_token: impl FnMut() -> Option<Token<'a, C>>,
@@ -147,17 +345,31 @@ impl<'a, C: PixelColor + From<Rgb888>> Plugin<'a, C> for Ansi<'a, C> {
         };
 
         if let Some(Token::Word(text)) = token {
+            if let Some((url, rest)) = parse_hyperlink(text) {
+                if !rest.is_empty() {
+                    self.carry = Some(Token::Word(rest));
+                }
+
+                let link = if url.is_empty() { None } else { Some(url) };
+                return match self.set_link(link) {
+                    Some(change) => Some(Token::ChangeTextStyle(change)),
+                    None => self.next_token(next_token),
+                };
+            }
+
             let mut chars = text.char_indices();
 
             match chars.find(|(_, c)| *c == '\u{1b}') {
                 Some((0, _)) => match ansi_parser::parse_escape(text) {
                     Ok((string, output)) => {
+                        let sequence = &text[..text.len() - string.len()];
                         let new_token = match output {
                             AnsiSequence::CursorForward(chars) => {
                                 self.carry = Some(Token::Word(string));
                                 Token::MoveCursor {
                                     chars: chars as i32,
                                     draw_background: true,
+                                    wrap: self.cursor_overflow == CursorOverflow::Wrap,
                                 }
                             }
                             AnsiSequence::CursorBackward(chars) => {
@@ -165,16 +377,25 @@ impl<'a, C: PixelColor + From<Rgb888>> Plugin<'a, C> for Ansi<'a, C> {
                                 Token::MoveCursor {
                                     chars: -(chars as i32),
                                     draw_background: true,
+                                    wrap: self.cursor_overflow == CursorOverflow::Wrap,
                                 }
                             }
                             AnsiSequence::SetGraphicsMode(sgr) => try_parse_sgr(&sgr)
                                 .map(|sgr| {
                                     self.carry = Some(Token::Word(string));
-                                    Token::ChangeTextStyle(sgr.into())
+                                    let change = sgr.into();
+                                    self.track_underline(change);
+                                    Token::ChangeTextStyle(change)
                                 })
-                                .or_else(|| self.next_token(next_token))?,
+                                .unwrap_or_else(|| {
+                                    self.carry = Some(Token::Word(string));
+                                    Token::EscapeSequence(sequence)
+                                }),
 
-                            _ => self.next_token(next_token)?,
+                            _ => {
+                                self.carry = Some(Token::Word(string));
+                                Token::EscapeSequence(sequence)
+                            }
                         };
 
                         Some(new_token)
@@ -203,6 +424,118 @@ impl<'a, C: PixelColor + From<Rgb888>> Plugin<'a, C> for Ansi<'a, C> {
             token
         }
     }
+
+    #[inline]
+    fn post_render<T, D>(
+        &mut self,
+        _draw_target: &mut D,
+        _character_style: &T,
+        text: Option<&str>,
+        bounds: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        T: TextRenderer<Color = C>,
+        D: DrawTarget<Color = C>,
+    {
+        if let (Some(url), Some(text)) = (self.link, text) {
+            if !bounds.is_zero_sized() && !matches!(text, "\n" | "\r") {
+                self.record_link(url, bounds);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn on_start_render<S: CharacterStyle + TextRenderer>(
+        &mut self,
+        _cursor: &mut Cursor,
+        _props: &TextBoxProperties<'_, S>,
+    ) {
+        self.link = None;
+        self.underline = DecorationColor::None;
+        self.link_count = 0;
+    }
+}
+
+/// Iterator over the tokens of a piece of text, with escape sequences exposed.
+///
+/// Escape sequences are returned as `Token::EscapeSequence` tokens, the rest of the text is split
+/// into tokens the same way the text box does.
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_graphics::pixelcolor::Rgb888;
+/// use embedded_text::{plugin::ansi::AnsiTokens, Token};
+///
+/// let escapes = AnsiTokens::<Rgb888>::new("a\x1b[1mb")
+///     .filter(|token| matches!(token, Token::EscapeSequence(_)))
+///     .count();
+///
+/// assert_eq!(escapes, 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AnsiTokens<'a, C: PixelColor> {
+    parser: Parser<'a, C>,
+    carry: Option<&'a str>,
+}
+
+impl<'a, C: PixelColor> AnsiTokens<'a, C> {
+    /// Creates a new iterator over the tokens of `text`.
+    #[inline]
+    #[must_use]
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            parser: Parser::parse(text),
+            carry: None,
+        }
+    }
+}
+
+impl<'a, C: PixelColor> Iterator for AnsiTokens<'a, C> {
+    type Item = Token<'a, C>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = match self.carry.take() {
+            Some(text) => text,
+            None => match self.parser.next()? {
+                Token::Word(text) => text,
+                token => return Some(token),
+            },
+        };
+
+        match text.find('\u{1b}') {
+            Some(0) => {
+                let escape_len = match parse_hyperlink(text) {
+                    Some((_, rest)) => Some(text.len() - rest.len()),
+                    None => ansi_parser::parse_escape(text)
+                        .ok()
+                        .map(|(rest, _)| text.len() - rest.len()),
+                };
+
+                let (token, rest) = match escape_len {
+                    Some(len) => (Token::EscapeSequence(&text[..len]), &text[len..]),
+                    // Not a valid sequence, the escape character is returned as a word.
+                    None => (Token::Word(&text[..1]), &text[1..]),
+                };
+
+                if !rest.is_empty() {
+                    self.carry = Some(rest);
+                }
+                Some(token)
+            }
+
+            Some(idx) => {
+                let (word, rest) = text.split_at(idx);
+                self.carry = Some(rest);
+                Some(Token::Word(word))
+            }
+
+            None => Some(Token::Word(text)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -216,14 +549,19 @@ mod test {
         pixelcolor::{BinaryColor, Rgb888},
         prelude::{Point, Size},
         primitives::Rectangle,
+        text::{Baseline, Text},
         Drawable,
     };
 
     use crate::{
         alignment::{HorizontalAlignment, VerticalAlignment},
-        parser::{ChangeTextStyle, Parser},
-        plugin::{ansi::Ansi, PluginWrapper},
+        parser::{ChangeTextStyle, Parser, Token},
+        plugin::{
+            ansi::{Ansi, AnsiTokens, CursorOverflow},
+            PluginWrapper,
+        },
         rendering::{
+            bidi::Isolates,
             cursor::LineCursor,
             line::{LineRenderState, StyledLineRenderer},
             line_iter::{
@@ -236,6 +574,50 @@ mod test {
         TextBox,
     };
 
+    #[test]
+    fn ansi_tokens_expose_escape_sequences() {
+        let tokens = AnsiTokens::<BinaryColor>::new("a\x1b[1mb").collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Word("a"),
+                Token::EscapeSequence("\x1b[1m"),
+                Token::Word("b")
+            ]
+        );
+    }
+
+    #[test]
+    fn ansi_tokens_expose_hyperlinks() {
+        let tokens =
+            AnsiTokens::<BinaryColor>::new("\x1b]8;;http://x\x07a\x1b]8;;\x07").collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::EscapeSequence("\x1b]8;;http://x\x07"),
+                Token::Word("a"),
+                Token::EscapeSequence("\x1b]8;;\x07")
+            ]
+        );
+    }
+
+    #[test]
+    fn ansi_tokens_return_invalid_escape_as_word() {
+        let tokens = AnsiTokens::<BinaryColor>::new("\x1bfoo bar").collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Word("\x1b"),
+                Token::Word("foo"),
+                Token::Whitespace(1, " "),
+                Token::Word("bar")
+            ]
+        );
+    }
+
     #[test]
 
     fn test_measure_line_cursor_back() {
@@ -322,6 +704,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn unsupported_escape_sequence_is_a_token() {
+        let mut parser = Parser::parse("foo\x1b[2Jbar baz");
+        let mw = PluginWrapper::new(Ansi::<Rgb888>::new());
+
+        let mut tokens = Vec::new();
+        while let Some(token) = mw.peek_token(&mut parser) {
+            tokens.push(token);
+            mw.consume_peeked_token();
+        }
+        assert_eq!(
+            tokens,
+            [
+                Token::Word("foo"),
+                Token::EscapeSequence("\x1b[2J"),
+                Token::Word("bar"),
+                Token::Whitespace(1, " "),
+                Token::Word("baz"),
+            ]
+        );
+
+        // The sequence is invisible, and it doesn't end the word.
+        let mut parser = Parser::parse("foo\x1b[2Jbar baz");
+        assert_line_elements(
+            &mut parser,
+            7,
+            &[
+                RenderElement::string("foo", 18),
+                RenderElement::string("bar", 18),
+                RenderElement::Space(1, false),
+            ],
+            &mw,
+        );
+    }
+
     #[test]
     fn ansi_cursor_backwards() {
         let mut display = MockDisplay::new();
@@ -347,12 +764,17 @@ mod test {
             parser,
             text_renderer,
             end_type: LineEndType::EndOfText,
+            isolates: Isolates::new(),
             plugin: &plugin,
+            reveal: None,
+            vertical_offset: 0,
         };
         StyledLineRenderer {
             cursor,
             state: &mut state,
             style: &style,
+            avoid_broken_word: false,
+            line_area: None,
         }
         .draw(&mut display)
         .unwrap();
@@ -370,6 +792,61 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn cursor_forward_overflow() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 2));
+
+        let draw = |overflow| {
+            let mut display = MockDisplay::new();
+            TextBox::new("ab\x1b[9Cc", bounds, character_style)
+                .add_plugin(Ansi::new().cursor_overflow(overflow))
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+        let expected = |c_position| {
+            let mut display = MockDisplay::new();
+            Text::with_baseline("ab", Point::zero(), character_style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            Text::with_baseline("c", c_position, character_style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+
+        // The cursor stops at the end of the first line, and "c" is wrapped.
+        draw(CursorOverflow::Clamp).assert_eq(&expected(Point::new(0, 9)));
+
+        // 4 characters fit into the first line, the cursor moves 5 more in the second line.
+        draw(CursorOverflow::Wrap).assert_eq(&expected(Point::new(30, 9)));
+    }
+
+    #[test]
+    fn wrapped_cursor_movement_is_measured() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .height_mode(HeightMode::FitToText)
+            .build();
+
+        let height = |overflow| {
+            TextBox::with_textbox_style(
+                "ab\x1b[9C",
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 1)),
+                character_style,
+                style,
+            )
+            .add_plugin(Ansi::new().cursor_overflow(overflow))
+            .bounds
+            .size
+            .height
+        };
+
+        assert_eq!(height(CursorOverflow::Clamp), 9);
+        assert_eq!(height(CursorOverflow::Wrap), 18);
+    }
+
     #[test]
     fn ansi_style_measure() {
         let text = "Some \x1b[4mstylish\x1b[24m multiline text that expands the widget vertically";
@@ -443,4 +920,124 @@ mod test {
             "############                                    ",
         ]);
     }
+
+    #[test]
+    fn hyperlinks_are_invisible() {
+        let render = |text| {
+            let mut display = MockDisplay::new();
+            TextBox::new(
+                text,
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            )
+            .add_plugin(Ansi::new())
+            .draw(&mut display)
+            .unwrap();
+            display
+        };
+
+        render("a \x1b]8;;http://x\x07bc de\x1b]8;;\x1b\\ f").assert_eq(&render("a bc de f"));
+    }
+
+    #[test]
+    fn wrapped_hyperlink_has_an_area_per_line() {
+        let text = "a \x1b]8;id=1;http://x\x07bc de\x1b]8;;\x07 f";
+        let text_box = TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(Ansi::<_, 4>::with_links());
+
+        text_box.draw(&mut MockDisplay::new()).unwrap();
+
+        let ansi = text_box.take_plugins().object;
+        let links = ansi.links().collect::<Vec<_>>();
+
+        assert_eq!(
+            links,
+            [
+                (
+                    Rectangle::new(Point::new(12, 0), Size::new(12, 9)),
+                    "http://x"
+                ),
+                (
+                    Rectangle::new(Point::new(0, 9), Size::new(12, 9)),
+                    "http://x"
+                ),
+            ]
+        );
+
+        // The URL is a part of the source text.
+        assert!(text
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&links[0].1.as_ptr()));
+    }
+
+    #[test]
+    fn hyperlinks_can_be_underlined() {
+        let mut display = MockDisplay::new();
+        TextBox::new(
+            "a\x1b]8;;http://x\x07b\x1b]8;;\x07c",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 1)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(Ansi::new().underline_links(BinaryColor::On))
+        .draw(&mut display)
+        .unwrap();
+
+        display.assert_pattern(&[
+            "                 ",
+            "       #         ",
+            "       #         ",
+            "  ###  ###    ###",
+            " #  #  #  #  #   ",
+            " #  #  #  #  #   ",
+            "  ###  ###    ###",
+            "                 ",
+            "      ######     ",
+        ]);
+    }
+
+    #[test]
+    fn custom_color_type_with_rgb_conversion() {
+        use embedded_graphics::{
+            geometry::Dimensions,
+            pixelcolor::{raw::RawU2, Gray2, GrayColor, PixelColor},
+            primitives::PointsIter,
+        };
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        struct Lcd(u8);
+
+        impl PixelColor for Lcd {
+            type Raw = RawU2;
+        }
+
+        impl From<Rgb888> for Lcd {
+            fn from(color: Rgb888) -> Self {
+                Lcd(Gray2::from(color).luma())
+            }
+        }
+
+        let mut display = MockDisplay::new();
+        TextBox::new(
+            "\x1b[92mI",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 1, 1)),
+            MonoTextStyle::new(&FONT_6X9, Lcd(3)),
+        )
+        .add_plugin(Ansi::new())
+        .draw(&mut display)
+        .unwrap();
+
+        // Bright green (22, 198, 12) has a luma of 124, which is the 2nd of 4 gray levels.
+        let colors = display
+            .bounding_box()
+            .points()
+            .filter_map(|p| display.get_pixel(p))
+            .collect::<Vec<_>>();
+        assert!(!colors.is_empty());
+        assert!(colors.iter().all(|&c| c == Lcd(1)));
+    }
 }
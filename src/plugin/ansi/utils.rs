@@ -35,6 +35,25 @@ pub(crate) enum Sgr {
     DefaultBackgroundColor,
 }
 
+/// Parses an `OSC 8` hyperlink sequence at the start of `text`.
+///
+/// Returns the URL and the rest of the text. An empty URL ends the current link.
+pub(crate) fn parse_hyperlink(text: &str) -> Option<(&str, &str)> {
+    let body = text.strip_prefix("\u{1b}]8;")?;
+
+    // The sequence is terminated by either BEL or ST.
+    let (body, rest) = match body.find(['\u{7}', '\u{1b}']) {
+        Some(idx) if body[idx..].starts_with('\u{7}') => (&body[..idx], &body[idx + 1..]),
+        Some(idx) if body[idx..].starts_with("\u{1b}\\") => (&body[..idx], &body[idx + 2..]),
+        _ => return None,
+    };
+
+    // The URL is preceded by optional parameters.
+    let (_params, url) = body.split_once(';')?;
+
+    Some((url, rest))
+}
+
 impl<C: PixelColor + From<Rgb888>> From<Sgr> for ChangeTextStyle<C> {
     #[inline]
     fn from(sgr: Sgr) -> Self {
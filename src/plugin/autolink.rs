@@ -0,0 +1,385 @@
+//! Automatic link detection.
+//!
+//! The [`AutoLink`] plugin finds URLs in the text and displays them underlined, using a link
+//! color. A URL starts at the beginning of a word with `http://`, `https://` or `www.`, and ends
+//! at the next whitespace character. URLs that don't fit into a line are broken like any other
+//! word, and the styling continues on the next line.
+//!
+//! While rendering, the plugin records the area of every displayed part of a URL. These areas
+//! can be used to find the link under a touch point after the text box is drawn.
+//!
+//! ```rust
+//! # use embedded_graphics::{
+//! #     mock_display::MockDisplay,
+//! #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+//! #     pixelcolor::Rgb888,
+//! #     prelude::*,
+//! #     primitives::Rectangle,
+//! # };
+//! use embedded_text::{plugin::autolink::AutoLink, TextBox};
+//! use object_chain::ChainElement;
+//!
+//! let text = "see http://x.com now";
+//! let text_box = TextBox::new(
+//!     text,
+//!     Rectangle::new(Point::zero(), Size::new(120, 9)),
+//!     MonoTextStyle::new(&FONT_6X9, Rgb888::WHITE),
+//! )
+//! .add_plugin(AutoLink::<_, 4>::new(Rgb888::BLUE, Rgb888::WHITE));
+//!
+//! # let mut display = MockDisplay::new();
+//! # display.set_allow_overdraw(true);
+//! text_box.draw(&mut display).unwrap();
+//!
+//! let (links, _) = text_box.take_plugins().pop();
+//! let offset = links.link_at(Point::new(30, 4)).unwrap();
+//! assert_eq!(text[offset..].split_whitespace().next(), Some("http://x.com"));
+//! ```
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    prelude::{PixelColor, Point},
+    primitives::Rectangle,
+    text::{
+        renderer::{CharacterStyle, TextRenderer},
+        DecorationColor,
+    },
+};
+
+use crate::{
+    parser::{ChangeTextStyle, Token},
+    plugin::Plugin,
+    rendering::{cursor::Cursor, TextBoxProperties},
+};
+
+/// Prefixes that start a URL.
+const PREFIXES: [&str; 3] = ["http://", "https://", "www."];
+
+fn is_link(word: &str) -> bool {
+    PREFIXES.iter().any(|prefix| word.starts_with(prefix))
+}
+
+/// The area of a displayed part of a link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LinkArea {
+    /// Byte offset of the first character of the link in the text.
+    pub offset: usize,
+
+    /// The area the part of the link is displayed in.
+    pub bounds: Rectangle,
+}
+
+/// URL detection and styling plugin.
+///
+/// Links are displayed in `link_color`, underlined. After a link, the text color is set to
+/// `text_color` and the underline is removed, so other style changes made by a link are not
+/// restored.
+///
+/// The areas of at most `N` displayed link parts are recorded, further parts are styled but not
+/// recorded.
+#[derive(Clone, Debug)]
+pub struct AutoLink<'a, C, const N: usize> {
+    link_color: C,
+    text_color: C,
+
+    /// Tokens to return before reading the next token.
+    pending: [Option<Token<'a, C>>; 2],
+    after_whitespace: bool,
+    in_link: bool,
+
+    /// The address and length of the rendered text.
+    text: Option<(usize, usize)>,
+    /// The offset of the link that is being rendered.
+    rendered_link: Option<usize>,
+    rendered_after_whitespace: bool,
+    areas: [Option<LinkArea>; N],
+    area_count: usize,
+}
+
+impl<'a, C, const N: usize> AutoLink<'a, C, N>
+where
+    C: PixelColor,
+{
+    /// Creates a new link detection plugin.
+    #[inline]
+    pub const fn new(link_color: C, text_color: C) -> Self {
+        Self {
+            link_color,
+            text_color,
+            pending: [None, None],
+            after_whitespace: true,
+            in_link: false,
+            text: None,
+            rendered_link: None,
+            rendered_after_whitespace: true,
+            areas: [None; N],
+            area_count: 0,
+        }
+    }
+
+    /// Returns the areas of the link parts displayed by the last render.
+    #[inline]
+    pub fn areas(&self) -> impl Iterator<Item = LinkArea> + '_ {
+        self.areas[..self.area_count].iter().flatten().copied()
+    }
+
+    /// Returns the byte offset of the link that is displayed at the given point.
+    #[inline]
+    pub fn link_at(&self, point: Point) -> Option<usize> {
+        self.areas()
+            .find(|area| area.bounds.contains(point))
+            .map(|area| area.offset)
+    }
+
+    /// Returns the byte offset of a part of the text.
+    fn offset_of(&self, piece: &str) -> Option<usize> {
+        let (start, len) = self.text?;
+        let offset = (piece.as_ptr() as usize).checked_sub(start)?;
+
+        (offset < len).then(|| offset)
+    }
+
+    fn record(&mut self, area: LinkArea) {
+        let areas = &mut self.areas[..self.area_count];
+        for existing in areas.iter_mut().flatten() {
+            let same_line = existing.offset == area.offset
+                && existing.bounds.top_left.y == area.bounds.top_left.y
+                && existing.bounds.size.height == area.bounds.size.height;
+            if !same_line {
+                continue;
+            }
+
+            let existing_end = existing.bounds.top_left.x + existing.bounds.size.width as i32;
+            let area_end = area.bounds.top_left.x + area.bounds.size.width as i32;
+            if area.bounds.top_left.x <= existing_end && existing.bounds.top_left.x <= area_end {
+                // Overlapping or adjacent parts of the same link are merged.
+                let left = existing.bounds.top_left.x.min(area.bounds.top_left.x);
+                let right = existing_end.max(area_end);
+                existing.bounds.top_left.x = left;
+                existing.bounds.size.width = (right - left) as u32;
+                return;
+            }
+        }
+
+        if let Some(slot) = self.areas.get_mut(self.area_count) {
+            *slot = Some(area);
+            self.area_count += 1;
+        }
+    }
+}
+
+impl<'a, C, const N: usize> Plugin<'a, C> for AutoLink<'a, C, N>
+where
+    C: PixelColor,
+{
+    #[inline]
+    fn next_token(
+        &mut self,
+        mut next_token: impl FnMut() -> Option<Token<'a, C>>,
+    ) -> Option<Token<'a, C>> {
+        if let Some(token) = self.pending.iter_mut().find_map(Option::take) {
+            return Some(token);
+        }
+
+        let token = next_token();
+        match token {
+            Some(Token::Word(word)) if self.after_whitespace && is_link(word) => {
+                self.after_whitespace = false;
+                self.in_link = true;
+                self.pending = [
+                    Some(Token::ChangeTextStyle(ChangeTextStyle::Underline(
+                        DecorationColor::TextColor,
+                    ))),
+                    token,
+                ];
+
+                Some(Token::ChangeTextStyle(ChangeTextStyle::TextColor(Some(
+                    self.link_color,
+                ))))
+            }
+
+            Some(Token::Word(_)) | Some(Token::Break(_)) => {
+                self.after_whitespace = false;
+                token
+            }
+
+            Some(Token::Whitespace(_, _))
+            | Some(Token::Tab)
+            | Some(Token::NewLine)
+            | Some(Token::CarriageReturn) => {
+                self.after_whitespace = true;
+                if !self.in_link {
+                    return token;
+                }
+
+                self.in_link = false;
+                self.pending = [
+                    Some(Token::ChangeTextStyle(ChangeTextStyle::Underline(
+                        DecorationColor::None,
+                    ))),
+                    token,
+                ];
+
+                Some(Token::ChangeTextStyle(ChangeTextStyle::TextColor(Some(
+                    self.text_color,
+                ))))
+            }
+
+            token => token,
+        }
+    }
+
+    #[inline]
+    fn post_render<T, D>(
+        &mut self,
+        _draw_target: &mut D,
+        _character_style: &T,
+        text: Option<&str>,
+        bounds: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        T: TextRenderer<Color = C>,
+        D: DrawTarget<Color = C>,
+    {
+        let text = match text {
+            Some(text) if !text.is_empty() => text,
+            // Cursor movements don't end links.
+            _ => return Ok(()),
+        };
+
+        if text.contains(char::is_whitespace) {
+            self.rendered_link = None;
+            self.rendered_after_whitespace = true;
+            return Ok(());
+        }
+
+        if self.rendered_after_whitespace && is_link(text) {
+            self.rendered_link = self.offset_of(text);
+        }
+        self.rendered_after_whitespace = false;
+
+        if let Some(offset) = self.rendered_link {
+            if bounds.size.width > 0 {
+                self.record(LinkArea { offset, bounds });
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn on_start_render<S: CharacterStyle + TextRenderer>(
+        &mut self,
+        _cursor: &mut Cursor,
+        props: &TextBoxProperties<'_, S>,
+    ) {
+        self.text = Some((props.text.as_ptr() as usize, props.text.len()));
+        self.rendered_link = None;
+        self.rendered_after_whitespace = true;
+        self.areas = [None; N];
+        self.area_count = 0;
+    }
+
+    #[inline]
+    fn on_rendering_finished(&mut self) {
+        self.text = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle, MonoTextStyleBuilder},
+        pixelcolor::Rgb888,
+        prelude::*,
+        primitives::Rectangle,
+        text::{Baseline, Text},
+    };
+
+    use object_chain::ChainElement;
+
+    use super::{AutoLink, LinkArea};
+    use crate::{utils::test::size_for, TextBox};
+
+    fn render(text: &str, width: u32) -> (MockDisplay<Rgb888>, Vec<LinkArea>) {
+        let text_box = TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, width, 4)),
+            MonoTextStyle::new(&FONT_6X9, Rgb888::WHITE),
+        )
+        .add_plugin(AutoLink::<_, 4>::new(Rgb888::BLUE, Rgb888::WHITE));
+
+        // Underlines overlap descenders.
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        text_box.draw(&mut display).unwrap();
+
+        let (links, _) = text_box.take_plugins().pop();
+        (display, links.areas().collect())
+    }
+
+    #[test]
+    fn link_is_styled_and_recorded() {
+        let (display, areas) = render("a www.b c", 10);
+
+        assert_eq!(
+            areas,
+            [LinkArea {
+                offset: 2,
+                bounds: Rectangle::new(Point::new(12, 0), Size::new(30, 9)),
+            }]
+        );
+
+        let text_style = MonoTextStyle::new(&FONT_6X9, Rgb888::WHITE);
+        let link_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(Rgb888::BLUE)
+            .underline()
+            .build();
+
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        Text::with_baseline("a ", Point::zero(), text_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Text::with_baseline("www.b", Point::new(12, 0), link_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Text::with_baseline(" c", Point::new(42, 0), text_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn url_is_detected() {
+        assert_eq!(
+            render("see http://x.com now", 20).1,
+            [LinkArea {
+                offset: 4,
+                bounds: Rectangle::new(Point::new(24, 0), Size::new(72, 9)),
+            }]
+        );
+    }
+
+    #[test]
+    fn wrapped_link_is_recorded_on_every_line() {
+        let (_, areas) = render("go https://example.com now", 10);
+
+        assert!(areas.len() > 1);
+        assert!(areas.iter().all(|area| area.offset == 3));
+        assert_eq!(
+            areas.iter().map(|area| area.bounds.size.width).sum::<u32>(),
+            19 * 6
+        );
+    }
+
+    #[test]
+    fn links_start_at_the_beginning_of_words() {
+        assert_eq!(render("www.a.b", 10).1.len(), 1);
+        assert_eq!(render("awww.a.b", 10).1, []);
+        assert_eq!(render("a\nhttps://b c", 10).1.len(), 1);
+    }
+}
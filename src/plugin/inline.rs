@@ -0,0 +1,362 @@
+//! Inline fragments.
+//!
+//! The [`InlineFragments`] plugin embeds objects that are drawn by your own code, like a value
+//! rendered with a different font, into the text. A fragment is referenced by a `{frag:N}`
+//! marker in the text, where `N` is the index of the fragment. The marker must be a word on its
+//! own, i.e. it must be separated from the surrounding text by whitespace.
+//!
+//! Fragments are laid out like a word with the width of the fragment, and they are never broken:
+//! a fragment that doesn't fit into the rest of a line is moved to the next line. A fragment that
+//! is wider than the text box is cut off at the end of the line.
+//!
+//! Fragments are bottom-aligned to the baseline of the text. Fragments are clipped to their line,
+//! so a fragment that is taller than the ascent of the font is moved down to start at the top of
+//! the line. Increase the [line height] of the text box to make room for tall fragments.
+//!
+//! ```rust
+//! # use embedded_graphics::{
+//! #     mock_display::MockDisplay,
+//! #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+//! #     pixelcolor::BinaryColor,
+//! #     prelude::*,
+//! #     primitives::{PrimitiveStyle, Rectangle},
+//! # };
+//! use embedded_text::{
+//!     plugin::inline::{InlineFragment, InlineFragments},
+//!     TextBox,
+//! };
+//!
+//! struct Battery(u32);
+//!
+//! impl InlineFragment<BinaryColor> for Battery {
+//!     fn size(&self) -> Size {
+//!         Size::new(12, 5)
+//!     }
+//!
+//!     fn draw_at<D>(&self, position: Point, target: &mut D) -> Result<(), D::Error>
+//!     where
+//!         D: DrawTarget<Color = BinaryColor>,
+//!     {
+//!         Rectangle::new(position, Size::new(12 * self.0 / 100, 5))
+//!             .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+//!             .draw(target)
+//!     }
+//! }
+//!
+//! let fragments = [Battery(50)];
+//! let text_box = TextBox::new(
+//!     "Battery: {frag:0}",
+//!     Rectangle::new(Point::zero(), Size::new(60, 9)),
+//!     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+//! )
+//! .add_plugin(InlineFragments::new(&fragments));
+//!
+//! # let mut display = MockDisplay::new();
+//! text_box.draw(&mut display).unwrap();
+//! ```
+//!
+//! [line height]: embedded_graphics::text::TextStyle::line_height
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    prelude::PixelColor,
+    primitives::Rectangle,
+    text::{renderer::TextRenderer, Baseline},
+};
+
+use crate::{parser::Token, plugin::Plugin};
+
+/// An object that can be embedded into the text by the [`InlineFragments`] plugin.
+pub trait InlineFragment<C>
+where
+    C: PixelColor,
+{
+    /// Returns the size of the object.
+    fn size(&self) -> Size;
+
+    /// Draws the object with its top left corner at the given position.
+    fn draw_at<D>(&self, position: Point, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>;
+}
+
+/// Returns the index of the fragment referenced by a `{frag:N}` marker.
+fn marker_index(word: &str) -> Option<usize> {
+    word.strip_prefix("{frag:")?.strip_suffix('}')?.parse().ok()
+}
+
+/// Inline fragment plugin.
+///
+/// Replaces `{frag:N}` markers with the `N`th fragment of the given slice. Markers that refer to
+/// a fragment that doesn't exist are displayed as text. To embed different kinds of objects,
+/// implement [`InlineFragment`] for an enum.
+#[derive(Debug)]
+pub struct InlineFragments<'f, F> {
+    fragments: &'f [F],
+
+    /// The fragment that is being rendered.
+    rendered: Option<&'f F>,
+}
+
+// Derived `Clone` would require `F: Clone`.
+impl<F> Clone for InlineFragments<'_, F> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            fragments: self.fragments,
+            rendered: self.rendered,
+        }
+    }
+}
+
+impl<'f, F> InlineFragments<'f, F> {
+    /// Creates a new inline fragment plugin.
+    #[inline]
+    pub const fn new(fragments: &'f [F]) -> Self {
+        Self {
+            fragments,
+            rendered: None,
+        }
+    }
+
+    fn fragment(&self, marker: &str) -> Option<&'f F> {
+        self.fragments.get(marker_index(marker)?)
+    }
+}
+
+impl<'a, 'f, C, F> Plugin<'a, C> for InlineFragments<'f, F>
+where
+    C: PixelColor,
+    F: InlineFragment<C>,
+{
+    #[inline]
+    fn next_token(
+        &mut self,
+        mut next_token: impl FnMut() -> Option<Token<'a, C>>,
+    ) -> Option<Token<'a, C>> {
+        match next_token() {
+            Some(Token::Word(text)) => match self.fragment(text) {
+                Some(fragment) => Some(Token::Inline {
+                    text,
+                    width: fragment.size().width,
+                }),
+                None => Some(Token::Word(text)),
+            },
+
+            token => token,
+        }
+    }
+
+    #[inline]
+    fn render_token(&mut self, token: Token<'a, C>) -> Option<Token<'a, C>> {
+        if let Token::Inline { text, .. } = token {
+            self.rendered = self.fragment(text);
+        }
+
+        Some(token)
+    }
+
+    #[inline]
+    fn post_render<T, D>(
+        &mut self,
+        draw_target: &mut D,
+        character_style: &T,
+        _text: Option<&str>,
+        bounds: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        T: TextRenderer<Color = C>,
+        D: DrawTarget<Color = C>,
+    {
+        let fragment = match self.rendered.take() {
+            Some(fragment) => fragment,
+            None => return Ok(()),
+        };
+
+        let baseline = -character_style
+            .measure_string(" ", Point::zero(), Baseline::Alphabetic)
+            .bounding_box
+            .top_left
+            .y;
+        let top = (baseline - fragment.size().height as i32).max(0);
+
+        fragment.draw_at(bounds.top_left + Point::new(0, top), draw_target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::{PrimitiveStyle, Rectangle},
+        text::{Baseline, Text},
+    };
+
+    use super::{InlineFragment, InlineFragments};
+    use crate::{
+        style::{HeightMode, TextBoxStyleBuilder},
+        utils::test::size_for,
+        TextBox,
+    };
+
+    struct Block(Size);
+
+    impl InlineFragment<BinaryColor> for Block {
+        fn size(&self) -> Size {
+            self.0
+        }
+
+        fn draw_at<D>(&self, position: Point, target: &mut D) -> Result<(), D::Error>
+        where
+            D: DrawTarget<Color = BinaryColor>,
+        {
+            Rectangle::new(position, self.0)
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(target)
+        }
+    }
+
+    fn render(text: &str, fragments: &[Block], width: u32) -> MockDisplay<BinaryColor> {
+        let text_box = TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, width, 3)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(InlineFragments::new(fragments));
+
+        let mut display = MockDisplay::new();
+        text_box.draw(&mut display).unwrap();
+        display
+    }
+
+    #[test]
+    fn fragment_is_bottom_aligned_to_baseline() {
+        let display = render("a {frag:0}", &[Block(Size::new(4, 3))], 10);
+
+        let mut expected = MockDisplay::new();
+        Text::with_baseline(
+            "a",
+            Point::zero(),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            Baseline::Top,
+        )
+        .draw(&mut expected)
+        .unwrap();
+        Rectangle::new(
+            Point::new(12, FONT_6X9.baseline as i32 - 3),
+            Size::new(4, 3),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(&mut expected)
+        .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn tall_fragment_starts_at_top_of_line() {
+        let display = render("{frag:0}", &[Block(Size::new(4, 20))], 10);
+
+        assert_eq!(
+            display.affected_area(),
+            Rectangle::new(Point::zero(), Size::new(4, 9))
+        );
+    }
+
+    #[test]
+    fn fragments_are_wrapped_as_a_whole() {
+        let fragments = [Block(Size::new(24, 3))];
+        let line_count = |text| {
+            TextBox::new(
+                text,
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 3)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            )
+            .add_plugin(InlineFragments::new(&fragments))
+            .line_count()
+        };
+
+        // The fragment doesn't fit after "a ", and "b" doesn't fit after the fragment.
+        assert_eq!(line_count("a {frag:0} b"), 3);
+
+        let display = render("a {frag:0} b", &fragments, 5);
+
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let mut expected = MockDisplay::new();
+        Text::with_baseline("a", Point::zero(), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Rectangle::new(
+            Point::new(0, 9 + FONT_6X9.baseline as i32 - 3),
+            Size::new(24, 3),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(&mut expected)
+        .unwrap();
+        Text::with_baseline("b", Point::new(0, 18), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn unknown_markers_are_displayed_as_text() {
+        let display = render("{frag:1}", &[Block(Size::new(4, 3))], 10);
+
+        let mut expected = MockDisplay::new();
+        Text::with_baseline(
+            "{frag:1}",
+            Point::zero(),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            Baseline::Top,
+        )
+        .draw(&mut expected)
+        .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn enlarged_word_is_measured_like_it_is_rendered() {
+        // A 48px wide fragment stands in for a word drawn with a larger font.
+        let fragments = [Block(Size::new(48, 3))];
+        let text_box = TextBox::with_textbox_style(
+            "ab {frag:0} cd",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 0)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            TextBoxStyleBuilder::new()
+                .height_mode(HeightMode::FitToText)
+                .build(),
+        )
+        .add_plugin(InlineFragments::new(&fragments));
+
+        // "ab " / fragment / "cd"
+        assert_eq!(text_box.bounding_box().size.height, 27);
+
+        let mut display = MockDisplay::new();
+        text_box.draw(&mut display).unwrap();
+
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let mut expected = MockDisplay::new();
+        Text::with_baseline("ab", Point::zero(), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Rectangle::new(
+            Point::new(0, 9 + FONT_6X9.baseline as i32 - 3),
+            Size::new(48, 3),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(&mut expected)
+        .unwrap();
+        Text::with_baseline("cd", Point::new(0, 18), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+}
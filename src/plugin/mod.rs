@@ -4,10 +4,9 @@
 //! you need to activate the `plugin` feature.
 
 use core::{
-    cell::UnsafeCell,
+    cell::RefCell,
     hash::{Hash, Hasher},
     marker::PhantomData,
-    ptr::addr_of,
 };
 use embedded_graphics::{
     draw_target::DrawTarget,
@@ -19,20 +18,25 @@ use embedded_graphics::{
 use crate::{
     parser::{Parser, Token},
     rendering::{cursor::Cursor, TextBoxProperties},
+    utils::remaining_len_from,
 };
 
 #[cfg(feature = "plugin")]
 pub mod private;
 #[cfg(feature = "plugin")]
-pub use private::Plugin;
+pub use private::{Plugin, TruncationInfo, WordSplitReason};
 
 #[cfg(not(feature = "plugin"))]
 mod private;
 #[cfg(not(feature = "plugin"))]
 use private::Plugin;
+#[cfg(not(feature = "plugin"))]
+pub(crate) use private::{TruncationInfo, WordSplitReason};
 
 #[cfg(feature = "ansi")]
 pub mod ansi;
+pub mod autolink;
+pub mod inline;
 pub mod tail;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -42,7 +46,7 @@ pub(crate) enum ProcessingState {
 }
 
 /// Placeholder type when no plugin is used.
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct NoPlugin<C>(PhantomData<C>)
 where
     C: PixelColor;
@@ -56,6 +60,26 @@ where
     }
 }
 
+impl<C> Hash for NoPlugin<C>
+where
+    C: PixelColor,
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl<C> PartialEq for NoPlugin<C>
+where
+    C: PixelColor,
+{
+    #[inline]
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<C> Eq for NoPlugin<C> where C: PixelColor {}
+
 /// Plugin marker trait.
 ///
 /// This trait is an implementation detail. Most likely you don't need to implement this.
@@ -78,41 +102,64 @@ pub(crate) struct PluginInner<'a, M, C> {
     plugin: M,
     state: ProcessingState,
     peeked_token: Option<Token<'a, C>>,
+    /// The rest of a word that was split at a style span boundary.
+    word_rest: Option<&'a str>,
+    /// The index of the current paragraph.
+    paragraph: u32,
+    /// Whether the next line starts a paragraph.
+    paragraph_start: bool,
+    /// The prefix of the current paragraph.
+    prefix: Option<&'a str>,
 }
 
+/// Wraps the plugin object and the state shared between the plugin and the renderer.
+///
+/// The wrapper hands out references to its contents using a checked borrow. Plugin callbacks never
+/// receive a reference to the wrapper, so they can not re-enter it. If the wrapper is re-entered
+/// anyway, it panics instead of creating aliasing mutable references.
 #[derive(Debug)]
 pub(crate) struct PluginWrapper<'a, M, C> {
-    inner: UnsafeCell<PluginInner<'a, M, C>>,
+    inner: RefCell<PluginInner<'a, M, C>>,
 }
 
 impl<'a, M: Clone, C: Clone> Clone for PluginWrapper<'a, M, C> {
     fn clone(&self) -> Self {
         Self {
-            inner: UnsafeCell::new(self.with(|this| PluginInner {
-                plugin: this.plugin.clone(),
-                state: this.state,
-                peeked_token: unsafe { addr_of!(this.peeked_token).read() },
-            })),
+            inner: RefCell::new(self.with(|this| this.clone())),
         }
     }
 }
 
+/// Only the plugin is hashed and compared, the state of the renderer is ignored.
 impl<'a, M, C> Hash for PluginWrapper<'a, M, C>
 where
-    C: PixelColor,
+    M: Hash,
 {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.with(|this| this.state.hash(state))
+        self.with(|this| this.plugin.hash(state))
+    }
+}
+
+impl<'a, M, C> PartialEq for PluginWrapper<'a, M, C>
+where
+    M: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.with(|this| other.with(|other| this.plugin == other.plugin))
     }
 }
 
 impl<'a, M, C> PluginWrapper<'a, M, C> {
     pub fn new(plugin: M) -> Self {
         Self {
-            inner: UnsafeCell::new(PluginInner {
+            inner: RefCell::new(PluginInner {
                 plugin,
                 state: ProcessingState::Measure,
                 peeked_token: None,
+                word_rest: None,
+                paragraph: 0,
+                paragraph_start: true,
+                prefix: None,
             }),
         }
     }
@@ -122,21 +169,21 @@ impl<'a, M, C> PluginWrapper<'a, M, C> {
     }
 
     fn with<R>(&self, cb: impl FnOnce(&PluginInner<'a, M, C>) -> R) -> R {
-        let inner = unsafe {
-            // SAFETY: This is safe because we aren't exposing the reference.
-            self.inner.get().as_ref().unwrap_unchecked()
-        };
+        let inner = self
+            .inner
+            .try_borrow()
+            .expect("PluginWrapper was accessed while a plugin callback was running");
 
-        cb(inner)
+        cb(&inner)
     }
 
     fn with_mut<R>(&self, cb: impl FnOnce(&mut PluginInner<'a, M, C>) -> R) -> R {
-        let inner = unsafe {
-            // SAFETY: This is safe because we aren't exposing the reference.
-            self.inner.get().as_mut().unwrap_unchecked()
-        };
+        let mut inner = self
+            .inner
+            .try_borrow_mut()
+            .expect("PluginWrapper was accessed while a plugin callback was running");
 
-        cb(inner)
+        cb(&mut inner)
     }
 }
 
@@ -149,6 +196,27 @@ where
         self.with_mut(|this| this.plugin.new_line());
     }
 
+    /// Returns the prefix of the current paragraph, and whether the line starts the paragraph.
+    pub fn line_prefix(&self) -> Option<(&'a str, bool)> {
+        self.with_mut(|this| {
+            let first_line = this.paragraph_start;
+            if first_line {
+                this.paragraph_start = false;
+                this.prefix = this.plugin.line_prefix(this.paragraph);
+            }
+
+            this.prefix.map(|prefix| (prefix, first_line))
+        })
+    }
+
+    /// Marks the end of the current paragraph.
+    pub fn end_paragraph(&self) {
+        self.with_mut(|this| {
+            this.paragraph = this.paragraph.saturating_add(1);
+            this.paragraph_start = true;
+        });
+    }
+
     pub fn set_state(&self, state: ProcessingState) {
         self.with_mut(|this| this.state = state);
     }
@@ -161,20 +229,76 @@ where
         })
     }
 
+    pub fn on_word_split(&self, word: &str, at: usize, reason: WordSplitReason) {
+        self.with_mut(|this| {
+            if this.state == ProcessingState::Render {
+                this.plugin.on_word_split(word, at, reason);
+            }
+        });
+    }
+
+    pub fn on_cursor_overflow(&self, delta: i32) {
+        self.with_mut(|this| {
+            if this.state == ProcessingState::Render {
+                this.plugin.on_cursor_overflow(delta);
+            }
+        });
+    }
+
     pub fn peek_token(&self, source: &mut Parser<'a, C>) -> Option<Token<'a, C>> {
         self.with_mut(|this| {
             if this.peeked_token.is_none() {
-                this.peeked_token = this.plugin.next_token(|| source.next());
+                this.peeked_token = match this.word_rest.take() {
+                    Some(rest) => Some(Token::Word(rest)),
+                    None => this.plugin.next_token(|| source.next()),
+                };
+
+                if let Some(Token::Word(word)) = this.peeked_token {
+                    let text = source.text();
+                    let boundary = remaining_len_from(text, word)
+                        .and_then(|rest| this.plugin.span_boundary(text.len() - rest, word))
+                        .filter(|at| (1..word.len()).contains(at) && word.is_char_boundary(*at));
+
+                    if let Some(at) = boundary {
+                        let (word, rest) = word.split_at(at);
+                        this.peeked_token = Some(Token::Word(word));
+                        this.word_rest = Some(rest);
+                    }
+                }
             }
 
             this.peeked_token.clone()
         })
     }
 
+    /// Returns whether the peeked token is followed by the rest of a word that was split at a
+    /// style span boundary.
+    pub fn continues_word(&self) -> bool {
+        self.with(|this| this.word_rest.is_some())
+    }
+
+    /// Returns the text of the peeked token, if it is a word, whitespace or an inline object.
+    pub fn peeked_str(&self) -> Option<&'a str> {
+        self.with(|this| match this.peeked_token {
+            Some(Token::Word(s))
+            | Some(Token::Whitespace(_, s))
+            | Some(Token::Inline { text: s, .. }) => Some(s),
+            _ => None,
+        })
+    }
+
     pub fn consume_peeked_token(&self) {
         self.with_mut(|this| this.peeked_token = None);
     }
 
+    /// Replaces the peeked token with the part of it that is left to process.
+    pub fn replace_peeked_token(&self, token: Token<'a, C>) {
+        self.with_mut(|this| this.peeked_token = Some(token));
+    }
+
+    /// Consumes the first `len` bytes of the peeked token.
+    ///
+    /// `len` must be at a character boundary of the token's text.
     pub fn consume_partial(&self, len: usize) {
         self.with_mut(|this| {
             // Only string-like tokens can be partially consumed.
@@ -183,20 +307,19 @@ where
                 Some(Token::Whitespace(_, _)) | Some(Token::Word(_))
             ));
 
-            let skip_chars = |str: &'a str, n| {
-                let mut chars = str.chars();
-                for _ in 0..n {
-                    chars.next();
-                }
-                chars.as_str()
+            let split = |str: &'a str| {
+                debug_assert!(str.is_char_boundary(len));
+                str.split_at(len)
             };
 
             if let Some(token) = this.peeked_token.take() {
                 let token = match token {
                     Token::Whitespace(count, seq) => {
-                        Token::Whitespace(count - len as u32, skip_chars(seq, len))
+                        let (consumed, rest) = split(seq);
+                        let consumed_count = consumed.chars().count() as u32;
+                        Token::Whitespace(count.saturating_sub(consumed_count), rest)
                     }
-                    Token::Word(w) => Token::Word(skip_chars(w, len)),
+                    Token::Word(w) => Token::Word(split(w).1),
                     _ => return,
                 };
 
@@ -212,11 +335,19 @@ where
     ) {
         self.with_mut(|this| {
             this.peeked_token = None;
+            this.word_rest = None;
+            this.paragraph = 0;
+            this.paragraph_start = true;
+            this.prefix = None;
 
             this.plugin.on_start_render(cursor, &props);
         });
     }
 
+    pub fn on_truncated(&self, info: TruncationInfo) {
+        self.with_mut(|this| this.plugin.on_truncated(info));
+    }
+
     pub fn on_rendering_finished(&self) {
         self.with_mut(|this| this.plugin.on_rendering_finished());
     }
@@ -238,3 +369,829 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::text::{
+        renderer::{CharacterStyle, TextMetrics, TextRenderer},
+        Baseline, Text,
+    };
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+    };
+    use object_chain::ChainElement;
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::{NoPlugin, Plugin, PluginWrapper, TruncationInfo, WordSplitReason};
+    use crate::{
+        alignment::HorizontalAlignment,
+        parser::{ChangeTextStyle, Parser, Token},
+        rendering::{cursor::Cursor, TextBoxProperties},
+        style::TextBoxStyleBuilder,
+        utils::test::size_for,
+        TextBox,
+    };
+    #[cfg(feature = "ansi")]
+    use crate::{parser::StyleDiff, plugin::ansi::Ansi, rendering::line_iter::ElementHandler};
+    #[cfg(feature = "ansi")]
+    use core::convert::Infallible;
+    #[cfg(feature = "ansi")]
+    use embedded_graphics::{pixelcolor::Rgb888, text::DecorationColor};
+
+    #[test]
+    #[should_panic(expected = "PluginWrapper was accessed while a plugin callback was running")]
+    fn reentry_panics() {
+        let wrapper = PluginWrapper::new(NoPlugin::<BinaryColor>::new());
+
+        wrapper.with_mut(|_| {
+            // A callback that somehow got hold of the wrapper tries to re-enter it.
+            wrapper.peek_token(&mut Parser::parse("text"));
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "PluginWrapper was accessed while a plugin callback was running")]
+    fn shared_reentry_panics() {
+        let wrapper = PluginWrapper::new(NoPlugin::<BinaryColor>::new());
+
+        wrapper.with(|_| wrapper.new_line());
+    }
+
+    #[derive(Clone, Default)]
+    struct SplitRecorder {
+        splits: Vec<(String, usize, WordSplitReason)>,
+    }
+
+    impl<'a> Plugin<'a, BinaryColor> for SplitRecorder {
+        fn on_word_split(&mut self, word: &str, at: usize, reason: WordSplitReason) {
+            self.splits.push((word.to_owned(), at, reason));
+        }
+    }
+
+    #[test]
+    fn long_word_splits_are_reported() {
+        let text_box = TextBox::new(
+            "a verylongword",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 4)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(SplitRecorder::default());
+
+        text_box.draw(&mut MockDisplay::new()).unwrap();
+
+        let (recorder, _) = text_box.take_plugins().pop();
+        assert_eq!(
+            recorder.splits,
+            [
+                ("verylongword".to_owned(), 0, WordSplitReason::Wrapped),
+                ("verylongword".to_owned(), 5, WordSplitReason::Characters),
+                ("ongword".to_owned(), 5, WordSplitReason::Characters),
+            ]
+        );
+    }
+
+    #[test]
+    fn soft_hyphen_and_wrapped_word_splits_are_reported() {
+        let text_box = TextBox::new(
+            "ab cd\u{ad}efg hi",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 4)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(SplitRecorder::default());
+
+        text_box.draw(&mut MockDisplay::new()).unwrap();
+
+        // "ab cd-" / "efg hi"
+        let (recorder, _) = text_box.take_plugins().pop();
+        assert_eq!(
+            recorder.splits,
+            [("cd".to_owned(), 2, WordSplitReason::SoftHyphen)]
+        );
+
+        let text_box = TextBox::new(
+            "ab cdefg",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 4)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(SplitRecorder::default());
+
+        text_box.draw(&mut MockDisplay::new()).unwrap();
+
+        let (recorder, _) = text_box.take_plugins().pop();
+        assert_eq!(
+            recorder.splits,
+            [("cdefg".to_owned(), 0, WordSplitReason::Wrapped)]
+        );
+    }
+
+    /// Character style that can be hashed.
+    #[derive(Clone, Debug, PartialEq)]
+    struct HashableStyle(MonoTextStyle<'static, BinaryColor>);
+
+    impl Hash for HashableStyle {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.text_color.hash(state);
+            self.0.background_color.hash(state);
+        }
+    }
+
+    impl TextRenderer for HashableStyle {
+        type Color = BinaryColor;
+
+        fn draw_string<D>(
+            &self,
+            text: &str,
+            position: Point,
+            baseline: Baseline,
+            target: &mut D,
+        ) -> Result<Point, D::Error>
+        where
+            D: DrawTarget<Color = Self::Color>,
+        {
+            self.0.draw_string(text, position, baseline, target)
+        }
+
+        fn draw_whitespace<D>(
+            &self,
+            width: u32,
+            position: Point,
+            baseline: Baseline,
+            target: &mut D,
+        ) -> Result<Point, D::Error>
+        where
+            D: DrawTarget<Color = Self::Color>,
+        {
+            self.0.draw_whitespace(width, position, baseline, target)
+        }
+
+        fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+            self.0.measure_string(text, position, baseline)
+        }
+
+        fn line_height(&self) -> u32 {
+            self.0.line_height()
+        }
+    }
+
+    impl CharacterStyle for HashableStyle {
+        type Color = BinaryColor;
+    }
+
+    fn hash_of(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn drawing_does_not_change_hash_or_equality() {
+        let text_box = TextBox::new(
+            "Some text",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+            HashableStyle(MonoTextStyle::new(&FONT_6X9, BinaryColor::On)),
+        );
+        let copy = text_box.clone();
+        let hash = hash_of(&text_box);
+
+        text_box.draw(&mut MockDisplay::new()).unwrap();
+
+        assert_eq!(hash_of(&text_box), hash);
+        assert_eq!(text_box, copy);
+
+        let mut moved = copy.clone();
+        moved.bounds.top_left.x += 1;
+        assert_ne!(hash_of(&moved), hash);
+        assert_ne!(moved, copy);
+    }
+
+    /// Counts the visible lines by moving the cursor, before the text is rendered.
+    #[derive(Clone, Default)]
+    struct TwoPass {
+        visible_lines: u32,
+    }
+
+    impl<'a> Plugin<'a, BinaryColor> for TwoPass {
+        fn on_start_render<S: CharacterStyle + TextRenderer>(
+            &mut self,
+            cursor: &mut Cursor,
+            _props: &TextBoxProperties<'_, S>,
+        ) {
+            let state = cursor.save();
+            while cursor.in_display_area() {
+                self.visible_lines += 1;
+                cursor.new_line();
+            }
+            cursor.restore(state);
+        }
+    }
+
+    #[test]
+    fn restored_cursor_renders_identical_text() {
+        let text_box = |bounds| {
+            TextBox::new(
+                "Some text that is wrapped into multiple lines",
+                bounds,
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            )
+        };
+        let bounds = Rectangle::new(Point::new(2, 3), size_for(&FONT_6X9, 8, 3));
+
+        let mut expected = MockDisplay::new();
+        text_box(bounds).draw(&mut expected).unwrap();
+
+        let two_pass = text_box(bounds).add_plugin(TwoPass::default());
+        let mut display = MockDisplay::new();
+        two_pass.draw(&mut display).unwrap();
+
+        display.assert_eq(&expected);
+        assert_eq!(two_pass.take_plugins().pop().0.visible_lines, 3);
+    }
+
+    /// Records truncation reports.
+    #[derive(Clone, Default)]
+    struct TruncationRecorder {
+        truncations: Vec<TruncationInfo>,
+    }
+
+    impl<'a> Plugin<'a, BinaryColor> for TruncationRecorder {
+        fn on_truncated(&mut self, info: TruncationInfo) {
+            self.truncations.push(info);
+        }
+    }
+
+    fn truncations(text: &str, rows: u32, avoid_broken_last_word: bool) -> Vec<TruncationInfo> {
+        let text_box = || {
+            TextBox::with_textbox_style(
+                text,
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, rows)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                TextBoxStyleBuilder::new()
+                    .hyphenation(|_, _| true)
+                    .avoid_broken_last_word(avoid_broken_last_word)
+                    .build(),
+            )
+            .add_plugin(TruncationRecorder::default())
+        };
+
+        let drawn = text_box();
+        drawn.draw(&mut MockDisplay::new()).unwrap();
+
+        // Chunked rendering reports the same truncation.
+        let chunked = text_box();
+        chunked.pixel_chunks().for_each(drop);
+
+        let truncations = drawn.take_plugins().pop().0.truncations;
+        assert_eq!(chunked.take_plugins().pop().0.truncations, truncations);
+
+        truncations
+    }
+
+    #[test]
+    fn truncation_is_reported() {
+        // Lines: "one", "two", "three", "four\n", "".
+        assert_eq!(
+            truncations("one two three four\n", 2, false),
+            [TruncationInfo {
+                offset: 8,
+                hidden_lines: 3,
+            }]
+        );
+
+        assert_eq!(truncations("one two three", 3, false), []);
+        assert_eq!(truncations("one two three", 4, false), []);
+    }
+
+    #[test]
+    fn partially_consumed_multibyte_words_continue_on_the_next_line() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 3));
+        let text_box = |text| {
+            TextBox::new(text, bounds, character_style).add_plugin(TruncationRecorder::default())
+        };
+
+        for (text, expected) in [
+            ("😀😀😀😀", "😀😀😀\n😀"),
+            // Wide spaces wrap like ASCII spaces.
+            ("ab\u{3000}\u{3000}\u{3000}cd", "ab   cd"),
+        ] {
+            let mut display = MockDisplay::new();
+            text_box(text).draw(&mut display).unwrap();
+
+            let mut expected_display = MockDisplay::new();
+            text_box(expected).draw(&mut expected_display).unwrap();
+
+            display.assert_eq(&expected_display);
+
+            // Measurement consumes the text the same way.
+            let mut measured = text_box(text);
+            measured.fit_height();
+            assert_eq!(measured.bounds.size.height, 18, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn last_line_avoids_broken_words() {
+        // "ab " leaves room for "c-" in the first line.
+        assert_eq!(
+            truncations("ab cdefgh", 1, false),
+            [TruncationInfo {
+                offset: 4,
+                hidden_lines: 1,
+            }]
+        );
+        assert_eq!(
+            truncations("ab cdefgh", 1, true),
+            [TruncationInfo {
+                offset: 3,
+                hidden_lines: 2,
+            }]
+        );
+
+        // Only the last displayed line is affected.
+        assert_eq!(
+            truncations("ab cdefgh ij kl", 2, true),
+            [TruncationInfo {
+                offset: 10,
+                hidden_lines: 1,
+            }]
+        );
+
+        // A word that fills the line is broken anyway.
+        assert_eq!(
+            truncations("abcdefgh", 1, true),
+            [TruncationInfo {
+                offset: 4,
+                hidden_lines: 1,
+            }]
+        );
+    }
+
+    /// Splits words at the given byte offsets of the text, and records the rendered pieces.
+    #[derive(Clone, Default)]
+    struct SpanSplitter {
+        boundaries: Vec<usize>,
+        pieces: Vec<String>,
+    }
+
+    impl SpanSplitter {
+        fn new(boundaries: &[usize]) -> Self {
+            Self {
+                boundaries: boundaries.to_vec(),
+                pieces: Vec::new(),
+            }
+        }
+    }
+
+    impl<'a> Plugin<'a, BinaryColor> for SpanSplitter {
+        fn span_boundary(&mut self, offset: usize, word: &str) -> Option<usize> {
+            self.boundaries
+                .iter()
+                .map(|boundary| boundary.wrapping_sub(offset))
+                .filter(|at| (1..word.len()).contains(at))
+                .min()
+        }
+
+        fn post_render<T, D>(
+            &mut self,
+            _draw_target: &mut D,
+            _character_style: &T,
+            text: Option<&str>,
+            _bounds: Rectangle,
+        ) -> Result<(), D::Error>
+        where
+            T: embedded_graphics::text::renderer::TextRenderer<Color = BinaryColor>,
+            D: DrawTarget<Color = BinaryColor>,
+        {
+            match text {
+                Some(text) if !text.trim().is_empty() => self.pieces.push(text.to_owned()),
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn words_are_split_at_span_boundaries() {
+        // "é" and "ö" are 2 bytes long. Boundaries at the edges of words, and inside "ö" are
+        // ignored.
+        let text_box = TextBox::new(
+            "héllo wörld",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 12, 1)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .add_plugin(SpanSplitter::new(&[0, 1, 3, 5, 6, 7, 9, 11]));
+
+        let mut display = MockDisplay::new();
+        text_box.draw(&mut display).unwrap();
+
+        let (splitter, _) = text_box.take_plugins().pop();
+        assert_eq!(splitter.pieces, ["h", "é", "ll", "o", "wörld"]);
+    }
+
+    #[test]
+    fn split_words_are_laid_out_like_whole_words() {
+        let text = "aaaa bbbbbb cc dddddddd";
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        for alignment in [
+            HorizontalAlignment::Left,
+            HorizontalAlignment::Center,
+            HorizontalAlignment::Right,
+            HorizontalAlignment::Justified,
+        ] {
+            for width in 1..=12 {
+                let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, width, 8));
+
+                let mut expected = MockDisplay::new();
+                TextBox::with_alignment(text, bounds, character_style, alignment)
+                    .draw(&mut expected)
+                    .unwrap();
+
+                let mut display = MockDisplay::new();
+                TextBox::with_alignment(text, bounds, character_style, alignment)
+                    .add_plugin(SpanSplitter::new(&[2, 6, 7, 10, 13, 17, 18, 19, 21]))
+                    .draw(&mut display)
+                    .unwrap();
+
+                assert_eq!(
+                    display, expected,
+                    "alignment: {:?}, width: {}",
+                    alignment, width
+                );
+            }
+        }
+    }
+
+    /// Raises the digits that follow letters by 3 pixels.
+    #[derive(Clone, Default)]
+    struct Superscript<'a> {
+        pending: Vec<Token<'a, BinaryColor>>,
+    }
+
+    impl<'a> Plugin<'a, BinaryColor> for Superscript<'a> {
+        fn next_token(
+            &mut self,
+            mut next_token: impl FnMut() -> Option<Token<'a, BinaryColor>>,
+        ) -> Option<Token<'a, BinaryColor>> {
+            if let Some(token) = self.pending.pop() {
+                return Some(token);
+            }
+
+            match next_token() {
+                Some(Token::Word(word)) => match word.find(|c: char| c.is_ascii_digit()) {
+                    Some(digits) if digits > 0 => {
+                        self.pending = vec![
+                            Token::ChangeTextStyle(ChangeTextStyle::VerticalOffset(0)),
+                            Token::Word(&word[digits..]),
+                            Token::ChangeTextStyle(ChangeTextStyle::VerticalOffset(-3)),
+                        ];
+                        Some(Token::Word(&word[..digits]))
+                    }
+                    _ => Some(Token::Word(word)),
+                },
+                token => token,
+            }
+        }
+    }
+
+    /// Replaces tokens with ones that don't come from the text, or that have wrong lengths.
+    #[derive(Clone, Default)]
+    struct Misbehaving {
+        words: usize,
+    }
+
+    impl<'a> Plugin<'a, BinaryColor> for Misbehaving {
+        fn next_token(
+            &mut self,
+            mut next_token: impl FnMut() -> Option<Token<'a, BinaryColor>>,
+        ) -> Option<Token<'a, BinaryColor>> {
+            match next_token() {
+                Some(Token::Word(_)) => {
+                    self.words += 1;
+                    Some(match self.words % 5 {
+                        0 => Token::Word("fo\u{a0}reign\u{fc}\u{fc}\u{fc}\u{fc}\u{fc}"),
+                        1 => Token::Inline {
+                            text: "huge",
+                            width: u32::MAX,
+                        },
+                        2 => Token::MoveCursor {
+                            chars: i32::MAX,
+                            draw_background: true,
+                            wrap: false,
+                        },
+                        3 => Token::MoveCursor {
+                            chars: -25,
+                            draw_background: true,
+                            wrap: true,
+                        },
+                        _ => Token::Break(""),
+                    })
+                }
+                Some(Token::Whitespace(n, _)) => Some(Token::Whitespace(n + 4, " ")),
+                token => token,
+            }
+        }
+
+        fn span_boundary(&mut self, _offset: usize, word: &str) -> Option<usize> {
+            Some(if word.len() % 2 == 0 { 1 } else { usize::MAX })
+        }
+    }
+
+    #[test]
+    fn misbehaving_plugin_does_not_break_layout() {
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 6));
+        let text = "\u{fc}\u{fc} one two three four five\nsix seven eight nine ten";
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        for alignment in [HorizontalAlignment::Left, HorizontalAlignment::Justified] {
+            let text_box = TextBox::with_textbox_style(
+                text,
+                bounds,
+                character_style,
+                TextBoxStyleBuilder::new().alignment(alignment).build(),
+            )
+            .add_plugin(Misbehaving::default());
+
+            assert!(text_box.line_count() > 0);
+
+            let mut display = MockDisplay::new();
+            display.set_allow_overdraw(true);
+            let rest = text_box.draw(&mut display).unwrap();
+
+            assert!(text.ends_with(rest));
+            if let Some(corner) = display.affected_area().bottom_right() {
+                assert!(bounds.contains(corner));
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct Timestamps<'a>(&'a [&'a str]);
+
+    impl<'a> Plugin<'a, BinaryColor> for Timestamps<'a> {
+        fn line_prefix(&mut self, paragraph_index: u32) -> Option<&'a str> {
+            self.0.get(paragraph_index as usize).copied()
+        }
+    }
+
+    #[test]
+    fn paragraphs_are_prefixed_and_wrapped_lines_are_indented() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let timestamps = ["12:03 ", "12:04\u{a0}", "12:05 "];
+        let text_box = TextBox::new(
+            "aaa bbb\nccc ddd\neee fff",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 6)),
+            character_style,
+        )
+        .add_plugin(Timestamps(&timestamps));
+
+        // The prefixes leave room for 4 characters, so every paragraph is wrapped once.
+        assert_eq!(text_box.line_count(), 6);
+
+        let mut display = MockDisplay::new();
+        text_box.draw(&mut display).unwrap();
+
+        let mut expected = MockDisplay::new();
+        let lines = [
+            "12:03 aaa",
+            "      bbb",
+            "12:04 ccc",
+            "      ddd",
+            "12:05 eee",
+            "      fff",
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            Text::with_baseline(
+                line,
+                Point::new(0, 9 * i as i32),
+                character_style,
+                Baseline::Top,
+            )
+            .draw(&mut expected)
+            .unwrap();
+        }
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn vertical_offset_moves_glyphs() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::new(0, 10), size_for(&FONT_6X9, 6, 1));
+
+        let mut display = MockDisplay::new();
+        TextBox::new("m2 x", bounds, character_style)
+            .add_plugin(Superscript::default())
+            .draw(&mut display)
+            .unwrap();
+
+        // The offset doesn't change the advance, and the raised glyph is clipped to the text area.
+        let mut expected = MockDisplay::new();
+        let mut clipped = expected.clipped(&bounds);
+        for (text, position) in [
+            ("m", Point::new(0, 10)),
+            ("2", Point::new(6, 7)),
+            ("x", Point::new(18, 10)),
+        ] {
+            Text::with_baseline(text, position, character_style, Baseline::Top)
+                .draw(&mut clipped)
+                .unwrap();
+        }
+
+        display.assert_eq(&expected);
+
+        let mut plain = MockDisplay::new();
+        TextBox::new("m2 x", bounds, character_style)
+            .draw(&mut plain)
+            .unwrap();
+        assert_ne!(display, plain);
+    }
+
+    #[test]
+    fn vertical_offset_moves_glyphs_into_the_previous_line() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::new(0, 10), size_for(&FONT_6X9, 2, 2));
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        TextBox::new("m\nm2", bounds, character_style)
+            .add_plugin(Superscript::default())
+            .draw(&mut display)
+            .unwrap();
+
+        // The raised glyph overlaps the first line instead of being clipped to its own line.
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        let mut clipped = expected.clipped(&bounds);
+        for (text, position) in [
+            ("m", Point::new(0, 10)),
+            ("m", Point::new(0, 19)),
+            ("2", Point::new(6, 16)),
+        ] {
+            Text::with_baseline(text, position, character_style, Baseline::Top)
+                .draw(&mut clipped)
+                .unwrap();
+        }
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn ordered_drawing_keeps_glyphs_moved_into_the_previous_line() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::new(0, 10), size_for(&FONT_6X9, 2, 2));
+        let text_box =
+            TextBox::new("m\nm2", bounds, character_style).add_plugin(Superscript::default());
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        text_box.draw(&mut display).unwrap();
+
+        let mut ordered = MockDisplay::new();
+        ordered.set_allow_overdraw(true);
+        text_box
+            .draw_ordered(&mut ordered, &mut [None; 64])
+            .unwrap();
+        ordered.assert_eq(&display);
+
+        let mut chunked = MockDisplay::new();
+        chunked.set_allow_overdraw(true);
+        for chunk in text_box.pixel_chunks() {
+            chunk.draw(&mut chunked).unwrap();
+        }
+        chunked.assert_eq(&display);
+    }
+
+    #[cfg(feature = "ansi")]
+    #[derive(Clone, Debug, PartialEq)]
+    enum Event {
+        Text(String),
+        Style(StyleDiff<Rgb888>),
+        CursorOverflow(i32),
+    }
+
+    #[cfg(feature = "ansi")]
+    #[derive(Clone, Default)]
+    struct EventRecorder {
+        events: Vec<Event>,
+    }
+
+    #[cfg(feature = "ansi")]
+    impl ElementHandler for EventRecorder {
+        type Error = Infallible;
+        type Color = Rgb888;
+
+        fn measure(&self, st: &str) -> u32 {
+            st.chars().count() as u32 * 6
+        }
+
+        fn printed_characters(&mut self, st: &str, _width: Option<u32>) -> Result<(), Infallible> {
+            self.events.push(Event::Text(st.to_owned()));
+            Ok(())
+        }
+
+        fn change_text_style(&mut self, change: ChangeTextStyle<Rgb888>) -> Result<(), Infallible> {
+            self.events.push(Event::Style(StyleDiff::from(change)));
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "ansi")]
+    impl<'a> Plugin<'a, Rgb888> for EventRecorder {
+        fn on_cursor_overflow(&mut self, delta: i32) {
+            self.events.push(Event::CursorOverflow(delta));
+        }
+
+        fn post_render<T, D>(
+            &mut self,
+            _draw_target: &mut D,
+            _character_style: &T,
+            text: Option<&str>,
+            _bounds: Rectangle,
+        ) -> Result<(), D::Error>
+        where
+            T: TextRenderer<Color = Rgb888>,
+            D: DrawTarget<Color = Rgb888>,
+        {
+            if let Some(text) = text {
+                self.events.push(Event::Text(text.to_owned()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ansi")]
+    fn style_changes_split_words() {
+        let text_box = TextBox::new(
+            "ab\x1b[31mcd\x1b[4mef",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 1)),
+            MonoTextStyle::new(&FONT_6X9, Rgb888::WHITE),
+        )
+        .add_plugin(Ansi::new());
+
+        let mut recorder = EventRecorder::default();
+        text_box.process_elements(&mut recorder).unwrap();
+
+        assert_eq!(
+            recorder.events,
+            [
+                Event::Text("ab".to_owned()),
+                Event::Style(StyleDiff {
+                    text_color: Some(Some(Rgb888::new(197, 15, 31))),
+                    background_color: None,
+                    underline: None,
+                    strikethrough: None,
+                    vertical_offset: None,
+                }),
+                Event::Text("cd".to_owned()),
+                Event::Style(StyleDiff {
+                    text_color: None,
+                    background_color: None,
+                    underline: Some(DecorationColor::TextColor),
+                    strikethrough: None,
+                    vertical_offset: None,
+                }),
+                Event::Text("ef".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ansi")]
+    fn cursor_overflow_is_reported() {
+        let text_box = TextBox::new(
+            "ab\x1b[100Ccd\x1b[5De",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 4, 2)),
+            MonoTextStyle::new(&FONT_6X9, Rgb888::WHITE),
+        )
+        .add_plugin(Ansi::new())
+        .add_plugin(EventRecorder::default());
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        text_box.draw(&mut display).unwrap();
+
+        // The cursor stops at the end of the first line, and at the start of the second one.
+        let (recorder, _) = text_box.take_plugins().pop();
+        assert_eq!(
+            recorder.events,
+            [
+                Event::Text("ab".to_owned()),
+                Event::CursorOverflow(100 * 6 - 12),
+                Event::Text("".to_owned()),
+                Event::Text("cd".to_owned()),
+                Event::CursorOverflow(-(5 * 6 - 12)),
+                Event::Text("".to_owned()),
+                Event::Text("e".to_owned()),
+            ]
+        );
+    }
+}
@@ -13,10 +13,49 @@ use crate::{
     rendering::{cursor::Cursor, TextBoxProperties},
 };
 
+/// The reason a word was split across lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WordSplitReason {
+    /// The word is wider than a line, so it was broken between two characters.
+    Characters,
+
+    /// The word doesn't fit into the line, so it was hyphenated using [`TextBoxStyle::hyphenation`].
+    ///
+    /// [`TextBoxStyle::hyphenation`]: crate::style::TextBoxStyle::hyphenation
+    Hyphenation,
+
+    /// The word doesn't fit into the line, so it was broken at a soft hyphen.
+    ///
+    /// The parts of a word around soft hyphens are laid out separately, so the reported word is
+    /// the part before the soft hyphen, and it is broken at its end.
+    SoftHyphen,
+
+    /// The word doesn't fit into the line, so it was moved to the next line as a whole.
+    ///
+    /// The word is broken at its start.
+    Wrapped,
+}
+
+/// Information about text that didn't fit into the text box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct TruncationInfo {
+    /// Byte offset of the first character that was not rendered.
+    pub offset: usize,
+
+    /// Number of lines the rest of the text would take up.
+    pub hidden_lines: u32,
+}
+
 /// Plugin trait.
 ///
 /// Plugins allow modifying and extending TextBox's internals.
 ///
+/// Plugin callbacks are called while the text box holds exclusive access to the plugin and its
+/// internal state. Callbacks must not try to access the text box that is being rendered, e.g.
+/// through a shared reference smuggled into the plugin. Doing so results in a panic.
+///
 /// *Important*:
 /// This is an experimental, unstable feature. It can be, and probably will be modified without
 /// any prior notice.
@@ -29,7 +68,26 @@ where
     #[inline]
     fn new_line(&mut self) {}
 
+    /// Returns the prefix of a paragraph.
+    ///
+    /// Called when the first line of a paragraph is laid out. Paragraphs are separated by newline
+    /// characters, and `paragraph_index` counts them from the start of the laid out text. The
+    /// prefix is measured and drawn like text at the start of the line, and the wrapped lines of
+    /// the paragraph are indented by the width of the prefix. Use non-breaking spaces to separate
+    /// words of the prefix. A prefix that doesn't fit into the line is not displayed.
+    ///
+    /// This function is called both when the text is measured and when it is rendered, so it
+    /// must return the same prefix for the same paragraph every time.
+    #[inline]
+    fn line_prefix(&mut self, _paragraph_index: u32) -> Option<&'a str> {
+        None
+    }
+
     /// Generate the next text token.
+    ///
+    /// Returned tokens don't need to be borrowed from the text. Tokens that aren't part of the
+    /// text are laid out like any other token, but they are not considered when calculating the
+    /// position of the rest of the text, e.g. the text returned by `draw`.
     #[inline]
     fn next_token(
         &mut self,
@@ -47,6 +105,38 @@ where
         Some(token)
     }
 
+    /// Returns the position of the first style span boundary inside a word.
+    ///
+    /// `offset` is the byte offset of `word` in the text of the text box, and the returned value
+    /// is a byte offset in `word`. The word is split at the returned position, and both parts are
+    /// measured and rendered separately, so the parts are passed to
+    /// [`post_render`](Self::post_render) one by one. The rest of the word is passed to this
+    /// function again, which allows splitting a word at multiple boundaries. The parts of a split
+    /// word are still wrapped as a single word.
+    ///
+    /// Positions at the edges of the word, and positions that are not character boundaries, are
+    /// ignored.
+    #[inline]
+    fn span_boundary(&mut self, _offset: usize, _word: &str) -> Option<usize> {
+        None
+    }
+
+    /// Called when a word is split across lines while rendering.
+    ///
+    /// `word` is the part of the word that has not been rendered on previous lines, and `at` is
+    /// the byte offset in `word` where it is broken. Words that are moved to the next line as a
+    /// whole are reported, too, with `at` set to 0, see [`WordSplitReason`] for the details.
+    #[inline]
+    fn on_word_split(&mut self, _word: &str, _at: usize, _reason: WordSplitReason) {}
+
+    /// Called when a cursor movement doesn't fit into the line while rendering.
+    ///
+    /// The cursor stops at the edge of the line. `delta` is the part of the movement in pixels
+    /// that was not performed, positive for movements to the right and negative for movements to
+    /// the left.
+    #[inline]
+    fn on_cursor_overflow(&mut self, _delta: i32) {}
+
     /// Called after a piece of text is rendered.
     #[inline]
     fn post_render<T, D>(
@@ -64,6 +154,11 @@ where
     }
 
     /// Called before TextBox rendering is started.
+    ///
+    /// This is the only hook that receives the cursor, and it is called before any text is
+    /// rendered. Plugins that move the cursor to lay out the text in multiple passes can use
+    /// [`Cursor::save`] and [`Cursor::restore`] to return to the starting state before the final
+    /// pass. Restoring the cursor doesn't undo text that was already rendered.
     #[inline]
     fn on_start_render<S: CharacterStyle + TextRenderer>(
         &mut self,
@@ -72,6 +167,13 @@ where
     ) {
     }
 
+    /// Called when rendering stops because the rest of the text is below the text box.
+    ///
+    /// This is called before [`on_rendering_finished`](Self::on_rendering_finished), and only if
+    /// some of the text was not rendered.
+    #[inline]
+    fn on_truncated(&mut self, _info: TruncationInfo) {}
+
     /// Called after rendering has finished.
     #[inline]
     fn on_rendering_finished(&mut self) {}
@@ -89,6 +191,11 @@ where
         self.object.new_line();
     }
 
+    #[inline]
+    fn line_prefix(&mut self, paragraph_index: u32) -> Option<&'a str> {
+        self.object.line_prefix(paragraph_index)
+    }
+
     fn next_token(
         &mut self,
         next_token: impl FnMut() -> Option<Token<'a, C>>,
@@ -100,6 +207,21 @@ where
         self.object.render_token(token)
     }
 
+    #[inline]
+    fn span_boundary(&mut self, offset: usize, word: &str) -> Option<usize> {
+        self.object.span_boundary(offset, word)
+    }
+
+    #[inline]
+    fn on_word_split(&mut self, word: &str, at: usize, reason: WordSplitReason) {
+        self.object.on_word_split(word, at, reason);
+    }
+
+    #[inline]
+    fn on_cursor_overflow(&mut self, delta: i32) {
+        self.object.on_cursor_overflow(delta);
+    }
+
     fn post_render<T, D>(
         &mut self,
         draw_target: &mut D,
@@ -123,6 +245,11 @@ where
         self.object.on_start_render(cursor, props);
     }
 
+    #[inline]
+    fn on_truncated(&mut self, info: TruncationInfo) {
+        self.object.on_truncated(info);
+    }
+
     fn on_rendering_finished(&mut self) {
         self.object.on_rendering_finished();
     }
@@ -140,6 +267,14 @@ where
         self.object.new_line();
     }
 
+    #[inline]
+    fn line_prefix(&mut self, paragraph_index: u32) -> Option<&'a str> {
+        let parent = self.parent.line_prefix(paragraph_index);
+        let object = self.object.line_prefix(paragraph_index);
+
+        parent.or(object)
+    }
+
     fn next_token(
         &mut self,
         mut next_token: impl FnMut() -> Option<Token<'a, C>>,
@@ -155,6 +290,31 @@ where
             .and_then(|t| self.object.render_token(t))
     }
 
+    #[inline]
+    fn span_boundary(&mut self, offset: usize, word: &str) -> Option<usize> {
+        let inside = |at: &usize| (1..word.len()).contains(at) && word.is_char_boundary(*at);
+
+        let parent = self.parent.span_boundary(offset, word).filter(inside);
+        let object = self.object.span_boundary(offset, word).filter(inside);
+
+        match (parent, object) {
+            (Some(parent), Some(object)) => Some(parent.min(object)),
+            (parent, object) => parent.or(object),
+        }
+    }
+
+    #[inline]
+    fn on_word_split(&mut self, word: &str, at: usize, reason: WordSplitReason) {
+        self.parent.on_word_split(word, at, reason);
+        self.object.on_word_split(word, at, reason);
+    }
+
+    #[inline]
+    fn on_cursor_overflow(&mut self, delta: i32) {
+        self.parent.on_cursor_overflow(delta);
+        self.object.on_cursor_overflow(delta);
+    }
+
     fn post_render<T, D>(
         &mut self,
         draw_target: &mut D,
@@ -181,6 +341,12 @@ where
         self.object.on_start_render(cursor, props);
     }
 
+    #[inline]
+    fn on_truncated(&mut self, info: TruncationInfo) {
+        self.parent.on_truncated(info);
+        self.object.on_truncated(info);
+    }
+
     fn on_rendering_finished(&mut self) {
         self.parent.on_rendering_finished();
         self.object.on_rendering_finished();
@@ -24,11 +24,15 @@ impl<'a, C: PixelColor> Plugin<'a, C> for Tail {
         cursor: &mut Cursor,
         props: &TextBoxProperties<'_, S>,
     ) {
-        let box_height = props.bounding_box.size.height.saturating_as();
+        let box_height = props
+            .box_style
+            .padding
+            .inner_height(props.bounding_box.size.height)
+            .saturating_as();
         if props.text_height > box_height {
             let offset = box_height - props.text_height;
 
-            cursor.y += offset;
+            cursor.move_down(offset);
         }
     }
 }
@@ -36,15 +40,22 @@ impl<'a, C: PixelColor> Plugin<'a, C> for Tail {
 #[cfg(test)]
 mod test {
     use embedded_graphics::{
+        draw_target::DrawTargetExt,
         mock_display::MockDisplay,
         mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
         pixelcolor::BinaryColor,
         prelude::{Point, Size},
         primitives::Rectangle,
+        text::LineHeight,
         Drawable,
     };
 
-    use crate::{plugin::tail::Tail, style::TextBoxStyle, utils::test::size_for, TextBox};
+    use crate::{
+        plugin::tail::Tail,
+        style::{HeightMode, TextBoxStyle, TextBoxStyleBuilder, VerticalOverdraw},
+        utils::test::size_for,
+        TextBox,
+    };
 
     #[track_caller]
     pub fn assert_rendered(text: &str, size: Size, pattern: &[&str]) {
@@ -126,4 +137,53 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn last_line_is_visible_with_paragraph_spacing_and_line_height() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+        let style = TextBoxStyleBuilder::new()
+            .paragraph_spacing(6)
+            .line_height(LineHeight::Percent(150))
+            .height_mode(HeightMode::Exact(VerticalOverdraw::Hidden))
+            .build();
+
+        let text = "aa bb\ncc dd ee";
+        let width = size_for(&FONT_6X9, 2, 1).width;
+        let text_height = style.measure_text_height(&character_style, text, width);
+        // 4 lines of 13px, a paragraph break and the glyphs of the last line.
+        assert_eq!(text_height, 4 * 13 + 6 + 9);
+
+        // The box cuts the second paragraph in the middle of "cc".
+        let bounds = Rectangle::new(Point::zero(), Size::new(width, 30));
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(text, bounds, character_style, style)
+            .add_plugin(Tail)
+            .draw(&mut display)
+            .unwrap();
+
+        // The same text, moved up so that it ends at the bottom of the box.
+        let mut expected = MockDisplay::new();
+        TextBox::with_textbox_style(
+            text,
+            Rectangle::new(
+                Point::new(0, 30 - text_height as i32),
+                Size::new(width, text_height),
+            ),
+            character_style,
+            style,
+        )
+        .draw(&mut expected.clipped(&bounds))
+        .unwrap();
+
+        display.assert_eq(&expected);
+        assert_eq!(
+            display.affected_area().bottom_right().map(|p| p.y),
+            Some(29)
+        );
+    }
 }
@@ -0,0 +1,246 @@
+//! Character advance cache.
+//!
+//! Line processing measures the same words several times while looking ahead for wrapping
+//! decisions. Instead of asking the character style every time, the advances of individual
+//! characters are measured once and strings are measured by adding them up.
+//!
+//! Character styles are expected to measure a run of characters as the sum of their advances,
+//! plus a constant spacing between adjacent characters. This is how the monospace fonts of
+//! `embedded-graphics` and most proportional font renderers work.
+
+use crate::{
+    rendering::line_iter::ElementHandler,
+    style::{ControlCharacters, TextTransform},
+    utils::{is_zero_width, kerning_adjustment},
+};
+
+/// Number of cached ASCII characters.
+const ASCII_LEN: usize = 128;
+
+/// Number of cached non-ASCII characters.
+const OTHER_LEN: usize = 4;
+
+/// Marks an ASCII character whose advance is not cached yet.
+const UNKNOWN: u16 = u16::MAX;
+
+/// Advance widths of the characters measured using the current text style.
+#[derive(Clone, Debug)]
+pub(crate) struct AdvanceCache {
+    kerning: Option<fn(char, char) -> i8>,
+    transform: TextTransform,
+    control_characters: ControlCharacters,
+    spacing: Option<i32>,
+    ascii: [u16; ASCII_LEN],
+
+    /// Recently used non-ASCII characters, most recently used first.
+    other: [Option<(char, u16)>; OTHER_LEN],
+}
+
+impl AdvanceCache {
+    pub const fn new(
+        kerning: Option<fn(char, char) -> i8>,
+        transform: TextTransform,
+        control_characters: ControlCharacters,
+    ) -> Self {
+        Self {
+            kerning,
+            transform,
+            control_characters,
+            spacing: None,
+            ascii: [UNKNOWN; ASCII_LEN],
+            other: [None; OTHER_LEN],
+        }
+    }
+
+    /// Forgets every measured value. Must be called when the text style changes.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.kerning, self.transform, self.control_characters);
+    }
+
+    /// Returns the width of a string, including kerning adjustments, the text transformation and
+    /// the display of control characters.
+    pub fn str_width<E: ElementHandler + ?Sized>(&mut self, handler: &E, st: &str) -> u32 {
+        let mut width = 0;
+        let mut prev = None;
+        let mut in_run = false;
+
+        for c in st.chars() {
+            if is_zero_width(c) {
+                // Zero-width characters are not measured, the characters around them are measured
+                // as separate runs.
+                in_run = false;
+                continue;
+            }
+            for c in self.control_characters.apply(c) {
+                let c = self.transform.apply(c);
+
+                if in_run {
+                    width += self.spacing(handler);
+                }
+                if let Some(prev) = prev {
+                    width += kerning_adjustment(self.kerning, prev, c);
+                }
+                width += self.advance(handler, c) as i32;
+
+                prev = Some(c);
+                in_run = true;
+            }
+        }
+
+        width.max(0) as u32
+    }
+
+    /// Returns the spacing the character style inserts between adjacent characters.
+    fn spacing<E: ElementHandler + ?Sized>(&mut self, handler: &E) -> i32 {
+        match self.spacing {
+            Some(spacing) => spacing,
+            None => {
+                // Kerning is not applied to whitespace, so a pair of spaces measures the spacing.
+                let spacing = handler.measure("  ") as i32 - 2 * self.advance(handler, ' ') as i32;
+                self.spacing = Some(spacing);
+                spacing
+            }
+        }
+    }
+
+    fn advance<E: ElementHandler + ?Sized>(&mut self, handler: &E, c: char) -> u32 {
+        if c.is_ascii() {
+            let cached = self.ascii[c as usize];
+            if cached != UNKNOWN {
+                return cached as u32;
+            }
+
+            let advance = measure_char(handler, c);
+            if advance < UNKNOWN as u32 {
+                self.ascii[c as usize] = advance as u16;
+            }
+            return advance;
+        }
+
+        let hit = self
+            .other
+            .iter()
+            .position(|entry| matches!(entry, Some((cached, _)) if *cached == c));
+
+        let (idx, advance) = match hit {
+            Some(idx) => (
+                idx,
+                self.other[idx].map_or(0, |(_, advance)| advance as u32),
+            ),
+            None => {
+                let advance = measure_char(handler, c);
+                if advance >= UNKNOWN as u32 {
+                    return advance;
+                }
+
+                // Replace the least recently used entry.
+                let idx = OTHER_LEN - 1;
+                self.other[idx] = Some((c, advance as u16));
+                (idx, advance)
+            }
+        };
+
+        // Move the entry to the front.
+        self.other[..=idx].rotate_right(1);
+
+        advance
+    }
+}
+
+fn measure_char<E: ElementHandler + ?Sized>(handler: &E, c: char) -> u32 {
+    let mut buffer = [0; 4];
+
+    handler.measure(c.encode_utf8(&mut buffer))
+}
+
+#[cfg(test)]
+mod test {
+    use core::cell::Cell;
+
+    use embedded_graphics::{
+        mono_font::{ascii::FONT_6X9, MonoFont, MonoTextStyle},
+        pixelcolor::BinaryColor,
+    };
+
+    use super::AdvanceCache;
+    use crate::{
+        parser::Parser,
+        plugin::{NoPlugin, PluginWrapper},
+        rendering::line_iter::ElementHandler,
+        style::{ControlCharacters, TextBoxStyleBuilder, TextTransform},
+        utils::{
+            str_width_kerned,
+            test::{size_for, Counting},
+        },
+    };
+
+    struct Measure<'a>(MonoTextStyle<'a, BinaryColor>, Option<fn(char, char) -> i8>);
+
+    impl ElementHandler for Measure<'_> {
+        type Error = ();
+        type Color = BinaryColor;
+
+        fn measure(&self, st: &str) -> u32 {
+            str_width_kerned(&self.0, st, self.1)
+        }
+    }
+
+    fn kerning(prev: char, next: char) -> i8 {
+        match (prev, next) {
+            ('A', 'V') => -2,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn cached_widths_match_measured_widths() {
+        const SPACED: MonoFont = MonoFont {
+            character_spacing: 2,
+            ..FONT_6X9
+        };
+
+        for font in [&FONT_6X9, &SPACED] {
+            for kerning in [None, Some(kerning as fn(char, char) -> i8)] {
+                let handler = Measure(MonoTextStyle::new(font, BinaryColor::On), kerning);
+                let mut cache =
+                    AdvanceCache::new(kerning, TextTransform::None, ControlCharacters::Keep);
+
+                for text in ["", "a", "AVA", "A V", "word", "A\u{fe0f}B", "áé\u{a0}ő"] {
+                    assert_eq!(
+                        cache.str_width(&handler, text),
+                        handler.measure(text),
+                        "{:?}",
+                        text
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn characters_are_measured_once_per_line() {
+        let text = "the cat sat on the mat and the rat sat on the hat";
+
+        let calls = Cell::new(0);
+        let chars = Cell::new(0);
+        let counting = Counting {
+            inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            calls: &calls,
+            chars: &chars,
+        };
+
+        let style = TextBoxStyleBuilder::new().build();
+        let lm = style.measure_line(
+            &PluginWrapper::new(NoPlugin::new()),
+            &counting,
+            &mut Parser::parse(text),
+            size_for(&FONT_6X9, 60, 1).width,
+        );
+        assert_eq!(lm.width, size_for(&FONT_6X9, 49, 1).width);
+
+        // The tab size, the 13 different characters, and the spacing between two spaces. Without
+        // the cache, the line takes 40 calls that measure 110 characters.
+        assert_eq!(calls.get(), 1 + 13 + 1);
+        assert_eq!(chars.get(), 1 + 13 + 2);
+    }
+}
@@ -0,0 +1,126 @@
+//! Minimal support for right-to-left text in directional isolates.
+//!
+//! Full bidirectional text layout is not supported. Text is laid out in logical order, and only
+//! the characters between a right-to-left isolate (U+2067) and the matching pop directional
+//! isolate (U+2069) are displayed in reverse order. The part of a run that is laid out in a line
+//! is mirrored as a whole, so the order of its words and spaces is reversed, too.
+//!
+//! Nested isolates are not supported. The part of a right-to-left run after a nested isolate is
+//! displayed in logical order.
+
+use crate::parser::{SPEC_CHAR_FSI, SPEC_CHAR_LRI, SPEC_CHAR_PDI, SPEC_CHAR_RLI};
+
+/// Directional isolate state of the rendered text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Isolates {
+    depth: u32,
+    rtl: bool,
+    nested: bool,
+}
+
+impl Isolates {
+    pub const fn new() -> Self {
+        Self {
+            depth: 0,
+            rtl: false,
+            nested: false,
+        }
+    }
+
+    /// Returns whether the characters at the current position are displayed in reverse order.
+    pub const fn is_reversed(&self) -> bool {
+        self.depth == 1 && self.rtl && !self.nested
+    }
+
+    fn update(&mut self, c: char) {
+        match c {
+            SPEC_CHAR_LRI | SPEC_CHAR_RLI | SPEC_CHAR_FSI => {
+                self.depth += 1;
+                if self.depth == 1 {
+                    self.rtl = c == SPEC_CHAR_RLI;
+                } else {
+                    self.nested = true;
+                }
+            }
+            SPEC_CHAR_PDI if self.depth > 0 => {
+                self.depth -= 1;
+                if self.depth == 0 {
+                    *self = Self::new();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Splits off the longest prefix of `st` that is displayed in the same direction.
+    ///
+    /// Returns the prefix, whether it is displayed in reverse order, and the rest of the string.
+    pub fn split<'s>(&mut self, st: &'s str) -> (&'s str, bool, &'s str) {
+        let reversed = self.is_reversed();
+
+        for (idx, c) in st.char_indices() {
+            self.update(c);
+            if self.is_reversed() != reversed {
+                // The mark that changes the direction is zero-width, it can be in either part.
+                let (prefix, rest) = st.split_at(idx + c.len_utf8());
+                return (prefix, reversed, rest);
+            }
+        }
+
+        (st, reversed, "")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Isolates;
+
+    fn split_all(st: &str) -> Vec<(&str, bool)> {
+        let mut isolates = Isolates::new();
+        let mut rest = st;
+        let mut parts = Vec::new();
+
+        while !rest.is_empty() {
+            let (part, reversed, next) = isolates.split(rest);
+            parts.push((part, reversed));
+            rest = next;
+        }
+
+        parts
+    }
+
+    #[test]
+    fn rtl_isolates_are_reversed() {
+        assert_eq!(split_all("abc"), [("abc", false)]);
+        assert_eq!(
+            split_all("a\u{2067}bc\u{2069}d"),
+            [("a\u{2067}", false), ("bc\u{2069}", true), ("d", false)]
+        );
+        assert_eq!(
+            split_all("a\u{2066}bc\u{2069}d"),
+            [("a\u{2066}bc\u{2069}d", false)]
+        );
+    }
+
+    #[test]
+    fn nested_isolates_are_displayed_in_logical_order() {
+        assert_eq!(
+            split_all("\u{2067}ab\u{2066}cd\u{2069}ef\u{2069}g"),
+            [
+                ("\u{2067}", false),
+                ("ab\u{2066}", true),
+                ("cd\u{2069}ef\u{2069}g", false)
+            ]
+        );
+
+        // Unmatched marks don't panic.
+        assert_eq!(
+            split_all("\u{2069}\u{2069}a"),
+            [("\u{2069}\u{2069}a", false)]
+        );
+        assert_eq!(
+            split_all("\u{2067}\u{2067}a"),
+            [("\u{2067}", false), ("\u{2067}", true), ("a", false)]
+        );
+    }
+}
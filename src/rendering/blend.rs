@@ -0,0 +1,208 @@
+//! Blending text over the existing contents of a draw target.
+use embedded_graphics::{
+    draw_target::DrawTarget, geometry::Dimensions, image::GetPixel, pixelcolor::PixelColor,
+    primitives::Rectangle, Pixel,
+};
+
+/// Colors that can be blended over a background color.
+///
+/// Implement this trait for color types that carry transparency information, to render text
+/// using [`TextBox::draw_blended`].
+///
+/// [`TextBox::draw_blended`]: crate::TextBox::draw_blended
+pub trait BlendColor: PixelColor {
+    /// Returns the color of a pixel after drawing `self` over `background`.
+    fn blend(self, background: Self) -> Self;
+}
+
+/// Draw target adapter that blends every pixel over the current contents of the target.
+pub(crate) struct Blended<'d, D> {
+    parent: &'d mut D,
+}
+
+impl<'d, D> Blended<'d, D> {
+    pub fn new(parent: &'d mut D) -> Self {
+        Self { parent }
+    }
+}
+
+impl<D: Dimensions> Dimensions for Blended<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.parent.bounding_box()
+    }
+}
+
+impl<C, D> DrawTarget for Blended<'_, D>
+where
+    C: BlendColor,
+    D: DrawTarget<Color = C> + GetPixel<Color = C>,
+{
+    type Color = C;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            // Pixels outside of the target are passed on unchanged, the target will ignore them.
+            let color = match self.parent.pixel(point) {
+                Some(background) => color.blend(background),
+                None => color,
+            };
+
+            self.parent
+                .draw_iter(core::iter::once(Pixel(point, color)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        draw_target::DrawTarget,
+        geometry::{OriginDimensions, Point, Size},
+        image::GetPixel,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle},
+        pixelcolor::{PixelColor, Rgb888, RgbColor},
+        primitives::Rectangle,
+        Pixel,
+    };
+
+    use super::BlendColor;
+    use crate::TextBox;
+
+    /// Gray color with an alpha channel.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct GrayAlpha {
+        luma: u8,
+        alpha: u8,
+    }
+
+    impl GrayAlpha {
+        const fn new(luma: u8, alpha: u8) -> Self {
+            Self { luma, alpha }
+        }
+    }
+
+    impl PixelColor for GrayAlpha {
+        type Raw = ();
+    }
+
+    impl From<Rgb888> for GrayAlpha {
+        fn from(color: Rgb888) -> Self {
+            Self::new(color.g(), u8::MAX)
+        }
+    }
+
+    impl BlendColor for GrayAlpha {
+        fn blend(self, background: Self) -> Self {
+            let alpha = self.alpha as u32;
+            let luma =
+                (self.luma as u32 * alpha + background.luma as u32 * (255 - alpha) + 127) / 255;
+
+            Self::new(luma as u8, background.alpha)
+        }
+    }
+
+    const WIDTH: usize = 24;
+    const HEIGHT: usize = 9;
+
+    struct Framebuffer([GrayAlpha; WIDTH * HEIGHT]);
+
+    impl Framebuffer {
+        /// Creates a framebuffer filled with a horizontal gradient.
+        fn gradient() -> Self {
+            let mut buffer = [GrayAlpha::new(0, 255); WIDTH * HEIGHT];
+            for (i, pixel) in buffer.iter_mut().enumerate() {
+                pixel.luma = ((i % WIDTH) * 10) as u8;
+            }
+
+            Self(buffer)
+        }
+
+        fn index(&self, point: Point) -> Option<usize> {
+            let (x, y) = (point.x as usize, point.y as usize);
+
+            if point.x >= 0 && point.y >= 0 && x < WIDTH && y < HEIGHT {
+                Some(y * WIDTH + x)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl OriginDimensions for Framebuffer {
+        fn size(&self) -> Size {
+            Size::new(WIDTH as u32, HEIGHT as u32)
+        }
+    }
+
+    impl GetPixel for Framebuffer {
+        type Color = GrayAlpha;
+
+        fn pixel(&self, point: Point) -> Option<GrayAlpha> {
+            self.index(point).map(|idx| self.0[idx])
+        }
+    }
+
+    impl DrawTarget for Framebuffer {
+        type Color = GrayAlpha;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<GrayAlpha>>,
+        {
+            for Pixel(point, color) in pixels {
+                if let Some(idx) = self.index(point) {
+                    self.0[idx] = color;
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    fn render(color: GrayAlpha) -> Framebuffer {
+        let mut framebuffer = Framebuffer::gradient();
+
+        TextBox::new(
+            "word",
+            Rectangle::new(Point::zero(), Size::new(WIDTH as u32, HEIGHT as u32)),
+            MonoTextStyle::new(&FONT_6X9, color),
+        )
+        .draw_blended(&mut framebuffer)
+        .unwrap();
+
+        framebuffer
+    }
+
+    #[test]
+    fn half_transparent_text_is_blended_over_background() {
+        let background = Framebuffer::gradient();
+        let opaque = render(GrayAlpha::new(255, 255));
+        let blended = render(GrayAlpha::new(255, 128));
+
+        let mut text_pixels = 0;
+        for ((bg, opaque), blended) in background.0.iter().zip(opaque.0).zip(blended.0) {
+            if opaque == *bg {
+                // Pixels not covered by the text are not changed.
+                assert_eq!(blended, *bg);
+            } else {
+                text_pixels += 1;
+                let expected = (255 * 128 + bg.luma as u32 * 127 + 127) / 255;
+                assert_eq!(blended, GrayAlpha::new(expected as u8, 255));
+            }
+        }
+        assert!(text_pixels > 0);
+
+        // The last column of "d" is drawn over a background luma of 220.
+        assert_eq!(
+            blended.pixel(Point::new(22, 1)),
+            Some(GrayAlpha::new(238, 255))
+        );
+    }
+}
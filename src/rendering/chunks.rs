@@ -0,0 +1,443 @@
+//! Resumable, chunked rendering.
+//!
+//! [`PixelChunks`] renders the text box one row segment at a time, in the same order as
+//! [`TextBox::draw_ordered`]. Rendering is suspended between chunks, so the pixels of a chunk can
+//! be sent to the display, for example over DMA, while the next chunk is waiting to be rendered.
+use crate::{
+    plugin::PluginMarker as Plugin,
+    rendering::{
+        cursor::Cursor,
+        line::LineRenderState,
+        line_iter::LineEndType,
+        ordered::{PixelChunk, RowSegment, SEGMENT_WIDTH},
+    },
+    TextBox,
+};
+use embedded_graphics::{
+    draw_target::DrawTargetExt,
+    geometry::{Point, Size},
+    pixelcolor::Rgb888,
+    primitives::Rectangle,
+    text::renderer::{CharacterStyle, TextRenderer},
+};
+
+/// The part of the current line that is left to render.
+struct LineProgress {
+    /// The rendered area of the line, including the list marker and the rows the glyphs are
+    /// moved into by vertical offsets.
+    area: Rectangle,
+
+    /// The area of the line itself, including the list marker.
+    line_area: Rectangle,
+
+    /// The list marker of the line, and its position.
+    marker: Option<(&'static str, Point)>,
+
+    /// The origin of the next chunk.
+    next: Point,
+
+    /// Whether the line must not end with a broken word.
+    avoid_broken_word: bool,
+}
+
+/// Iterator over the pixels of a text box, in row segments.
+///
+/// Chunks are produced in the order [`TextBox::draw_ordered`] draws pixels. Each chunk is
+/// rendered when it is requested, so the iterator can be paused between chunks without
+/// buffering the rendered text. Drawing every chunk produces the same image as
+/// [`draw`](embedded_graphics::Drawable::draw).
+///
+/// Created by [`TextBox::pixel_chunks`].
+pub struct PixelChunks<'a, 'b, F, M>
+where
+    F: TextRenderer<Color = <F as CharacterStyle>::Color> + CharacterStyle,
+    <F as CharacterStyle>::Color: From<Rgb888>,
+    M: Plugin<'a, <F as TextRenderer>::Color> + Plugin<'a, <F as CharacterStyle>::Color>,
+{
+    text_box: &'b TextBox<'a, F, M>,
+    cursor: Cursor,
+    state: LineRenderState<'a, 'b, F, M>,
+    marker_width: u32,
+    line: Option<LineProgress>,
+    anything_drawn: bool,
+    paragraph_start: bool,
+    done: bool,
+}
+
+impl<'a, 'b, F, M> PixelChunks<'a, 'b, F, M>
+where
+    F: TextRenderer<Color = <F as CharacterStyle>::Color> + CharacterStyle,
+    <F as CharacterStyle>::Color: From<Rgb888>,
+    M: Plugin<'a, <F as TextRenderer>::Color> + Plugin<'a, <F as CharacterStyle>::Color>,
+{
+    pub(crate) fn new(text_box: &'b TextBox<'a, F, M>) -> Self {
+        let (cursor, state, marker_width) = text_box.start_render(0);
+
+        Self {
+            text_box,
+            cursor,
+            paragraph_start: matches!(
+                state.end_type,
+                LineEndType::EndOfText | LineEndType::NewLine
+            ),
+            state,
+            marker_width,
+            line: None,
+            anything_drawn: false,
+            done: false,
+        }
+    }
+
+    /// Prepares rendering the line at the cursor.
+    fn start_line(&mut self) {
+        self.state.plugin.new_line();
+
+        let line_start = self.cursor.line_start();
+        let line_area = self.text_box.displayed_line_area(&self.cursor);
+
+        if line_area.size.height == 0 {
+            // Display range can be empty if we are above, or below the visible text section
+            if self.anything_drawn {
+                // We are below, so we won't be drawing anything else
+                let consumed_bytes = self.text_box.consumed_bytes(&self.state);
+                self.state
+                    .plugin
+                    .post_render(
+                        &mut RowSegment::new(Point::zero()).clipped(&Rectangle::zero()),
+                        &self.text_box.character_style,
+                        None,
+                        Rectangle::new(line_start, Size::new(0, self.cursor.line_height())),
+                    )
+                    .unwrap_or_else(|e| match e {});
+                self.text_box
+                    .report_truncation(&self.cursor, &self.state, consumed_bytes);
+                self.state.plugin.on_rendering_finished();
+                self.done = true;
+                return;
+            }
+        } else {
+            self.anything_drawn = true;
+        }
+
+        let marker = self
+            .text_box
+            .line_marker(self.paragraph_start, line_start, self.marker_width);
+        let line_area = TextBox::<F, M>::marker_area(line_area, marker, self.marker_width);
+        let text_area = self
+            .text_box
+            .style
+            .text_area(self.text_box.bounds, self.marker_width);
+        let avoid_broken_word = self.text_box.avoid_broken_word(&self.cursor);
+        let area = self.text_box.glyph_area(
+            line_area,
+            text_area,
+            self.cursor.line(),
+            &self.state,
+            avoid_broken_word,
+        );
+
+        self.line = Some(LineProgress {
+            area,
+            line_area,
+            marker,
+            next: area.top_left,
+            avoid_broken_word,
+        });
+    }
+
+    /// Advances the render state past the current line, and moves the cursor to the next line.
+    fn finish_line(&mut self) {
+        let avoid_broken_word = self
+            .line
+            .take()
+            .map_or(false, |line| line.avoid_broken_word);
+        self.text_box
+            .skip_line(self.cursor.line(), &mut self.state, avoid_broken_word);
+
+        self.paragraph_start = self.state.end_type == LineEndType::NewLine;
+        if self.state.end_type == LineEndType::EndOfText {
+            self.state.plugin.on_rendering_finished();
+            self.done = true;
+        } else {
+            self.text_box
+                .next_line(&mut self.cursor, self.state.end_type);
+        }
+    }
+}
+
+impl<'a, 'b, F, M> Iterator for PixelChunks<'a, 'b, F, M>
+where
+    F: TextRenderer<Color = <F as CharacterStyle>::Color> + CharacterStyle,
+    <F as CharacterStyle>::Color: From<Rgb888>,
+    M: Plugin<'a, <F as TextRenderer>::Color> + Plugin<'a, <F as CharacterStyle>::Color>,
+{
+    type Item = PixelChunk<<F as CharacterStyle>::Color>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let line = match self.line.as_mut() {
+                Some(line) => line,
+                None => {
+                    self.start_line();
+                    continue;
+                }
+            };
+
+            let bottom_right = match line.area.bottom_right() {
+                Some(bottom_right) if line.next.y <= bottom_right.y => bottom_right,
+                _ => {
+                    self.finish_line();
+                    continue;
+                }
+            };
+
+            let origin = line.next;
+            line.next.x += SEGMENT_WIDTH as i32;
+            if line.next.x > bottom_right.x {
+                line.next = Point::new(line.area.top_left.x, line.next.y + 1);
+            }
+
+            let (area, line_area, marker, avoid_broken_word) = (
+                line.area,
+                line.line_area,
+                line.marker,
+                line.avoid_broken_word,
+            );
+            let chunk = self.text_box.render_segment(
+                &self.state,
+                self.cursor.line(),
+                area,
+                line_area,
+                marker,
+                origin,
+                avoid_broken_word,
+            );
+            if !chunk.is_empty() {
+                return Some(chunk);
+            }
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // The number of chunks is only known after the text is laid out.
+        if self.done {
+            (0, Some(0))
+        } else {
+            (0, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+    };
+
+    use crate::{
+        alignment::HorizontalAlignment,
+        style::{HeightMode, TextBoxStyleBuilder, VerticalOverdraw},
+        TextBox,
+    };
+
+    #[test]
+    fn chunks_draw_the_same_pixels_as_draw() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .underline()
+            .build();
+
+        for alignment in [HorizontalAlignment::Left, HorizontalAlignment::Justified] {
+            let style = TextBoxStyleBuilder::new()
+                .alignment(alignment)
+                .height_mode(HeightMode::Exact(VerticalOverdraw::FullRowsOnly))
+                .paragraph_spacing(2)
+                .list_marker("-")
+                .build();
+
+            let text_box = TextBox::with_textbox_style(
+                "Some text that is wrapped\ninto multiple paragraphs\rand lines.",
+                Rectangle::new(Point::new(1, 2), Size::new(50, 40)),
+                character_style,
+                style,
+            );
+
+            let mut expected = MockDisplay::new();
+            expected.set_allow_overdraw(true);
+            text_box.draw(&mut expected).unwrap();
+
+            let mut display = MockDisplay::new();
+            display.set_allow_overdraw(true);
+            for chunk in text_box.pixel_chunks() {
+                chunk.draw(&mut display).unwrap();
+            }
+
+            display.assert_eq(&expected);
+        }
+    }
+
+    #[test]
+    fn pixel_count_upper_bound_holds_for_random_text() {
+        let transparent = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .underline()
+            .build();
+        let opaque = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+
+        let mut seed = 0x2545_f491_u32;
+        let mut random_text = |len: usize| {
+            (0..len)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    ['a', 'b', 'c', ' ', ' ', '\n', '\r', '\t'][seed as usize % 8]
+                })
+                .collect::<String>()
+        };
+
+        for len in 0..60 {
+            let text = random_text(len);
+
+            for height_mode in [
+                HeightMode::Exact(VerticalOverdraw::FullRowsOnly),
+                HeightMode::Exact(VerticalOverdraw::Visible),
+                HeightMode::FitToText,
+            ] {
+                let style = TextBoxStyleBuilder::new()
+                    .height_mode(height_mode)
+                    .pad_lines(true)
+                    .build();
+                let bounds = Rectangle::new(Point::new(1, 2), Size::new(50, 40));
+
+                let text_box = TextBox::with_textbox_style(&text, bounds, transparent, style);
+                let drawn = text_box
+                    .pixel_chunks()
+                    .map(|chunk| chunk.pixels().count())
+                    .sum::<usize>();
+                assert!(drawn <= text_box.pixel_count_upper_bound() as usize);
+
+                // Every pixel of every line is drawn, except the space of tabs.
+                let text_box = TextBox::with_textbox_style(&text, bounds, opaque, style);
+                let drawn = text_box
+                    .pixel_chunks()
+                    .map(|chunk| chunk.pixels().count())
+                    .sum::<usize>();
+                let bound = text_box.pixel_count_upper_bound() as usize;
+                if text.contains('\t') {
+                    assert!(drawn <= bound);
+                } else {
+                    assert_eq!(drawn, bound, "{:?}", text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_are_ordered_row_segments() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+
+        let text_box = TextBox::new(
+            "Some text that is long enough to be split into chunks",
+            Rectangle::new(Point::zero(), Size::new(60, 20)),
+            character_style,
+        );
+
+        let mut last: Option<Point> = None;
+        for chunk in text_box.pixel_chunks() {
+            let pixels = chunk.pixels().collect::<Vec<_>>();
+            assert!(!pixels.is_empty());
+            assert!(pixels.iter().all(|Pixel(p, _)| p.y == chunk.origin().y));
+            assert!(pixels.len() <= 32);
+
+            if let Some(last) = last {
+                let origin = chunk.origin();
+                assert!(
+                    (origin.y, origin.x) > (last.y, last.x),
+                    "{origin:?} {last:?}"
+                );
+            }
+            last = Some(chunk.origin());
+        }
+        assert!(last.is_some());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn draw_chunked_passes_every_chunk() {
+        use core::{
+            future::{ready, Future},
+            pin::Pin,
+            task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+        };
+
+        fn block_on<T>(future: impl Future<Output = T>) -> T {
+            fn raw_waker() -> RawWaker {
+                fn clone(_: *const ()) -> RawWaker {
+                    raw_waker()
+                }
+                fn noop(_: *const ()) {}
+
+                static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+                RawWaker::new(core::ptr::null(), &VTABLE)
+            }
+
+            let waker = unsafe { Waker::from_raw(raw_waker()) };
+            let mut context = Context::from_waker(&waker);
+            let mut future = Box::pin(future);
+
+            loop {
+                if let Poll::Ready(output) = Pin::as_mut(&mut future).poll(&mut context) {
+                    return output;
+                }
+            }
+        }
+
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let text_box = TextBox::new(
+            "Some text\ndrawn in chunks",
+            Rectangle::new(Point::zero(), Size::new(60, 30)),
+            character_style,
+        );
+
+        let mut display = MockDisplay::new();
+        block_on(text_box.draw_chunked(|chunk| ready(chunk.draw(&mut display)))).unwrap();
+
+        let mut expected = MockDisplay::new();
+        text_box.draw(&mut expected).unwrap();
+
+        display.assert_eq(&expected);
+
+        // Errors stop drawing.
+        let mut chunks = 0;
+        let result = block_on(text_box.draw_chunked(|_| {
+            chunks += 1;
+            ready(Err(()))
+        }));
+        assert_eq!(result, Err(()));
+        assert_eq!(chunks, 1);
+    }
+}
@@ -3,6 +3,14 @@ use embedded_graphics::{geometry::Point, primitives::Rectangle, text::LineHeight
 
 use az::SaturatingAs;
 
+use crate::utils::line_height_pixels;
+
+/// Width of lines that are not wrapped.
+pub(crate) const UNBOUNDED_LINE_WIDTH: u32 = u32::MAX >> 2;
+
+/// Maximum width of the text area. Wider text boxes lay out text in this width.
+pub(crate) const MAX_TEXT_WIDTH: u32 = 1 << 24;
+
 /// Tracks position within a line.
 #[derive(Debug, Clone)]
 pub struct LineCursor {
@@ -10,6 +18,8 @@ pub struct LineCursor {
     width: u32,
     position: u32,
     tab_width: u32,
+    /// Position that tab stops are measured from.
+    tab_origin: u32,
 }
 
 impl LineCursor {
@@ -20,6 +30,17 @@ impl LineCursor {
             width,
             tab_width,
             position: 0,
+            tab_origin: 0,
+        }
+    }
+
+    /// Returns a cursor for a line that is not limited in width, moved left by `scroll_x` pixels.
+    pub fn into_unbounded(self, scroll_x: u32) -> Self {
+        Self {
+            // Lines are at most `UNBOUNDED_LINE_WIDTH` wide, scrolling further only hides them.
+            start: self.start - Point::new(scroll_x.min(UNBOUNDED_LINE_WIDTH).saturating_as(), 0),
+            width: UNBOUNDED_LINE_WIDTH,
+            ..self
         }
     }
 
@@ -27,14 +48,20 @@ impl LineCursor {
         self.start + Point::new(self.position.saturating_as(), 0)
     }
 
+    /// Measures tab stops from the current position.
+    pub fn anchor_tabs(&mut self) {
+        self.tab_origin = self.position;
+    }
+
     /// Returns the distance to the next tab position.
     pub const fn next_tab_width(&self) -> u32 {
+        let position = self.position.saturating_sub(self.tab_origin);
         let next_tab_pos = if self.tab_width == 0 {
-            self.position
+            position
         } else {
-            (self.position / self.tab_width + 1) * self.tab_width
+            (position / self.tab_width + 1).saturating_mul(self.tab_width)
         };
-        next_tab_pos - self.position
+        next_tab_pos - position
     }
 
     /// Returns the width of the text box.
@@ -49,7 +76,7 @@ impl LineCursor {
 
     /// Returns the amount of empty space in the line.
     pub const fn space(&self) -> u32 {
-        self.width - self.position
+        self.width.saturating_sub(self.position)
     }
 
     /// Moves the cursor by a given amount.
@@ -60,10 +87,10 @@ impl LineCursor {
                 self.position -= abs;
                 Ok(by)
             } else {
-                Err(-(self.position as i32))
+                Err(-self.position.saturating_as::<i32>())
             }
         } else {
-            let space = self.space() as i32;
+            let space = self.space().saturating_as::<i32>();
             if by <= space {
                 // Here we know by > 0, cast is safe
                 self.position += by as u32;
@@ -101,7 +128,11 @@ pub struct Cursor {
     line_width: u32,
     line_height: u32,
     line_spacing: i32,
+    line_heights: Option<fn(line: u32) -> u32>,
     tab_width: u32,
+
+    /// The number of lines started before the current one.
+    line_index: u32,
 }
 
 impl Cursor {
@@ -118,13 +149,30 @@ impl Cursor {
             y: bounds.top_left.y,
 
             top_left: bounds.top_left,
-            bottom: bounds.top_left.y + bounds.size.height.saturating_as::<i32>()
-                - base_line_height.saturating_as::<i32>(),
+            bottom: bounds
+                .top_left
+                .y
+                .saturating_add(bounds.size.height.saturating_as())
+                .saturating_sub(base_line_height.saturating_as()),
 
             line_width: bounds.size.width,
             line_height: base_line_height,
-            line_spacing: line_height.to_absolute(base_line_height).saturating_as(),
+            line_spacing: line_height_pixels(line_height, base_line_height).saturating_as(),
+            line_heights: None,
             tab_width,
+            line_index: 0,
+        }
+    }
+
+    /// Uses a function that returns the line height of each line, instead of a fixed line height.
+    #[must_use]
+    pub(crate) const fn with_line_heights(
+        self,
+        line_heights: Option<fn(line: u32) -> u32>,
+    ) -> Self {
+        Self {
+            line_heights,
+            ..self
         }
     }
 
@@ -135,9 +183,16 @@ impl Cursor {
             width: self.line_width,
             position: 0,
             tab_width: self.tab_width,
+            tab_origin: 0,
         }
     }
 
+    /// Returns the index of the current line, counted from the first line of the text.
+    #[inline]
+    pub(crate) fn line_index(&self) -> u32 {
+        self.line_index
+    }
+
     /// Returns the coordinates of the start of the current line.
     #[inline]
     pub(crate) fn line_start(&self) -> Point {
@@ -171,7 +226,41 @@ impl Cursor {
     /// Starts a new line.
     #[inline]
     pub fn new_line(&mut self) {
-        self.y += self.line_spacing;
+        let line_spacing = match self.line_heights {
+            Some(line_heights) => line_heights(self.line_index).saturating_as(),
+            None => self.line_spacing,
+        };
+        self.move_down(line_spacing);
+        self.line_index = self.line_index.saturating_add(1);
+    }
+
+    /// Moves the cursor down by the given number of pixels. Negative values move the cursor up.
+    #[inline]
+    pub(crate) fn move_down(&mut self, by: i32) {
+        // Lines that far from the text box are never displayed, but the coordinates of their
+        // glyphs must stay representable.
+        let limit = UNBOUNDED_LINE_WIDTH as i32;
+        self.y = self.y.saturating_add(by).clamp(-limit, limit);
+    }
+
+    /// Saves the state of the cursor.
+    ///
+    /// The saved state includes the vertical position, the start and width of the lines and the
+    /// number of lines started so far. Restoring it with [`restore`] and repeating the same
+    /// operations produces the same layout.
+    ///
+    /// [`restore`]: Cursor::restore
+    #[inline]
+    pub fn save(&self) -> CursorState {
+        CursorState(*self)
+    }
+
+    /// Restores a state previously returned by [`save`].
+    ///
+    /// [`save`]: Cursor::save
+    #[inline]
+    pub fn restore(&mut self, state: CursorState) {
+        *self = state.0;
     }
 
     /// Returns whether the cursor is completely in the bounding box.
@@ -186,3 +275,10 @@ impl Cursor {
         self.top_left.y <= self.y && self.y <= self.bottom
     }
 }
+
+/// A saved state of a [`Cursor`].
+///
+/// Created by [`Cursor::save`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct CursorState(Cursor);
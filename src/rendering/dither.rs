@@ -0,0 +1,203 @@
+//! Drawing dimmed text.
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point},
+    pixelcolor::{
+        Bgr555, Bgr565, Bgr666, Bgr888, BinaryColor, Gray2, Gray4, Gray8, GrayColor, PixelColor,
+        Rgb555, Rgb565, Rgb666, Rgb888, RgbColor,
+    },
+    primitives::{PointsIter, Rectangle},
+    Pixel,
+};
+
+/// Colors that can be drawn with reduced intensity.
+///
+/// [`TextBox::draw_dimmed`] uses [`dim`] to scale the colors of text drawn with an [`intensity`]
+/// below 255 towards black. Colors without intermediate levels are dithered instead: pixels are
+/// left out using an ordered dither pattern. The trait is implemented for the color types of
+/// `embedded-graphics`, where only `BinaryColor` is dithered. Custom color types can implement the
+/// trait without overriding [`dim`] to use dithering.
+///
+/// The other drawing methods of the text box dither every color type, so they don't need this
+/// trait.
+///
+/// [`TextBox::draw_dimmed`]: crate::TextBox::draw_dimmed
+/// [`intensity`]: crate::style::TextBoxStyle::intensity
+/// [`dim`]: DimColor::dim
+pub trait DimColor: PixelColor {
+    /// Returns the color scaled to the given intensity, or `None` to dither the color instead.
+    #[inline]
+    fn dim(self, intensity: u8) -> Option<Self> {
+        let _ = intensity;
+
+        None
+    }
+}
+
+/// Scales a color component to the given intensity.
+fn scale(component: u8, intensity: u8) -> u8 {
+    ((component as u16 * intensity as u16 + 127) / 255) as u8
+}
+
+impl DimColor for BinaryColor {}
+
+macro_rules! impl_gray {
+    ($($color:ty),*) => {
+        $(
+            impl DimColor for $color {
+                #[inline]
+                fn dim(self, intensity: u8) -> Option<Self> {
+                    Some(Self::new(scale(self.luma(), intensity)))
+                }
+            }
+        )*
+    };
+}
+
+impl_gray!(Gray2, Gray4, Gray8);
+
+macro_rules! impl_rgb {
+    ($($color:ty),*) => {
+        $(
+            impl DimColor for $color {
+                #[inline]
+                fn dim(self, intensity: u8) -> Option<Self> {
+                    Some(Self::new(
+                        scale(self.r(), intensity),
+                        scale(self.g(), intensity),
+                        scale(self.b(), intensity),
+                    ))
+                }
+            }
+        )*
+    };
+}
+
+impl_rgb!(Rgb555, Rgb565, Rgb666, Rgb888, Bgr555, Bgr565, Bgr666, Bgr888);
+
+/// 4x4 Bayer matrix.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Returns whether a pixel at the given position is drawn with the given intensity.
+///
+/// The result only depends on the position, so the same text renders the same way every time.
+#[inline]
+pub(crate) fn is_pixel_visible(intensity: u8, point: Point) -> bool {
+    let threshold = BAYER_4X4[(point.y & 3) as usize][(point.x & 3) as usize] * 16 + 8;
+
+    intensity > threshold
+}
+
+/// Returns a color scaled to an intensity, or `None` to dither the color instead.
+pub(crate) type Dim<C> = fn(C, u8) -> Option<C>;
+
+/// Dithers every color, used when the color type doesn't implement [`DimColor`].
+pub(crate) fn dither<C>(_color: C, _intensity: u8) -> Option<C> {
+    None
+}
+
+/// Draw target adapter that dims pixels, or drops them according to an ordered dither pattern.
+pub(crate) struct Dithered<D: DrawTarget> {
+    parent: D,
+    intensity: u8,
+    dim: Dim<D::Color>,
+}
+
+impl<D: DrawTarget> Dithered<D> {
+    /// Creates a new adapter. An intensity of 255 draws every pixel.
+    pub fn new(parent: D, intensity: u8, dim: Dim<D::Color>) -> Self {
+        Self {
+            parent,
+            intensity,
+            dim,
+        }
+    }
+
+    fn is_opaque(&self) -> bool {
+        self.intensity == u8::MAX
+    }
+}
+
+impl<D: DrawTarget> Dimensions for Dithered<D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.parent.bounding_box()
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Dithered<D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let intensity = self.intensity;
+        let dim = self.dim;
+        if self.is_opaque() {
+            return self.parent.draw_iter(pixels);
+        }
+
+        self.parent
+            .draw_iter(pixels.into_iter().filter_map(|Pixel(point, color)| {
+                match dim(color, intensity) {
+                    Some(color) => Some(Pixel(point, color)),
+                    None if is_pixel_visible(intensity, point) => Some(Pixel(point, color)),
+                    None => None,
+                }
+            }))
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.is_opaque() {
+            self.parent.fill_contiguous(area, colors)
+        } else {
+            self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            )
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if self.is_opaque() {
+            self.parent.fill_solid(area, color)
+        } else if let Some(color) = (self.dim)(color, self.intensity) {
+            self.parent.fill_solid(area, color)
+        } else {
+            self.draw_iter(area.points().map(|point| Pixel(point, color)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        pixelcolor::{BinaryColor, Gray2, Gray8, Rgb565},
+        prelude::Point,
+    };
+
+    use super::{is_pixel_visible, DimColor};
+
+    #[test]
+    fn extreme_intensities() {
+        for y in 0..4 {
+            for x in 0..4 {
+                assert!(is_pixel_visible(255, Point::new(x, y)));
+                assert!(!is_pixel_visible(0, Point::new(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn only_binary_colors_are_dithered() {
+        assert_eq!(BinaryColor::On.dim(128), None);
+        assert_eq!(Gray8::new(200).dim(128), Some(Gray8::new(100)));
+        assert_eq!(Gray2::new(3).dim(255), Some(Gray2::new(3)));
+        assert_eq!(Rgb565::new(31, 63, 0).dim(0), Some(Rgb565::new(0, 0, 0)));
+    }
+}
@@ -0,0 +1,120 @@
+//! Drawing text upside down.
+use az::SaturatingAs;
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point, Size},
+    primitives::Rectangle,
+    Pixel,
+};
+
+fn flip(corners: Point, point: Point) -> Point {
+    Point::new(
+        corners.x.saturating_sub(point.x),
+        corners.y.saturating_sub(point.y),
+    )
+}
+
+/// Draw target adapter that rotates every pixel by 180° around the center of an area.
+pub(crate) struct Flipped<'d, D> {
+    parent: &'d mut D,
+
+    /// The sum of the top left and the bottom right corner of the area.
+    corners: Point,
+}
+
+impl<'d, D> Flipped<'d, D> {
+    pub fn new(parent: &'d mut D, area: Rectangle) -> Self {
+        let size = area.size.saturating_sub(Size::new(1, 1));
+        let corner = |top_left: i32, size: u32| {
+            top_left
+                .saturating_mul(2)
+                .saturating_add(size.saturating_as())
+        };
+
+        Self {
+            parent,
+            corners: Point::new(
+                corner(area.top_left.x, size.width),
+                corner(area.top_left.y, size.height),
+            ),
+        }
+    }
+
+    fn flip(&self, point: Point) -> Point {
+        flip(self.corners, point)
+    }
+}
+
+impl<D: Dimensions> Dimensions for Flipped<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.parent.bounding_box()
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Flipped<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let corners = self.corners;
+        self.parent.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(flip(corners, point), color)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = match area.bottom_right() {
+            Some(bottom_right) => Rectangle::new(self.flip(bottom_right), area.size),
+            None => return Ok(()),
+        };
+
+        self.parent.fill_solid(&area, color)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+    };
+
+    use crate::{utils::test::size_for, TextBox};
+
+    #[test]
+    fn flipped_text_is_point_reflected_about_box_center() {
+        let bounds = Rectangle::new(Point::new(1, 2), size_for(&FONT_6X9, 3, 2));
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+        let text_box = TextBox::new("Fly hi", bounds, character_style);
+
+        let mut normal = MockDisplay::new();
+        text_box.draw(&mut normal).unwrap();
+
+        let mut flipped = MockDisplay::new();
+        assert_eq!(text_box.draw_flipped(&mut flipped).unwrap(), "");
+
+        let bottom_right = bounds.bottom_right().unwrap();
+        let mut expected = MockDisplay::new();
+        for point in normal.affected_area().points() {
+            if let Some(color) = normal.get_pixel(point) {
+                let reflected = bounds.top_left + bottom_right - point;
+                Pixel(reflected, color).draw(&mut expected).unwrap();
+            }
+        }
+
+        flipped.assert_eq(&expected);
+        assert!(bounds.contains(flipped.affected_area().top_left));
+    }
+}
@@ -0,0 +1,293 @@
+//! Randomized test that the layout and rendering pipeline doesn't panic.
+//!
+//! Tests are built with debug assertions, so arithmetic overflows are caught as well. Every case
+//! is derived from a fixed seed, so failures are reproducible. Set `EMBEDDED_TEXT_FUZZ_CASES` to
+//! run more cases than the default 300, for example in an optimized build:
+//!
+//! ```sh
+//! CARGO_PROFILE_TEST_OPT_LEVEL=2 EMBEDDED_TEXT_FUZZ_CASES=100000 cargo test --all-features fuzz
+//! ```
+
+use core::{convert::Infallible, num::NonZeroU32};
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Point, Size},
+    mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
+    pixelcolor::{BinaryColor, Rgb888},
+    primitives::Rectangle,
+    text::LineHeight,
+    Drawable, Pixel,
+};
+
+use crate::{
+    alignment::{HorizontalAlignment, VerticalAlignment},
+    style::{
+        ControlCharacters, HeightMode, LineBreak, Padding, TabAnchor, TabLeader, TabSize,
+        TextBoxStyle, TextBoxStyleBuilder, TextTransform, VerticalOverdraw,
+    },
+    TextBox,
+};
+
+/// Characters that are handled specially by the parser or the renderer.
+const SPECIAL_CHARS: &[char] = &[
+    ' ',
+    ' ',
+    ' ',
+    '\n',
+    '\r',
+    '\t',
+    '\u{a0}',
+    '\u{200b}',
+    '\u{ad}',
+    '\u{301}',
+    '\u{fe0f}',
+    '\u{feff}',
+    '\u{2003}',
+    '\u{2066}',
+    '\u{2067}',
+    '\u{2068}',
+    '\u{2069}',
+    '\u{1b}',
+    '[',
+    ';',
+    'm',
+    '-',
+    '\u{e4}',
+    '\u{1f605}',
+];
+
+/// Complete and malformed ANSI escape sequences.
+const ESCAPE_SEQUENCES: &[&str] = &[
+    "\u{1b}[1;31m",
+    "\u{1b}[38;2;300;1;1m",
+    "\u{1b}[48;5;255m",
+    "\u{1b}[4;58;5;1m",
+    "\u{1b}[99999999999C",
+    "\u{1b}[9D",
+    "\u{1b}[2K",
+    "\u{1b}[0m",
+    "\u{1b}]8;;http://a\u{1b}\\",
+    "\u{1b}[",
+];
+
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+
+    fn below(&mut self, n: u32) -> u32 {
+        self.next() % n
+    }
+
+    fn flip(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    /// Returns a value below `n`, or occasionally an extreme value.
+    fn amount(&mut self, n: u32) -> u32 {
+        if self.below(16) == 0 {
+            self.pick(&[u16::MAX as u32, i32::MAX as u32, u32::MAX - 1, u32::MAX])
+        } else {
+            self.below(n)
+        }
+    }
+
+    fn coordinate(&mut self) -> i32 {
+        self.below(40) as i32 - 20
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u32) as usize]
+    }
+
+    fn text(&mut self) -> std::string::String {
+        let mut text = std::string::String::new();
+        for _ in 0..self.below(40) {
+            match self.below(16) {
+                0 => text.push_str(self.pick(ESCAPE_SEQUENCES)),
+                1..=4 => text.push(self.pick(SPECIAL_CHARS)),
+                5..=8 => text.push(char::from_u32(self.below(0x11_0000)).unwrap_or('?')),
+                _ => text.push(self.pick(&['a', 'b', 'W', '1', '.'])),
+            }
+        }
+
+        text
+    }
+
+    fn style(&mut self) -> TextBoxStyle {
+        let overdraw = self.pick(&[
+            VerticalOverdraw::FullRowsOnly,
+            VerticalOverdraw::Hidden,
+            VerticalOverdraw::Visible,
+        ]);
+        let mut builder = TextBoxStyleBuilder::new()
+            .alignment(self.pick(&[
+                HorizontalAlignment::Left,
+                HorizontalAlignment::Center,
+                HorizontalAlignment::Right,
+                HorizontalAlignment::Justified,
+            ]))
+            .vertical_alignment(self.pick(&[
+                VerticalAlignment::Top,
+                VerticalAlignment::Middle,
+                VerticalAlignment::Bottom,
+            ]))
+            .height_mode(self.pick(&[
+                HeightMode::Exact(overdraw),
+                HeightMode::FitToText,
+                HeightMode::ShrinkToText(overdraw),
+            ]))
+            .line_height(self.pick(&[
+                LineHeight::Percent(0),
+                LineHeight::Percent(50),
+                LineHeight::Percent(100),
+                LineHeight::Percent(250),
+                LineHeight::Pixels(0),
+                LineHeight::Pixels(3),
+                LineHeight::Percent(u32::MAX),
+                LineHeight::Pixels(u32::MAX),
+            ]))
+            .paragraph_spacing(self.amount(5))
+            .tab_size(self.pick(&[
+                TabSize::Spaces(0),
+                TabSize::Spaces(4),
+                TabSize::Pixels(7),
+                TabSize::Pixels(u16::MAX),
+                TabSize::Spaces(u16::MAX),
+            ]))
+            .leading_spaces(self.flip())
+            .trailing_spaces(self.flip())
+            .justify_nbsp(self.flip())
+            .justify_tabbed_lines(self.flip())
+            .hanging_punctuation(self.flip())
+            .line_break(self.pick(&[
+                LineBreak::Greedy,
+                LineBreak::Balanced,
+                LineBreak::SpacesOnly,
+            ]))
+            .text_transform(self.pick(&[
+                TextTransform::None,
+                TextTransform::Upper,
+                TextTransform::Lower,
+            ]))
+            .control_characters(self.pick(&[
+                ControlCharacters::Keep,
+                ControlCharacters::Ignore,
+                ControlCharacters::Replace('?'),
+                ControlCharacters::Caret,
+            ]))
+            .padding(Padding::new(
+                self.amount(8),
+                self.amount(8),
+                self.amount(8),
+                self.amount(8),
+            ))
+            .reserve_caret_width(self.amount(4))
+            .pad_lines(self.flip())
+            .tab_anchor(self.pick(&[TabAnchor::Content, TabAnchor::Box]));
+
+        if self.flip() {
+            builder = builder.max_word_length(self.amount(5));
+        }
+        if self.flip() {
+            builder = builder.max_consecutive_newlines(self.amount(3));
+        }
+        if self.flip() {
+            builder = builder.max_lines(NonZeroU32::new(self.below(3) + 1).unwrap());
+        }
+        if self.flip() {
+            builder = builder.list_marker(self.pick(&["", "-", "10."]));
+        }
+        if self.flip() {
+            builder = builder.hyphenation(|_, index| index % 2 == 1);
+        }
+        if self.flip() {
+            builder = builder.tab_leader(TabLeader::new('.', Rgb888::new(255, 255, 255)));
+        }
+        if self.flip() {
+            builder = builder.no_wrap_horizontal_scroll(self.amount(30));
+        }
+        if self.flip() {
+            builder = builder.line_heights(|line| if line == 1 { u32::MAX } else { line * 4 });
+        }
+
+        builder.build()
+    }
+
+    fn bounds(&mut self) -> Rectangle {
+        Rectangle::new(
+            Point::new(self.coordinate(), self.coordinate()),
+            Size::new(self.below(80), self.below(40)),
+        )
+    }
+}
+
+/// Draw target that accepts every pixel.
+struct Sink;
+
+impl OriginDimensions for Sink {
+    fn size(&self) -> Size {
+        Size::new(256, 256)
+    }
+}
+
+impl DrawTarget for Sink {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        pixels.into_iter().for_each(drop);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn arbitrary_text_and_bounds_do_not_panic() {
+    let character_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X9)
+        .text_color(BinaryColor::On)
+        .background_color(BinaryColor::Off)
+        .build();
+
+    let cases = std::env::var("EMBEDDED_TEXT_FUZZ_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(300);
+
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..cases {
+        let text = random.text();
+        let style = random.style();
+        let bounds = random.bounds();
+
+        let width = bounds.size.width;
+        let _ = style.measure_text_height(&character_style, &text, width);
+        let _ = style.measure_size(&character_style, &text, width);
+        let _ = style.natural_width(&character_style, &text);
+
+        let mut text_box = TextBox::with_textbox_style(&text, bounds, character_style, style);
+        text_box.set_vertical_offset(random.coordinate());
+        let _ = text_box.line_count();
+        let _ = text_box.pixel_count_upper_bound();
+
+        text_box.draw(&mut Sink).unwrap();
+        text_box.draw_flipped(&mut Sink).unwrap();
+
+        #[cfg(feature = "ansi")]
+        {
+            let text_box = text_box.add_plugin(crate::plugin::ansi::Ansi::new());
+            let _ = text_box.line_count();
+            text_box.draw(&mut Sink).unwrap();
+        }
+    }
+}
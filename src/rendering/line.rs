@@ -1,19 +1,26 @@
 //! Line rendering.
 
 use crate::{
-    parser::{ChangeTextStyle, Parser},
+    alignment::HorizontalAlignment,
+    parser::{ChangeTextStyle, Parser, StyleDiff},
     plugin::{PluginMarker as Plugin, PluginWrapper, ProcessingState},
     rendering::{
+        bidi::Isolates,
         cursor::LineCursor,
         line_iter::{ElementHandler, LineElementParser, LineEndType},
+        space_config::SpaceConfig,
+    },
+    style::{ControlCharacters, TabAnchor, TextBoxStyle, TextTransform, WhitespaceStyle},
+    utils::{
+        is_control, is_zero_width, kerning_adjustment, str_width, str_width_displayed,
+        str_width_kerned,
     },
-    style::TextBoxStyle,
-    utils::str_width,
 };
+use core::ops::Range;
 use embedded_graphics::{
-    draw_target::DrawTarget,
+    draw_target::{DrawTarget, DrawTargetExt},
     geometry::Point,
-    pixelcolor::{BinaryColor, Rgb888},
+    pixelcolor::Rgb888,
     prelude::{PixelColor, Size},
     primitives::Rectangle,
     text::{
@@ -27,17 +34,19 @@ where
     C: PixelColor + From<Rgb888>,
 {
     pub(crate) fn apply<S: CharacterStyle<Color = C>>(self, text_renderer: &mut S) {
-        match self {
-            ChangeTextStyle::Reset => {
-                text_renderer.set_text_color(Some(Into::<Rgb888>::into(BinaryColor::On).into()));
-                text_renderer.set_background_color(None);
-                text_renderer.set_underline_color(DecorationColor::None);
-                text_renderer.set_strikethrough_color(DecorationColor::None);
-            }
-            ChangeTextStyle::TextColor(color) => text_renderer.set_text_color(color),
-            ChangeTextStyle::BackgroundColor(color) => text_renderer.set_background_color(color),
-            ChangeTextStyle::Underline(color) => text_renderer.set_underline_color(color),
-            ChangeTextStyle::Strikethrough(color) => text_renderer.set_strikethrough_color(color),
+        let style = StyleDiff::from(self);
+
+        if let Some(color) = style.text_color {
+            text_renderer.set_text_color(color);
+        }
+        if let Some(color) = style.background_color {
+            text_renderer.set_background_color(color);
+        }
+        if let Some(color) = style.underline {
+            text_renderer.set_underline_color(color);
+        }
+        if let Some(color) = style.strikethrough {
+            text_renderer.set_strikethrough_color(color);
         }
     }
 }
@@ -51,6 +60,14 @@ where
     pub(crate) cursor: LineCursor,
     pub(crate) state: &'c mut LineRenderState<'a, 'b, S, M>,
     pub(crate) style: &'c TextBoxStyle,
+    /// Whether words that don't fit are wrapped instead of broken, see
+    /// [`TextBoxStyle::avoid_broken_last_word`].
+    pub(crate) avoid_broken_word: bool,
+
+    /// The area the line is clipped to. Glyphs moved by a [`ChangeTextStyle::VerticalOffset`]
+    /// are only clipped to the draw target, so they can extend into the neighbouring lines.
+    /// `None` clips everything to the draw target.
+    pub(crate) line_area: Option<Rectangle>,
 }
 
 #[derive(Clone)]
@@ -62,7 +79,14 @@ where
     pub parser: Parser<'a, S::Color>,
     pub text_renderer: S,
     pub end_type: LineEndType,
+    pub isolates: Isolates,
     pub plugin: &'b PluginWrapper<'a, M, S::Color>,
+
+    /// The number of characters that can still be drawn, if limited.
+    pub reveal: Option<usize>,
+
+    /// Vertical offset of the glyphs, set by [`ChangeTextStyle::VerticalOffset`].
+    pub vertical_offset: i8,
 }
 
 struct RenderElementHandler<'a, 'b, F, D, M>
@@ -72,30 +96,430 @@ where
 {
     text_renderer: &'b mut F,
     display: &'b mut D,
+    /// The area everything but the glyphs moved by the vertical offset is clipped to.
+    line_area: Rectangle,
     pos: Point,
     plugin: &'b PluginWrapper<'a, M, F::Color>,
+    whitespace_style: Option<WhitespaceStyle>,
+    line_end: i32,
+    tab_leader_color: Option<F::Color>,
+    kerning: Option<fn(char, char) -> i8>,
+    text_transform: TextTransform,
+    control_characters: ControlCharacters,
+    isolates: Isolates,
+    /// The start and the end of the right-to-left run at the current position.
+    run: Option<(i32, i32)>,
+    /// Returns the end of the right-to-left run that starts at the given position.
+    run_end: &'b dyn Fn(i32) -> i32,
+    visible_columns: Option<Range<i32>>,
+    reveal: Option<usize>,
+    vertical_offset: i8,
+}
+
+impl<'a, 'b, S, M> LineRenderState<'a, 'b, S, M>
+where
+    S: TextRenderer + Clone,
+    M: Plugin<'a, S::Color>,
+{
+    /// Returns a copy of the state that uses a copy of the plugin, so rendering a line with the
+    /// copy doesn't advance this state.
+    pub fn fork<'c>(
+        &self,
+        plugin: &'c PluginWrapper<'a, M, S::Color>,
+    ) -> LineRenderState<'a, 'c, S, M> {
+        LineRenderState {
+            parser: self.parser.clone(),
+            text_renderer: self.text_renderer.clone(),
+            end_type: self.end_type,
+            isolates: self.isolates,
+            plugin,
+            reveal: self.reveal,
+            vertical_offset: self.vertical_offset,
+        }
+    }
+}
+
+impl<'a, 'b, S, M> LineRenderState<'a, 'b, S, M>
+where
+    S: TextRenderer + Clone,
+    S::Color: From<Rgb888>,
+    M: Plugin<'a, S::Color>,
+{
+    /// Returns the smallest and the largest vertical offset of the glyphs in the line at
+    /// `cursor`, or `(0, 0)` if no glyph is moved.
+    ///
+    /// The line is laid out using a copy of the state, without drawing it.
+    pub fn offset_range(
+        &self,
+        cursor: LineCursor,
+        style: &TextBoxStyle,
+        avoid_broken_word: bool,
+    ) -> (i8, i8) {
+        let plugin = self.plugin.clone();
+        plugin.set_state(ProcessingState::Measure);
+        let mut parser = self.parser.clone();
+        let mut handler = OffsetRangeHandler {
+            text_renderer: &self.text_renderer,
+            kerning: style.kerning,
+            offset: self.vertical_offset,
+            range: (0, 0),
+        };
+
+        let space_config = SpaceConfig::new(str_width(&self.text_renderer, " "));
+        let mut line_parser =
+            LineElementParser::new(&mut parser, &plugin, cursor, space_config, style);
+        line_parser.avoid_broken_word = avoid_broken_word;
+        line_parser.process_infallible(&mut handler);
+
+        handler.range
+    }
+}
+
+/// Finds the vertical offsets of the glyphs of a line by laying out the line without drawing it.
+struct OffsetRangeHandler<'a, S: TextRenderer> {
+    text_renderer: &'a S,
+    kerning: Option<fn(char, char) -> i8>,
+    offset: i8,
+    range: (i8, i8),
+}
+
+impl<'a, S: TextRenderer> ElementHandler for OffsetRangeHandler<'a, S> {
+    type Error = core::convert::Infallible;
+    type Color = S::Color;
+
+    fn measure(&self, st: &str) -> u32 {
+        str_width_kerned(self.text_renderer, st, self.kerning)
+    }
+
+    fn printed_characters(&mut self, _st: &str, _width: Option<u32>) -> Result<(), Self::Error> {
+        let (min, max) = self.range;
+        self.range = (min.min(self.offset), max.max(self.offset));
+        Ok(())
+    }
+
+    fn change_text_style(&mut self, change: ChangeTextStyle<S::Color>) -> Result<(), Self::Error> {
+        match change {
+            ChangeTextStyle::VerticalOffset(offset) => self.offset = offset,
+            ChangeTextStyle::Reset => self.offset = 0,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Finds the end of a right-to-left run by laying out the line without drawing it.
+struct RunEndHandler<'a, S> {
+    text_renderer: &'a S,
+    kerning: Option<fn(char, char) -> i8>,
+    text_transform: TextTransform,
+    control_characters: ControlCharacters,
+    isolates: Isolates,
+    pos: i32,
+    from: i32,
+    end: Option<i32>,
+}
+
+impl<'a, S: TextRenderer> ElementHandler for RunEndHandler<'a, S> {
+    type Error = core::convert::Infallible;
+    type Color = S::Color;
+
+    fn measure(&self, st: &str) -> u32 {
+        str_width_kerned(self.text_renderer, st, self.kerning)
+    }
+
+    fn whitespace(&mut self, _st: &str, _space_count: u32, width: u32) -> Result<(), Self::Error> {
+        self.pos += width as i32;
+        Ok(())
+    }
+
+    fn printed_characters(&mut self, st: &str, width: Option<u32>) -> Result<(), Self::Error> {
+        let start = self.pos;
+        let mut rest = st;
+        while !rest.is_empty() {
+            let (part, reversed, next) = self.isolates.split(rest);
+            self.pos += str_width_displayed(
+                self.text_renderer,
+                part,
+                self.kerning,
+                self.text_transform,
+                self.control_characters,
+            ) as i32;
+            if reversed && !self.isolates.is_reversed() && self.pos >= self.from {
+                self.end = self.end.or(Some(self.pos));
+            }
+            rest = next;
+        }
+        if let Some(width) = width {
+            self.pos = start + width as i32;
+        }
+
+        Ok(())
+    }
+
+    fn inline(&mut self, _st: &str, width: u32) -> Result<(), Self::Error> {
+        self.pos += width as i32;
+        Ok(())
+    }
+
+    fn move_cursor(&mut self, by: i32) -> Result<(), Self::Error> {
+        self.pos += by;
+        Ok(())
+    }
 }
 
 impl<'a, 'b, F, D, M> RenderElementHandler<'a, 'b, F, D, M>
 where
-    F: CharacterStyle + TextRenderer,
+    F: CharacterStyle + TextRenderer<Color = <F as CharacterStyle>::Color>,
     <F as CharacterStyle>::Color: From<Rgb888>,
     D: DrawTarget<Color = <F as TextRenderer>::Color>,
     M: Plugin<'a, <F as TextRenderer>::Color>,
 {
-    fn post_print(&mut self, width: u32, st: &str) -> Result<(), D::Error> {
-        let bounds = Rectangle::new(self.pos, Size::new(width, self.text_renderer.line_height()));
+    /// Returns the position where an element of the given width is drawn.
+    ///
+    /// Inside a right-to-left run, elements are mirrored between the start and the end of the run.
+    fn display_pos(&self, run: Option<(i32, i32)>, x: i32, width: u32) -> Point {
+        let x = match run {
+            Some((start, end)) => start + end - x - width as i32,
+            None => x,
+        };
+
+        Point::new(x, self.pos.y)
+    }
+
+    /// Updates the right-to-left run after the isolate state has changed at the given position.
+    fn update_run(&mut self, x: i32) {
+        if !self.isolates.is_reversed() {
+            self.run = None;
+        } else if self.run.is_none() {
+            self.run = Some((x, (self.run_end)(x)));
+        }
+    }
+
+    fn post_print(&mut self, pos: Point, width: u32, st: &str) -> Result<(), D::Error> {
+        let bounds = Rectangle::new(pos, Size::new(width, self.text_renderer.line_height()));
 
         self.pos += Point::new(width as i32, 0);
 
-        self.plugin
-            .post_render(self.display, self.text_renderer, Some(st), bounds)
+        self.plugin.post_render(
+            &mut self.display.clipped(&self.line_area),
+            self.text_renderer,
+            Some(st),
+            bounds,
+        )
+    }
+
+    /// Draws glyphs at the given position, moved by the vertical offset, and returns the position
+    /// after the last glyph.
+    ///
+    /// Moved glyphs are not clipped to the line, only to the draw target.
+    fn draw_string(&mut self, st: &str, pos: Point) -> Result<Point, D::Error> {
+        if self.vertical_offset == 0 {
+            return self.text_renderer.draw_string(
+                st,
+                pos,
+                Baseline::Top,
+                &mut self.display.clipped(&self.line_area),
+            );
+        }
+
+        let offset = Point::new(0, self.vertical_offset as i32);
+
+        self.text_renderer
+            .draw_string(st, pos + offset, Baseline::Top, self.display)
+            .map(|next| next - offset)
+    }
+
+    /// Draws a string of printable characters and returns the position after the last character.
+    fn draw_glyphs(&mut self, st: &str) -> Result<Point, D::Error> {
+        if self.kerning.is_none()
+            && self.visible_columns.is_none()
+            && self.text_transform == TextTransform::None
+            && (self.control_characters == ControlCharacters::Keep || !st.contains(is_control))
+            && !self.isolates.is_reversed()
+            && !st.contains(is_zero_width)
+            && self
+                .reveal
+                .map_or(true, |reveal| reveal >= st.chars().count())
+        {
+            if let Some(reveal) = self.reveal.as_mut() {
+                *reveal -= st.chars().count();
+            }
+
+            return self.draw_string(st, self.pos);
+        }
+
+        // Runs of right-to-left isolates are drawn in reverse order, mirrored within the run.
+        let mut pos = self.pos;
+        let mut rest = st;
+        while !rest.is_empty() {
+            let (part, reversed, next) = self.isolates.split(rest);
+            pos = if reversed {
+                let width = str_width_displayed(
+                    self.text_renderer,
+                    part,
+                    self.kerning,
+                    self.text_transform,
+                    self.control_characters,
+                );
+                let start = self.display_pos(self.run, pos.x, width);
+                self.draw_chars(part.chars().rev(), start)?;
+                pos + Point::new(width as i32, 0)
+            } else {
+                self.draw_chars(part.chars(), pos)?
+            };
+            self.update_run(pos.x);
+            rest = next;
+        }
+
+        Ok(pos)
+    }
+
+    /// Draws characters one by one to apply kerning between them, to transform them, and to skip
+    /// glyphs that are outside of the visible columns, not revealed yet or have no width.
+    fn draw_chars(
+        &mut self,
+        chars: impl Iterator<Item = char>,
+        mut pos: Point,
+    ) -> Result<Point, D::Error> {
+        let mut prev = None;
+        let mut buffer = [0; 4];
+        let control_characters = self.control_characters;
+        for c in chars.flat_map(|c| control_characters.apply(c)) {
+            if is_zero_width(c) {
+                continue;
+            }
+            let revealed = match self.reveal.as_mut() {
+                Some(0) => false,
+                Some(reveal) => {
+                    *reveal -= 1;
+                    true
+                }
+                None => true,
+            };
+            let c = self.text_transform.apply(c);
+            if let Some(prev) = prev {
+                pos.x += kerning_adjustment(self.kerning, prev, c);
+            }
+            prev = Some(c);
+
+            let glyph = c.encode_utf8(&mut buffer);
+
+            let visible = match self.visible_columns {
+                Some(ref columns) => {
+                    let advance = str_width(self.text_renderer, glyph) as i32;
+                    pos.x < columns.end && pos.x.saturating_add(advance) > columns.start
+                }
+                None => true,
+            };
+
+            pos = if visible && revealed {
+                self.draw_string(glyph, pos)?
+            } else {
+                pos + Point::new(str_width(self.text_renderer, glyph) as i32, 0)
+            };
+        }
+
+        Ok(pos)
+    }
+
+    fn draw_invisibles(
+        &mut self,
+        st: &str,
+        pos: Point,
+        space_count: u32,
+        width: u32,
+    ) -> Result<(), D::Error> {
+        let style = match self.whitespace_style {
+            Some(style) => style,
+            None => return Ok(()),
+        };
+
+        let mut marker_renderer = self.text_renderer.clone();
+        marker_renderer.set_text_color(Some(style.color.into()));
+        marker_renderer.set_background_color(None);
+        marker_renderer.set_underline_color(DecorationColor::None);
+        marker_renderer.set_strikethrough_color(DecorationColor::None);
+
+        let mut buffer = [0; 4];
+        match st {
+            "\n" => {
+                if let Some(marker) = style.newline {
+                    let marker = marker.encode_utf8(&mut buffer);
+
+                    // The marker is only drawn if it fits into the line.
+                    let marker_width = str_width(&marker_renderer, marker) as i32;
+                    if pos.x + marker_width <= self.line_end {
+                        marker_renderer.draw_string(
+                            marker,
+                            pos,
+                            Baseline::Top,
+                            &mut self.display.clipped(&self.line_area),
+                        )?;
+                    }
+                }
+            }
+            "\t" if width > 0 => {
+                if let Some(marker) = style.tab {
+                    draw_centered(
+                        &marker_renderer,
+                        marker.encode_utf8(&mut buffer),
+                        pos,
+                        width,
+                        &mut self.display.clipped(&self.line_area),
+                    )?;
+                }
+            }
+            // Empty strings are used by cursor movement to draw background.
+            "" | "\r" => {}
+            _ if width > 0 && space_count > 0 => {
+                if let Some(marker) = style.space {
+                    let marker = marker.encode_utf8(&mut buffer);
+                    let single = width / space_count;
+                    for i in 0..space_count {
+                        draw_centered(
+                            &marker_renderer,
+                            marker,
+                            pos + Point::new((i * single) as i32, 0),
+                            single,
+                            &mut self.display.clipped(&self.line_area),
+                        )?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
     }
 }
 
+/// Draws a marker glyph centered in a space of the given width.
+fn draw_centered<S, D>(
+    renderer: &S,
+    marker: &str,
+    pos: Point,
+    width: u32,
+    display: &mut D,
+) -> Result<(), D::Error>
+where
+    S: TextRenderer,
+    D: DrawTarget<Color = S::Color>,
+{
+    let offset = width.saturating_sub(str_width(renderer, marker)) / 2;
+
+    renderer
+        .draw_string(
+            marker,
+            pos + Point::new(offset as i32, 0),
+            Baseline::Top,
+            display,
+        )
+        .map(drop)
+}
+
 impl<'a, 'c, F, D, M> ElementHandler for RenderElementHandler<'a, 'c, F, D, M>
 where
-    F: CharacterStyle + TextRenderer,
+    F: CharacterStyle + TextRenderer<Color = <F as CharacterStyle>::Color>,
     <F as CharacterStyle>::Color: From<Rgb888>,
     D: DrawTarget<Color = <F as TextRenderer>::Color>,
     M: Plugin<'a, <F as TextRenderer>::Color>,
@@ -104,26 +528,95 @@ where
     type Color = <F as CharacterStyle>::Color;
 
     fn measure(&self, st: &str) -> u32 {
-        str_width(self.text_renderer, st)
+        str_width_kerned(self.text_renderer, st, self.kerning)
+    }
+
+    fn whitespace(&mut self, st: &str, space_count: u32, width: u32) -> Result<(), Self::Error> {
+        let pos = self.display_pos(self.run, self.pos.x, width);
+
+        // Whitespace after the revealed characters is hidden, too.
+        if self.reveal != Some(0) {
+            if width > 0 {
+                self.text_renderer.draw_whitespace(
+                    width,
+                    pos,
+                    Baseline::Top,
+                    &mut self.display.clipped(&self.line_area),
+                )?;
+            }
+
+            self.draw_invisibles(st, pos, space_count, width)?;
+        }
+
+        self.post_print(pos, width, st)
     }
 
-    fn whitespace(&mut self, st: &str, _space_count: u32, width: u32) -> Result<(), Self::Error> {
+    fn tab_leader(&mut self, fill: char, width: u32) -> Result<(), Self::Error> {
+        let start = self.display_pos(self.run, self.pos.x, width);
+        if self.reveal == Some(0) {
+            return self.post_print(start, width, "\t");
+        }
+
         if width > 0 {
-            self.text_renderer
-                .draw_whitespace(width, self.pos, Baseline::Top, self.display)?;
+            self.text_renderer.draw_whitespace(
+                width,
+                start,
+                Baseline::Top,
+                &mut self.display.clipped(&self.line_area),
+            )?;
+        }
+
+        let mut buffer = [0; 4];
+        let glyph = fill.encode_utf8(&mut buffer);
+        let glyph_width = str_width(self.text_renderer, glyph);
+
+        if let Some(count) = width.checked_div(glyph_width) {
+            let mut leader_renderer = self.text_renderer.clone();
+            leader_renderer.set_text_color(self.tab_leader_color);
+            leader_renderer.set_background_color(None);
+            leader_renderer.set_underline_color(DecorationColor::None);
+            leader_renderer.set_strikethrough_color(DecorationColor::None);
+
+            // Align the fill characters to the end of the tab, so that leaders of consecutive
+            // lines line up with the text after them.
+            let mut pos = start + Point::new((width - count * glyph_width) as i32, 0);
+            for _ in 0..count {
+                pos = leader_renderer.draw_string(
+                    glyph,
+                    pos,
+                    Baseline::Top,
+                    &mut self.display.clipped(&self.line_area),
+                )?;
+            }
         }
 
-        self.post_print(width, st)
+        self.post_print(start, width, "\t")
     }
 
     fn printed_characters(&mut self, st: &str, width: Option<u32>) -> Result<(), Self::Error> {
-        let render_width =
-            self.text_renderer
-                .draw_string(st, self.pos, Baseline::Top, self.display)?;
+        let run = self.run;
+        let render_width = self.draw_glyphs(st)?;
 
         let width = width.unwrap_or((render_width - self.pos).x as u32);
 
-        self.post_print(width, st)
+        let pos = self.display_pos(run, self.pos.x, width);
+        self.post_print(pos, width, st)
+    }
+
+    fn inline(&mut self, st: &str, width: u32) -> Result<(), Self::Error> {
+        let pos = self.display_pos(self.run, self.pos.x, width);
+
+        // The object is drawn by the plugin, over the background of the text.
+        if width > 0 {
+            self.text_renderer.draw_whitespace(
+                width,
+                pos,
+                Baseline::Top,
+                &mut self.display.clipped(&self.line_area),
+            )?;
+        }
+
+        self.post_print(pos, width, st)
     }
 
     fn move_cursor(&mut self, by: i32) -> Result<(), Self::Error> {
@@ -136,6 +629,11 @@ where
         &mut self,
         change: ChangeTextStyle<<F as CharacterStyle>::Color>,
     ) -> Result<(), Self::Error> {
+        let diff = StyleDiff::from(change);
+        if let Some(offset) = diff.vertical_offset {
+            self.vertical_offset = offset;
+        }
+
         change.apply(self.text_renderer);
         Ok(())
     }
@@ -155,41 +653,175 @@ where
         let LineRenderState {
             ref mut parser,
             ref mut text_renderer,
+            ref mut isolates,
+            ref mut reveal,
+            ref mut vertical_offset,
             plugin,
             ..
         } = self.state;
 
-        let lm = {
+        let line_area = self.line_area.unwrap_or_else(|| display.bounding_box());
+        let line_width = self.style.line_layout_width(self.cursor.line_width());
+        let (style, avoid_broken_word) = (self.style, self.avoid_broken_word);
+        let measure = |start_x: u32| {
             // Ensure the clone lives for as short as possible.
             let mut cloned_parser = parser.clone();
             let measure_plugin = plugin.clone();
             measure_plugin.set_state(ProcessingState::Measure);
-            self.style.measure_line(
+            let lm = style.measure_line_impl(
                 &measure_plugin,
-                text_renderer,
+                &*text_renderer,
                 &mut cloned_parser,
-                self.cursor.line_width(),
-            )
+                line_width,
+                avoid_broken_word,
+                start_x,
+            );
+
+            (lm, cloned_parser.as_str().len())
+        };
+
+        let (mut lm, rest) = measure(0);
+
+        // Lines that are not wrapped are aligned within the visible area.
+        lm.max_line_width = self.cursor.line_width();
+
+        let (left, space_config) = self
+            .style
+            .alignment
+            .place_line(str_width(text_renderer, " "), lm);
+        let left = if lm.has_tab && self.style.tab_anchor == TabAnchor::Box && left > 0 {
+            // Tab stops are resolved relative to the text box, so the width of the line depends
+            // on its offset. The line is moved as far as it keeps its content and alignment.
+            let max_width = lm.max_line_width as i32;
+            let alignment = self.style.alignment;
+            let fits = |offset: i32| {
+                let (placed, placed_rest) = measure(offset as u32);
+                let end = offset + placed.width as i32;
+                let aligned = match alignment {
+                    HorizontalAlignment::Center => offset <= max_width - end + 1,
+                    _ => end <= max_width,
+                };
+
+                aligned && placed_rest == rest && placed.line_end_type == lm.line_end_type
+            };
+
+            let (mut low, mut high) = (0, max_width);
+            while low < high {
+                let mid = low + (high - low + 1) / 2;
+                if fits(mid) {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+
+            low
+        } else {
+            left
+        };
+
+        if self.style.pad_lines && left > 0 {
+            text_renderer.draw_whitespace(
+                left as u32,
+                self.cursor.pos(),
+                Baseline::Top,
+                &mut display.clipped(&line_area),
+            )?;
+        }
+
+        let line_start = self.cursor.pos().x;
+        let line_end = line_start + self.cursor.line_width() as i32;
+
+        let visible_columns = if let Some(scroll_x) = self.style.horizontal_scroll {
+            self.cursor = self.cursor.into_unbounded(scroll_x);
+            Some(line_start..line_end)
+        } else {
+            None
         };
 
-        let (left, space_config) = self.style.alignment.place_line(text_renderer, lm);
+        if self.style.tab_anchor == TabAnchor::Box {
+            self.cursor.anchor_tabs();
+            self.cursor.move_cursor(left).ok();
+        } else {
+            self.cursor.move_cursor(left).ok();
+            self.cursor.anchor_tabs();
+        }
 
-        self.cursor.move_cursor(left as i32).ok();
+        // Right-to-left runs are measured on demand by laying out the line again.
+        let start_isolates = *isolates;
+        let start_parser = parser.clone();
+        let start_plugin = plugin.clone();
+        start_plugin.set_state(ProcessingState::Measure);
+        let run_renderer = text_renderer.clone();
+        let run_cursor = self.cursor.clone();
+        let run_end = |from: i32| {
+            let mut handler = RunEndHandler {
+                text_renderer: &run_renderer,
+                kerning: style.kerning,
+                text_transform: style.text_transform,
+                control_characters: style.control_characters,
+                isolates: start_isolates,
+                pos: run_cursor.pos().x,
+                from,
+                end: None,
+            };
+            let measure_plugin = start_plugin.clone();
+            let mut parser = start_parser.clone();
+            let mut line_parser = LineElementParser::new(
+                &mut parser,
+                &measure_plugin,
+                run_cursor.clone(),
+                space_config,
+                style,
+            );
+            line_parser.avoid_broken_word = avoid_broken_word;
+            line_parser.process_infallible(&mut handler);
+
+            handler.end.unwrap_or(handler.pos)
+        };
 
         let mut render_element_handler = RenderElementHandler {
             text_renderer,
             display,
+            line_area,
             pos: self.cursor.pos(),
             plugin: *plugin,
+            whitespace_style: self.style.whitespace_style,
+            line_end,
+            tab_leader_color: self.style.tab_leader.map(|leader| leader.color.into()),
+            kerning: self.style.kerning,
+            text_transform: self.style.text_transform,
+            control_characters: self.style.control_characters,
+            isolates: *isolates,
+            run: None,
+            run_end: &run_end,
+            visible_columns,
+            reveal: *reveal,
+            vertical_offset: *vertical_offset,
         };
-        let end_type =
-            LineElementParser::new(parser, plugin, self.cursor, space_config, self.style)
-                .process(&mut render_element_handler)?;
+        render_element_handler.update_run(render_element_handler.pos.x);
+        let mut line_parser =
+            LineElementParser::new(parser, plugin, self.cursor, space_config, self.style);
+        line_parser.avoid_broken_word = self.avoid_broken_word;
+        let end_type = line_parser.process(&mut render_element_handler)?;
+
+        let end_pos = render_element_handler.pos;
+        *isolates = render_element_handler.isolates;
+        *reveal = render_element_handler.reveal;
+        *vertical_offset = render_element_handler.vertical_offset;
+
+        if self.style.pad_lines && end_pos.x < line_end {
+            text_renderer.draw_whitespace(
+                (line_end - end_pos.x) as u32,
+                end_pos,
+                Baseline::Top,
+                &mut display.clipped(&line_area),
+            )?;
+        }
 
         if end_type == LineEndType::EndOfText {
-            let end_pos = render_element_handler.pos;
             plugin.post_render(
-                display,
+                &mut display.clipped(&line_area),
                 text_renderer,
                 None,
                 Rectangle::new(end_pos, Size::new(0, text_renderer.line_height())),
@@ -208,20 +840,24 @@ mod test {
         parser::Parser,
         plugin::{NoPlugin, PluginWrapper},
         rendering::{
+            bidi::Isolates,
             cursor::LineCursor,
             line::{LineRenderState, StyledLineRenderer},
             line_iter::LineEndType,
         },
         style::{TabSize, TextBoxStyle, TextBoxStyleBuilder},
-        utils::test::size_for,
+        utils::test::{size_for, CountingDraws},
+        TextBox,
     };
+    use core::cell::Cell;
     use embedded_graphics::{
         geometry::Point,
         mock_display::MockDisplay,
-        mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
+        mono_font::{ascii::FONT_6X9, MonoTextStyle, MonoTextStyleBuilder},
         pixelcolor::{BinaryColor, Rgb888},
         primitives::Rectangle,
         text::renderer::{CharacterStyle, TextRenderer},
+        Drawable,
     };
 
     fn test_rendered_text<'a, S>(
@@ -246,13 +882,18 @@ mod test {
             parser,
             text_renderer: character_style,
             end_type: LineEndType::EndOfText,
+            isolates: Isolates::new(),
             plugin: &plugin,
+            reveal: None,
+            vertical_offset: 0,
         };
 
         let renderer = StyledLineRenderer {
             cursor,
             state: &mut state,
             style: &style,
+            avoid_broken_word: false,
+            line_area: None,
         };
         let mut display = MockDisplay::new();
         display.set_allow_overdraw(true);
@@ -377,4 +1018,40 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn glyph_runs_are_drawn_in_one_call() {
+        let draws = Cell::new(0);
+        let character_style = CountingDraws {
+            inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            draws: &draws,
+        };
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 12, 1));
+
+        let mut display = MockDisplay::new();
+        TextBox::new("sample", bounds, character_style.clone())
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!(draws.get(), 1);
+
+        draws.set(0);
+        let mut display = MockDisplay::new();
+        TextBox::new("Some sample", bounds, character_style.clone())
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!(draws.get(), 2);
+
+        // Kerning is applied between glyphs, so they are drawn one by one.
+        draws.set(0);
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "sample",
+            bounds,
+            character_style,
+            TextBoxStyleBuilder::new().kerning(|_, _| 0).build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+        assert_eq!(draws.get(), 6);
+    }
 }
@@ -5,14 +5,20 @@
 //! handling tab characters, soft wrapping characters, non-breaking spaces, etc.
 
 use crate::{
-    parser::{ChangeTextStyle, Parser, Token, SPEC_CHAR_NBSP},
-    plugin::{PluginMarker as Plugin, PluginWrapper},
-    rendering::{cursor::LineCursor, space_config::SpaceConfig},
-    style::TextBoxStyle,
+    alignment::HorizontalAlignment,
+    parser::{ChangeTextStyle, Parser, Token, SPEC_CHAR_NBSP, SPEC_CHAR_ZWSP},
+    plugin::{PluginMarker as Plugin, PluginWrapper, WordSplitReason},
+    rendering::{advance_cache::AdvanceCache, cursor::LineCursor, space_config::SpaceConfig},
+    style::{LineBreak, TextBoxStyle},
+    utils::{cluster_indices, is_zero_width, kerning_adjustment},
 };
 use az::SaturatingAs;
+use core::convert::Infallible;
 use embedded_graphics::{pixelcolor::Rgb888, prelude::PixelColor};
 
+/// Characters that may hang past the end of a justified line.
+const HANGING_PUNCTUATION: [char; 6] = ['.', ',', ';', ':', '!', '?'];
+
 /// Parser to break down a line into primitive elements used by measurement and rendering.
 #[derive(Debug)]
 #[must_use]
@@ -26,48 +32,117 @@ where
     parser: &'b mut Parser<'a, C>,
 
     spaces: SpaceConfig,
-    empty: bool,
+    /// Whether nothing was printed in the line yet.
+    pub empty: bool,
+    /// Whether the last printed word is continued by the rest of a word split at a span boundary.
+    mid_word: bool,
+    /// Whether words that don't fit are wrapped, instead of being broken, if the line isn't empty.
+    pub avoid_broken_word: bool,
+    /// Index of the grid cell the cursor is in.
+    column: usize,
+    /// Width of the punctuation that hangs past the end of the line.
+    pub hang: u32,
+    /// The last word printed in the line.
+    last_word: &'a str,
     plugin: &'b PluginWrapper<'a, M, C>,
     style: &'b TextBoxStyle,
+    advances: AdvanceCache,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The reason a line of text ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum LineEndType {
+    /// The line ended with a newline character.
     NewLine,
+
+    /// The line ended with a carriage return character.
     CarriageReturn,
+
+    /// The line is the last line of the text.
     EndOfText,
+
+    /// The line was wrapped because the next word did not fit.
     LineBreak,
 }
 
+/// Receives the elements of laid out text.
+///
+/// Text is processed line by line. Each line is turned into a sequence of events, like a block of
+/// whitespace or a string of printable characters, together with their widths. The measurement
+/// and the rendering of a [`TextBox`] are both implemented as element handlers.
+///
+/// Custom handlers can be driven by [`TextBoxStyle::process_elements`], which makes it possible
+/// to export text in other formats while using the same line breaking rules as the text box.
+///
+/// [`TextBox`]: crate::TextBox
 pub trait ElementHandler {
+    /// The error type returned by the event handlers.
     type Error;
+
+    /// The color type of the text.
     type Color: PixelColor;
 
     /// Returns the width of the given string in pixels.
     fn measure(&self, st: &str) -> u32;
 
     /// A whitespace block with the given width.
+    ///
+    /// On justified lines, spaces may have different widths, so they are reported one at a time.
+    #[inline]
     fn whitespace(&mut self, _st: &str, _space_count: u32, _width: u32) -> Result<(), Self::Error> {
         Ok(())
     }
 
+    /// A tab filled with leader characters.
+    #[inline]
+    fn tab_leader(&mut self, _fill: char, width: u32) -> Result<(), Self::Error> {
+        self.whitespace("\t", 0, width)
+    }
+
     /// A string of printable characters.
+    #[inline]
     fn printed_characters(&mut self, _st: &str, _width: Option<u32>) -> Result<(), Self::Error> {
         Ok(())
     }
 
+    /// An inline object with the given width, produced by a plugin in place of the text `st`.
+    ///
+    /// By default, the object is reported as a string of printable characters.
+    #[inline]
+    fn inline(&mut self, st: &str, width: u32) -> Result<(), Self::Error> {
+        self.printed_characters(st, Some(width))
+    }
+
     /// A cursor movement event.
+    #[inline]
     fn move_cursor(&mut self, _by: i32) -> Result<(), Self::Error> {
         Ok(())
     }
 
-    /// Text style change
+    /// A text style change, e.g. caused by an ANSI sequence.
+    ///
+    /// Style changes are reported in order with the other events of the line. The text reported
+    /// using [`printed_characters`](Self::printed_characters) never spans a style change, words
+    /// that contain style changes are split at the change points. Use [`StyleDiff::from`] to get
+    /// the changed attributes.
+    ///
+    /// [`StyleDiff::from`]: crate::StyleDiff
+    #[inline]
     fn change_text_style(
         &mut self,
         _change: ChangeTextStyle<Self::Color>,
     ) -> Result<(), Self::Error> {
         Ok(())
     }
+
+    /// The end of a line.
+    ///
+    /// This event is only emitted by [`TextBoxStyle::process_elements`].
+    #[inline]
+    fn end_line(&mut self, _end: LineEndType) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 impl<'a, 'b, M, C> LineElementParser<'a, 'b, M, C>
@@ -89,12 +164,118 @@ where
             spaces,
             cursor,
             empty: true,
+            mid_word: false,
+            avoid_broken_word: false,
+            column: 0,
+            hang: 0,
+            last_word: "",
             plugin,
             style,
+            advances: AdvanceCache::new(
+                style.kerning,
+                style.text_transform,
+                style.control_characters,
+            ),
+        }
+    }
+
+    fn measure<E: ElementHandler + ?Sized>(&mut self, handler: &E, st: &str) -> u32 {
+        self.advances.str_width(handler, st)
+    }
+
+    /// Returns whether a whitespace sequence consists of the extra space characters of the
+    /// parser configuration.
+    fn is_extra_space(&self, seq: &str) -> bool {
+        seq.chars().next().map_or(false, |c| {
+            self.style.parser_config.extra_space_chars.contains(&c)
+        })
+    }
+
+    /// Returns the width of `n` whitespace characters.
+    ///
+    /// Extra space characters are measured using their glyphs, other whitespace is measured as
+    /// spaces of `spaces`.
+    fn whitespace_width<E: ElementHandler + ?Sized>(
+        &mut self,
+        handler: &E,
+        spaces: &mut SpaceConfig,
+        n: u32,
+        seq: &str,
+    ) -> u32 {
+        if self.is_extra_space(seq) {
+            self.measure(handler, seq)
+        } else {
+            spaces.consume(n)
         }
     }
 
-    fn next_word_width<E: ElementHandler>(&mut self, handler: &E) -> Option<u32> {
+    /// Returns the width of the punctuation at the end of `word` if the word only fits into the
+    /// line by letting the punctuation hang past its end.
+    ///
+    /// `word_width` includes the rest of a word that is split at span boundaries, in which case
+    /// the punctuation isn't at the end of the word.
+    fn hanging_width<E: ElementHandler + ?Sized>(
+        &mut self,
+        handler: &E,
+        cursor: &LineCursor,
+        word: &str,
+        word_width: u32,
+        reserved: u32,
+    ) -> Option<u32> {
+        if !self.style.hanging_punctuation || self.style.alignment != HorizontalAlignment::Justified
+        {
+            return None;
+        }
+
+        let last = word.char_indices().last()?;
+        if !HANGING_PUNCTUATION.contains(&last.1) {
+            return None;
+        }
+
+        if self.measure(handler, word) != word_width {
+            return None;
+        }
+
+        // Punctuation only hangs as far as the right padding reaches.
+        let hang = self.measure(handler, &word[last.0..]);
+        if hang > self.style.padding.right {
+            return None;
+        }
+
+        let rest = word_width.checked_sub(hang).filter(|&rest| rest > 0)?;
+
+        Some(hang).filter(|_| cursor.fits_in_line(rest.saturating_add(reserved)))
+    }
+
+    /// Returns the width of the parts of a word that follow the token peeked by `plugin`.
+    ///
+    /// Words that are split at style span boundaries are wrapped as a single word. The plugin and
+    /// the parser are only cloned if the peeked token is followed by the rest of a word.
+    fn rest_of_word_width<E: ElementHandler + ?Sized>(
+        advances: &mut AdvanceCache,
+        handler: &E,
+        plugin: &PluginWrapper<'a, M, C>,
+        parser: &Parser<'a, C>,
+    ) -> u32 {
+        if !plugin.continues_word() {
+            return 0;
+        }
+
+        let lookahead = plugin.clone();
+        let mut lookahead_parser = parser.clone();
+
+        let mut width = 0;
+        while lookahead.continues_word() {
+            lookahead.consume_peeked_token();
+            if let Some(Token::Word(w)) = lookahead.peek_token(&mut lookahead_parser) {
+                width += advances.str_width(handler, w);
+            }
+        }
+
+        width
+    }
+
+    fn next_word_width<E: ElementHandler + ?Sized>(&mut self, handler: &E) -> Option<u32> {
         // This looks extremely inefficient.
         let lookahead = self.plugin.clone();
         let mut lookahead_parser = self.parser.clone();
@@ -106,12 +287,19 @@ where
             lookahead.consume_peeked_token();
             match lookahead.peek_token(&mut lookahead_parser) {
                 Some(Token::Word(w)) => {
-                    width += handler.measure(w);
+                    width += self.measure(handler, w);
+                    width_set = true;
+                }
+
+                Some(Token::Inline { width: w, .. }) => {
+                    width = width.saturating_add(w);
                     width_set = true;
                 }
 
-                Some(Token::Break(w)) => return Some(width + handler.measure(w)),
-                Some(Token::ChangeTextStyle(_)) | Some(Token::MoveCursor { .. }) => {}
+                Some(Token::Break(w)) => return Some(width + self.measure(handler, w)),
+                Some(Token::ChangeTextStyle(_))
+                | Some(Token::EscapeSequence(_))
+                | Some(Token::MoveCursor { .. }) => {}
 
                 _ => {
                     return match width_set {
@@ -123,6 +311,151 @@ where
         }
     }
 
+    /// Returns whether the current word is the last one in the text.
+    fn is_last_word(&mut self) -> bool {
+        let lookahead = self.plugin.clone();
+        let mut lookahead_parser = self.parser.clone();
+
+        loop {
+            lookahead.consume_peeked_token();
+            match lookahead.peek_token(&mut lookahead_parser) {
+                Some(Token::ChangeTextStyle(_)) | Some(Token::EscapeSequence(_)) => {}
+                Some(_) => return false,
+                None => return true,
+            }
+        }
+    }
+
+    /// Returns the width that needs to be free after the current word.
+    fn reserved_width_after_word(&mut self) -> u32 {
+        let reserved = self.style.reserve_caret_width;
+        if reserved > 0 && self.is_last_word() {
+            reserved
+        } else {
+            0
+        }
+    }
+
+    /// Returns the width of the rest of the line, up to the next tab or line break.
+    ///
+    /// The width of the current token is only included if `skip_current` is false.
+    fn rest_of_line_width<E: ElementHandler + ?Sized>(
+        &mut self,
+        handler: &E,
+        skip_current: bool,
+    ) -> u32 {
+        let lookahead = self.plugin.clone();
+        let mut lookahead_parser = self.parser.clone();
+        let mut spaces = self.spaces;
+
+        let mut skip = skip_current;
+        let mut width = 0u32;
+        loop {
+            if skip {
+                lookahead.consume_peeked_token();
+            }
+            skip = true;
+            width = width.saturating_add(match lookahead.peek_token(&mut lookahead_parser) {
+                Some(Token::Word(w)) => self.measure(handler, w),
+                Some(Token::Inline { width, .. }) => width,
+                Some(Token::Whitespace(n, seq)) => {
+                    self.whitespace_width(handler, &mut spaces, n, seq)
+                }
+                Some(Token::MoveCursor { chars, .. }) => (chars
+                    .saturating_mul(self.measure(handler, " ").saturating_as::<i32>()))
+                .max(0) as u32,
+                Some(Token::Break(_))
+                | Some(Token::ChangeTextStyle(_))
+                | Some(Token::EscapeSequence(_)) => 0,
+                _ => return width,
+            });
+        }
+    }
+
+    /// Returns the distance of the content of the `column`th grid cell from the left edge of the
+    /// line, or `None` if the column is not defined.
+    fn cell_position<E: ElementHandler + ?Sized>(
+        &mut self,
+        handler: &E,
+        column: usize,
+        skip_current: bool,
+    ) -> Option<u32> {
+        let columns = self.style.columns?;
+        let spec = columns.get(column)?;
+        let scale = self.style.column_scale;
+        let start = columns[..column].iter().fold(0u32, |start, column| {
+            start.saturating_add(scale.apply(column.width))
+        });
+
+        let free = scale
+            .apply(spec.width)
+            .saturating_sub(self.rest_of_line_width(handler, skip_current));
+        let offset = match spec.alignment {
+            HorizontalAlignment::Right => free,
+            HorizontalAlignment::Center => free / 2,
+            HorizontalAlignment::Left | HorizontalAlignment::Justified => 0,
+        };
+
+        Some(start.saturating_add(offset))
+    }
+
+    /// Lays out the prefix of the paragraph, or the matching indentation in its wrapped lines.
+    fn line_prefix<E: ElementHandler + ?Sized>(&mut self, handler: &mut E) -> Result<(), E::Error> {
+        if let Some((prefix, first_line)) = self.plugin.line_prefix() {
+            let width = self.measure(handler, prefix);
+            if self.move_cursor_forward(width).is_ok() {
+                if first_line {
+                    self.process_word(handler, prefix)?;
+                } else {
+                    handler.whitespace("", 0, width)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves the cursor to the content of the first grid cell of the line.
+    fn start_first_cell<E: ElementHandler + ?Sized>(
+        &mut self,
+        handler: &mut E,
+    ) -> Result<(), E::Error> {
+        if let Some(position) = self.cell_position(handler, 0, false) {
+            let offset = position.saturating_sub(self.cursor.line_width() - self.cursor.space());
+            if offset > 0 {
+                let moved = match self.move_cursor_forward(offset) {
+                    Ok(moved) => moved,
+                    Err(space) => {
+                        let _ = self.move_cursor_forward(space);
+                        space
+                    }
+                };
+                handler.move_cursor(moved.saturating_as())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the width of the tab at the cursor position.
+    fn tab_width<E: ElementHandler + ?Sized>(&mut self, handler: &E) -> u32 {
+        if let Some(position) = self.cell_position(handler, self.column + 1, true) {
+            // Grid cells never overlap, content that is too wide pushes the next cell right.
+            return position.saturating_sub(self.cursor.line_width() - self.cursor.space());
+        }
+
+        match self.style.tab_leader {
+            Some(leader) if leader.right_aligned => {
+                let rest = self.rest_of_line_width(handler, true);
+                match self.cursor.space().checked_sub(rest) {
+                    Some(width) => width,
+                    None => self.cursor.next_tab_width(),
+                }
+            }
+            _ => self.cursor.next_tab_width(),
+        }
+    }
+
     fn move_cursor(&mut self, by: i32) -> Result<i32, i32> {
         self.cursor.move_cursor(by)
     }
@@ -131,33 +464,80 @@ where
         self.cursor.move_cursor_forward(by)
     }
 
-    fn longest_fitting_substr<E: ElementHandler>(
+    fn longest_fitting_substr<E: ElementHandler + ?Sized>(
         &mut self,
         handler: &E,
         w: &'a str,
     ) -> (&'a str, &'a str) {
         let mut width = 0;
-        for (idx, c) in w.char_indices() {
-            let char_width = handler.measure(unsafe {
-                // SAFETY: we are working on character boundaries
-                w.get_unchecked(idx..idx + c.len_utf8())
-            });
-            if !self.cursor.fits_in_line(width + char_width) {
-                unsafe {
-                    if w.is_char_boundary(idx) {
-                        return w.split_at(idx);
-                    } else {
-                        core::hint::unreachable_unchecked();
-                    }
+        let mut prev = None;
+        // The end of the last weak break character that fits into the line.
+        let mut weak_break = None;
+        for (idx, cluster) in cluster_indices(w) {
+            let mut char_width = self.measure(handler, cluster);
+            let c = cluster.chars().next().unwrap_or_default();
+            let displayed = self.style.control_characters.apply(c).next();
+            if let Some(c) = displayed.filter(|&c| !is_zero_width(c)) {
+                let c = self.style.text_transform.apply(c);
+                if let Some(prev) = prev {
+                    char_width = (char_width as i32
+                        + kerning_adjustment(self.style.kerning, prev, c))
+                    .max(0) as u32;
                 }
+                prev = Some(c);
+            }
+            if !self.cursor.fits_in_line(width + char_width) {
+                // Both indices are cluster boundaries of `w`.
+                return w.split_at(weak_break.unwrap_or(idx));
             }
             width += char_width;
+
+            if self.is_weak_break(cluster) {
+                weak_break = Some(idx + cluster.len());
+            }
         }
 
         (w, "")
     }
 
-    fn next_word_fits<E: ElementHandler>(&self, handler: &E) -> bool {
+    /// Returns whether a word wider than a line should preferably be broken after `cluster`.
+    fn is_weak_break(&self, cluster: &str) -> bool {
+        let mut chars = cluster.chars();
+        match (self.style.weak_break_chars, chars.next(), chars.next()) {
+            (Some(weak_break_chars), Some(c), None) => weak_break_chars.contains(c),
+            _ => false,
+        }
+    }
+
+    /// Returns the longest hyphenated part of the word that fits into the line, followed by a
+    /// hyphen, and the rest of the word.
+    fn hyphenate<E: ElementHandler + ?Sized>(
+        &mut self,
+        handler: &E,
+        cursor: &LineCursor,
+        w: &'a str,
+    ) -> Option<(&'a str, &'a str)> {
+        let hyphenator = self
+            .style
+            .hyphenation
+            .filter(|_| self.style.line_break != LineBreak::SpacesOnly)?;
+        let hyphen_width = self.measure(handler, "-");
+
+        for (idx, _) in w.char_indices().rev() {
+            if idx == 0 || !hyphenator.allows_break(w, idx) {
+                continue;
+            }
+
+            let (word, remainder) = w.split_at(idx);
+            if cursor.fits_in_line(self.measure(handler, word) + hyphen_width) {
+                return Some((word, remainder));
+            }
+        }
+
+        None
+    }
+
+    fn next_word_fits<E: ElementHandler + ?Sized>(&mut self, handler: &E) -> bool {
         let mut cursor = self.cursor.clone();
 
         let mut spaces = self.spaces;
@@ -170,19 +550,49 @@ where
         while !exit {
             lookahead.consume_peeked_token();
             let width = match lookahead.peek_token(&mut lookahead_parser) {
-                Some(Token::Word(w)) | Some(Token::Break(w)) => {
+                Some(Token::Word(w)) => {
+                    let width = self.measure(handler, w)
+                        + Self::rest_of_word_width(
+                            &mut self.advances,
+                            handler,
+                            &lookahead,
+                            &lookahead_parser,
+                        );
+                    if !cursor.fits_in_line(width) {
+                        // The word may still start in this line if its punctuation can hang past
+                        // the end of the line, or if it can be hyphenated.
+                        return self.hanging_width(handler, &cursor, w, width, 0).is_some()
+                            || self.hyphenate(handler, &cursor, w).is_some();
+                    }
+
+                    exit = true;
+                    width.saturating_as()
+                }
+
+                Some(Token::Inline { width, .. }) => {
+                    if !cursor.fits_in_line(width) {
+                        return false;
+                    }
+
                     exit = true;
-                    handler.measure(w).saturating_as()
+                    width.saturating_as()
                 }
 
-                Some(Token::Whitespace(n, _)) => spaces.consume(n).saturating_as(),
+                Some(Token::Break(w)) => {
+                    exit = true;
+                    self.measure(handler, w).saturating_as()
+                }
+
+                Some(Token::Whitespace(n, seq)) => self
+                    .whitespace_width(handler, &mut spaces, n, seq)
+                    .saturating_as(),
                 Some(Token::Tab) => cursor.next_tab_width().saturating_as(),
 
                 Some(Token::MoveCursor { chars, .. }) => {
-                    chars * handler.measure(" ").saturating_as::<i32>()
+                    chars.saturating_mul(self.measure(handler, " ").saturating_as::<i32>())
                 }
 
-                Some(Token::ChangeTextStyle(_)) => 0,
+                Some(Token::ChangeTextStyle(_)) | Some(Token::EscapeSequence(_)) => 0,
 
                 _ => return false,
             };
@@ -203,12 +613,13 @@ where
         self.empty && !self.style.leading_spaces
     }
 
-    fn draw_whitespace<E: ElementHandler>(
+    fn draw_whitespace<E: ElementHandler + ?Sized>(
         &mut self,
         handler: &mut E,
         string: &'a str,
         space_count: u32,
         space_width: u32,
+        spaces: SpaceConfig,
     ) -> Result<bool, E::Error> {
         if self.skip_leading_spaces() {
             handler.whitespace(string, space_count, 0)?;
@@ -216,6 +627,33 @@ where
         }
 
         match self.move_cursor_forward(space_width) {
+            Ok(_) if spaces.is_justified() && space_count > 1 => {
+                // Justified spaces may have different widths, so they are reported one by one.
+                let draw = self.should_draw_whitespace(handler) as u32;
+                let mut spaces = spaces;
+                let mut start = 0;
+                let mut counted = 0;
+                for (idx, c) in string.char_indices() {
+                    if c == SPEC_CHAR_ZWSP {
+                        continue;
+                    }
+
+                    counted += 1;
+                    let end = if counted == space_count {
+                        string.len()
+                    } else {
+                        idx + c.len_utf8()
+                    };
+                    handler.whitespace(&string[start..end], 1, spaces.consume(1) * draw)?;
+                    start = end;
+
+                    if counted == space_count {
+                        break;
+                    }
+                }
+                Ok(false)
+            }
+
             Ok(moved) => {
                 handler.whitespace(
                     string,
@@ -232,10 +670,7 @@ where
                     let consumed_str = string
                         .char_indices()
                         .nth(consumed as usize)
-                        .map(|(pos, _)| unsafe {
-                            // SAFETY: Pos is a valid index, we just got it
-                            string.get_unchecked(0..pos)
-                        })
+                        .map(|(pos, _)| &string[..pos])
                         .unwrap_or(string);
 
                     let consumed_width = consumed * single;
@@ -248,23 +683,36 @@ where
                     )?;
                 }
 
-                self.plugin
-                    .consume_partial((consumed + 1).min(space_count) as usize);
+                // The space at the wrap point is normally swallowed by the line break. If
+                // trailing spaces are rendered, it is carried to the next line instead, so that
+                // every space remains visible. A space is still swallowed if nothing else was
+                // consumed on an empty line, to make progress.
+                let carry_all = self.render_trailing_spaces() && (consumed > 0 || !self.empty);
+                let consumed = if carry_all { consumed } else { consumed + 1 };
+
+                let consumed_len = string
+                    .char_indices()
+                    .nth(consumed as usize)
+                    .map_or(string.len(), |(pos, _)| pos);
+                self.plugin.consume_partial(consumed_len);
                 Ok(true)
             }
         }
     }
 
-    fn draw_tab<E: ElementHandler>(&mut self, handler: &mut E) -> Result<(), E::Error> {
-        if self.skip_leading_spaces() {
+    fn draw_tab<E: ElementHandler + ?Sized>(&mut self, handler: &mut E) -> Result<(), E::Error> {
+        // Tabs separate grid cells, so they are kept even at the start of a line.
+        if self.skip_leading_spaces() && self.style.columns.is_none() {
             return Ok(());
         }
 
-        let space_width = self.cursor.next_tab_width();
+        let space_width = self.tab_width(handler);
+        self.column += 1;
         match self.move_cursor_forward(space_width) {
-            Ok(moved) if self.should_draw_whitespace(handler) => {
-                handler.whitespace("\t", 0, moved)?
-            }
+            Ok(moved) if self.should_draw_whitespace(handler) => match self.style.tab_leader {
+                Some(leader) => handler.tab_leader(leader.fill, moved)?,
+                None => handler.whitespace("\t", 0, moved)?,
+            },
 
             Ok(moved) | Err(moved) => handler.move_cursor(moved as i32)?,
         }
@@ -279,16 +727,42 @@ where
         self.plugin.consume_peeked_token();
     }
 
+    /// Processes the line with a handler that can't fail.
+    ///
+    /// The handler is passed as a trait object, so every measurement pass shares a single copy
+    /// of the layout code, regardless of the character style.
+    pub fn process_infallible(
+        &mut self,
+        handler: &mut dyn ElementHandler<Error = Infallible, Color = C>,
+    ) -> LineEndType {
+        self.process(handler).unwrap_or_else(|e| match e {})
+    }
+
     #[inline]
-    pub fn process<E: ElementHandler<Color = C>>(
+    pub fn process<E: ElementHandler<Color = C> + ?Sized>(
         &mut self,
         handler: &mut E,
     ) -> Result<LineEndType, E::Error> {
+        if self.empty {
+            self.line_prefix(handler)?;
+            self.start_first_cell(handler)?;
+        }
+
         while let Some(token) = self.peek_next_token() {
             match token {
+                Token::Whitespace(n, seq) if self.is_extra_space(seq) => {
+                    // Extra space characters are not stretched by justification.
+                    let space_width = self.measure(handler, seq);
+                    let spaces = SpaceConfig::new(space_width / n.max(1));
+                    if self.draw_whitespace(handler, seq, n, space_width, spaces)? {
+                        return Ok(LineEndType::LineBreak);
+                    }
+                }
+
                 Token::Whitespace(n, seq) => {
+                    let spaces = self.spaces;
                     let space_width = self.spaces.consume(n);
-                    if self.draw_whitespace(handler, seq, n, space_width)? {
+                    if self.draw_whitespace(handler, seq, n, space_width, spaces)? {
                         return Ok(LineEndType::LineBreak);
                     }
                 }
@@ -303,7 +777,7 @@ where
                             // this line is done, decide how to end
 
                             // If the next Word token does not fit the line, display break character
-                            let width = handler.measure(c);
+                            let width = self.measure(handler, c);
                             if self.move_cursor_forward(width).is_ok() {
                                 if let Some(Token::Break(c)) = self.plugin.render_token(token) {
                                     handler.printed_characters(c, Some(width))?;
@@ -312,6 +786,11 @@ where
                             }
 
                             if !self.empty {
+                                self.plugin.on_word_split(
+                                    self.last_word,
+                                    self.last_word.len(),
+                                    WordSplitReason::SoftHyphen,
+                                );
                                 return Ok(LineEndType::LineBreak);
                             }
                         }
@@ -321,34 +800,106 @@ where
                 }
 
                 Token::Word(w) => {
-                    let width = handler.measure(w);
-                    let (word, remainder) = if self.move_cursor_forward(width).is_ok() {
+                    let width = self.measure(handler, w);
+                    let word_width = width
+                        + Self::rest_of_word_width(
+                            &mut self.advances,
+                            handler,
+                            self.plugin,
+                            self.parser,
+                        );
+                    // A word that doesn't fit into an empty line has to be broken anyway, so the
+                    // rest of a word split at a span boundary doesn't cause wrapping.
+                    let must_break = self.empty || self.mid_word;
+                    self.mid_word = self.plugin.continues_word();
+
+                    // Wrapping a word that is alone in its line wouldn't make room.
+                    let reserved = if self.empty {
+                        0
+                    } else {
+                        self.reserved_width_after_word()
+                    };
+                    let mut hyphenated = false;
+                    let (word, remainder) = if (self
+                        .cursor
+                        .fits_in_line(word_width.saturating_add(reserved))
+                        || must_break && self.cursor.fits_in_line(width))
+                        && self.move_cursor_forward(width).is_ok()
+                    {
                         // We can move the cursor here since `process_word()`
                         // doesn't depend on it.
                         (w, "")
-                    } else if self.empty {
+                    } else if let Some(hang) =
+                        self.hanging_width(handler, &self.cursor.clone(), w, word_width, reserved)
+                    {
+                        // The punctuation hangs past the end of the line, which is now full.
+                        self.hang = hang;
+                        let _ = self.move_cursor_forward(self.cursor.space());
+                        (w, "")
+                    } else if self.style.line_break == LineBreak::SpacesOnly && !must_break {
+                        self.plugin.on_word_split(w, 0, WordSplitReason::Wrapped);
+                        return Ok(LineEndType::LineBreak);
+                    } else if self.style.line_break == LineBreak::SpacesOnly {
+                        // Words are never broken, a word that is wider than the line overflows.
+                        let _ = self.move_cursor_forward(self.cursor.space());
+                        (w, "")
+                    } else if self.avoid_broken_word && !self.empty {
+                        // Wrap the whole word instead of ending the line with a part of it.
+                        self.plugin.on_word_split(w, 0, WordSplitReason::Wrapped);
+                        return Ok(LineEndType::LineBreak);
+                    } else if let Some((word, remainder)) =
+                        self.hyphenate(handler, &self.cursor.clone(), w)
+                    {
+                        self.plugin
+                            .on_word_split(w, word.len(), WordSplitReason::Hyphenation);
+                        hyphenated = true;
+                        (word, remainder)
+                    } else if must_break {
                         // This word does not fit into an empty line. Find longest part
                         // that fits and push the rest to the next line.
                         match self.longest_fitting_substr(handler, w) {
+                            // The rest of a split word continues in the next line.
+                            ("", _) if !self.empty => {
+                                self.plugin.on_word_split(w, 0, WordSplitReason::Wrapped);
+                                return Ok(LineEndType::LineBreak);
+                            }
                             ("", _) => {
                                 // Weird case where width doesn't permit drawing anything.
                                 // End here to prevent infinite looping.
                                 self.consume_token();
                                 return Ok(LineEndType::LineBreak);
                             }
-                            other => other,
+                            (word, remainder) => {
+                                if !remainder.is_empty() {
+                                    self.plugin.on_word_split(
+                                        w,
+                                        word.len(),
+                                        WordSplitReason::Characters,
+                                    );
+                                }
+                                (word, remainder)
+                            }
                         }
                     } else {
                         // word wrapping - push this word to the next line
+                        self.plugin.on_word_split(w, 0, WordSplitReason::Wrapped);
                         return Ok(LineEndType::LineBreak);
                     };
 
                     self.empty = false;
+                    self.last_word = word;
 
                     if let Some(Token::Word(word)) = self.plugin.render_token(Token::Word(word)) {
                         self.process_word(handler, word)?;
                     }
 
+                    if hyphenated {
+                        let width = self.measure(handler, "-");
+                        if let Some(Token::Break(c)) = self.plugin.render_token(Token::Break("-")) {
+                            handler.printed_characters(c, Some(width))?;
+                        }
+                    }
+
                     if !remainder.is_empty() {
                         // Consume what was printed.
                         self.plugin.consume_partial(word.len());
@@ -356,6 +907,26 @@ where
                     }
                 }
 
+                Token::Inline { text, width } => {
+                    // Inline objects can't be broken. An object that is wider than an empty line
+                    // is cut off at the end of the line.
+                    let width = match self.move_cursor_forward(width) {
+                        Ok(width) => width,
+                        Err(_) if !self.empty => return Ok(LineEndType::LineBreak),
+                        Err(space) => {
+                            let _ = self.move_cursor_forward(space);
+                            space
+                        }
+                    };
+                    self.empty = false;
+
+                    if let Some(Token::Inline { text, width }) =
+                        self.plugin.render_token(Token::Inline { text, width })
+                    {
+                        handler.inline(text, width)?;
+                    }
+                }
+
                 // Cursor movement can't rely on the text, as it's permitted
                 // to move the cursor outside of the current line.
                 // Example:
@@ -365,35 +936,60 @@ where
                 // [Some text  |  ]
                 Token::MoveCursor {
                     chars,
-                    draw_background: true,
+                    draw_background,
+                    wrap,
                 } => {
-                    let delta = chars * handler.measure(" ").saturating_as::<i32>();
-                    match self.move_cursor(delta) {
-                        Ok(delta) | Err(delta) => {
-                            if chars > 0 {
-                                handler.whitespace("", 1, delta.saturating_as())?;
-                            } else {
-                                handler.move_cursor(delta)?;
-                                handler.whitespace("", 1, delta.abs().saturating_as())?;
-                                handler.move_cursor(delta)?;
-                            }
+                    let space_width = self.measure(handler, " ").saturating_as::<i32>();
+
+                    // A wrapped movement moves over the whole characters that fit into the line
+                    // and continues in the next line. Lines that can't fit a single character
+                    // clamp the movement instead, to avoid looping forever.
+                    let fitting = if space_width > 0 {
+                        (self.cursor.space().saturating_as::<i32>() / space_width).min(chars)
+                    } else {
+                        chars
+                    };
+                    let at_line_start = self.cursor.space() == self.cursor.line_width();
+                    let wrapped = wrap && chars > fitting && (fitting > 0 || !at_line_start);
+
+                    let chars_moved = if wrapped { fitting } else { chars };
+                    let requested = chars_moved.saturating_mul(space_width);
+                    let delta = match self.move_cursor(requested) {
+                        Ok(delta) => delta,
+                        Err(delta) => {
+                            // Stop at the end of the line.
+                            self.move_cursor(delta).ok();
+                            self.plugin.on_cursor_overflow(requested - delta);
+                            delta
                         }
+                    };
+                    if !draw_background {
+                        handler.move_cursor(delta)?;
+                    } else if chars > 0 {
+                        handler.whitespace("", 1, delta.saturating_as())?;
+                    } else {
+                        handler.move_cursor(delta)?;
+                        handler.whitespace("", 1, delta.abs().saturating_as())?;
+                        handler.move_cursor(delta)?;
                     }
-                }
 
-                Token::MoveCursor {
-                    chars,
-                    draw_background: false,
-                } => {
-                    let delta = chars * handler.measure(" ").saturating_as::<i32>();
-                    match self.move_cursor(delta) {
-                        Ok(delta) | Err(delta) => {
-                            handler.move_cursor(delta)?;
-                        }
+                    if wrapped {
+                        self.plugin.replace_peeked_token(Token::MoveCursor {
+                            chars: chars - fitting,
+                            draw_background,
+                            wrap,
+                        });
+                        return Ok(LineEndType::LineBreak);
                     }
                 }
 
-                Token::ChangeTextStyle(change) => handler.change_text_style(change)?,
+                Token::ChangeTextStyle(change) => {
+                    handler.change_text_style(change)?;
+                    self.advances.clear();
+                }
+
+                // Unsupported escape sequences are invisible, and they don't end the word.
+                Token::EscapeSequence(_) => {}
 
                 Token::CarriageReturn => {
                     handler.whitespace("\r", 0, 0)?;
@@ -404,6 +1000,7 @@ where
                 Token::NewLine => {
                     handler.whitespace("\n", 0, 0)?;
                     self.consume_token();
+                    self.plugin.end_paragraph();
                     return Ok(LineEndType::NewLine);
                 }
             }
@@ -413,7 +1010,7 @@ where
         Ok(LineEndType::EndOfText)
     }
 
-    fn process_word<E: ElementHandler>(
+    fn process_word<E: ElementHandler + ?Sized>(
         &mut self,
         handler: &mut E,
         mut w: &str,
@@ -424,25 +1021,31 @@ where
                 Some((space_pos, _)) => {
                     // If we have anything before the space...
                     if space_pos != 0 {
-                        let word = unsafe {
-                            // Safety: space_pos must be a character boundary
-                            w.get_unchecked(0..space_pos)
-                        };
-                        handler.printed_characters(word, None)?;
+                        let word = &w[..space_pos];
+                        let width = self.measure(handler, word);
+                        handler.printed_characters(word, Some(width))?;
                     }
 
-                    handler.whitespace("\u{a0}", 1, self.spaces.consume(1))?;
+                    let width = if self.style.justify_nbsp {
+                        self.spaces.consume(1)
+                    } else {
+                        self.measure(handler, " ")
+                    };
+                    handler.whitespace("\u{a0}", 1, width)?;
 
                     // If we have anything after the space...
                     w = iter.as_str();
                 }
 
-                None => return handler.printed_characters(w, None),
+                None => {
+                    let width = self.measure(handler, w);
+                    return handler.printed_characters(w, Some(width));
+                }
             }
         }
     }
 
-    fn should_draw_whitespace<E: ElementHandler>(&self, handler: &E) -> bool {
+    fn should_draw_whitespace<E: ElementHandler + ?Sized>(&mut self, handler: &E) -> bool {
         self.empty // We know that when this function is called,
                    // an empty line means leading spaces are allowed
             || self.render_trailing_spaces()
@@ -551,7 +1154,7 @@ pub(crate) mod test {
         let style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On.into());
 
         let space_width = str_width(&style, " ");
-        let config = SpaceConfig::new(space_width, None);
+        let config = SpaceConfig::new(space_width);
         let cursor = Cursor::new(
             Rectangle::new(Point::zero(), size_for(&FONT_6X9, max_chars, 1)),
             style.line_height(),
@@ -577,7 +1180,7 @@ pub(crate) mod test {
         let style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On.into());
 
         let space_width = str_width(&style, " ");
-        let config = SpaceConfig::new(space_width, None);
+        let config = SpaceConfig::new(space_width);
         let cursor = Cursor::new(
             Rectangle::new(Point::zero(), size_for(&FONT_6X9, 1, 1) - Size::new(1, 0)),
             style.line_height(),
@@ -598,6 +1201,36 @@ pub(crate) mod test {
         assert_eq!(handler.elements, &[]);
     }
 
+    #[test]
+    #[cfg(feature = "grapheme")]
+    fn decomposed_character_is_not_split() {
+        let mut parser = Parser::parse("abcde\u{301}f");
+        let mw = PluginWrapper::new(NoPlugin::<Rgb888>::new());
+
+        assert_line_elements(&mut parser, 5, &[RenderElement::string("abcd", 24)], &mw);
+        assert_line_elements(
+            &mut parser,
+            5,
+            &[RenderElement::string("e\u{301}f", 18)],
+            &mw,
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "grapheme")]
+    fn zwj_sequence_is_not_split() {
+        let mut parser = Parser::parse("ab\u{1f469}\u{200d}\u{1f467}");
+        let mw = PluginWrapper::new(NoPlugin::<Rgb888>::new());
+
+        assert_line_elements(&mut parser, 3, &[RenderElement::string("ab", 12)], &mw);
+        assert_line_elements(
+            &mut parser,
+            3,
+            &[RenderElement::string("\u{1f469}\u{200d}\u{1f467}", 18)],
+            &mw,
+        );
+    }
+
     #[test]
     fn soft_hyphen_no_wrapping() {
         let mut parser = Parser::parse("sam\u{00AD}ple");
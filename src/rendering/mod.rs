@@ -1,27 +1,48 @@
 //! Pixel iterators used for text rendering.
 
+pub(crate) mod advance_cache;
+pub(crate) mod bidi;
+pub(crate) mod blend;
+pub(crate) mod chunks;
 pub(crate) mod cursor;
+pub(crate) mod dither;
+pub(crate) mod flip;
+#[cfg(test)]
+mod fuzz;
 pub(crate) mod line;
 pub(crate) mod line_iter;
+pub(crate) mod ordered;
 pub(crate) mod space_config;
 
 use crate::{
-    parser::Parser,
-    plugin::{PluginMarker as Plugin, ProcessingState},
+    alignment::HorizontalAlignment,
+    plugin::{PluginMarker as Plugin, PluginWrapper, ProcessingState, TruncationInfo},
     rendering::{
-        cursor::Cursor,
+        bidi::Isolates,
+        blend::{BlendColor, Blended},
+        chunks::PixelChunks,
+        cursor::{Cursor, LineCursor},
+        dither::{dither, Dim, DimColor, Dithered},
+        flip::Flipped,
         line::{LineRenderState, StyledLineRenderer},
+        ordered::{PixelChunk, RowBuffer, RowSegment, SEGMENT_WIDTH},
     },
     style::TextBoxStyle,
+    utils::{clip_area, remaining_len_from, str_width},
     TextBox,
 };
 use az::SaturatingAs;
+use core::convert::Infallible;
 use embedded_graphics::{
     draw_target::{DrawTarget, DrawTargetExt},
+    image::GetPixel,
     pixelcolor::Rgb888,
-    prelude::{Dimensions, Point, Size},
+    prelude::{Dimensions, OriginDimensions, Point, Size},
     primitives::Rectangle,
-    text::renderer::{CharacterStyle, TextRenderer},
+    text::{
+        renderer::{CharacterStyle, TextRenderer},
+        Baseline,
+    },
     Drawable,
 };
 use line_iter::LineEndType;
@@ -42,6 +63,9 @@ pub struct TextBoxProperties<'a, S> {
 
     /// The bounds of the text box.
     pub bounding_box: Rectangle,
+
+    /// The text of the text box.
+    pub text: &'a str,
 }
 
 impl<'a, F, M> Drawable for TextBox<'a, F, M>
@@ -58,12 +82,526 @@ where
         &self,
         display: &mut D,
     ) -> Result<&'a str, D::Error> {
+        self.draw_impl(display, None, 0, dither)
+            .map(|(remaining, _)| remaining)
+    }
+}
+
+impl<'a, F, M> TextBox<'a, F, M>
+where
+    F: TextRenderer<Color = <F as CharacterStyle>::Color> + CharacterStyle,
+    <F as CharacterStyle>::Color: From<Rgb888>,
+    M: Plugin<'a, <F as TextRenderer>::Color> + Plugin<'a, <F as CharacterStyle>::Color>,
+{
+    /// Draws the text box, emitting pixels in a well-defined order.
+    ///
+    /// Character styles draw the background, the glyphs and the decorations of a string in
+    /// separate passes, so [`draw`] can draw the same pixel multiple times, and the rows of a line
+    /// are drawn out of order. This method guarantees that:
+    ///
+    ///  - each pixel is drawn at most once, with its final color, and
+    ///  - pixels are drawn row by row, from left to right, with the rows of each line in
+    ///    non-decreasing order.
+    ///
+    /// Lines don't overlap, so pixels of later lines are only drawn below earlier lines. Carriage
+    /// returns are an exception, as they draw the next line over the same rows again, and so are
+    /// glyphs that a [`ChangeTextStyle::VerticalOffset`] moves into the rows of other lines.
+    ///
+    /// [`ChangeTextStyle::VerticalOffset`]: crate::ChangeTextStyle::VerticalOffset
+    ///
+    /// This is intended for draw targets that forward every pixel separately, like displays
+    /// driven over a slow serial link. Each line is rendered into `buffer` first, then the buffer
+    /// is drawn row by row. A buffer that holds every row of a line, i.e. the width of the text
+    /// box times the line height, lets every line be rendered once. Smaller buffers work, too, but
+    /// a line is rendered again for every band of rows that fits into the buffer, which makes
+    /// drawing slower.
+    ///
+    /// Returns the part of the text that was not drawn, like [`draw`] does.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     mock_display::MockDisplay,
+    ///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    ///     pixelcolor::BinaryColor,
+    ///     prelude::*,
+    ///     primitives::Rectangle,
+    /// };
+    /// use embedded_text::TextBox;
+    ///
+    /// let text_box = TextBox::new(
+    ///     "Hello, World!",
+    ///     Rectangle::new(Point::zero(), Size::new(48, 18)),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// );
+    ///
+    /// // Room for one line of text.
+    /// let mut buffer = [None; 48 * 9];
+    ///
+    /// let mut display = MockDisplay::new();
+    /// text_box.draw_ordered(&mut display, &mut buffer).unwrap();
+    /// ```
+    ///
+    /// [`draw`]: Drawable::draw
+    #[inline]
+    pub fn draw_ordered<D>(
+        &self,
+        display: &mut D,
+        buffer: &mut [Option<<F as CharacterStyle>::Color>],
+    ) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>,
+    {
+        self.draw_impl(display, Some(buffer), 0, dither)
+            .map(|(remaining, _)| remaining)
+    }
+
+    /// Returns an iterator over the pixels of the text box, in short row segments.
+    ///
+    /// The chunks are produced in the same order [`draw_ordered`] draws pixels, and each chunk is
+    /// only rendered when it is requested. This makes it possible to interleave rendering with
+    /// sending the pixels to the display, without buffering the whole text box.
+    ///
+    /// [`draw_ordered`]: TextBox::draw_ordered
+    #[inline]
+    pub fn pixel_chunks(&self) -> PixelChunks<'a, '_, F, M> {
+        PixelChunks::new(self)
+    }
+
+    /// Returns an upper bound of the number of pixels produced by [`pixel_chunks`].
+    ///
+    /// The bound is the number of pixels in the displayed area of every line, including the list
+    /// markers, so it can be used to size a buffer for the rendered pixels. It is only an upper
+    /// bound: pixels that the character style leaves transparent are not produced. Even with
+    /// [`pad_lines`], the gaps of tabs are not filled, so fewer pixels can be produced. Lines that
+    /// follow a carriage return are drawn over the same rows again, and they are counted again.
+    ///
+    /// The text is laid out to calculate the bound, but it is not rendered.
+    ///
+    /// [`pixel_chunks`]: TextBox::pixel_chunks
+    /// [`pad_lines`]: crate::style::TextBoxStyleBuilder::pad_lines
+    #[inline]
+    pub fn pixel_count_upper_bound(&self) -> u32 {
+        let (mut cursor, mut state, marker_width) = self.start_render(0);
+        state.plugin.set_state(ProcessingState::Measure);
+
+        let mut paragraph_start = matches!(
+            state.end_type,
+            LineEndType::EndOfText | LineEndType::NewLine
+        );
+        let mut anything_drawn = false;
+        let mut count = 0_u32;
+        loop {
+            state.plugin.new_line();
+
+            let line_area = self.displayed_line_area(&cursor);
+            if line_area.size.height == 0 {
+                if anything_drawn {
+                    break;
+                }
+            } else {
+                anything_drawn = true;
+
+                let marker = self.line_marker(paragraph_start, cursor.line_start(), marker_width);
+                let area = Self::marker_area(line_area, marker, marker_width);
+                count = count.saturating_add(area.size.width.saturating_mul(area.size.height));
+            }
+
+            let avoid_broken_word = self.avoid_broken_word(&cursor);
+            self.skip_line(cursor.line(), &mut state, avoid_broken_word);
+
+            paragraph_start = state.end_type == LineEndType::NewLine;
+            if state.end_type == LineEndType::EndOfText {
+                break;
+            }
+            self.next_line(&mut cursor, state.end_type);
+        }
+
+        count
+    }
+
+    /// Draws the text box chunk by chunk, waiting for the future returned by `chunk` after each
+    /// one.
+    ///
+    /// Every [`PixelChunk`] produced by [`pixel_chunks`] is passed to `chunk`, and rendering is
+    /// suspended until the returned future completes. This lets an async executor run other
+    /// tasks while the pixels are being transferred to the display, for example using DMA.
+    ///
+    /// Drawing stops at the first error returned by a future.
+    ///
+    /// [`pixel_chunks`]: TextBox::pixel_chunks
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn draw_chunked<E, Fut>(
+        &self,
+        mut chunk: impl FnMut(PixelChunk<<F as CharacterStyle>::Color>) -> Fut,
+    ) -> Result<(), E>
+    where
+        Fut: core::future::Future<Output = Result<(), E>>,
+    {
+        for pixels in self.pixel_chunks() {
+            chunk(pixels).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the text box scrolled by `scroll_y` pixels, clipped to the bounding box.
+    ///
+    /// Positive values move the text up, like scrolling down in a list. The scroll position is
+    /// applied after the vertical alignment and the vertical offset, and isn't limited to the
+    /// height of the text, so negative values and values larger than the text height can be used
+    /// to animate overscrolling and bouncing. Unlike [`draw`], this method never draws outside of
+    /// the bounding box, regardless of the height mode.
+    ///
+    /// Use [`VerticalOverdraw::Hidden`] to display partially visible lines at the edges of the
+    /// box. Returns the part of the text that was not drawn, like [`draw`] does.
+    ///
+    /// [`draw`]: Drawable::draw
+    /// [`VerticalOverdraw::Hidden`]: crate::style::VerticalOverdraw::Hidden
+    #[inline]
+    pub fn draw_scrolled<D>(&self, display: &mut D, scroll_y: i32) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>,
+    {
+        self.draw_impl(
+            &mut display.clipped(&clip_area(self.bounds)),
+            None,
+            scroll_y,
+            dither,
+        )
+        .map(|(remaining, _)| remaining)
+    }
+
+    /// Draws the text box rotated by 180°, clipped to the bounding box.
+    ///
+    /// The text is laid out as usual, then both the positions and the pixels of the glyphs are
+    /// rotated around the center of the bounding box, so the text can be read from the opposite
+    /// side of the display. Returns the part of the text that was not drawn, like [`draw`] does.
+    ///
+    /// [`draw`]: Drawable::draw
+    #[inline]
+    pub fn draw_flipped<D>(&self, display: &mut D) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>,
+    {
+        let area = clip_area(self.bounds);
+
+        self.draw(&mut Flipped::new(display, area).clipped(&area))
+    }
+
+    /// Draws the text box into an offscreen canvas.
+    ///
+    /// The top left corner of the bounding box is drawn at the origin of the canvas, and the text
+    /// is clipped to the part of the bounding box that fits into the canvas. Drawing the text
+    /// into a canvas in RAM and copying it to the display in a single transfer avoids the tearing
+    /// that is visible when lines are updated on the display one by one.
+    ///
+    /// Only the pixels of the text are drawn, so the canvas should be cleared to the background
+    /// of the display first. Returns the area of the display that needs to be updated, in display
+    /// coordinates. Copy the same sized area from the origin of the canvas.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     mock_display::MockDisplay,
+    ///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    ///     pixelcolor::BinaryColor,
+    ///     prelude::*,
+    ///     primitives::Rectangle,
+    /// };
+    /// use embedded_text::TextBox;
+    ///
+    /// let text_box = TextBox::new(
+    ///     "Hello",
+    ///     Rectangle::new(Point::new(100, 200), Size::new(30, 9)),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// );
+    ///
+    /// let mut canvas = MockDisplay::new();
+    /// let dirty = text_box.draw_offscreen(&mut canvas).unwrap();
+    ///
+    /// assert_eq!(dirty, text_box.bounds);
+    /// ```
+    #[inline]
+    pub fn draw_offscreen<C>(&self, canvas: &mut C) -> Result<Rectangle, C::Error>
+    where
+        C: DrawTarget<Color = <F as CharacterStyle>::Color> + OriginDimensions,
+    {
+        let area = clip_area(self.bounds);
+        let dirty = Rectangle::new(area.top_left, area.size.component_min(canvas.size()));
+
+        self.draw_tile(canvas, dirty)?;
+
+        Ok(dirty)
+    }
+
+    /// Draws the part of the text box that is displayed by a tile of a larger display.
+    ///
+    /// `tile_area` is the position and size of the tile in display coordinates, and the origin of
+    /// the tile's draw target is its top left corner. The text is laid out in display coordinates,
+    /// so drawing the text box into every tile of the display produces the same image as drawing
+    /// it into a single draw target.
+    ///
+    /// Returns the part of the text that did not fit into the text box, like
+    /// [`draw`](Drawable::draw).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     mock_display::MockDisplay,
+    ///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    ///     pixelcolor::BinaryColor,
+    ///     prelude::*,
+    ///     primitives::Rectangle,
+    /// };
+    /// use embedded_text::TextBox;
+    ///
+    /// let text_box = TextBox::new(
+    ///     "Hello, World!",
+    ///     Rectangle::new(Point::zero(), Size::new(78, 9)),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// );
+    ///
+    /// // The display is made up of two tiles that are 40 pixels wide.
+    /// let mut left = MockDisplay::new();
+    /// let mut right = MockDisplay::new();
+    /// text_box
+    ///     .draw_tile(&mut left, Rectangle::new(Point::zero(), Size::new(40, 9)))
+    ///     .unwrap();
+    /// text_box
+    ///     .draw_tile(&mut right, Rectangle::new(Point::new(40, 0), Size::new(40, 9)))
+    ///     .unwrap();
+    /// ```
+    #[inline]
+    pub fn draw_tile<D>(&self, tile: &mut D, tile_area: Rectangle) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>,
+    {
+        let tile_area = clip_area(tile_area);
+
+        self.draw(&mut tile.translated(-tile_area.top_left).clipped(&tile_area))
+    }
+
+    /// Draws the text into a sequence of rectangles.
+    ///
+    /// The text fills the first rectangle, continues in the second one, and so on, like the
+    /// columns of a newspaper. Every rectangle is drawn using the style of this text box, the
+    /// bounding box of the text box itself is not used, and the height mode is not applied.
+    ///
+    /// The state of the plugins and the text style changes made by the text, for example by
+    /// ANSI sequences, are carried over from one rectangle to the next.
+    ///
+    /// Returns `None` if the whole text was drawn, otherwise the part of the text that did not fit
+    /// into the last rectangle.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     mock_display::MockDisplay,
+    ///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    ///     pixelcolor::BinaryColor,
+    ///     prelude::*,
+    ///     primitives::Rectangle,
+    /// };
+    /// use embedded_text::TextBox;
+    ///
+    /// let mut display = MockDisplay::new();
+    ///
+    /// let text_box = TextBox::new(
+    ///     "Lorem ipsum dolor sit amet",
+    ///     Rectangle::zero(),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// );
+    ///
+    /// let columns = [
+    ///     Rectangle::new(Point::new(0, 0), Size::new(30, 18)),
+    ///     Rectangle::new(Point::new(32, 0), Size::new(30, 18)),
+    /// ];
+    ///
+    /// let remaining = text_box.flow_into(&columns, &mut display)?;
+    ///
+    /// assert_eq!(remaining, Some("amet"));
+    /// # Ok::<(), core::convert::Infallible>(())
+    /// ```
+    #[inline]
+    pub fn flow_into<D>(
+        &self,
+        columns: &[Rectangle],
+        display: &mut D,
+    ) -> Result<Option<&'a str>, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>,
+    {
+        let mut text = self.text;
+        let mut character_style = self.character_style.clone();
+        let mut plugin = self.plugin.clone().into_inner();
+
+        for bounds in columns {
+            if text.is_empty() {
+                break;
+            }
+
+            let column = TextBox {
+                text,
+                bounds: *bounds,
+                character_style,
+                style: self.style,
+                vertical_offset: self.vertical_offset,
+                first_visible_line: 0,
+                plugin: PluginWrapper::new(plugin),
+            };
+
+            let (remaining, next_style) = column.draw_impl(display, None, 0, dither)?;
+
+            text = remaining;
+            character_style = next_style;
+            plugin = column.plugin.into_inner();
+        }
+
+        if text.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(text))
+        }
+    }
+
+    /// Draws the text box.
+    ///
+    /// Returns the part of the text that was not drawn, and the character style at the end of the
+    /// drawn text, which includes the style changes made by the text. If a row buffer is given,
+    /// the lines are drawn using [`draw_line_ordered`]. The text is moved up by `scroll_y` pixels, in
+    /// addition to the vertical offset.
+    ///
+    /// [`draw_line_ordered`]: Self::draw_line_ordered
+    fn draw_impl<D: DrawTarget<Color = <F as CharacterStyle>::Color>>(
+        &self,
+        display: &mut D,
+        mut row_buffer: Option<&mut [Option<<F as CharacterStyle>::Color>]>,
+        scroll_y: i32,
+        dim: Dim<<F as CharacterStyle>::Color>,
+    ) -> Result<(&'a str, F), D::Error> {
+        let (mut cursor, mut state, marker_width) = self.start_render(scroll_y);
+        let text_area = self.style.text_area(self.bounds, marker_width);
+
+        let mut anything_drawn = false;
+        // The first displayed line starts a paragraph, unless it follows a skipped wrapped line.
+        let mut paragraph_start = matches!(
+            state.end_type,
+            LineEndType::EndOfText | LineEndType::NewLine
+        );
+        loop {
+            state.plugin.new_line();
+
+            let line_start = cursor.line_start();
+            let line_area = self.displayed_line_area(&cursor);
+
+            if line_area.size.height == 0 {
+                // Display range can be empty if we are above, or below the visible text section
+                if anything_drawn {
+                    // We are below, so we won't be drawing anything else
+                    let consumed_bytes = self.consumed_bytes(&state);
+
+                    state.plugin.post_render(
+                        &mut display.clipped(&clip_area(line_area)),
+                        &self.character_style,
+                        None,
+                        Rectangle::new(line_start, Size::new(0, cursor.line_height())),
+                    )?;
+                    self.report_truncation(&cursor, &state, consumed_bytes);
+                    state.plugin.on_rendering_finished();
+                    return Ok((
+                        self.text.get(consumed_bytes..).unwrap(),
+                        state.text_renderer,
+                    ));
+                }
+            } else {
+                anything_drawn = true;
+            }
+
+            let marker = self.line_marker(paragraph_start, line_start, marker_width);
+            let avoid_broken_word = self.avoid_broken_word(&cursor);
+
+            if let Some(buffer) = row_buffer.as_deref_mut() {
+                let area = Self::marker_area(line_area, marker, marker_width);
+                self.draw_line_ordered(
+                    display,
+                    buffer,
+                    area,
+                    text_area,
+                    cursor.line(),
+                    &mut state,
+                    marker,
+                    avoid_broken_word,
+                    dim,
+                )?;
+            } else {
+                if let Some((marker, position)) = marker {
+                    let marker_area = Rectangle::new(
+                        Point::new(position.x, line_area.top_left.y),
+                        Size::new(marker_width, line_area.size.height),
+                    );
+                    state.text_renderer.draw_string(
+                        marker,
+                        position,
+                        Baseline::Top,
+                        &mut Dithered::new(
+                            display.clipped(&clip_area(marker_area)),
+                            self.style.intensity,
+                            dim,
+                        ),
+                    )?;
+                }
+
+                let offset_area = Self::offset_area(line_area, text_area);
+
+                // FIXME: cropping isn't necessary for whole lines, but make sure not to blow up
+                // the binary size as well. We could also use a different way to consume invisible
+                // text.
+                StyledLineRenderer {
+                    cursor: cursor.line(),
+                    state: &mut state,
+                    style: &self.style,
+                    avoid_broken_word,
+                    line_area: Some(clip_area(line_area)),
+                }
+                .draw(&mut Dithered::new(
+                    display.clipped(&clip_area(offset_area)),
+                    self.style.intensity,
+                    dim,
+                ))?;
+            }
+
+            paragraph_start = state.end_type == LineEndType::NewLine;
+            if state.end_type == LineEndType::EndOfText {
+                state.plugin.on_rendering_finished();
+                break;
+            }
+            self.next_line(&mut cursor, state.end_type);
+        }
+
+        Ok(("", state.text_renderer))
+    }
+
+    /// Creates the cursor and the render state used to draw the first line of text.
+    ///
+    /// Returns the cursor, the render state and the width of the list marker.
+    fn start_render(&self, scroll_y: i32) -> (Cursor, LineRenderState<'a, '_, F, M>, u32) {
+        let marker_width = self
+            .style
+            .list_marker_width(|marker| str_width(&self.character_style, marker));
+        let text_area = self.balanced_area(self.style.text_area(self.bounds, marker_width));
         let mut cursor = Cursor::new(
-            self.bounds,
+            text_area,
             self.character_style.line_height(),
             self.style.line_height,
             self.style.tab_size.into_pixels(&self.character_style),
-        );
+        )
+        .with_line_heights(self.style.line_heights);
 
         let text_height = self
             .style
@@ -71,11 +609,11 @@ where
                 self.plugin.clone(),
                 &self.character_style,
                 self.text,
-                cursor.line_width(),
+                self.bounds.size.width,
             )
             .saturating_as::<i32>();
 
-        let box_height = self.bounding_box().size.height.saturating_as::<i32>();
+        let box_height = text_area.size.height.saturating_as::<i32>();
 
         self.style.vertical_alignment.apply_vertical_alignment(
             &mut cursor,
@@ -83,110 +621,539 @@ where
             box_height,
         );
 
-        cursor.y += self.vertical_offset;
+        cursor.move_down(self.vertical_offset.saturating_sub(scroll_y));
 
         let props = TextBoxProperties {
             box_style: &self.style,
             char_style: &self.character_style,
             text_height,
             bounding_box: self.bounding_box(),
+            text: self.text,
         };
 
         self.plugin.on_start_render(&mut cursor, props);
 
         let mut state = LineRenderState {
             text_renderer: self.character_style.clone(),
-            parser: Parser::parse(self.text),
+            parser: self.style.parser(self.text),
             end_type: LineEndType::EndOfText,
+            isolates: Isolates::new(),
             plugin: &self.plugin,
+            reveal: self.style.reveal_chars,
+            vertical_offset: 0,
         };
 
         state.plugin.set_state(ProcessingState::Render);
 
-        let mut anything_drawn = false;
-        loop {
+        self.skip_hidden_lines(cursor, &mut state, text_height - box_height);
+
+        (cursor, state, marker_width)
+    }
+
+    /// Returns the part of the text area that balanced lines are laid out in.
+    fn balanced_area(&self, text_area: Rectangle) -> Rectangle {
+        let box_width = self.bounds.size.width;
+        let width = self.style.balanced_width(box_width, |width| {
+            self.style.measure_line_count_impl(
+                self.plugin.clone(),
+                &self.character_style,
+                self.text,
+                width,
+            )
+        });
+
+        let narrowed = box_width - width;
+        let offset = match self.style.alignment {
+            HorizontalAlignment::Left | HorizontalAlignment::Justified => 0,
+            HorizontalAlignment::Center => narrowed / 2,
+            HorizontalAlignment::Right => narrowed,
+        };
+
+        Rectangle::new(
+            text_area.top_left + Point::new(offset.saturating_as(), 0),
+            Size::new(
+                text_area.size.width.saturating_sub(narrowed),
+                text_area.size.height,
+            ),
+        )
+    }
+
+    /// Advances the render state past the lines before [`first_visible_line`].
+    ///
+    /// Lines are only skipped while the rest of the text is taller than `max_skipped_height`, so
+    /// the last page of the text is displayed when the first visible line is out of range.
+    ///
+    /// [`first_visible_line`]: TextBox::first_visible_line
+    fn skip_hidden_lines(
+        &self,
+        mut cursor: Cursor,
+        state: &mut LineRenderState<'a, '_, F, M>,
+        max_skipped_height: i32,
+    ) {
+        let top = cursor.y;
+        let mut skipped = 0;
+        while skipped < self.first_visible_line && cursor.y - top < max_skipped_height {
             state.plugin.new_line();
+            self.skip_line(cursor.line(), state, false);
 
-            let display_range = self
-                .style
-                .height_mode
-                .calculate_displayed_row_range(&cursor);
-            let display_range_start = display_range.start.saturating_as::<i32>();
-            let display_range_count = display_range.count() as u32;
-            let display_size = Size::new(cursor.line_width(), display_range_count);
+            match state.end_type {
+                LineEndType::EndOfText => break,
+                LineEndType::CarriageReturn => {}
+                LineEndType::LineBreak | LineEndType::NewLine => skipped += 1,
+            }
+            self.next_line(&mut cursor, state.end_type);
+        }
+    }
 
-            let line_start = cursor.line_start();
+    /// Returns the number of bytes of the text that were rendered.
+    fn consumed_bytes(&self, state: &LineRenderState<'a, '_, F, M>) -> usize {
+        // The token that didn't fit into the last line is not part of the
+        // parser's remaining text any more.
+        let remaining_bytes = state
+            .plugin
+            .peeked_str()
+            .and_then(|peeked| remaining_len_from(self.text, peeked))
+            .unwrap_or_else(|| state.parser.as_str().len());
 
-            // FIXME: cropping isn't necessary for whole lines, but make sure not to blow up the
-            // binary size as well. We could also use a different way to consume invisible text.
-            let mut display = display.clipped(&Rectangle::new(
-                line_start + Point::new(0, display_range_start),
-                display_size,
-            ));
-            if display_range_count == 0 {
-                // Display range can be empty if we are above, or below the visible text section
-                if anything_drawn {
-                    // We are below, so we won't be drawing anything else
-                    let remaining_bytes = state.parser.as_str().len();
-                    let consumed_bytes = self.text.len() - remaining_bytes;
+        self.text.len() - remaining_bytes
+    }
 
-                    state.plugin.post_render(
-                        &mut display,
-                        &self.character_style,
-                        None,
-                        Rectangle::new(line_start, Size::new(0, cursor.line_height())),
-                    )?;
-                    state.plugin.on_rendering_finished();
-                    return Ok(self.text.get(consumed_bytes..).unwrap());
-                }
-            } else {
-                anything_drawn = true;
+    /// Reports the text after the first `offset` bytes, that was not rendered, to the plugins.
+    fn report_truncation(
+        &self,
+        cursor: &Cursor,
+        state: &LineRenderState<'a, '_, F, M>,
+        offset: usize,
+    ) {
+        if offset >= self.text.len() {
+            return;
+        }
+
+        let plugin = state.plugin.clone();
+        plugin.set_state(ProcessingState::Measure);
+        let mut parser = state.parser.clone();
+        let max_width = self.style.line_layout_width(cursor.line_width());
+
+        let mut hidden_lines = 1;
+        loop {
+            plugin.new_line();
+            let lm =
+                self.style
+                    .measure_line(&plugin, &self.character_style, &mut parser, max_width);
+
+            match lm.line_end_type {
+                LineEndType::EndOfText => break,
+                LineEndType::CarriageReturn => {}
+                LineEndType::LineBreak | LineEndType::NewLine => hidden_lines += 1,
             }
+        }
+
+        state.plugin.on_truncated(TruncationInfo {
+            offset,
+            hidden_lines,
+        });
+    }
+
+    /// Returns whether the line at the cursor is the last displayed line, which shouldn't end with
+    /// a broken word.
+    fn avoid_broken_word(&self, cursor: &Cursor) -> bool {
+        if !self.style.avoid_broken_last_word || self.displayed_line_area(cursor).size.height == 0 {
+            return false;
+        }
+
+        let mut next = *cursor;
+        next.new_line();
+
+        self.displayed_line_area(&next).size.height == 0
+    }
+
+    /// Returns the displayed rows of the line at the cursor.
+    fn displayed_line_area(&self, cursor: &Cursor) -> Rectangle {
+        if self.style.is_past_max_lines(cursor.line_index()) {
+            return Rectangle::new(cursor.line_start(), Size::zero());
+        }
+
+        let display_range = self.style.height_mode.calculate_displayed_row_range(cursor);
+        let display_range_start = display_range.start.saturating_as::<i32>();
+        let display_range_count = display_range.count() as u32;
+
+        // Hanging punctuation is drawn into the right padding.
+        let hang_width = if self.style.hanging_punctuation
+            && self.style.alignment == HorizontalAlignment::Justified
+        {
+            self.style.padding.right.min(self.bounds.size.width)
+        } else {
+            0
+        };
+
+        Rectangle::new(
+            cursor.line_start() + Point::new(0, display_range_start),
+            Size::new(
+                cursor.line_width().saturating_add(hang_width),
+                display_range_count,
+            ),
+        )
+    }
+
+    /// Returns the list marker drawn before a line, and its position.
+    ///
+    /// The list marker hangs in the gutter left of the first line of each paragraph.
+    fn line_marker(
+        &self,
+        paragraph_start: bool,
+        line_start: Point,
+        marker_width: u32,
+    ) -> Option<(&'static str, Point)> {
+        self.style
+            .list_marker
+            .filter(|_| paragraph_start)
+            .map(|marker| {
+                (
+                    marker,
+                    line_start - Point::new(marker_width.saturating_as(), 0),
+                )
+            })
+    }
+
+    /// Returns the area of a line, extended to the gutter if the line has a list marker.
+    fn marker_area(
+        line_area: Rectangle,
+        marker: Option<(&str, Point)>,
+        marker_width: u32,
+    ) -> Rectangle {
+        match marker {
+            Some(_) => Rectangle::new(
+                line_area.top_left - Point::new(marker_width.saturating_as(), 0),
+                line_area.size + Size::new(marker_width, 0),
+            ),
+            None => line_area,
+        }
+    }
+
+    /// Returns the area the glyphs of a line may be moved into by a vertical offset.
+    ///
+    /// Glyphs may leave their line, but not the text area, so the area covers the rows of both the
+    /// line and the text area, and the columns of the line.
+    fn offset_area(line_area: Rectangle, text_area: Rectangle) -> Rectangle {
+        if line_area.size.height == 0 {
+            return line_area;
+        }
+
+        let end = |area: Rectangle| area.top_left.y as i64 + area.size.height as i64;
+        let top = line_area.top_left.y.min(text_area.top_left.y);
+        let height = end(line_area).max(end(text_area)) - top as i64;
+
+        Rectangle::new(
+            Point::new(line_area.top_left.x, top),
+            Size::new(line_area.size.width, height.saturating_as()),
+        )
+    }
+
+    /// Returns the area of a line, extended by the rows its glyphs are moved into by vertical
+    /// offsets.
+    ///
+    /// The line is laid out using a copy of the render state to find the offsets, without
+    /// drawing it.
+    fn glyph_area(
+        &self,
+        area: Rectangle,
+        text_area: Rectangle,
+        cursor: LineCursor,
+        state: &LineRenderState<'a, '_, F, M>,
+        avoid_broken_word: bool,
+    ) -> Rectangle {
+        let (min, max) = state.offset_range(cursor, &self.style, avoid_broken_word);
+        if (min, max) == (0, 0) {
+            return area;
+        }
+
+        let top = area.top_left.y.saturating_add(min.min(0) as i32);
+        let height = area
+            .size
+            .height
+            .saturating_add((max.max(0) - min.min(0)) as u32);
+        let moved = Rectangle::new(
+            Point::new(area.top_left.x, top),
+            Size::new(area.size.width, height),
+        );
+
+        moved.intersection(&Self::offset_area(area, text_area))
+    }
 
-            StyledLineRenderer {
-                cursor: cursor.line(),
-                state: &mut state,
-                style: &self.style,
+    /// Moves the cursor to the line after a line that ended with `end_type`.
+    fn next_line(&self, cursor: &mut Cursor, end_type: LineEndType) {
+        match end_type {
+            LineEndType::EndOfText | LineEndType::CarriageReturn => {}
+            LineEndType::LineBreak => cursor.new_line(),
+            LineEndType::NewLine => {
+                cursor.new_line();
+                cursor.move_down(self.style.paragraph_spacing.saturating_as());
             }
-            .draw(&mut display)?;
+        }
+    }
 
-            match state.end_type {
-                LineEndType::EndOfText => {
-                    state.plugin.on_rendering_finished();
-                    break;
-                }
-                LineEndType::CarriageReturn => {}
-                _ => {
-                    cursor.new_line();
+    /// Draws a line of text clipped to `area`, row by row.
+    ///
+    /// The line is rendered into `buffer`, and only the last color drawn to each pixel is kept.
+    /// If the buffer can't hold the whole area, the line is rendered once for every band of rows
+    /// that fits into the buffer, using a copy of the render state for every band but the last.
+    ///
+    /// If `marker` is set, the list marker is drawn at the given position in every pass. Glyphs
+    /// moved by a vertical offset are drawn outside of `area`, within `text_area`.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_line_ordered<D: DrawTarget<Color = <F as CharacterStyle>::Color>>(
+        &self,
+        display: &mut D,
+        buffer: &mut [Option<<F as CharacterStyle>::Color>],
+        line_area: Rectangle,
+        text_area: Rectangle,
+        cursor: LineCursor,
+        state: &mut LineRenderState<'a, '_, F, M>,
+        marker: Option<(&str, Point)>,
+        avoid_broken_word: bool,
+        dim: Dim<<F as CharacterStyle>::Color>,
+    ) -> Result<(), D::Error> {
+        let area = self
+            .glyph_area(
+                line_area,
+                text_area,
+                cursor.clone(),
+                state,
+                avoid_broken_word,
+            )
+            .intersection(&display.bounding_box());
 
-                    if state.end_type == LineEndType::NewLine {
-                        cursor.y += self.style.paragraph_spacing.saturating_as::<i32>();
-                    }
-                }
+        // Short buffers fall back to a single row segment.
+        let mut segment = [None; SEGMENT_WIDTH];
+        let buffer = if buffer.len() < SEGMENT_WIDTH {
+            &mut segment[..]
+        } else {
+            buffer
+        };
+
+        let mut bands = ordered::bands(area, buffer.len()).peekable();
+        if bands.peek().is_none() {
+            self.skip_line(cursor.clone(), state, avoid_broken_word);
+        }
+        while let Some(band) = bands.next() {
+            let mut rows = RowBuffer::new(band, buffer);
+            if bands.peek().is_some() {
+                let plugin = state.plugin.clone();
+                let mut pass_state = state.fork(&plugin);
+                self.render_line(
+                    &mut pass_state,
+                    cursor.clone(),
+                    area,
+                    line_area,
+                    marker,
+                    avoid_broken_word,
+                    dim,
+                    &mut rows,
+                );
+            } else {
+                self.render_line(
+                    state,
+                    cursor.clone(),
+                    area,
+                    line_area,
+                    marker,
+                    avoid_broken_word,
+                    dim,
+                    &mut rows,
+                );
             }
+            rows.flush(display)?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders a line into a draw target that can't fail, clipped to `area`.
+    ///
+    /// Everything but the glyphs moved by a vertical offset is also clipped to `line_area`. If
+    /// `marker` is set, the list marker is drawn at the given position.
+    #[allow(clippy::too_many_arguments)]
+    fn render_line<T>(
+        &self,
+        state: &mut LineRenderState<'a, '_, F, M>,
+        cursor: LineCursor,
+        area: Rectangle,
+        line_area: Rectangle,
+        marker: Option<(&str, Point)>,
+        avoid_broken_word: bool,
+        dim: Dim<<F as CharacterStyle>::Color>,
+        target: &mut T,
+    ) where
+        T: DrawTarget<Color = <F as CharacterStyle>::Color, Error = Infallible>,
+    {
+        let mut target = Dithered::new(target.clipped(&clip_area(area)), self.style.intensity, dim);
+        if let Some((marker, position)) = marker {
+            state
+                .text_renderer
+                .draw_string(marker, position, Baseline::Top, &mut target)
+                .unwrap_or_else(|e| match e {});
+        }
+        StyledLineRenderer {
+            cursor,
+            state,
+            style: &self.style,
+            avoid_broken_word,
+            line_area: Some(clip_area(line_area)),
+        }
+        .draw(&mut target)
+        .unwrap_or_else(|e| match e {});
+    }
+
+    /// Renders the pixels of a line in the row segment that starts at `origin`.
+    ///
+    /// The line is rendered using a copy of the render state, so the state is not advanced.
+    #[allow(clippy::too_many_arguments)]
+    fn render_segment(
+        &self,
+        state: &LineRenderState<'a, '_, F, M>,
+        cursor: LineCursor,
+        area: Rectangle,
+        line_area: Rectangle,
+        marker: Option<(&str, Point)>,
+        origin: Point,
+        avoid_broken_word: bool,
+    ) -> PixelChunk<<F as CharacterStyle>::Color> {
+        let plugin = state.plugin.clone();
+        let mut pass_state = state.fork(&plugin);
+
+        let mut segment = RowSegment::new(origin);
+        self.render_line(
+            &mut pass_state,
+            cursor,
+            area,
+            line_area,
+            marker,
+            avoid_broken_word,
+            dither,
+            &mut segment,
+        );
+
+        segment.into_chunk()
+    }
+
+    /// Renders a line without drawing anything, to advance the render state.
+    fn skip_line(
+        &self,
+        cursor: LineCursor,
+        state: &mut LineRenderState<'a, '_, F, M>,
+        avoid_broken_word: bool,
+    ) {
+        StyledLineRenderer {
+            cursor,
+            state,
+            style: &self.style,
+            avoid_broken_word,
+            line_area: None,
         }
+        .draw(&mut RowSegment::new(Point::zero()).clipped(&Rectangle::zero()))
+        .unwrap_or_else(|e| match e {});
+    }
+}
+
+impl<'a, F, M> TextBox<'a, F, M>
+where
+    F: TextRenderer<Color = <F as CharacterStyle>::Color> + CharacterStyle,
+    <F as CharacterStyle>::Color: From<Rgb888> + BlendColor,
+    M: Plugin<'a, <F as TextRenderer>::Color> + Plugin<'a, <F as CharacterStyle>::Color>,
+{
+    /// Draws the text box by blending it over the current contents of the draw target.
+    ///
+    /// Every pixel of the text is combined with the pixel it is drawn over using
+    /// [`BlendColor::blend`]. This requires reading back the draw target, so it only works with
+    /// draw targets that implement [`GetPixel`], like framebuffers.
+    ///
+    /// Returns the part of the text that was not drawn, like [`draw`] does.
+    ///
+    /// [`draw`]: Drawable::draw
+    #[inline]
+    pub fn draw_blended<D>(&self, display: &mut D) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>
+            + GetPixel<Color = <F as CharacterStyle>::Color>,
+    {
+        self.draw(&mut Blended::new(display))
+    }
+}
 
-        Ok("")
+impl<'a, F, M> TextBox<'a, F, M>
+where
+    F: TextRenderer<Color = <F as CharacterStyle>::Color> + CharacterStyle,
+    <F as CharacterStyle>::Color: From<Rgb888> + DimColor,
+    M: Plugin<'a, <F as TextRenderer>::Color> + Plugin<'a, <F as CharacterStyle>::Color>,
+{
+    /// Draws the text box, scaling the colors of dimmed text.
+    ///
+    /// With an [`intensity`] below 255, [`draw`] leaves out pixels using an ordered dither pattern
+    /// for every color type. This method scales the colors towards black using [`DimColor::dim`]
+    /// instead, which looks better on displays with gray or RGB colors. Colors that don't
+    /// implement scaling, like `BinaryColor`, are dithered.
+    ///
+    /// Returns the part of the text that was not drawn, like [`draw`] does.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     mock_display::MockDisplay,
+    ///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    ///     pixelcolor::Rgb565,
+    ///     prelude::*,
+    ///     primitives::Rectangle,
+    /// };
+    /// use embedded_text::{style::TextBoxStyleBuilder, TextBox};
+    ///
+    /// let text_box = TextBox::with_textbox_style(
+    ///     "Disabled",
+    ///     Rectangle::new(Point::zero(), Size::new(48, 9)),
+    ///     MonoTextStyle::new(&FONT_6X9, Rgb565::WHITE),
+    ///     TextBoxStyleBuilder::new().intensity(128).build(),
+    /// );
+    ///
+    /// let mut display = MockDisplay::new();
+    /// text_box.draw_dimmed(&mut display).unwrap();
+    /// ```
+    ///
+    /// [`intensity`]: crate::style::TextBoxStyle::intensity
+    /// [`draw`]: Drawable::draw
+    #[inline]
+    pub fn draw_dimmed<D>(&self, display: &mut D) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <F as CharacterStyle>::Color>,
+    {
+        self.draw_impl(display, None, 0, DimColor::dim)
+            .map(|(remaining, _)| remaining)
     }
 }
 
 #[cfg(test)]
 pub mod test {
+    use core::{cell::Cell, num::NonZeroU32};
     use embedded_graphics::{
         mock_display::MockDisplay,
         mono_font::{
             ascii::{FONT_6X10, FONT_6X9},
-            MonoTextStyleBuilder,
+            iso_8859_1::FONT_6X9 as FONT_6X9_LATIN1,
+            MonoTextStyle, MonoTextStyleBuilder,
         },
-        pixelcolor::BinaryColor,
+        pixelcolor::{BinaryColor, Rgb888},
         prelude::*,
         primitives::Rectangle,
+        text::{Baseline, LineHeight, Text},
     };
 
     use crate::{
-        alignment::HorizontalAlignment,
-        style::{HeightMode, TextBoxStyle, TextBoxStyleBuilder, VerticalOverdraw},
-        utils::test::size_for,
+        alignment::{HorizontalAlignment, VerticalAlignment},
+        style::{
+            ControlCharacters, GridColumn, HeightMode, LayoutSnapshot, LineBreak, Padding,
+            ParserConfig, TabLeader, TabSize, TextBoxStyle, TextBoxStyleBuilder, TextTransform,
+            VerticalOverdraw, WhitespaceStyle,
+        },
+        utils::test::{size_for, CountingDraws},
         TextBox,
     };
 
@@ -403,16 +1370,2009 @@ pub mod test {
             "#...#..###...###...###...###....#...........................",
             "...............................#............................",
             "............................................................",
-            "............                                                ",
-            "............                                                ",
-            "............                                                ",
-            ".......###..                                                ",
-            "......#.....                                                ",
-            ".......###..                                                ",
-            "..........#.                                                ",
-            "......####..                                                ",
-            "............                                                ",
-            "............                                                ",
+            // The two spaces that don't fit in the first line are carried to the second one.
+            "..................                                          ",
+            "..................                                          ",
+            "..................                                          ",
+            ".............###..                                          ",
+            "............#.....                                          ",
+            ".............###..                                          ",
+            "................#.                                          ",
+            "............####..                                          ",
+            "..................                                          ",
+            "..................                                          ",
         ]);
     }
+
+    #[test]
+    fn horizontal_padding_insets_text() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let size = size_for(&FONT_6X9, 8, 2);
+
+        for alignment in [HorizontalAlignment::Left, HorizontalAlignment::Right] {
+            let style = TextBoxStyleBuilder::new()
+                .alignment(alignment)
+                .padding(Padding::horizontal(8, 2))
+                .build();
+
+            // "aaa bbb" is 42px wide, which doesn't fit into the 38px wide padded area.
+            assert_eq!(
+                style.measure_text_height(&character_style, "aaa bbb", 48),
+                18
+            );
+
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(
+                "aaa bbb",
+                Rectangle::new(Point::zero(), size),
+                character_style,
+                style,
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            let mut expected = MockDisplay::new();
+            TextBox::with_alignment(
+                "aaa\nbbb",
+                Rectangle::new(Point::new(8, 0), size - Size::new(10, 0)),
+                character_style,
+                alignment,
+            )
+            .draw(&mut expected)
+            .unwrap();
+
+            display.assert_eq(&expected);
+        }
+    }
+
+    #[test]
+    fn vertical_padding_insets_text() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let size = size_for(&FONT_6X9, 3, 3);
+
+        for alignment in [
+            VerticalAlignment::Top,
+            VerticalAlignment::Middle,
+            VerticalAlignment::Bottom,
+        ] {
+            let style = TextBoxStyleBuilder::new()
+                .vertical_alignment(alignment)
+                .height_mode(HeightMode::Exact(VerticalOverdraw::FullRowsOnly))
+                .padding(Padding::vertical(5, 4))
+                .build();
+
+            // The first line is moved down by 5px, and only 2 lines fit into the padded area.
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(
+                "a b c",
+                Rectangle::new(Point::zero(), size),
+                character_style,
+                style,
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            let mut expected = MockDisplay::new();
+            TextBox::with_textbox_style(
+                "a b c",
+                Rectangle::new(Point::new(0, 5), size - Size::new(0, 9)),
+                character_style,
+                TextBoxStyleBuilder::new()
+                    .vertical_alignment(alignment)
+                    .height_mode(HeightMode::Exact(VerticalOverdraw::FullRowsOnly))
+                    .build(),
+            )
+            .draw(&mut expected)
+            .unwrap();
+
+            display.assert_eq(&expected);
+        }
+    }
+
+    #[test]
+    fn fitting_height_includes_vertical_padding() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .height_mode(HeightMode::FitToText)
+            .padding(Padding::vertical(5, 4))
+            .build();
+
+        let text_box = TextBox::with_textbox_style(
+            "a b",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 1, 1)),
+            character_style,
+            style,
+        );
+
+        assert_eq!(text_box.bounds.size.height, 2 * 9 + 5 + 4);
+        assert!(style.fits_in(&character_style, "a b", Size::new(6, 27)));
+        assert!(!style.fits_in(&character_style, "a b", Size::new(6, 26)));
+    }
+
+    #[test]
+    fn fitting_height_excludes_trailing_leading() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        for (percent, height, height_with_leading) in [(100, 27, 27), (120, 29, 30), (200, 45, 54)]
+        {
+            for (include, expected) in [(false, height), (true, height_with_leading)] {
+                let style = TextBoxStyleBuilder::new()
+                    .height_mode(HeightMode::FitToText)
+                    .line_height(LineHeight::Percent(percent))
+                    .include_trailing_leading(include)
+                    .build();
+
+                let text_box = TextBox::with_textbox_style(
+                    "a\nb\nc",
+                    Rectangle::new(Point::zero(), size_for(&FONT_6X9, 1, 1)),
+                    character_style,
+                    style,
+                );
+
+                assert_eq!(
+                    text_box.bounds.size.height, expected,
+                    "{percent}% {include}"
+                );
+                assert_eq!(
+                    style.measure_text_height(&character_style, "a\nb\nc", 6),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vertical_alignment_uses_fitted_height() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), Size::new(6, 54));
+
+        let draw = |include, alignment| {
+            let style = TextBoxStyleBuilder::new()
+                .line_height(LineHeight::Percent(200))
+                .include_trailing_leading(include)
+                .vertical_alignment(alignment)
+                .build();
+
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style("a\nb\nc", bounds, character_style, style)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+
+        // The text fills the box if the trailing leading is included.
+        draw(true, VerticalAlignment::Middle).assert_eq(&draw(true, VerticalAlignment::Top));
+        draw(true, VerticalAlignment::Bottom).assert_eq(&draw(true, VerticalAlignment::Top));
+
+        // Otherwise the glyphs of the last line end at the bottom edge.
+        let mut expected = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "a\nb\nc",
+            bounds.translate(Point::new(0, 9)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .line_height(LineHeight::Percent(200))
+                .build(),
+        )
+        .draw(&mut expected)
+        .unwrap();
+        draw(false, VerticalAlignment::Bottom).assert_eq(&expected);
+    }
+
+    #[track_caller]
+    fn assert_max_lines(text: &str, avoid_broken_last_word: bool, expected: &str, lines: u32) {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 10));
+
+        let text_box = TextBox::with_textbox_style(
+            text,
+            bounds,
+            character_style,
+            TextBoxStyleBuilder::new()
+                .height_mode(HeightMode::FitToText)
+                .hyphenation(|_, _| true)
+                .avoid_broken_last_word(avoid_broken_last_word)
+                .max_lines(NonZeroU32::new(2).unwrap())
+                .build(),
+        );
+        assert_eq!(text_box.bounds.size.height, lines * 9);
+
+        let mut display = MockDisplay::new();
+        text_box.draw(&mut display).unwrap();
+
+        let mut expected_display = MockDisplay::new();
+        TextBox::new(expected, bounds, character_style)
+            .draw(&mut expected_display)
+            .unwrap();
+
+        display.assert_eq(&expected_display);
+    }
+
+    #[test]
+    fn max_lines_limits_displayed_lines() {
+        assert_max_lines("abc", false, "abc", 1);
+        assert_max_lines("abc def", false, "abc\ndef", 2);
+        assert_max_lines("one two three four five", false, "one\ntwo", 2);
+        assert_max_lines("one\ntwo\nthree", false, "one\ntwo", 2);
+
+        // The last displayed line can end with a broken word...
+        assert_max_lines("ab cd ef ghij", false, "ab cd\nef g-", 2);
+        assert_max_lines("abc", true, "abc", 1);
+        assert_max_lines("abc def", true, "abc\ndef", 2);
+
+        // ... unless it is avoided.
+        assert_max_lines("ab cd ef ghij", true, "ab cd\nef", 2);
+    }
+
+    #[test]
+    fn max_lines_are_aligned_as_the_whole_text() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 6));
+
+        let draw = |text, max_lines| {
+            let mut builder =
+                TextBoxStyleBuilder::new().vertical_alignment(VerticalAlignment::Bottom);
+            if let Some(max_lines) = NonZeroU32::new(max_lines) {
+                builder = builder.max_lines(max_lines);
+            }
+
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(text, bounds, character_style, builder.build())
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+
+        draw("one two three four five", 2).assert_eq(&draw("one two", 0));
+    }
+
+    #[test]
+    fn first_visible_line_skips_lines() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 3));
+        let text = "l0 l1\nl2 l3 l4 l5\nl6 l7\nl8\nl9";
+
+        let draw = |text, first_visible_line| {
+            let mut text_box = TextBox::new(text, bounds, character_style);
+            text_box.set_first_visible_line(first_visible_line);
+
+            let mut display = MockDisplay::new();
+            text_box.draw(&mut display).unwrap();
+            display
+        };
+
+        assert_eq!(TextBox::new(text, bounds, character_style).line_count(), 10);
+
+        draw(text, 0).assert_eq(&draw("l0\nl1\nl2", 0));
+        draw(text, 4).assert_eq(&draw("l4\nl5\nl6", 0));
+
+        // Out of range lines display the last page.
+        draw(text, 8).assert_eq(&draw("l7\nl8\nl9", 0));
+        draw(text, 100).assert_eq(&draw("l7\nl8\nl9", 0));
+    }
+
+    #[test]
+    fn reveal_chars_draws_first_characters() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2));
+
+        let draw = |text, reveal_chars: Option<usize>| {
+            let mut builder = TextBoxStyleBuilder::new();
+            if let Some(reveal_chars) = reveal_chars {
+                builder = builder.reveal_chars(reveal_chars);
+            }
+            let text_box =
+                TextBox::with_textbox_style(text, bounds, character_style, builder.build());
+
+            let mut display = MockDisplay::new();
+            text_box.draw(&mut display).unwrap();
+            display
+        };
+
+        draw("hello", Some(3)).assert_eq(&draw("hel", None));
+        draw("hello", Some(0)).assert_eq(&draw("", None));
+        draw("hello", Some(10)).assert_eq(&draw("hello", None));
+
+        // Hidden characters keep their place, so later lines don't move.
+        draw("ab cd\nef", Some(3)).assert_eq(&draw("ab c", None));
+    }
+
+    #[test]
+    fn balanced_lines_are_aligned_in_text_box() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 20, 2));
+
+        for alignment in [
+            HorizontalAlignment::Left,
+            HorizontalAlignment::Center,
+            HorizontalAlignment::Right,
+        ] {
+            let draw = |text, line_break| {
+                let style = TextBoxStyleBuilder::new()
+                    .alignment(alignment)
+                    .line_break(line_break)
+                    .build();
+
+                let mut display = MockDisplay::new();
+                TextBox::with_textbox_style(text, bounds, character_style, style)
+                    .draw(&mut display)
+                    .unwrap();
+                display
+            };
+
+            draw("The quick brown fox jumps over", LineBreak::Balanced)
+                .assert_eq(&draw("The quick brown\nfox jumps over", LineBreak::Greedy));
+        }
+    }
+
+    #[test]
+    fn list_marker_indents_wrapped_lines() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new().list_marker("- ").build();
+
+        // The marker takes 2 of the 5 characters of each line.
+        assert_eq!(
+            style.measure_text_height(&character_style, "aa bb\ncc", 30),
+            27
+        );
+
+        for ordered in [false, true] {
+            let text_box = TextBox::with_textbox_style(
+                "aa bb\ncc",
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 3)),
+                character_style,
+                style,
+            );
+
+            let mut display = MockDisplay::new();
+            if ordered {
+                text_box
+                    .draw_ordered(&mut display, &mut [None; 64])
+                    .unwrap();
+            } else {
+                text_box.draw(&mut display).unwrap();
+            }
+
+            let mut expected = MockDisplay::new();
+            TextBox::new(
+                "aa bb\ncc",
+                Rectangle::new(Point::new(12, 0), size_for(&FONT_6X9, 3, 3)),
+                character_style,
+            )
+            .draw(&mut expected)
+            .unwrap();
+            for y in [0, 18] {
+                Text::with_baseline("-", Point::new(0, y), character_style, Baseline::Top)
+                    .draw(&mut expected)
+                    .unwrap();
+            }
+
+            display.assert_eq(&expected);
+        }
+    }
+
+    #[test]
+    fn scrolled_text_is_moved_and_clipped() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 3));
+        let text = "1\n2\n3\n4";
+
+        let text_box = TextBox::with_textbox_style(
+            text,
+            bounds,
+            character_style,
+            TextBoxStyleBuilder::new()
+                .height_mode(HeightMode::Exact(VerticalOverdraw::Hidden))
+                .build(),
+        );
+
+        let first_row = |scroll_y| {
+            let mut display = MockDisplay::new();
+            text_box.draw_scrolled(&mut display, scroll_y).unwrap();
+
+            // The whole text, moved up by `scroll_y` and clipped to the text box.
+            let mut expected = MockDisplay::new();
+            TextBox::new(
+                text,
+                Rectangle::new(Point::new(0, -scroll_y), size_for(&FONT_6X9, 3, 4)),
+                character_style,
+            )
+            .draw(&mut expected.clipped(&bounds))
+            .unwrap();
+
+            display.assert_eq(&expected);
+
+            let area = display.affected_area();
+            (area.size.height > 0).then(|| area.top_left.y)
+        };
+
+        // The top of "1" is 1px below the top of the line.
+        assert_eq!(first_row(0), Some(1));
+        assert_eq!(first_row(-5), Some(6));
+        assert_eq!(first_row(-25), Some(26));
+        assert_eq!(first_row(-26), None);
+        assert_eq!(first_row(4), Some(0));
+        assert_eq!(first_row(30), Some(0));
+        assert_eq!(first_row(36), None);
+    }
+
+    /// Draw target that records the order of the drawn pixels.
+    struct Recorder {
+        display: MockDisplay<BinaryColor>,
+        points: Vec<Point>,
+    }
+
+    impl Dimensions for Recorder {
+        fn bounding_box(&self) -> Rectangle {
+            self.display.bounding_box()
+        }
+    }
+
+    impl DrawTarget for Recorder {
+        type Color = BinaryColor;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<BinaryColor>>,
+        {
+            for pixel in pixels {
+                self.points.push(pixel.0);
+                self.display.draw_iter(core::iter::once(pixel))?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn ordered_drawing_emits_each_pixel_once_in_row_order() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .underline()
+            .build();
+
+        let text_box = TextBox::new(
+            "Lorem ipsum dolor sit amet",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 8, 4)),
+            character_style,
+        );
+
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        let expected_remaining = text_box.draw(&mut expected).unwrap();
+
+        // A whole line, a band of rows, a row segment and a buffer that is too short.
+        for buffer_len in [48 * 9, 100, 40, 0] {
+            let mut recorder = Recorder {
+                display: MockDisplay::new(),
+                points: Vec::new(),
+            };
+            let mut buffer = vec![None; buffer_len];
+            let remaining = text_box.draw_ordered(&mut recorder, &mut buffer).unwrap();
+            assert_eq!(remaining, expected_remaining);
+
+            recorder.display.assert_eq(&expected);
+            assert!(!recorder.points.is_empty());
+            for pair in recorder.points.windows(2) {
+                assert!(
+                    (pair[0].y, pair[0].x) < (pair[1].y, pair[1].x),
+                    "{:?} is drawn after {:?}",
+                    pair[1],
+                    pair[0]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ordered_drawing_renders_lines_once_with_line_buffer() {
+        let draws = Cell::new(0);
+        let character_style = CountingDraws {
+            inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            draws: &draws,
+        };
+
+        let text_box = TextBox::new(
+            "Lorem ipsum dolor sit amet",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 8, 4)),
+            character_style.clone(),
+        );
+
+        text_box.draw(&mut MockDisplay::new()).unwrap();
+        let single_pass = draws.replace(0);
+
+        let mut buffer = [None; 48 * 9];
+        text_box
+            .draw_ordered(&mut MockDisplay::new(), &mut buffer)
+            .unwrap();
+        assert_eq!(draws.get(), single_pass);
+    }
+
+    #[track_caller]
+    fn assert_renders_like(text: &str, expected: &str, max_chars: u32) {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, max_chars, 2));
+
+        let mut display = MockDisplay::new();
+        TextBox::new(text, bounds, character_style)
+            .draw(&mut display)
+            .unwrap();
+
+        let mut expected_display = MockDisplay::new();
+        TextBox::new(expected, bounds, character_style)
+            .draw(&mut expected_display)
+            .unwrap();
+
+        display.assert_eq(&expected_display);
+    }
+
+    #[test]
+    fn rtl_isolates_are_drawn_in_reverse_order() {
+        assert_renders_like("price: \u{2067}123\u{2069} USD", "price: 321 USD", 15);
+        assert_renders_like("price: \u{2067}a-b\u{2069} USD", "price: b-a USD", 15);
+
+        // Left-to-right isolates don't change the order.
+        assert_renders_like("price: \u{2066}123\u{2069} USD", "price: 123 USD", 15);
+
+        // The run is wrapped like any other text, and continues in the next line.
+        assert_renders_like("\u{2067}abc def\u{2069} ghi", "cba\nfed\nghi", 5);
+    }
+
+    #[test]
+    fn rtl_isolates_reverse_word_order() {
+        assert_renders_like("\u{2067}abc def\u{2069}", "fed cba", 10);
+        assert_renders_like(
+            "price: \u{2067}ab  cde f\u{2069} USD",
+            "price: f edc  ba USD",
+            25,
+        );
+
+        // Each line of a wrapped run is reversed separately.
+        assert_renders_like("\u{2067}ab cd ef gh\u{2069}", "dc ba\nhg fe", 5);
+    }
+
+    #[test]
+    fn nested_isolates_are_drawn_in_logical_order() {
+        assert_renders_like("\u{2067}ab\u{2066}cd\u{2069}ef\u{2069}g", "bacdefg", 8);
+        assert_renders_like("\u{2069}a\u{2067}\u{2067}b", "ab", 8);
+    }
+
+    #[test]
+    fn text_transform_changes_displayed_case() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 1));
+
+        for (text, transform, expected) in [
+            ("abc", TextTransform::Upper, "ABC"),
+            ("aBc ő", TextTransform::Upper, "ABC ő"),
+            ("ABC", TextTransform::Lower, "abc"),
+            ("aBc", TextTransform::None, "aBc"),
+        ] {
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(
+                text,
+                bounds,
+                character_style,
+                TextBoxStyleBuilder::new().text_transform(transform).build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            let mut expected_display = MockDisplay::new();
+            TextBox::new(expected, bounds, character_style)
+                .draw(&mut expected_display)
+                .unwrap();
+
+            display.assert_eq(&expected_display);
+        }
+    }
+
+    #[test]
+    fn control_characters_are_displayed_by_policy() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 1));
+
+        for (text, control_characters, expected) in [
+            ("a\u{1}b", ControlCharacters::Keep, "a\u{1}b"),
+            ("a\u{1}b", ControlCharacters::Ignore, "ab"),
+            ("a\u{1}b", ControlCharacters::Replace('?'), "a?b"),
+            ("a\u{1}b\u{1b}", ControlCharacters::Caret, "a^Ab^["),
+        ] {
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(
+                text,
+                bounds,
+                character_style,
+                TextBoxStyleBuilder::new()
+                    .control_characters(control_characters)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            let mut expected_display = MockDisplay::new();
+            TextBox::new(expected, bounds, character_style)
+                .draw(&mut expected_display)
+                .unwrap();
+
+            display.assert_eq(&expected_display);
+        }
+    }
+
+    #[test]
+    fn rtl_isolates_are_measured_as_displayed() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 8, 1));
+
+        for (text, control_characters, expected) in [
+            (
+                "\u{2067}a\u{1} b\u{2069} x",
+                ControlCharacters::Caret,
+                "b ^Aa x",
+            ),
+            (
+                "\u{2067}a\u{1}b c\u{2069} x",
+                ControlCharacters::Ignore,
+                "c ba x",
+            ),
+        ] {
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(
+                text,
+                bounds,
+                character_style,
+                TextBoxStyleBuilder::new()
+                    .control_characters(control_characters)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            let mut expected_display = MockDisplay::new();
+            TextBox::new(expected, bounds, character_style)
+                .draw(&mut expected_display)
+                .unwrap();
+
+            display.assert_eq(&expected_display);
+        }
+    }
+
+    #[test]
+    fn caret_notation_is_measured() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .control_characters(ControlCharacters::Caret)
+            .build();
+
+        // "^A^A^A" is 6 characters wide, so it doesn't fit into a 5 character wide line.
+        assert_eq!(
+            style.measure_text_height(&character_style, "\u{1}\u{1}\u{1}", 5 * 6),
+            18
+        );
+        assert_eq!(
+            style.measure_text_height(&character_style, "\u{1}\u{1}\u{1}", 6 * 6),
+            9
+        );
+    }
+
+    fn hyphenate(word: &str, index: usize) -> bool {
+        word == "hyphenation" && index == 5
+    }
+
+    #[track_caller]
+    fn assert_hyphenated(text: &str, max_chars: u32, expected: &str) {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let size = size_for(&FONT_6X9, max_chars, 3);
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            text,
+            Rectangle::new(Point::zero(), size),
+            character_style,
+            TextBoxStyleBuilder::new().hyphenation(hyphenate).build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        let mut expected_display = MockDisplay::new();
+        TextBox::new(
+            expected,
+            Rectangle::new(Point::zero(), size),
+            character_style,
+        )
+        .draw(&mut expected_display)
+        .unwrap();
+
+        display.assert_eq(&expected_display);
+    }
+
+    #[test]
+    fn hyphenation_breaks_long_words() {
+        assert_hyphenated("hyphenation", 6, "hyphe-\nnation");
+        assert_hyphenated("a hyphenation", 8, "a hyphe-\nnation");
+
+        // The hyphen must fit into the line as well.
+        assert_hyphenated("a hyphenation", 7, "a\nhyphe-\nnation");
+
+        // Words without allowed break points are wrapped as usual.
+        assert_hyphenated("a hyphenated", 8, "a\nhyphenat\ned");
+    }
+
+    #[test]
+    fn hyphenation_is_measured() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new().hyphenation(hyphenate).build();
+
+        let width = size_for(&FONT_6X9, 6, 1).width;
+        assert_eq!(
+            style.measure_text_height(&character_style, "hyphenation", width),
+            18
+        );
+        assert_eq!(
+            TextBoxStyle::default().measure_text_height(&character_style, "hyphenation", width),
+            18
+        );
+        assert_eq!(
+            style.measure_text_height(&character_style, "a hyphenation", 48),
+            18
+        );
+        assert_eq!(
+            TextBoxStyle::default().measure_text_height(&character_style, "a hyphenation", 48),
+            27
+        );
+    }
+
+    #[track_caller]
+    fn assert_flow_matches_single_box(text: &str) {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let column = size_for(&FONT_6X9, 5, 2);
+
+        let mut flowed = MockDisplay::new();
+        let remaining = TextBox::new(text, Rectangle::zero(), character_style)
+            .flow_into(
+                &[
+                    Rectangle::new(Point::zero(), column),
+                    Rectangle::new(Point::new(0, column.height as i32), column),
+                    Rectangle::new(Point::new(0, 2 * column.height as i32), column),
+                ],
+                &mut flowed,
+            )
+            .unwrap();
+
+        let mut single = MockDisplay::new();
+        let expected_remaining = TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), Size::new(column.width, 3 * column.height)),
+            character_style,
+        )
+        .draw(&mut single)
+        .unwrap();
+
+        flowed.assert_eq(&single);
+        assert_eq!(remaining.unwrap_or(""), expected_remaining);
+    }
+
+    #[test]
+    fn flowing_into_stacked_columns_matches_single_box() {
+        assert_flow_matches_single_box("Lorem ipsum dolor sit amet");
+        assert_flow_matches_single_box("Lorem ipsum dolor sit amet, consectetur adipiscing elit");
+        assert_flow_matches_single_box("a\nb\n\nc\nd e f g\nh");
+        assert_flow_matches_single_box("supercalifragilisticexpialidocious");
+    }
+
+    #[test]
+    #[cfg(feature = "ansi")]
+    fn flowing_carries_text_style_into_next_column() {
+        assert_flow_matches_single_box("\x1b[4mLorem ipsum dolor sit\x1b[24m amet");
+    }
+
+    #[test]
+    fn flow_returns_remaining_text() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let column = size_for(&FONT_6X9, 5, 1);
+        let text_box = TextBox::new(
+            "Lorem ipsum dolor sit amet",
+            Rectangle::zero(),
+            character_style,
+        );
+
+        let mut display = MockDisplay::new();
+        let remaining = text_box
+            .flow_into(
+                &[
+                    Rectangle::new(Point::zero(), column),
+                    Rectangle::new(Point::new(0, 9), column),
+                ],
+                &mut display,
+            )
+            .unwrap();
+        assert_eq!(remaining, Some("dolor sit amet"));
+
+        let mut display = MockDisplay::new();
+        let remaining = text_box
+            .flow_into(
+                &[
+                    Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 3)),
+                    Rectangle::new(Point::new(0, 27), size_for(&FONT_6X9, 5, 2)),
+                ],
+                &mut display,
+            )
+            .unwrap();
+        assert_eq!(remaining, None);
+    }
+
+    #[test]
+    fn remaining_text_includes_word_that_did_not_fit() {
+        let mut display = MockDisplay::new();
+        let remaining = TextBox::new(
+            "dolor sit amet",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        assert_eq!(remaining, "amet");
+    }
+
+    #[test]
+    fn trailing_spaces_at_wrap_point() {
+        let render = |trailing_spaces| {
+            let mut display = MockDisplay::new();
+
+            TextBox::with_textbox_style(
+                "abc   def",
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 2)),
+                MonoTextStyleBuilder::new()
+                    .font(&FONT_6X9)
+                    .text_color(BinaryColor::On)
+                    .background_color(BinaryColor::Off)
+                    .build(),
+                TextBoxStyleBuilder::new()
+                    .trailing_spaces(trailing_spaces)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            display
+        };
+
+        // The spaces fill the first line up to the edge of the box.
+        let rendered = render(true);
+        assert_eq!(
+            rendered.get_pixel(Point::new(18, 0)),
+            Some(BinaryColor::Off)
+        );
+        assert_eq!(
+            rendered.get_pixel(Point::new(35, 8)),
+            Some(BinaryColor::Off)
+        );
+
+        let hidden = render(false);
+        assert_eq!(hidden.get_pixel(Point::new(18, 0)), None);
+        assert_eq!(hidden.get_pixel(Point::new(35, 8)), None);
+
+        // "def" starts the second line in both cases.
+        assert_eq!(
+            rendered.affected_area(),
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 6, 2))
+        );
+        assert_eq!(
+            hidden.affected_area(),
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 2))
+        );
+    }
+
+    #[test]
+    fn show_invisibles_draws_markers() {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+
+        let character_style = MonoTextStyle::new(&FONT_6X9_LATIN1, Rgb888::WHITE);
+
+        TextBox::with_textbox_style(
+            "a b\nc",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .show_invisibles(Rgb888::RED)
+                .build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        display.assert_pattern(&[
+            "                       ",
+            "             W     RRRR",
+            "             W    RRR R",
+            "  WWW        WWW  RRR R",
+            " W  W   R    W  W  RR R",
+            " W  W        W  W   R R",
+            "  WWW        WWW    R R",
+            "                       ",
+            "                       ",
+            "                       ",
+            "                       ",
+            "                       ",
+            "  WWW                  ",
+            " W                     ",
+            " W                     ",
+            "  WWW                  ",
+        ]);
+    }
+
+    fn render_with_whitespace_style(
+        text: &str,
+        whitespace_style: Option<WhitespaceStyle>,
+    ) -> MockDisplay<Rgb888> {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+
+        let mut style = TextBoxStyleBuilder::new()
+            .tab_size(TabSize::Spaces(3))
+            .build();
+        style.whitespace_style = whitespace_style;
+
+        TextBox::with_textbox_style(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 8, 3)),
+            MonoTextStyle::new(&FONT_6X9_LATIN1, Rgb888::WHITE),
+            style,
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        display
+    }
+
+    #[test]
+    fn whitespace_markers_dont_move_text() {
+        let text = "a b\tc\nde  f\ng";
+
+        let plain = render_with_whitespace_style(text, None);
+        let marked = render_with_whitespace_style(text, Some(WhitespaceStyle::new(Rgb888::RED)));
+
+        let mut markers = 0;
+        for y in 0..27 {
+            for x in 0..48 {
+                let point = Point::new(x, y);
+                match marked.get_pixel(point) {
+                    Some(Rgb888::RED) => {
+                        assert_eq!(plain.get_pixel(point), None);
+                        markers += 1;
+                    }
+                    color => assert_eq!(color, plain.get_pixel(point), "{:?}", point),
+                }
+            }
+        }
+        assert!(markers > 0);
+    }
+
+    #[test]
+    fn whitespace_markers_are_centered() {
+        let display = render_with_whitespace_style(
+            "a\tb",
+            Some(WhitespaceStyle {
+                tab: Some('>'),
+                ..WhitespaceStyle::new(Rgb888::RED)
+            }),
+        );
+
+        let mut markers = MockDisplay::new();
+        for y in 0..27 {
+            for x in 0..48 {
+                let point = Point::new(x, y);
+                if display.get_pixel(point) == Some(Rgb888::RED) {
+                    markers.set_pixel(point, Some(Rgb888::RED));
+                }
+            }
+        }
+
+        // The tab is 12px wide, so the 6px wide marker is moved by 3px.
+        let mut expected = MockDisplay::new();
+        Text::with_baseline(
+            ">",
+            Point::new(6 + 3, 0),
+            MonoTextStyle::new(&FONT_6X9_LATIN1, Rgb888::RED),
+            Baseline::Top,
+        )
+        .draw(&mut expected)
+        .unwrap();
+
+        markers.assert_eq(&expected);
+    }
+
+    #[test]
+    fn newline_marker_is_only_drawn_if_it_fits() {
+        let style = Some(WhitespaceStyle {
+            space: Some('_'),
+            tab: None,
+            newline: Some('$'),
+            color: Rgb888::RED,
+        });
+
+        let fits = render_with_whitespace_style("abcdefg\n", style);
+        let overflows = render_with_whitespace_style("abcdefgh\n", style);
+
+        let has_marker = |display: &MockDisplay<Rgb888>, x: i32| {
+            (0..9).any(|y| display.get_pixel(Point::new(x + 2, y)) == Some(Rgb888::RED))
+        };
+        assert!(has_marker(&fits, 42));
+        assert!((0..48).all(|x| !has_marker(&overflows, x - 2)));
+    }
+
+    /// Counts how pixels reach the draw target.
+    #[derive(Default)]
+    struct FillCounter {
+        display: MockDisplay<BinaryColor>,
+        pixels: usize,
+        contiguous_fills: usize,
+        solid_fills: usize,
+    }
+
+    impl Dimensions for FillCounter {
+        fn bounding_box(&self) -> Rectangle {
+            self.display.bounding_box()
+        }
+    }
+
+    impl DrawTarget for FillCounter {
+        type Color = BinaryColor;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<BinaryColor>>,
+        {
+            for pixel in pixels {
+                self.pixels += 1;
+                self.display.draw_iter(core::iter::once(pixel))?;
+            }
+
+            Ok(())
+        }
+
+        fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = BinaryColor>,
+        {
+            self.contiguous_fills += 1;
+            self.display.fill_contiguous(area, colors)
+        }
+
+        fn fill_solid(&mut self, area: &Rectangle, color: BinaryColor) -> Result<(), Self::Error> {
+            self.solid_fills += 1;
+            self.display.fill_solid(area, color)
+        }
+    }
+
+    #[test]
+    fn background_is_filled_in_rectangles() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .underline()
+            .build();
+        let style = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Center)
+            .pad_lines(true)
+            .build();
+
+        let text_box = TextBox::with_textbox_style(
+            "ab  cd\nef",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 8, 2)),
+            character_style,
+            style,
+        );
+
+        let mut counter = FillCounter::default();
+        counter.display.set_allow_overdraw(true);
+        text_box.draw(&mut counter).unwrap();
+
+        // Glyphs are filled cell by cell, spaces, padding and underlines as solid rectangles. No
+        // pixel is drawn individually.
+        assert_eq!(counter.pixels, 0);
+        assert_eq!(counter.contiguous_fills, 6);
+        assert!(counter.solid_fills > 0);
+
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        text_box.draw(&mut expected).unwrap();
+        counter.display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn pad_lines_fills_full_width() {
+        assert_styled_rendered(
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Center)
+                .pad_lines(true)
+                .build(),
+            "a\nbb\n",
+            size_for(&FONT_6X9, 4, 3),
+            &[
+                "........................",
+                "........................",
+                "........................",
+                "...........###..........",
+                "..........#..#..........",
+                "..........#..#..........",
+                "...........###..........",
+                "........................",
+                "........................",
+                "........................",
+                ".......#.....#..........",
+                ".......#.....#..........",
+                ".......###...###........",
+                ".......#..#..#..#.......",
+                ".......#..#..#..#.......",
+                ".......###...###........",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+                "........................",
+            ],
+        );
+    }
+
+    #[test]
+    fn horizontal_scroll_pans_lines() {
+        assert_styled_rendered(
+            TextBoxStyleBuilder::new()
+                .no_wrap_horizontal_scroll(0)
+                .build(),
+            "Hello wrap\nok",
+            size_for(&FONT_6X9, 3, 2),
+            &[
+                "..................",
+                ".#..#........##...",
+                ".#..#.........#...",
+                ".####...##....#...",
+                ".#..#..#.##...#...",
+                ".#..#..##.....#...",
+                ".#..#...###..###..",
+                "..................",
+                "..................",
+                "............      ",
+                ".......#....      ",
+                ".......#....      ",
+                "..##...#.#..      ",
+                ".#..#..##...      ",
+                ".#..#..#.#..      ",
+                "..##...#..#.      ",
+                "............      ",
+                "............      ",
+            ],
+        );
+
+        assert_styled_rendered(
+            TextBoxStyleBuilder::new()
+                .no_wrap_horizontal_scroll(6)
+                .build(),
+            "Hello wrap\nok",
+            size_for(&FONT_6X9, 3, 2),
+            &[
+                "..................",
+                ".......##....##...",
+                "........#.....#...",
+                "..##....#.....#...",
+                ".#.##...#.....#...",
+                ".##.....#.....#...",
+                "..###..###...###..",
+                "..................",
+                "..................",
+                "......            ",
+                ".#....            ",
+                ".#....            ",
+                ".#.#..            ",
+                ".##...            ",
+                ".#.#..            ",
+                ".#..#.            ",
+                "......            ",
+                "......            ",
+            ],
+        );
+
+        assert_styled_rendered(
+            TextBoxStyleBuilder::new()
+                .no_wrap_horizontal_scroll(3)
+                .build(),
+            "Hello wrap\nok",
+            size_for(&FONT_6X9, 3, 2),
+            &[
+                "..................",
+                ".#........##....##",
+                ".#.........#.....#",
+                "##...##....#.....#",
+                ".#..#.##...#.....#",
+                ".#..##.....#.....#",
+                ".#...###..###...##",
+                "..................",
+                "..................",
+                ".........         ",
+                "....#....         ",
+                "....#....         ",
+                "#...#.#..         ",
+                ".#..##...         ",
+                ".#..#.#..         ",
+                "#...#..#.         ",
+                ".........         ",
+                ".........         ",
+            ],
+        );
+    }
+
+    #[test]
+    fn kerning_moves_glyphs() {
+        fn kerning(prev: char, next: char) -> i8 {
+            match (prev, next) {
+                ('A', 'V') => -2,
+                _ => 0,
+            }
+        }
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+
+        TextBox::with_textbox_style(
+            "AV",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 2, 1)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            TextBoxStyleBuilder::new().kerning(kerning).build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        display.assert_pattern(&[
+            "         ",
+            "  #  #  #",
+            " # # #  #",
+            "#   ##  #",
+            "#########",
+            "#   # ## ",
+            "#   # ## ",
+        ]);
+    }
+
+    #[test]
+    fn kerned_text_is_aligned_using_kerned_width() {
+        fn kerning(prev: char, next: char) -> i8 {
+            match (prev, next) {
+                ('A', 'V') => -2,
+                _ => 0,
+            }
+        }
+
+        let render = |bounds, alignment| {
+            let mut display = MockDisplay::new();
+            display.set_allow_overdraw(true);
+
+            TextBox::with_textbox_style(
+                "AV",
+                bounds,
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                TextBoxStyleBuilder::new()
+                    .alignment(alignment)
+                    .kerning(kerning)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            display
+        };
+
+        // "AV" is 10px wide with kerning, so right aligned text starts 8px into an 18px wide box.
+        let right_aligned = render(
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 1)),
+            HorizontalAlignment::Right,
+        );
+        let left_aligned = render(
+            Rectangle::new(Point::new(8, 0), Size::new(10, 9)),
+            HorizontalAlignment::Left,
+        );
+
+        right_aligned.assert_eq(&left_aligned);
+    }
+
+    #[test]
+    fn intensity_drops_half_of_the_pixels() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::On)
+            .build();
+
+        let count_drawn = |intensity| {
+            let mut display = MockDisplay::new();
+
+            TextBox::with_textbox_style(
+                "Hello",
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 1)),
+                character_style,
+                TextBoxStyleBuilder::new().intensity(intensity).build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            display
+                .bounding_box()
+                .points()
+                .filter(|p| display.get_pixel(*p).is_some())
+                .count()
+        };
+
+        assert_eq!(count_drawn(255), 30 * 9);
+        assert_eq!(count_drawn(128), 30 * 9 / 2);
+    }
+
+    #[test]
+    fn intensity_scales_rgb_colors() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(Rgb888::new(200, 100, 50))
+            .background_color(Rgb888::new(0, 0, 255))
+            .build();
+
+        let mut display = MockDisplay::new();
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 1));
+        TextBox::with_textbox_style(
+            "Hello",
+            bounds,
+            character_style,
+            TextBoxStyleBuilder::new().intensity(128).build(),
+        )
+        .draw_dimmed(&mut display)
+        .unwrap();
+
+        let mut text_pixels = 0;
+        for point in bounds.points() {
+            match display.get_pixel(point) {
+                Some(color) if color == Rgb888::new(100, 50, 25) => text_pixels += 1,
+                Some(color) => assert_eq!(color, Rgb888::new(0, 0, 128)),
+                None => panic!("pixel {point:?} is not drawn"),
+            }
+        }
+        assert!(text_pixels > 0);
+    }
+
+    #[test]
+    fn intensity_dithers_rgb_colors_when_drawing() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(Rgb888::new(200, 100, 50))
+            .background_color(Rgb888::new(0, 0, 255))
+            .build();
+
+        let mut display = MockDisplay::new();
+        let bounds = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 1));
+        TextBox::with_textbox_style(
+            "Hello",
+            bounds,
+            character_style,
+            TextBoxStyleBuilder::new().intensity(128).build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        let mut drawn = 0;
+        for point in bounds.points() {
+            if let Some(color) = display.get_pixel(point) {
+                assert!(color == Rgb888::new(200, 100, 50) || color == Rgb888::new(0, 0, 255));
+                drawn += 1;
+            }
+        }
+        assert_eq!(drawn, 30 * 9 / 2);
+    }
+
+    fn render_with_tab_leader(leader: TabLeader, text: &str, width: u32) -> MockDisplay<Rgb888> {
+        let mut display = MockDisplay::new();
+
+        TextBox::with_textbox_style(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, width, 1)),
+            MonoTextStyle::new(&FONT_6X9, Rgb888::WHITE),
+            TextBoxStyleBuilder::new().tab_leader(leader).build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        display
+    }
+
+    #[test]
+    fn tab_leader_fills_tab() {
+        let display = render_with_tab_leader(TabLeader::new('.', Rgb888::RED), "a\tb", 5);
+
+        display.assert_pattern(&[
+            "                             ",
+            "                         W   ",
+            "                         W   ",
+            "  WWW                    WWW ",
+            " W  W                    W  W",
+            " W  W   RR    RR    RR   W  W",
+            "  WWW   RR    RR    RR   WWW ",
+        ]);
+    }
+
+    #[test]
+    fn right_aligned_tab_leader_pushes_text_to_the_right() {
+        let display =
+            render_with_tab_leader(TabLeader::new('.', Rgb888::RED).right_aligned(), "a\tb", 7);
+
+        display.assert_pattern(&[
+            "                                         ",
+            "                                     W   ",
+            "                                     W   ",
+            "  WWW                                WWW ",
+            " W  W                                W  W",
+            " W  W   RR    RR    RR    RR    RR   W  W",
+            "  WWW   RR    RR    RR    RR    RR   WWW ",
+        ]);
+    }
+
+    #[test]
+    fn huge_text_boxes_do_not_wrap() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let text = "Some text\tthat would wrap";
+
+        let mut expected = MockDisplay::new();
+        TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), Size::new(1000, 9)),
+            character_style,
+        )
+        .draw(&mut expected)
+        .unwrap();
+
+        for width in [
+            u32::MAX,
+            i32::MAX as u32,
+            (1 << 24) + 1,
+            1 << 24,
+            (1 << 24) - 1,
+        ] {
+            let style = TextBoxStyle::default();
+            assert_eq!(
+                style.measure_text_height(&character_style, text, width),
+                9,
+                "width {width}"
+            );
+
+            for size in [Size::new(width, 9), Size::new(width, width)] {
+                let text_box =
+                    TextBox::new(text, Rectangle::new(Point::zero(), size), character_style);
+
+                let mut display = MockDisplay::new();
+                text_box.draw(&mut display).unwrap();
+                display.assert_eq(&expected);
+
+                let mut display = MockDisplay::new();
+                text_box.draw_scrolled(&mut display, 0).unwrap();
+                display.assert_eq(&expected);
+            }
+        }
+    }
+
+    #[test]
+    fn grid_cells_are_aligned_in_columns() {
+        const COLUMNS: [GridColumn; 2] = [
+            GridColumn::new(30, HorizontalAlignment::Left),
+            GridColumn::new(24, HorizontalAlignment::Right),
+        ];
+
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "a\t1\nbb\t234\n\t5",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 3)),
+            character_style,
+            TextBoxStyleBuilder::new().columns(&COLUMNS).build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        // The second column ends at 54 pixels.
+        let mut expected = MockDisplay::new();
+        for (text, position) in [
+            ("a", Point::new(0, 0)),
+            ("1", Point::new(48, 0)),
+            ("bb", Point::new(0, 9)),
+            ("234", Point::new(36, 9)),
+            ("5", Point::new(48, 18)),
+        ] {
+            Text::with_baseline(text, position, character_style, Baseline::Top)
+                .draw(&mut expected)
+                .unwrap();
+        }
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn justify_nbsp_stretches_nbsp_gaps() {
+        // Returns the x coordinate of the first drawn column starting at `from`.
+        fn first_drawn_column(justify_nbsp: bool, from: i32) -> i32 {
+            let mut display = MockDisplay::new();
+
+            TextBox::with_textbox_style(
+                "mot\u{a0}: suite fin",
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 13, 2)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                TextBoxStyleBuilder::new()
+                    .alignment(HorizontalAlignment::Justified)
+                    .justify_nbsp(justify_nbsp)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            (from..78)
+                .find(|&x| (0..9).any(|y| display.get_pixel(Point::new(x, y)).is_some()))
+                .unwrap()
+        }
+
+        // "mot" takes up the first 18px, the 12px leftover space is distributed between the gaps.
+        let colon_fixed = first_drawn_column(false, 18);
+        let colon_justified = first_drawn_column(true, 18);
+        assert_eq!(colon_justified - colon_fixed, 6);
+
+        // The line is justified in both cases, "suite" starts at the same position.
+        assert_eq!(
+            first_drawn_column(false, colon_fixed + 6),
+            first_drawn_column(true, colon_justified + 6)
+        );
+    }
+
+    #[test]
+    fn spaces_only_line_break_overflows_long_words() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .line_break(LineBreak::SpacesOnly)
+            .hyphenation(|_, _| true)
+            .max_word_length(3)
+            .build();
+        let width = size_for(&FONT_6X9, 5, 1).width;
+
+        // The soft hyphen is not a break opportunity, the word overflows its line. The zero-width
+        // space still separates words.
+        let text = "ab a\u{AD}verylongword cd\u{200B}efgh";
+        let snapshot = LayoutSnapshot::<4>::capture(&style, &character_style, text, width);
+        let lines = snapshot
+            .lines()
+            .map(|line| &text[line.offset..])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "ab a\u{AD}verylongword cd\u{200B}efgh",
+                "a\u{AD}verylongword cd\u{200B}efgh",
+                "cd\u{200B}efgh",
+                "efgh"
+            ]
+        );
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "a\u{AD}verylongword",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+            character_style,
+            style,
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        // Only the first line is drawn, cut off at the edge of the text box. The soft hyphen is
+        // not displayed.
+        let mut expected = MockDisplay::new();
+        Text::with_baseline(
+            "averylongword",
+            Point::zero(),
+            character_style,
+            Baseline::Top,
+        )
+        .draw(&mut expected.clipped(&Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 1))))
+        .unwrap();
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn custom_line_heights_move_lines_down() {
+        fn line_heights(line: u32) -> u32 {
+            if line == 0 {
+                18
+            } else {
+                9
+            }
+        }
+
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .line_heights(line_heights)
+            .height_mode(HeightMode::FitToText)
+            .build();
+        let text = "aaa bbb ccc";
+        let width = size_for(&FONT_6X9, 3, 1).width;
+
+        assert_eq!(style.measure_text_height(&character_style, text, width), 36);
+
+        let snapshot = LayoutSnapshot::<4>::capture(&style, &character_style, text, width);
+        let lines = snapshot.lines().map(|line| line.y).collect::<Vec<_>>();
+        assert_eq!(lines, [0, 18, 27]);
+
+        let text_box = TextBox::with_textbox_style(
+            text,
+            Rectangle::new(Point::zero(), Size::new(width, 0)),
+            character_style,
+            style,
+        );
+        assert_eq!(text_box.bounds.size.height, 36);
+
+        let mut display = MockDisplay::new();
+        text_box.draw(&mut display).unwrap();
+
+        let mut expected = MockDisplay::new();
+        for (text, y) in [("aaa", 0), ("bbb", 18), ("ccc", 27)] {
+            Text::with_baseline(text, Point::new(0, y), character_style, Baseline::Top)
+                .draw(&mut expected)
+                .unwrap();
+        }
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn trailing_punctuation_hangs_past_justified_edge() {
+        fn render(hanging_punctuation: bool) -> MockDisplay<BinaryColor> {
+            let mut display = MockDisplay::new();
+
+            // The text area is 28px wide, the right padding makes room for the period.
+            TextBox::with_textbox_style(
+                "ab c. defg",
+                Rectangle::new(Point::zero(), Size::new(34, 18)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                TextBoxStyleBuilder::new()
+                    .alignment(HorizontalAlignment::Justified)
+                    .padding(Padding::new(0, 0, 6, 0))
+                    .hanging_punctuation(hanging_punctuation)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            display
+        }
+
+        // "ab c" is justified to the edge of the text area, the period is drawn past it.
+        let mut expected = MockDisplay::new();
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        for (text, position) in [
+            ("ab", Point::zero()),
+            ("c.", Point::new(22, 0)),
+            ("defg", Point::new(0, 9)),
+        ] {
+            Text::with_baseline(text, position, character_style, Baseline::Top)
+                .draw(&mut expected)
+                .unwrap();
+        }
+        render(true).assert_eq(&expected);
+
+        // Without the setting, "c." doesn't fit into the first line.
+        let display = render(false);
+        assert!((28..34).all(|x| (0..9).all(|y| display.get_pixel(Point::new(x, y)).is_none())));
+        assert_ne!(display, expected);
+    }
+
+    #[test]
+    fn punctuation_wider_than_the_padding_does_not_hang() {
+        fn render(hanging_punctuation: bool) -> MockDisplay<BinaryColor> {
+            let mut display = MockDisplay::new();
+
+            // The text area is 28px wide, the right padding is narrower than the period.
+            TextBox::with_textbox_style(
+                "ab c. defg",
+                Rectangle::new(Point::zero(), Size::new(31, 27)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                TextBoxStyleBuilder::new()
+                    .alignment(HorizontalAlignment::Justified)
+                    .padding(Padding::new(0, 0, 3, 0))
+                    .hanging_punctuation(hanging_punctuation)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            display
+        }
+
+        // "c." is wrapped into the second line, as if the setting was disabled.
+        let mut expected = MockDisplay::new();
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        for (text, position) in [
+            ("ab", Point::zero()),
+            ("c.", Point::new(0, 9)),
+            ("defg", Point::new(0, 18)),
+        ] {
+            Text::with_baseline(text, position, character_style, Baseline::Top)
+                .draw(&mut expected)
+                .unwrap();
+        }
+        render(true).assert_eq(&expected);
+        render(false).assert_eq(&expected);
+    }
+
+    #[test]
+    fn tabbed_lines_can_be_excluded_from_justification() {
+        fn render(style: TextBoxStyle) -> MockDisplay<BinaryColor> {
+            let mut display = MockDisplay::new();
+
+            // Only the first line, "ab c\tde", is drawn.
+            TextBox::with_textbox_style(
+                "ab c\tde fghij klm",
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 13, 1)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                style,
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            display
+        }
+
+        let left = render(TextBoxStyle::with_alignment(HorizontalAlignment::Left));
+        let justified = render(TextBoxStyle::with_alignment(HorizontalAlignment::Justified));
+        let tabs_kept = render(
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Justified)
+                .justify_tabbed_lines(false)
+                .build(),
+        );
+
+        // "de" starts at the tab stop instead of being moved by the stretched space.
+        assert_ne!(justified, left);
+        tabs_kept.assert_eq(&left);
+    }
+
+    #[test]
+    fn zero_width_characters_are_not_drawn() {
+        let style = TextBoxStyle::default();
+        let size = size_for(&FONT_6X9, 3, 1);
+
+        let mut expected = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "AB",
+            Rectangle::new(Point::zero(), size),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            style,
+        )
+        .draw(&mut expected)
+        .unwrap();
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "A\u{fe0f}B",
+            Rectangle::new(Point::zero(), size),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            style,
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn byte_order_mark_is_invisible() {
+        fn render(text: &str) -> MockDisplay<BinaryColor> {
+            let mut display = MockDisplay::new();
+            TextBox::new(
+                text,
+                Rectangle::new(Point::zero(), size_for(&FONT_6X9, 5, 2)),
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            )
+            .draw(&mut display)
+            .unwrap();
+            display
+        }
+
+        let expected = render("Lorem ipsum");
+
+        render("\u{feff}Lorem ipsum").assert_eq(&expected);
+        render("Lor\u{feff}em ipsum").assert_eq(&expected);
+        render("Lorem ipsum\u{feff}").assert_eq(&expected);
+    }
+
+    #[test]
+    fn consecutive_newlines_are_collapsed() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "a\n\n\n\nb",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 1, 5)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .max_consecutive_newlines(2)
+                .build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        // "a", one blank line, "b".
+        let mut expected = MockDisplay::new();
+        Text::with_baseline("a", Point::zero(), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Text::with_baseline("b", Point::new(0, 18), character_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn lines_are_wrapped_at_extra_space_chars() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "12\u{b7}34\u{b7}56",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 4, 3)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .parser_config(ParserConfig::new(&['\u{b7}'], &[]))
+                .build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        let mut expected = MockDisplay::new();
+        for (idx, line) in ["12", "34", "56"].iter().enumerate() {
+            Text::with_baseline(
+                line,
+                Point::new(0, 9 * idx as i32),
+                character_style,
+                Baseline::Top,
+            )
+            .draw(&mut expected)
+            .unwrap();
+        }
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn extra_space_chars_are_not_stretched() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let mut display = MockDisplay::new();
+        TextBox::with_textbox_style(
+            "ab c\u{b7}d efgh",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 7, 2)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Justified)
+                .parser_config(ParserConfig::new(&['\u{b7}'], &[]))
+                .build(),
+        )
+        .draw(&mut display)
+        .unwrap();
+
+        // Only the space is stretched, the middle dot keeps the width of its glyph.
+        let mut expected = MockDisplay::new();
+        for (text, position) in [
+            ("ab", Point::zero()),
+            ("c", Point::new(24, 0)),
+            ("d", Point::new(36, 0)),
+            ("efgh", Point::new(0, 9)),
+        ] {
+            Text::with_baseline(text, position, character_style, Baseline::Top)
+                .draw(&mut expected)
+                .unwrap();
+        }
+
+        display.assert_eq(&expected);
+    }
+
+    /// Copies `area.size` pixels from the origin of `source` to `area.top_left` of `target`.
+    fn blit(
+        source: &MockDisplay<BinaryColor>,
+        area: Rectangle,
+        target: &mut MockDisplay<BinaryColor>,
+    ) {
+        for point in Rectangle::new(Point::zero(), area.size).points() {
+            if let Some(color) = source.get_pixel(point) {
+                Pixel(point + area.top_left, color).draw(target).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn offscreen_drawing_matches_direct_drawing() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+        let text_box = TextBox::with_textbox_style(
+            "Hello offscreen world",
+            Rectangle::new(Point::new(3, 5), size_for(&FONT_6X9, 7, 4)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Center)
+                .vertical_alignment(VerticalAlignment::Middle)
+                .build(),
+        );
+
+        let mut direct = MockDisplay::new();
+        text_box.draw(&mut direct).unwrap();
+
+        let mut canvas = MockDisplay::new();
+        let dirty = text_box.draw_offscreen(&mut canvas).unwrap();
+        assert_eq!(dirty, text_box.bounds);
+        assert!(Rectangle::new(Point::zero(), dirty.size).contains(canvas.affected_area().top_left));
+
+        let mut blitted = MockDisplay::new();
+        blit(&canvas, dirty, &mut blitted);
+
+        blitted.assert_eq(&direct);
+    }
+
+    #[test]
+    fn tiles_combine_into_single_render() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+        let text_box = TextBox::with_textbox_style(
+            "Tiled displays show one text across tiles",
+            Rectangle::new(Point::new(2, 3), Size::new(58, 40)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Justified)
+                .height_mode(HeightMode::Exact(VerticalOverdraw::Visible))
+                .build(),
+        );
+
+        let mut direct = MockDisplay::new();
+        text_box.draw(&mut direct).unwrap();
+
+        // The tile boundary splits characters in half.
+        let tiles = [
+            Rectangle::new(Point::zero(), Size::new(29, 64)),
+            Rectangle::new(Point::new(29, 0), Size::new(35, 64)),
+        ];
+
+        let mut combined = MockDisplay::new();
+        for tile_area in tiles {
+            let mut tile = MockDisplay::new();
+            text_box.draw_tile(&mut tile, tile_area).unwrap();
+
+            blit(&tile, tile_area, &mut combined);
+
+            // Nothing is drawn outside of the tile.
+            let tile_bounds = Rectangle::new(Point::zero(), tile_area.size);
+            tile.set_pixels(tile_bounds.points(), None);
+            assert_eq!(tile.affected_area(), Rectangle::zero());
+        }
+
+        combined.assert_eq(&direct);
+        assert!(combined.affected_area().size.width > 29);
+    }
 }
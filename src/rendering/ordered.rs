@@ -0,0 +1,267 @@
+//! Ordered pixel output.
+//!
+//! Character styles draw the background, the glyphs and the decorations of a string in separate
+//! passes, so the same pixel can be drawn multiple times, and the rows of a line are not drawn in
+//! order. To provide ordered output, a line is rendered into a [`RowBuffer`] that keeps the last
+//! color of every pixel, and the buffer is flushed row by row. If the buffer can't hold the whole
+//! line, the line is rendered once for every band of rows that fits into the buffer.
+//!
+//! Chunked rendering doesn't have a buffer, so it uses a [`RowSegment`], which keeps the pixels of
+//! a short segment of a single row.
+use core::convert::Infallible;
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point, Size},
+    pixelcolor::PixelColor,
+    primitives::Rectangle,
+    Drawable, Pixel,
+};
+
+/// Number of pixels kept in a single pass.
+pub(crate) const SEGMENT_WIDTH: usize = 32;
+
+/// The pixels of a short horizontal segment of a row of rendered text.
+///
+/// Each pixel of the segment is either drawn once, with its final color, or not drawn at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelChunk<C> {
+    origin: Point,
+    pixels: [Option<C>; SEGMENT_WIDTH],
+}
+
+impl<C> PixelChunk<C>
+where
+    C: PixelColor,
+{
+    /// Returns the position of the leftmost pixel of the segment.
+    #[inline]
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// Returns the drawn pixels of the segment, from left to right.
+    #[inline]
+    pub fn pixels(&self) -> impl Iterator<Item = Pixel<C>> + '_ {
+        let origin = self.origin;
+
+        self.pixels
+            .iter()
+            .enumerate()
+            .filter_map(move |(x, color)| {
+                color.map(|color| Pixel(origin + Point::new(x as i32, 0), color))
+            })
+    }
+
+    /// Returns `true` if the segment has no drawn pixels.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pixels.iter().all(Option::is_none)
+    }
+}
+
+impl<C> Drawable for PixelChunk<C>
+where
+    C: PixelColor,
+{
+    type Color = C;
+    type Output = ();
+
+    #[inline]
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        target.draw_iter(self.pixels())
+    }
+}
+
+/// Draw target that collects the pixels of a row segment.
+///
+/// Pixels outside of the segment are dropped. Pixels inside the segment keep the last color drawn
+/// to them.
+pub(crate) struct RowSegment<C> {
+    chunk: PixelChunk<C>,
+}
+
+impl<C> RowSegment<C>
+where
+    C: PixelColor,
+{
+    /// Creates a new segment that starts at `origin`.
+    pub fn new(origin: Point) -> Self {
+        Self {
+            chunk: PixelChunk {
+                origin,
+                pixels: [None; SEGMENT_WIDTH],
+            },
+        }
+    }
+
+    /// Returns the collected pixels.
+    pub fn into_chunk(self) -> PixelChunk<C> {
+        self.chunk
+    }
+}
+
+impl<C> Dimensions for RowSegment<C> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.chunk.origin, Size::new(SEGMENT_WIDTH as u32, 1))
+    }
+}
+
+impl<C> DrawTarget for RowSegment<C>
+where
+    C: PixelColor,
+{
+    type Color = C;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let origin = self.chunk.origin;
+        for Pixel(point, color) in pixels {
+            if point.y != origin.y {
+                continue;
+            }
+
+            let x = point.x - origin.x;
+            if (0..SEGMENT_WIDTH as i32).contains(&x) {
+                self.chunk.pixels[x as usize] = Some(color);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Draw target that collects the pixels of a band of rows in a caller-provided buffer.
+///
+/// Pixels outside of the band are dropped. Pixels inside the band keep the last color drawn to
+/// them.
+pub(crate) struct RowBuffer<'b, C> {
+    area: Rectangle,
+    pixels: &'b mut [Option<C>],
+}
+
+impl<'b, C> RowBuffer<'b, C>
+where
+    C: PixelColor,
+{
+    /// Creates an empty buffer for the pixels of `area`.
+    ///
+    /// `pixels` must hold at least as many pixels as `area`.
+    pub fn new(area: Rectangle, pixels: &'b mut [Option<C>]) -> Self {
+        let len = (area.size.width * area.size.height) as usize;
+        let pixels = &mut pixels[..len];
+        pixels.fill(None);
+
+        Self { area, pixels }
+    }
+
+    /// Draws the collected pixels row by row, from left to right.
+    pub fn flush<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let origin = self.area.top_left;
+        let width = self.area.size.width as usize;
+
+        target.draw_iter(self.pixels.iter().enumerate().filter_map(|(i, color)| {
+            color.map(|color| {
+                let offset = Point::new((i % width) as i32, (i / width) as i32);
+                Pixel(origin + offset, color)
+            })
+        }))
+    }
+}
+
+impl<C> Dimensions for RowBuffer<'_, C> {
+    fn bounding_box(&self) -> Rectangle {
+        self.area
+    }
+}
+
+impl<C> DrawTarget for RowBuffer<'_, C>
+where
+    C: PixelColor,
+{
+    type Color = C;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let origin = self.area.top_left;
+        let width = self.area.size.width as usize;
+        for Pixel(point, color) in pixels {
+            if self.area.contains(point) {
+                let offset = point - origin;
+                self.pixels[offset.y as usize * width + offset.x as usize] = Some(color);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits `area` into bands of pixels that fit into a buffer of `capacity` pixels, in row order.
+///
+/// Bands are made of whole rows, unless a single row doesn't fit into the buffer. In that case,
+/// every row is split into segments of `capacity` pixels.
+pub(crate) fn bands(area: Rectangle, capacity: usize) -> impl Iterator<Item = Rectangle> {
+    let capacity = capacity.max(1) as u32;
+    let Size { width, height } = area.size;
+    let (band_width, band_height) = if capacity >= width {
+        (width, (capacity / width.max(1)).min(height))
+    } else {
+        (capacity, 1)
+    };
+
+    let rows = (0..height).step_by(band_height.max(1) as usize);
+    rows.flat_map(move |y| {
+        (0..width)
+            .step_by(band_width.max(1) as usize)
+            .map(move |x| {
+                Rectangle::new(
+                    area.top_left + Point::new(x as i32, y as i32),
+                    Size::new(band_width.min(width - x), band_height.min(height - y)),
+                )
+            })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::bands;
+    use embedded_graphics::{
+        geometry::{Point, Size},
+        primitives::Rectangle,
+    };
+
+    #[test]
+    fn bands_cover_the_area_in_row_order() {
+        let area = Rectangle::new(Point::new(1, 2), Size::new(10, 5));
+
+        assert_eq!(bands(area, 50).collect::<Vec<_>>(), [area]);
+        assert_eq!(
+            bands(area, 25).collect::<Vec<_>>(),
+            [
+                Rectangle::new(Point::new(1, 2), Size::new(10, 2)),
+                Rectangle::new(Point::new(1, 4), Size::new(10, 2)),
+                Rectangle::new(Point::new(1, 6), Size::new(10, 1)),
+            ]
+        );
+        assert_eq!(
+            bands(area, 6).take(3).collect::<Vec<_>>(),
+            [
+                Rectangle::new(Point::new(1, 2), Size::new(6, 1)),
+                Rectangle::new(Point::new(7, 2), Size::new(4, 1)),
+                Rectangle::new(Point::new(1, 3), Size::new(6, 1)),
+            ]
+        );
+        assert_eq!(bands(Rectangle::zero(), 6).count(), 0);
+    }
+}
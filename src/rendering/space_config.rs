@@ -1,38 +1,120 @@
 //! Space rendering config
 
+/// Extra pixels that are distributed between the spaces of a justified line.
+#[derive(Copy, Clone, Debug)]
+struct ExtraSpace {
+    /// The number of extra pixels.
+    pixels: u32,
+
+    /// The number of spaces the pixels are distributed between.
+    spaces: u32,
+
+    /// The number of spaces rendered so far. This field changes during rendering.
+    consumed: u32,
+}
+
+impl ExtraSpace {
+    /// Returns the number of extra pixels given to the first `n` spaces.
+    ///
+    /// The pixels are distributed by error diffusion: every space adds `pixels / spaces` to an
+    /// accumulator, and a space is widened when the accumulator passes an integer. The
+    /// accumulator starts at one half, so the wider spaces are spread evenly over the line, and
+    /// not clustered at either end.
+    const fn cumulative(&self, n: u32) -> u32 {
+        let n = if n < self.spaces { n } else { self.spaces };
+
+        ((n as u64 * self.pixels as u64 + self.spaces as u64 / 2) / self.spaces as u64) as u32
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct SpaceConfig {
     /// The width of the whitespace characters.
     width: u32,
 
-    /// Stores how many characters are rendered using the `width` width. This field changes
-    /// during rendering.
-    count: Option<u32>,
+    /// Extra pixels of a justified line.
+    extra: Option<ExtraSpace>,
 }
 
 /// Retrieves size of space characters.
 impl SpaceConfig {
     /// Creates a new SpaceConfig object.
-    pub const fn new(width: u32, count: Option<u32>) -> Self {
-        Self { width, count }
+    pub const fn new(width: u32) -> Self {
+        Self { width, extra: None }
+    }
+
+    /// Creates a new SpaceConfig object that distributes `extra_pixels` between `spaces` spaces.
+    pub const fn justified(width: u32, spaces: u32, extra_pixels: u32) -> Self {
+        Self {
+            width,
+            extra: if spaces == 0 {
+                None
+            } else {
+                Some(ExtraSpace {
+                    pixels: extra_pixels,
+                    spaces,
+                    consumed: 0,
+                })
+            },
+        }
+    }
+
+    /// Returns whether spaces can have different widths.
+    pub const fn is_justified(&self) -> bool {
+        self.extra.is_some()
     }
 
     /// Look at the size of next n spaces, without advancing.
     pub fn peek_next_width(&self, n: u32) -> u32 {
-        match self.count {
-            None => n * self.width,
-            Some(count) => n * self.width + count.min(n),
-        }
+        let extra = match self.extra {
+            None => 0,
+            Some(extra) => {
+                extra.cumulative(extra.consumed.saturating_add(n))
+                    - extra.cumulative(extra.consumed)
+            }
+        };
+
+        n.saturating_mul(self.width).saturating_add(extra)
     }
 
     /// Advance the internal state
     pub fn consume(&mut self, n: u32) -> u32 {
         let w = self.peek_next_width(n);
 
-        if let Some(count) = self.count.as_mut() {
-            *count = count.saturating_sub(n);
+        if let Some(extra) = self.extra.as_mut() {
+            extra.consumed = extra.consumed.saturating_add(n);
         }
 
         w
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::SpaceConfig;
+
+    fn widths(mut config: SpaceConfig, n: u32) -> Vec<u32> {
+        (0..n).map(|_| config.consume(1)).collect()
+    }
+
+    #[test]
+    fn extra_pixels_are_spread_evenly() {
+        assert_eq!(widths(SpaceConfig::justified(6, 5, 2), 5), [6, 7, 6, 7, 6]);
+        assert_eq!(widths(SpaceConfig::justified(6, 5, 3), 5), [7, 6, 7, 6, 7]);
+        assert_eq!(widths(SpaceConfig::justified(6, 4, 1), 4), [6, 7, 6, 6]);
+        assert_eq!(widths(SpaceConfig::justified(6, 3, 0), 3), [6, 6, 6]);
+
+        // Spaces after the distributed ones have the base width.
+        assert_eq!(widths(SpaceConfig::justified(6, 2, 1), 4), [7, 6, 6, 6]);
+        assert_eq!(widths(SpaceConfig::new(6), 2), [6, 6]);
+    }
+
+    #[test]
+    fn multiple_spaces_get_the_sum_of_their_widths() {
+        let mut config = SpaceConfig::justified(6, 5, 2);
+
+        assert_eq!(config.peek_next_width(2), 13);
+        assert_eq!(config.consume(3), 19);
+        assert_eq!(config.consume(2), 13);
+    }
+}
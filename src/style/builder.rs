@@ -1,9 +1,15 @@
 //! Text box style builder.
-use embedded_graphics::text::LineHeight;
+use core::{fmt, num::NonZeroU32};
+
+use embedded_graphics::{pixelcolor::Rgb888, text::LineHeight};
 
 use crate::{
     alignment::{HorizontalAlignment, VerticalAlignment},
-    style::{HeightMode, TabSize, TextBoxStyle, VerticalOverdraw},
+    style::{
+        ControlCharacters, GridColumn, HeightMode, Hyphenator, LineBreak, Padding, ParserConfig,
+        Scale, TabAnchor, TabLeader, TabSize, TextBoxStyle, TextBoxStyleModifier, TextTransform,
+        VerticalOverdraw, WhitespaceStyle,
+    },
 };
 
 /// [`TextBoxStyle`] builder object.
@@ -31,17 +37,61 @@ impl TextBoxStyleBuilder {
                 vertical_alignment: VerticalAlignment::Top,
                 height_mode: HeightMode::Exact(VerticalOverdraw::FullRowsOnly),
                 line_height: LineHeight::Percent(100),
+                line_heights: None,
                 paragraph_spacing: 0,
+                include_trailing_leading: false,
                 tab_size: TabSize::Spaces(4),
                 // we will update these at build time
                 leading_spaces: false,
                 trailing_spaces: false,
+                whitespace_style: None,
+                pad_lines: false,
+                kerning: None,
+                hyphenation: None,
+                horizontal_scroll: None,
+                intensity: u8::MAX,
+                justify_nbsp: false,
+                justify_tabbed_lines: true,
+                hanging_punctuation: false,
+                tab_leader: None,
+                reserve_caret_width: 0,
+                max_word_length: None,
+                max_consecutive_newlines: None,
+                parser_config: ParserConfig::new(&[], &[]),
+                line_break: LineBreak::Greedy,
+                weak_break_chars: None,
+                text_transform: TextTransform::None,
+                control_characters: ControlCharacters::Keep,
+                list_marker: None,
+                padding: Padding::new(0, 0, 0, 0),
+                avoid_broken_last_word: false,
+                max_lines: None,
+                reveal_chars: None,
+                columns: None,
+                column_scale: Scale::IDENTITY,
+                tab_anchor: TabAnchor::Content,
             },
             leading_spaces: None,
             trailing_spaces: None,
         }
     }
 
+    /// Applies the options set in `modifier`, and keeps the others.
+    ///
+    /// See [`TextBoxStyleModifier`] for an example.
+    #[inline]
+    pub fn merge(mut self, modifier: &TextBoxStyleModifier) -> Self {
+        self.style = self.style.modified(modifier);
+        if modifier.leading_spaces.is_some() {
+            self.leading_spaces = modifier.leading_spaces;
+        }
+        if modifier.trailing_spaces.is_some() {
+            self.trailing_spaces = modifier.trailing_spaces;
+        }
+
+        self
+    }
+
     /// Sets the line height.
     ///
     /// The line height is defined as the vertical distance between the baseline of two adjacent lines
@@ -64,6 +114,35 @@ impl TextBoxStyleBuilder {
         self
     }
 
+    /// Sets a function that returns the line height of each line.
+    ///
+    /// The function receives the index of a line, counting wrapped lines, and returns the
+    /// distance between the top of that line and the top of the next one, in pixels. This setting
+    /// overrides [`line_height`](Self::line_height).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// // Make room for a drop cap in the first line.
+    /// fn line_heights(line: u32) -> u32 {
+    ///     if line == 0 {
+    ///         18
+    ///     } else {
+    ///         9
+    ///     }
+    /// }
+    ///
+    /// let style = TextBoxStyleBuilder::new().line_heights(line_heights).build();
+    /// ```
+    #[inline]
+    pub const fn line_heights(mut self, line_heights: fn(line: u32) -> u32) -> Self {
+        self.style.line_heights = Some(line_heights);
+
+        self
+    }
+
     /// Sets the paragraph spacing.
     ///
     /// # Example
@@ -83,6 +162,31 @@ impl TextBoxStyleBuilder {
         self
     }
 
+    /// Sets whether the leading below the last line is part of the text height.
+    ///
+    /// By default, the text ends at the bottom of the glyphs of the last line, and the extra space
+    /// of a line height larger than the font is only added between lines. Enabling this option
+    /// adds it below the last line, too, which affects [`HeightMode::FitToText`] and the vertical
+    /// alignment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// # use embedded_graphics::text::LineHeight;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .line_height(LineHeight::Percent(150))
+    ///     .include_trailing_leading(true)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn include_trailing_leading(mut self, include: bool) -> Self {
+        self.style.include_trailing_leading = include;
+
+        self
+    }
+
     /// Sets the horizontal text alignment.
     #[inline]
     pub const fn alignment(mut self, alignment: HorizontalAlignment) -> TextBoxStyleBuilder {
@@ -127,6 +231,13 @@ impl TextBoxStyleBuilder {
     }
 
     /// Render trailing spaces.
+    ///
+    /// If enabled, spaces at the end of a wrapped line are drawn up to the edge of the text box,
+    /// and the spaces that don't fit are carried to the next line. This keeps every space visible,
+    /// which is useful for text editors. Whether the carried spaces are drawn at the start of the
+    /// next line depends on the [`leading_spaces`] setting.
+    ///
+    /// [`leading_spaces`]: Self::leading_spaces
     #[inline]
     pub const fn trailing_spaces(mut self, render: bool) -> Self {
         self.trailing_spaces = Some(render);
@@ -134,6 +245,617 @@ impl TextBoxStyleBuilder {
         self
     }
 
+    /// Sets the kerning function.
+    ///
+    /// The function receives two adjacent characters of a word and returns the adjustment, in
+    /// pixels, of the distance between them. Negative values move the characters closer. Kerning
+    /// is not applied next to whitespace, and is taken into account by word wrapping and alignment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// fn kerning(prev: char, next: char) -> i8 {
+    ///     match (prev, next) {
+    ///         ('A', 'V') | ('V', 'A') | ('T', 'o') => -1,
+    ///         _ => 0,
+    ///     }
+    /// }
+    ///
+    /// let style = TextBoxStyleBuilder::new().kerning(kerning).build();
+    /// ```
+    #[inline]
+    pub const fn kerning(mut self, kerning: fn(prev: char, next: char) -> i8) -> Self {
+        self.style.kerning = Some(kerning);
+
+        self
+    }
+
+    /// Sets the hyphenation function.
+    ///
+    /// The function receives a word and a byte index inside the word, and returns whether the
+    /// word may be broken before that index. When a word doesn't fit into the rest of the line,
+    /// it is broken at the last allowed index where the first part, followed by a hyphen, still
+    /// fits. The rest of the word is moved to the next line. By default, words are not hyphenated
+    /// automatically, only at soft hyphens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// fn hyphenate(word: &str, index: usize) -> bool {
+    ///     match word {
+    ///         "hyphenation" => index == 5,
+    ///         _ => false,
+    ///     }
+    /// }
+    ///
+    /// let style = TextBoxStyleBuilder::new().hyphenation(hyphenate).build();
+    /// ```
+    #[inline]
+    pub const fn hyphenation(mut self, hyphenate: fn(word: &str, index: usize) -> bool) -> Self {
+        self.style.hyphenation = Some(Hyphenator::new(hyphenate));
+
+        self
+    }
+
+    /// Sets whether non-breaking spaces are stretched in justified lines.
+    ///
+    /// By default, only regular spaces are stretched and non-breaking spaces keep the width of a
+    /// space character. This setting has no effect with other alignments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::{alignment::HorizontalAlignment, style::TextBoxStyleBuilder};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .alignment(HorizontalAlignment::Justified)
+    ///     .justify_nbsp(true)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn justify_nbsp(mut self, justify: bool) -> Self {
+        self.style.justify_nbsp = justify;
+
+        self
+    }
+
+    /// Sets whether lines that contain tab characters are justified.
+    ///
+    /// By default, every line of justified text is stretched. If this setting is disabled, lines
+    /// that contain a tab are rendered left aligned, so that the text after the tabs starts at the
+    /// tab stops. This setting has no effect with other alignments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::{alignment::HorizontalAlignment, style::TextBoxStyleBuilder};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .alignment(HorizontalAlignment::Justified)
+    ///     .justify_tabbed_lines(false)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn justify_tabbed_lines(mut self, justify: bool) -> Self {
+        self.style.justify_tabbed_lines = justify;
+
+        self
+    }
+
+    /// Sets whether punctuation at the end of justified lines hangs past the right edge.
+    ///
+    /// If enabled, a line may end in a word whose trailing punctuation character (one of
+    /// `.,;:!?`) doesn't fit into the line. The character is not counted when justifying the line,
+    /// and is drawn into the right padding of the text box. Characters that are wider than the
+    /// right padding don't hang. This setting has no effect with other alignments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::{
+    /// #     alignment::HorizontalAlignment,
+    /// #     style::{Padding, TextBoxStyleBuilder},
+    /// # };
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .alignment(HorizontalAlignment::Justified)
+    ///     .hanging_punctuation(true)
+    ///     .padding(Padding::new(0, 0, 6, 0))
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn hanging_punctuation(mut self, hanging: bool) -> Self {
+        self.style.hanging_punctuation = hanging;
+
+        self
+    }
+
+    /// Fills tabs with the given leader character.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::{TabLeader, TextBoxStyleBuilder};
+    /// # use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .tab_leader(TabLeader::new('.', Rgb888::WHITE))
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn tab_leader(mut self, leader: TabLeader) -> Self {
+        self.style.tab_leader = Some(leader);
+
+        self
+    }
+
+    /// Lays out tab separated cells in the given grid columns.
+    ///
+    /// Each tab moves the cursor to the next column, and the content of every cell is aligned
+    /// inside its column. Tabs after the last column are handled as usual.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::{alignment::HorizontalAlignment, style::{GridColumn, TextBoxStyleBuilder}};
+    /// #
+    /// const COLUMNS: [GridColumn; 2] = [
+    ///     GridColumn::new(60, HorizontalAlignment::Left),
+    ///     GridColumn::new(30, HorizontalAlignment::Right),
+    /// ];
+    ///
+    /// let style = TextBoxStyleBuilder::new().columns(&COLUMNS).build();
+    /// ```
+    #[inline]
+    pub const fn columns(mut self, columns: &'static [GridColumn]) -> Self {
+        self.style.columns = Some(columns);
+        self.style.column_scale = Scale::IDENTITY;
+
+        self
+    }
+
+    /// Sets the anchor of the tab stops in lines that are not left aligned.
+    ///
+    /// The same anchor is used to measure and to render lines, so the choice never changes where
+    /// lines are wrapped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::{alignment::HorizontalAlignment, style::{TabAnchor, TextBoxStyleBuilder}};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .alignment(HorizontalAlignment::Right)
+    ///     .tab_anchor(TabAnchor::Box)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn tab_anchor(mut self, anchor: TabAnchor) -> Self {
+        self.style.tab_anchor = anchor;
+
+        self
+    }
+
+    /// Keeps room for a caret after the last word of the text.
+    ///
+    /// If the last word of the text doesn't leave `width` pixels free in its line, it is wrapped
+    /// to a new line. Words that don't fit in an empty line are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// // Leave room for a 1 pixel wide caret.
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .reserve_caret_width(1)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn reserve_caret_width(mut self, width: u32) -> Self {
+        self.style.reserve_caret_width = width;
+
+        self
+    }
+
+    /// Splits words longer than the given number of characters.
+    ///
+    /// Long words are usually only broken when they don't fit in an empty line. With this setting,
+    /// a line can be wrapped after every `length` characters of a word, so long unbreakable
+    /// strings, like URLs, start right after the preceding text instead of on a new line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .max_word_length(10)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn max_word_length(mut self, length: u32) -> Self {
+        self.style.max_word_length = Some(length);
+
+        self
+    }
+
+    /// Limits the number of consecutive newlines.
+    ///
+    /// Newlines after the first `max` newlines of a run of blank lines are ignored, both when
+    /// measuring and when rendering the text. For example, a limit of 2 leaves at most one blank
+    /// line between paragraphs. The newline that ends a paragraph is always kept, so a limit of 0
+    /// works like a limit of 1, and is reported by [`try_build`](Self::try_build).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .max_consecutive_newlines(2)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn max_consecutive_newlines(mut self, max: u32) -> Self {
+        self.style.max_consecutive_newlines = Some(max);
+
+        self
+    }
+
+    /// Sets the character classes used to split the text into words.
+    ///
+    /// The classes are used both when measuring and when rendering the text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::{ParserConfig, TextBoxStyleBuilder};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .parser_config(ParserConfig::new(&['|'], &['\u{2009}']))
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn parser_config(mut self, config: ParserConfig) -> Self {
+        self.style.parser_config = config;
+
+        self
+    }
+
+    /// Sets the characters after which words wider than a line are preferably broken.
+    ///
+    /// A word that doesn't fit into an empty line is broken after the last of these characters
+    /// that still fits into the line. Only if there is none, the word is broken after the last
+    /// fitting character. The characters don't allow wrapping words that would fit into a line.
+    /// Words are never broken with [`LineBreak::SpacesOnly`], which ignores this setting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .weak_break_chars("_=.,/")
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn weak_break_chars(mut self, chars: &'static str) -> Self {
+        self.style.weak_break_chars = Some(chars);
+
+        self
+    }
+
+    /// Sets the line breaking strategy.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::{LineBreak, TextBoxStyleBuilder};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .line_break(LineBreak::Balanced)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn line_break(mut self, line_break: LineBreak) -> Self {
+        self.style.line_break = line_break;
+
+        self
+    }
+
+    /// Sets the case transformation of the displayed text.
+    ///
+    /// The text is transformed while it is measured and drawn, the source string is not modified.
+    /// Only ASCII letters are transformed, other characters are displayed unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::{TextBoxStyleBuilder, TextTransform};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .text_transform(TextTransform::Upper)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn text_transform(mut self, transform: TextTransform) -> Self {
+        self.style.text_transform = transform;
+
+        self
+    }
+
+    /// Sets how control characters in the text are displayed.
+    ///
+    /// By default, control characters are drawn by the character style.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::{ControlCharacters, TextBoxStyleBuilder};
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .control_characters(ControlCharacters::Caret)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn control_characters(mut self, control_characters: ControlCharacters) -> Self {
+        self.style.control_characters = control_characters;
+
+        self
+    }
+
+    /// Sets the marker that is drawn before the first line of each paragraph.
+    ///
+    /// Every line is indented by the width of the marker, so the lines of a paragraph are aligned
+    /// to the text after the marker. Include a trailing space in the marker to separate it from
+    /// the text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .list_marker("- ")
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn list_marker(mut self, marker: &'static str) -> Self {
+        self.style.list_marker = Some(marker);
+
+        self
+    }
+
+    /// Sets the space between the edges of the text box and the text.
+    ///
+    /// Lines are wrapped to the width of the text box minus the left and right padding. The first
+    /// line starts below the top padding, and alignment and clipping are applied within the
+    /// remaining area. The bounds of the text box are not changed. See [`Padding`] for an example.
+    #[inline]
+    pub const fn padding(mut self, padding: Padding) -> Self {
+        self.style.padding = padding;
+
+        self
+    }
+
+    /// Sets whether the last displayed line can end with a broken word.
+    ///
+    /// If enabled, a word that doesn't fit into the last line that is displayed in the text box
+    /// is not hyphenated or broken, but moved to the next, hidden line, unless it is the first
+    /// word of the line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .avoid_broken_last_word(true)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn avoid_broken_last_word(mut self, avoid: bool) -> Self {
+        self.style.avoid_broken_last_word = avoid;
+
+        self
+    }
+
+    /// Sets the maximum number of displayed lines.
+    ///
+    /// Lines after the limit are not displayed, even if the text box is tall enough to show them.
+    /// The measured height of the text is limited to the same number of lines.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use core::num::NonZeroU32;
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .max_lines(NonZeroU32::new(2).unwrap())
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn max_lines(mut self, max_lines: NonZeroU32) -> Self {
+        self.style.max_lines = Some(max_lines);
+
+        self
+    }
+
+    /// Sets the number of characters to draw.
+    ///
+    /// Only the glyphs of the first `count` characters are drawn, the rest of the text is laid out
+    /// but not drawn. Whitespace and zero-width characters are not counted. Increasing the count
+    /// over time reveals the text character by character.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let style = TextBoxStyleBuilder::new().reveal_chars(3).build();
+    /// ```
+    #[inline]
+    pub const fn reveal_chars(mut self, count: usize) -> Self {
+        self.style.reveal_chars = Some(count);
+
+        self
+    }
+
+    /// Disables word wrapping and scrolls the lines horizontally.
+    ///
+    /// In this mode, lines are only broken at newline characters. Each line is moved to the left
+    /// by `scroll_x` pixels and clipped to the bounding box. Lines that are shorter than the
+    /// text box are aligned according to the horizontal alignment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// // Don't wrap lines, and hide the first 12 pixels of every line.
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .no_wrap_horizontal_scroll(12)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub const fn no_wrap_horizontal_scroll(mut self, scroll_x: u32) -> Self {
+        self.style.horizontal_scroll = Some(scroll_x);
+
+        self
+    }
+
+    /// Sets the intensity of the rendered text.
+    ///
+    /// With an intensity below 255, pixels are left out using an ordered dither pattern, and an
+    /// intensity of 128 draws about half of the pixels. This can be used to display dimmed text,
+    /// e.g. a disabled state. [`TextBox::draw_dimmed`] scales gray and RGB colors towards black
+    /// using [`DimColor::dim`] instead. The intensity is applied to every pixel drawn by the text
+    /// box, including the background.
+    ///
+    /// [`TextBox::draw_dimmed`]: crate::TextBox::draw_dimmed
+    /// [`DimColor::dim`]: crate::DimColor::dim
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// #
+    /// let disabled = TextBoxStyleBuilder::new().intensity(128).build();
+    /// ```
+    #[inline]
+    pub const fn intensity(mut self, intensity: u8) -> Self {
+        self.style.intensity = intensity;
+
+        self
+    }
+
+    /// Fill the unused area of each line with the background color.
+    ///
+    /// Padding respects the horizontal alignment: left aligned lines are padded on the right,
+    /// right aligned lines on the left and centered lines on both sides. Padding is only visible if
+    /// the character style has a background color.
+    #[inline]
+    pub const fn pad_lines(mut self, pad: bool) -> Self {
+        self.style.pad_lines = pad;
+
+        self
+    }
+
+    /// Draw markers over invisible characters using the given color.
+    ///
+    /// A pilcrow (`¶`) is drawn at newlines, an arrow (`→`) at tabs and a dot (`·`) at each
+    /// rendered space. The markers are drawn using the character style's font, so fonts without
+    /// these glyphs display their replacement character instead.
+    ///
+    /// This is a shorthand for [`whitespace_style`] with the default markers of
+    /// [`WhitespaceStyle::new`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyleBuilder;
+    /// # use embedded_graphics::pixelcolor::Rgb888;
+    /// #
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .show_invisibles(Rgb888::new(128, 128, 128))
+    ///     .build();
+    /// ```
+    ///
+    /// [`whitespace_style`]: Self::whitespace_style
+    #[inline]
+    pub const fn show_invisibles(self, marker_color: Rgb888) -> Self {
+        self.whitespace_style(WhitespaceStyle::new(marker_color))
+    }
+
+    /// Sets the markers drawn over whitespace characters.
+    ///
+    /// Markers don't change the layout. Space and tab markers are centered in the space of the
+    /// whitespace they mark. The newline marker is drawn after the last character of lines that
+    /// end with a newline, if it fits into the line. See [`WhitespaceStyle`] for an example.
+    #[inline]
+    pub const fn whitespace_style(mut self, style: WhitespaceStyle) -> Self {
+        self.style.whitespace_style = Some(style);
+
+        self
+    }
+
+    /// Builds the [`TextBoxStyle`], checking the settings for contradictions first.
+    ///
+    /// [`build`] accepts every configuration. Use this method to catch settings that can't have
+    /// the intended effect.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::{
+    /// #     alignment::HorizontalAlignment,
+    /// #     style::{BuildError, TextBoxStyleBuilder},
+    /// # };
+    /// #
+    /// let result = TextBoxStyleBuilder::new()
+    ///     .alignment(HorizontalAlignment::Justified)
+    ///     .no_wrap_horizontal_scroll(0)
+    ///     .try_build();
+    ///
+    /// assert_eq!(result, Err(BuildError::JustifiedWithoutWrapping));
+    /// ```
+    ///
+    /// [`build`]: TextBoxStyleBuilder::build
+    #[inline]
+    pub const fn try_build(self) -> Result<TextBoxStyle, BuildError> {
+        if matches!(self.style.alignment, HorizontalAlignment::Justified)
+            && self.style.horizontal_scroll.is_some()
+        {
+            return Err(BuildError::JustifiedWithoutWrapping);
+        }
+
+        if matches!(self.style.height_mode, HeightMode::FitToText)
+            && !matches!(self.style.vertical_alignment, VerticalAlignment::Top)
+        {
+            return Err(BuildError::VerticalAlignmentWithFitToText);
+        }
+
+        if self.style.intensity == 0 {
+            return Err(BuildError::ZeroIntensity);
+        }
+
+        if self.style.hanging_punctuation && self.style.padding.right == 0 {
+            return Err(BuildError::HangingPunctuationWithoutPadding);
+        }
+
+        if matches!(self.style.max_consecutive_newlines, Some(0)) {
+            return Err(BuildError::ZeroConsecutiveNewlines);
+        }
+
+        Ok(self.build())
+    }
+
     /// Builds the [`TextBoxStyle`].
     #[inline]
     pub const fn build(mut self) -> TextBoxStyle {
@@ -151,6 +873,48 @@ impl TextBoxStyleBuilder {
     }
 }
 
+/// Contradicting settings found by [`TextBoxStyleBuilder::try_build`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// Justified alignment needs wrapped lines, but horizontal scrolling disables wrapping.
+    JustifiedWithoutWrapping,
+
+    /// Vertical alignment has no effect when the height of the text box fits the text.
+    VerticalAlignmentWithFitToText,
+
+    /// An intensity of 0 hides the text completely.
+    ZeroIntensity,
+
+    /// Hanging punctuation is drawn into the right padding, but the right padding is 0.
+    HangingPunctuationWithoutPadding,
+
+    /// A limit of 0 consecutive newlines can't remove the newline that ends a paragraph, so it
+    /// works like a limit of 1.
+    ZeroConsecutiveNewlines,
+}
+
+impl fmt::Display for BuildError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BuildError::JustifiedWithoutWrapping => {
+                "justified alignment can't be used without wrapping"
+            }
+            BuildError::VerticalAlignmentWithFitToText => {
+                "vertical alignment has no effect with the FitToText height mode"
+            }
+            BuildError::ZeroIntensity => "zero intensity hides the text",
+            BuildError::HangingPunctuationWithoutPadding => {
+                "hanging punctuation needs right padding"
+            }
+            BuildError::ZeroConsecutiveNewlines => {
+                "paragraph newlines are kept with a limit of 0 consecutive newlines"
+            }
+        })
+    }
+}
+
 impl From<&TextBoxStyle> for TextBoxStyleBuilder {
     #[inline]
     fn from(style: &TextBoxStyle) -> Self {
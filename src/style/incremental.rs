@@ -0,0 +1,234 @@
+//! Incremental text measurement.
+//!
+//! Measuring the height of a long text can take a long time. [`IncrementalMeasure`] splits the
+//! work into steps of a limited number of lines, so that the measurement can be interleaved with
+//! other tasks, or abandoned once the text is known to be taller than some threshold.
+
+use embedded_graphics::{pixelcolor::Rgb888, text::renderer::TextRenderer};
+
+use crate::{
+    parser::Parser,
+    plugin::{NoPlugin, PluginWrapper, ProcessingState},
+    style::{TextBoxStyle, TextHeight},
+    utils::str_width,
+};
+
+/// The result of a measurement step.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum MeasureProgress {
+    /// The text is not completely measured yet. Contains the height of the lines measured so far.
+    InProgress(u32),
+
+    /// The whole text is measured. Contains the height of the text.
+    Done(u32),
+}
+
+impl MeasureProgress {
+    /// Returns the measured height.
+    ///
+    /// While the measurement is in progress, the returned value is the height of the lines
+    /// measured so far, which is a lower bound of the height of the text.
+    #[inline]
+    #[must_use]
+    pub const fn height(self) -> u32 {
+        match self {
+            MeasureProgress::InProgress(height) | MeasureProgress::Done(height) => height,
+        }
+    }
+
+    /// Returns `true` if the whole text is measured.
+    #[inline]
+    #[must_use]
+    pub const fn is_done(self) -> bool {
+        matches!(self, MeasureProgress::Done(_))
+    }
+}
+
+/// Measures the height of a text in multiple steps.
+///
+/// The result of the measurement is the same as the value returned by
+/// [`TextBoxStyle::measure_text_height`].
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_graphics::{
+/// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+/// #     pixelcolor::BinaryColor,
+/// # };
+/// use embedded_text::style::{IncrementalMeasure, MeasureProgress, TextBoxStyle};
+///
+/// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+/// let style = TextBoxStyle::default();
+/// let text = "Lorem Ipsum is simply dummy text of the printing and typesetting industry.";
+///
+/// let mut measure = IncrementalMeasure::new(&style, &character_style, text, 72);
+///
+/// let height = loop {
+///     match measure.step(2) {
+///         // Stop measuring if the text is taller than 5 lines.
+///         MeasureProgress::InProgress(height) if height > 5 * 9 => break None,
+///         MeasureProgress::InProgress(_) => {
+///             // Do something else before continuing.
+///         }
+///         MeasureProgress::Done(height) => break Some(height),
+///     }
+/// };
+///
+/// assert_eq!(height, None);
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct IncrementalMeasure<'a, S>
+where
+    S: TextRenderer,
+{
+    style: &'a TextBoxStyle,
+    character_style: &'a S,
+    plugin: PluginWrapper<'a, NoPlugin<S::Color>, S::Color>,
+    parser: Parser<'a, S::Color>,
+    max_width: u32,
+    height: TextHeight,
+    done: bool,
+}
+
+impl<'a, S> IncrementalMeasure<'a, S>
+where
+    S: TextRenderer,
+    S::Color: From<Rgb888>,
+{
+    /// Creates a new measurement of `text` when rendered using a given width.
+    #[inline]
+    pub fn new(
+        style: &'a TextBoxStyle,
+        character_style: &'a S,
+        text: &'a str,
+        max_width: u32,
+    ) -> Self {
+        let plugin = PluginWrapper::new(NoPlugin::new());
+        plugin.set_state(ProcessingState::Measure);
+
+        Self {
+            style,
+            character_style,
+            plugin,
+            parser: style.parser(text),
+            max_width: style.layout_width(
+                max_width,
+                style.list_marker_width(|m| str_width(character_style, m)),
+            ),
+            height: TextHeight::new(style, character_style),
+            done: false,
+        }
+    }
+
+    /// Measures at most `max_lines` lines of the text.
+    ///
+    /// Once the whole text is measured, this method returns [`MeasureProgress::Done`] without
+    /// doing any more work.
+    #[inline]
+    pub fn step(&mut self, max_lines: u32) -> MeasureProgress {
+        for _ in 0..max_lines {
+            if self.done {
+                break;
+            }
+
+            self.plugin.new_line();
+            let lm = self.style.measure_line(
+                &self.plugin,
+                self.character_style,
+                &mut self.parser,
+                self.max_width,
+            );
+
+            self.done = self.height.add_line(lm);
+        }
+
+        self.progress()
+    }
+
+    /// Returns the current state of the measurement.
+    #[inline]
+    pub fn progress(&self) -> MeasureProgress {
+        if self.done {
+            MeasureProgress::Done(self.height.height)
+        } else {
+            MeasureProgress::InProgress(self.height.height)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mono_font::{ascii::FONT_6X9, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        text::LineHeight,
+    };
+
+    use crate::style::{IncrementalMeasure, MeasureProgress, TextBoxStyle, TextBoxStyleBuilder};
+
+    const CORPUS: &[&str] = &[
+        "",
+        "word",
+        "Lorem Ipsum is simply dummy text of the printing and typesetting industry.",
+        "paragraph\n\nanother paragraph\r\nwith a carriage return",
+        "\n\n\n",
+        "trailing spaces      \n   leading spaces",
+        "tabs\tare\there",
+        "soft\u{ad}hyphen\u{ad}ated\u{ad}word",
+        "supercalifragilisticexpialidocious",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "short aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa short",
+    ];
+
+    #[test]
+    fn incremental_measurement_matches_one_shot_measurement() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let styles = [
+            TextBoxStyle::default(),
+            TextBoxStyleBuilder::new()
+                .line_height(LineHeight::Pixels(11))
+                .paragraph_spacing(3)
+                .build(),
+            TextBoxStyleBuilder::new().max_word_length(5).build(),
+        ];
+
+        for style in styles.iter() {
+            for text in CORPUS {
+                for width in [6, 30, 72] {
+                    let expected = style.measure_text_height(&character_style, text, width);
+
+                    for lines in [1, 2, 7] {
+                        let mut measure =
+                            IncrementalMeasure::new(style, &character_style, text, width);
+
+                        let height = loop {
+                            match measure.step(lines) {
+                                MeasureProgress::InProgress(height) => assert!(height <= expected),
+                                MeasureProgress::Done(height) => break height,
+                            }
+                        };
+
+                        assert_eq!(height, expected, "{:?} {} {}", text, width, lines);
+                        assert_eq!(measure.step(lines), MeasureProgress::Done(expected));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn step_limits_the_number_of_lines() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyle::default();
+
+        let mut measure = IncrementalMeasure::new(&style, &character_style, "1\n2\n3\n4", 60);
+
+        assert_eq!(measure.progress(), MeasureProgress::InProgress(9));
+        assert_eq!(measure.step(2), MeasureProgress::InProgress(27));
+        assert_eq!(measure.step(0), MeasureProgress::InProgress(27));
+        assert_eq!(measure.step(2), MeasureProgress::Done(36));
+    }
+}
@@ -17,6 +17,9 @@
 //! alignment, line and paragraph spacing, tab size and some other advanced settings of text box
 //! objects.
 //!
+//! Styles that share most of their settings can be derived from a common base using a
+//! [`TextBoxStyleModifier`], which only changes the options it sets.
+//!
 //! The [`alignment`] option sets the horizontal alignment of the text.
 //! **Note: alignment works differently from `embedded-graphics`.**
 //! With the default value `Left` the start of each line will be lined up with the left side of the
@@ -37,9 +40,32 @@
 //! The [`paragraph_spacing`] setting sets the distance between paragraphs of text, in addition to
 //! the line spacing.
 //!
+//! The height of the text is measured from the top of the first line to the bottom of the glyphs
+//! of the last line, so `n` lines are `(n - 1) * line_height + font_height` pixels tall. The extra
+//! space a [`line_height`] larger than the font adds below the last line is not part of the text.
+//! Set [`include_trailing_leading`] to count it, which makes [`FitToText`] boxes taller and moves
+//! vertically aligned text up by the same amount.
+//!
+//! The [`line_heights`] setting replaces the fixed line height with a function that returns the
+//! line height of each line, in pixels, by the index of the line. The first line of the text has
+//! the index 0, and every wrapped line counts as a separate line. This can be used to make room
+//! for a drop cap or an inline heading. The measured height of `n` lines is the sum of the line
+//! heights of the first `n - 1` lines, plus the font height.
+//!
 //! The [`tab_size`] setting sets the maximum width of a tab character. It can be specified in
 //! either pixels of number of space characters.
 //!
+//! The [`tab_leader`] setting fills the space of tab characters with a repeated character, for
+//! example to connect the entries of a table of contents to their page numbers with dots. A
+//! right-aligned [`TabLeader`] also pushes the text following the tab to the right side of the
+//! line.
+//!
+//! The [`columns`] setting lays out lines of tab separated cells in a grid. Every tab moves to the
+//! next [`GridColumn`], and cells are aligned inside their column, e.g. to right-align the values
+//! of a two-column list. Cells that are wider than their column push the rest of the line to the
+//! right. Grids are laid out for left-aligned text boxes, and lines that don't fit are wrapped like
+//! any other text.
+//!
 //! Advanced settings
 //! -----------------
 //!
@@ -69,6 +95,104 @@
 //! | `Center`     | `false`          | `false`           |
 //! | `Justified`  | `false`          | `false`           |
 //!
+//! Disabling both settings trims every line: whitespace at the start and at the end of the lines
+//! is neither rendered nor measured, so `"  hi  "` takes up as much room as `"hi"`. Spaces
+//! between words are not affected.
+//!
+//! The [`justify_nbsp`] setting controls whether non-breaking spaces are stretched together with
+//! regular spaces in `Justified` lines. By default they keep their natural width, which is the
+//! typographic convention for spacing around punctuation.
+//!
+//! The [`justify_tabbed_lines`] setting controls whether lines that contain tab characters are
+//! justified. Stretching the spaces of such lines moves the text away from the tab stops, so
+//! disabling this setting renders them left aligned instead.
+//!
+//! The [`hanging_punctuation`] setting lets a period, comma, colon, semicolon, exclamation or
+//! question mark at the end of a `Justified` line hang past the right edge of the text. The
+//! punctuation does not count toward the width of the line, so the words before it are aligned
+//! to the edge. The hanging character is drawn into the right [`padding`]. Punctuation that is
+//! wider than the padding doesn't hang, and the word is wrapped as usual.
+//!
+//! The [`tab_anchor`] setting selects where the tab stops of centered and right aligned lines
+//! are. By default, tab stops are positioned relative to the start of the line's content, which is
+//! then aligned as a whole. [`TabAnchor::Box`] keeps the tab stops at fixed positions of the text
+//! box instead, and the tabs of an aligned line extend to the next tab stop of the box.
+//!
+//! The [`horizontal_scroll`] setting disables word wrapping. Each line of text is only broken at
+//! newline characters, and is moved to the left by the given number of pixels. Parts of the lines
+//! outside the bounding box are not drawn. This can be used to pan long lines.
+//!
+//! The [`pad_lines`] setting fills the parts of each line that are not covered by text with the
+//! background color of the character style. This results in solid rows behind ragged text.
+//!
+//! The [`intensity`] setting dims the text by leaving out pixels in an ordered dither pattern. The
+//! pattern only depends on the position of the pixel, so the output is stable between redraws.
+//! [`TextBox::draw_dimmed`] scales gray and RGB colors towards black instead, see [`DimColor`] for
+//! custom color types.
+//!
+//! The [`reserve_caret_width`] setting keeps the given number of pixels free at the end of the
+//! last line of text. Input fields can use this to leave room for a caret after the last
+//! character. A word that would fill the last line completely is wrapped to a new line instead.
+//!
+//! The [`max_word_length`] setting splits words that are longer than the given number of
+//! characters. This provides a wrap opportunity at least every few characters, even in long
+//! unbreakable strings.
+//!
+//! The [`parser_config`] setting adds characters that separate words like spaces, for example
+//! field separators of a data format, and characters that are part of words even if they are
+//! whitespace. See [`ParserConfig`] for details.
+//!
+//! The [`max_consecutive_newlines`] setting limits the number of consecutive newlines, to collapse
+//! runs of blank lines in messy input. Newlines over the limit are ignored, as if they were not in
+//! the text. Lines that only contain whitespace are considered blank. The newline that ends a
+//! paragraph is always kept, so the smallest effective limit is 1.
+//!
+//! The [`weak_break_chars`] setting lists characters, like `_` or `.`, after which a word that is
+//! wider than a line is preferably broken. These characters don't provide wrap opportunities
+//! otherwise, they are only used when a long word has to be broken anyway. If none of them fits
+//! into the line, the word is broken between any two characters as usual. Words are never broken
+//! with [`LineBreak::SpacesOnly`], so the setting has no effect then.
+//!
+//! The [`line_break`] setting selects how lines are broken. [`LineBreak::Balanced`] breaks short
+//! texts, like headings, into lines of similar width instead of filling the first lines. The
+//! number of lines, and so the height of the text, is the same as with greedy line breaking.
+//! [`LineBreak::SpacesOnly`] never breaks words, which is useful for code and identifiers. Words
+//! that are too long for the text box are cut off instead. Zero-width spaces still separate words,
+//! so lines can be broken at them.
+//!
+//! The [`text_transform`] setting changes the case of the displayed text without modifying the
+//! source string. Only ASCII letters are transformed, other characters are displayed unchanged.
+//!
+//! The [`control_characters`] setting selects how control characters in the text are displayed.
+//! By default they are passed to the character style, which usually draws them as an unknown
+//! glyph. They can also be hidden, replaced by a fixed character, or displayed in caret notation.
+//!
+//! The [`whitespace_style`] setting draws marker glyphs over whitespace, by default `¶` at the end
+//! of paragraphs, `→` at tabs and `·` at spaces. Markers are centered in the space of the
+//! whitespace they mark, and don't change the layout.
+//!
+//! The [`padding`] setting insets the text from the edges of the text box. Lines are wrapped,
+//! aligned and clipped within the remaining area, while the bounds of the text box itself are not
+//! changed.
+//!
+//! The [`list_marker`] setting draws a marker, like a bullet, before the first line of each
+//! paragraph. Every line is indented by the width of the marker, so wrapped lines are aligned to
+//! the text after the marker.
+//!
+//! The [`avoid_broken_last_word`] setting prevents the last displayed line from ending with the
+//! first part of a hyphenated or broken word, if it can end after the previous word instead. The
+//! whole word is moved to the first line that is not displayed.
+//!
+//! The [`max_lines`] setting limits the number of displayed lines, regardless of the height of the
+//! text box. Lines after the limit are not drawn, and they are not included in the measured text
+//! height, so [`HeightMode::FitToText`] and vertical alignment treat the first lines as the whole
+//! text. Hidden lines are reported like any other truncated text, and the last displayed line
+//! respects [`avoid_broken_last_word`].
+//!
+//! The [`reveal_chars`] setting draws only the first characters of the text, which can be used to
+//! animate text as if it was being typed. The text is laid out as a whole, so revealing more
+//! characters doesn't move the already visible ones.
+//!
 //! # Ways to create and apply text box styles
 //!
 //! ## Example 1: Setting multiple options using the [`TextBoxStyleBuilder`] object:
@@ -165,10 +289,37 @@
 //! [`vertical_alignment`]: TextBoxStyle::vertical_alignment
 //! [`line_height`]: TextBoxStyle::line_height
 //! [`paragraph_spacing`]: TextBoxStyle::paragraph_spacing
+//! [`line_heights`]: TextBoxStyle::line_heights
+//! [`include_trailing_leading`]: TextBoxStyle::include_trailing_leading
 //! [`tab_size`]: TextBoxStyle::tab_size
+//! [`tab_leader`]: TextBoxStyle::tab_leader
+//! [`columns`]: TextBoxStyle::columns
+//! [`tab_anchor`]: TextBoxStyle::tab_anchor
 //! [`height_mode`]: TextBoxStyle::height_mode
 //! [`leading_spaces`]: TextBoxStyle::leading_spaces
 //! [`trailing_spaces`]: TextBoxStyle::trailing_spaces
+//! [`whitespace_style`]: TextBoxStyle::whitespace_style
+//! [`pad_lines`]: TextBoxStyle::pad_lines
+//! [`horizontal_scroll`]: TextBoxStyle::horizontal_scroll
+//! [`justify_nbsp`]: TextBoxStyle::justify_nbsp
+//! [`hanging_punctuation`]: TextBoxStyle::hanging_punctuation
+//! [`justify_tabbed_lines`]: TextBoxStyle::justify_tabbed_lines
+//! [`intensity`]: TextBoxStyle::intensity
+//! [`TextBox::draw_dimmed`]: crate::TextBox::draw_dimmed
+//! [`DimColor`]: crate::DimColor
+//! [`reserve_caret_width`]: TextBoxStyle::reserve_caret_width
+//! [`max_word_length`]: TextBoxStyle::max_word_length
+//! [`max_consecutive_newlines`]: TextBoxStyle::max_consecutive_newlines
+//! [`parser_config`]: TextBoxStyle::parser_config
+//! [`weak_break_chars`]: TextBoxStyle::weak_break_chars
+//! [`text_transform`]: TextBoxStyle::text_transform
+//! [`line_break`]: TextBoxStyle::line_break
+//! [`control_characters`]: TextBoxStyle::control_characters
+//! [`padding`]: TextBoxStyle::padding
+//! [`list_marker`]: TextBoxStyle::list_marker
+//! [`avoid_broken_last_word`]: TextBoxStyle::avoid_broken_last_word
+//! [`max_lines`]: TextBoxStyle::max_lines
+//! [`reveal_chars`]: TextBoxStyle::reveal_chars
 //! [`Exact`]: HeightMode::Exact
 //! [`FitToText`]: HeightMode::FitToText
 //! [`ShrinkToText`]: HeightMode::ShrinkToText
@@ -179,30 +330,292 @@
 
 mod builder;
 mod height_mode;
+mod incremental;
+mod modifier;
+mod snapshot;
 mod vertical_overdraw;
 
-use core::convert::Infallible;
+use core::{convert::Infallible, num::NonZeroU32, ops::Range};
 
 use crate::{
     alignment::{HorizontalAlignment, VerticalAlignment},
-    parser::Parser,
+    parser::{paragraphs_end, Parser, SPEC_CHAR_SHY},
     plugin::{NoPlugin, PluginMarker as Plugin, PluginWrapper, ProcessingState},
     rendering::{
-        cursor::LineCursor,
+        cursor::{LineCursor, MAX_TEXT_WIDTH, UNBOUNDED_LINE_WIDTH},
         line_iter::{ElementHandler, LineElementParser, LineEndType},
         space_config::SpaceConfig,
     },
-    utils::str_width,
+    utils::{is_control, line_height_pixels, str_width, str_width_kerned},
 };
+use az::SaturatingAs;
 use embedded_graphics::{
-    pixelcolor::Rgb888,
+    geometry::{Point, Size},
+    pixelcolor::{PixelColor, Rgb888},
+    primitives::Rectangle,
     text::{renderer::TextRenderer, LineHeight},
 };
 
+use self::snapshot::{starts_line, LineWalker};
+
+pub use crate::parser::ParserConfig;
+
 pub use self::{
-    builder::TextBoxStyleBuilder, height_mode::HeightMode, vertical_overdraw::VerticalOverdraw,
+    builder::{BuildError, TextBoxStyleBuilder},
+    height_mode::HeightMode,
+    incremental::{IncrementalMeasure, MeasureProgress},
+    modifier::TextBoxStyleModifier,
+    snapshot::{LayoutSnapshot, LineRecord},
+    vertical_overdraw::VerticalOverdraw,
 };
 
+/// Markers drawn over whitespace characters.
+///
+/// Markers are drawn using the character style's font, so fonts without the marker glyphs display
+/// their replacement character instead.
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_text::style::{TextBoxStyleBuilder, WhitespaceStyle};
+/// # use embedded_graphics::pixelcolor::Rgb888;
+/// #
+/// // Only mark tabs and the ends of paragraphs.
+/// let style = TextBoxStyleBuilder::new()
+///     .whitespace_style(WhitespaceStyle {
+///         space: None,
+///         ..WhitespaceStyle::new(Rgb888::new(128, 128, 128))
+///     })
+///     .build();
+/// ```
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct WhitespaceStyle {
+    /// Marker drawn at each space. `None` leaves spaces empty.
+    pub space: Option<char>,
+
+    /// Marker drawn at tabs. `None` leaves tabs empty.
+    pub tab: Option<char>,
+
+    /// Marker drawn after the last character of lines that end with a newline. `None` disables
+    /// the marker.
+    pub newline: Option<char>,
+
+    /// The color of the markers.
+    pub color: Rgb888,
+}
+
+impl WhitespaceStyle {
+    /// Creates a new whitespace style that draws `·` at spaces, `→` at tabs and `¶` at newlines.
+    #[inline]
+    pub const fn new(color: Rgb888) -> Self {
+        Self {
+            space: Some('·'),
+            tab: Some('→'),
+            newline: Some('¶'),
+            color,
+        }
+    }
+}
+
+/// Space between the edges of a text box and its text.
+///
+/// Padding reduces the area available to the text, but doesn't change the bounds of the text
+/// box. The first line of text starts below the top padding, and vertical alignment and
+/// clipping are applied to the area between the top and bottom padding.
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_text::style::{Padding, TextBoxStyleBuilder};
+/// #
+/// // Keep 8px free on the left and 2px on the right side of the text.
+/// let style = TextBoxStyleBuilder::new()
+///     .padding(Padding::horizontal(8, 2))
+///     .build();
+///
+/// // Keep 4px free on every side of the text.
+/// let style = TextBoxStyleBuilder::new()
+///     .padding(Padding::new(4, 4, 4, 4))
+///     .build();
+/// ```
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Padding {
+    /// Space on the left side of the text, in pixels.
+    pub left: u32,
+
+    /// Space above the text, in pixels.
+    pub top: u32,
+
+    /// Space on the right side of the text, in pixels.
+    pub right: u32,
+
+    /// Space below the text, in pixels.
+    pub bottom: u32,
+}
+
+impl Padding {
+    /// Creates a new padding with the given insets.
+    #[inline]
+    pub const fn new(left: u32, top: u32, right: u32, bottom: u32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Creates a new padding with the given left and right insets.
+    #[inline]
+    pub const fn horizontal(left: u32, right: u32) -> Self {
+        Self::new(left, 0, right, 0)
+    }
+
+    /// Creates a new padding with the given top and bottom insets.
+    #[inline]
+    pub const fn vertical(top: u32, bottom: u32) -> Self {
+        Self::new(0, top, 0, bottom)
+    }
+
+    /// Returns the width left for the text in a box of the given width.
+    const fn inner_width(&self, box_width: u32) -> u32 {
+        box_width
+            .saturating_sub(self.left)
+            .saturating_sub(self.right)
+    }
+
+    /// Returns the height left for the text in a box of the given height.
+    pub(crate) const fn inner_height(&self, box_height: u32) -> u32 {
+        box_height
+            .saturating_sub(self.top)
+            .saturating_sub(self.bottom)
+    }
+
+    /// Returns the height of a box that fits text of the given height.
+    pub(crate) const fn outer_height(&self, text_height: u32) -> u32 {
+        text_height
+            .saturating_add(self.top)
+            .saturating_add(self.bottom)
+    }
+}
+
+/// Element handler that counts the lines of a text, using the measurements of another handler.
+struct LineCounter<'h, E> {
+    handler: &'h E,
+    lines: u32,
+}
+
+impl<E: ElementHandler> ElementHandler for LineCounter<'_, E> {
+    type Error = Infallible;
+    type Color = E::Color;
+
+    fn measure(&self, st: &str) -> u32 {
+        self.handler.measure(st)
+    }
+
+    fn end_line(&mut self, end: LineEndType) -> Result<(), Self::Error> {
+        if end != LineEndType::CarriageReturn {
+            self.lines += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Line breaking strategy.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum LineBreak {
+    /// Every line is filled with as many words as fit into it.
+    Greedy,
+
+    /// Text that wraps into two or three lines is broken into lines of similar width.
+    ///
+    /// The text is laid out in the narrowest column that doesn't increase the number of lines.
+    /// The column is placed in the text box according to the horizontal alignment. Longer texts
+    /// are wrapped greedily.
+    Balanced,
+
+    /// Lines are only broken at spaces.
+    ///
+    /// Words are never broken: soft hyphens are invisible and take up no space, and automatic
+    /// hyphenation, [`max_word_length`](TextBoxStyle::max_word_length) and
+    /// [`weak_break_chars`](TextBoxStyle::weak_break_chars) are ignored. Zero-width spaces
+    /// separate words, so lines can still be broken at them. A word that is wider than the text
+    /// box is displayed in a line of its own, and is cut off at the edge of the text box.
+    SpacesOnly,
+}
+
+impl LineBreak {
+    /// The largest number of lines that are balanced.
+    const MAX_BALANCED_LINES: u32 = 3;
+}
+
+/// Case transformation applied to the displayed text.
+///
+/// Only ASCII letters are transformed. Characters of other scripts, and letters whose case
+/// mapping takes multiple characters, are displayed unchanged.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TextTransform {
+    /// The text is displayed as is.
+    None,
+
+    /// Lowercase letters are displayed in uppercase.
+    Upper,
+
+    /// Uppercase letters are displayed in lowercase.
+    Lower,
+}
+
+impl TextTransform {
+    /// Returns the displayed form of a character.
+    #[inline]
+    #[must_use]
+    pub const fn apply(self, c: char) -> char {
+        match self {
+            TextTransform::None => c,
+            TextTransform::Upper => c.to_ascii_uppercase(),
+            TextTransform::Lower => c.to_ascii_lowercase(),
+        }
+    }
+}
+
+/// Display of control characters in the text.
+///
+/// Applies to the C0 control characters (U+0000 to U+001F) that the parser passes through as
+/// printable text. Tabs, line breaks and other whitespace control characters are not affected.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ControlCharacters {
+    /// Control characters are drawn by the character style, like any other character.
+    Keep,
+
+    /// Control characters are not displayed, and take up no space.
+    Ignore,
+
+    /// Control characters are displayed as the given character.
+    Replace(char),
+
+    /// Control characters are displayed in caret notation, for example `^A` for U+0001.
+    Caret,
+}
+
+impl ControlCharacters {
+    /// Returns the characters displayed in place of a character.
+    #[inline]
+    pub(crate) fn apply(self, c: char) -> impl Iterator<Item = char> {
+        let (first, second) = if !is_control(c) {
+            (Some(c), None)
+        } else {
+            match self {
+                ControlCharacters::Keep => (Some(c), None),
+                ControlCharacters::Ignore => (None, None),
+                ControlCharacters::Replace(replacement) => (Some(replacement), None),
+                ControlCharacters::Caret => (Some('^'), Some((c as u8 + b'@') as char)),
+            }
+        };
+
+        first.into_iter().chain(second)
+    }
+}
+
 /// Tab size helper
 ///
 /// This type makes it more obvious what unit is used to define the width of tabs.
@@ -233,6 +646,144 @@ impl TabSize {
     }
 }
 
+/// Fill settings of tab characters.
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_text::style::{TabLeader, TextBoxStyleBuilder};
+/// # use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+/// #
+/// // Fill tabs with dots and align the text after a tab to the right side of the line.
+/// let style = TextBoxStyleBuilder::new()
+///     .tab_leader(TabLeader::new('.', Rgb888::WHITE).right_aligned())
+///     .build();
+/// ```
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TabLeader {
+    /// The character that fills the tab.
+    pub fill: char,
+
+    /// The color of the fill characters.
+    pub color: Rgb888,
+
+    /// True to align the text after the tab to the right side of the line.
+    pub right_aligned: bool,
+}
+
+impl TabLeader {
+    /// Creates a new tab leader that fills tabs with the given character.
+    #[inline]
+    pub const fn new(fill: char, color: Rgb888) -> Self {
+        Self {
+            fill,
+            color,
+            right_aligned: false,
+        }
+    }
+
+    /// Aligns the text after the tab to the right side of the line.
+    ///
+    /// If the rest of the line doesn't fit, the tab falls back to the next tab stop.
+    #[inline]
+    pub const fn right_aligned(mut self) -> Self {
+        self.right_aligned = true;
+
+        self
+    }
+}
+
+/// Anchor of the tab stops in lines that are not left aligned.
+///
+/// See [`TextBoxStyle::tab_anchor`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TabAnchor {
+    /// Tab stops are positioned relative to the start of the line's content, and the whole line
+    /// is aligned afterwards. Tabs keep their widths, but tab stops move together with the line.
+    Content,
+
+    /// Tab stops are positioned relative to the left edge of the text box, so they line up with
+    /// the tab stops of left aligned lines.
+    ///
+    /// Tabs are resolved after the line is aligned, so moving a line can change the widths of its
+    /// tabs. A line is moved as far as it can be without being wrapped differently: right aligned
+    /// lines as long as they don't extend past the right edge, centered lines as long as the space
+    /// on their left is not larger than the space on their right.
+    Box,
+}
+
+/// A column of a grid of tab separated cells.
+///
+/// See [`TextBoxStyle::columns`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct GridColumn {
+    /// Width of the column in pixels.
+    pub width: u32,
+
+    /// Alignment of the cells in the column. [`Justified`] cells are aligned to the left.
+    ///
+    /// [`Justified`]: HorizontalAlignment::Justified
+    pub alignment: HorizontalAlignment,
+}
+
+impl GridColumn {
+    /// Creates a new grid column.
+    #[inline]
+    pub const fn new(width: u32, alignment: HorizontalAlignment) -> Self {
+        Self { width, alignment }
+    }
+}
+
+/// Hyphenation function of a [`TextBoxStyle`], set using [`TextBoxStyleBuilder::hyphenation`].
+///
+/// Like the other function pointers in the style, the function is compared and hashed by its
+/// address. It is wrapped because function pointers that take references don't implement these
+/// traits on the minimum supported Rust version.
+#[derive(Copy, Clone)]
+pub struct Hyphenator(fn(word: &str, index: usize) -> bool);
+
+impl Hyphenator {
+    /// Creates a new hyphenator using the given function.
+    #[inline]
+    pub const fn new(hyphenate: fn(word: &str, index: usize) -> bool) -> Self {
+        Self(hyphenate)
+    }
+
+    /// Returns whether `word` may be broken before the byte at `index`.
+    #[inline]
+    #[must_use]
+    pub fn allows_break(&self, word: &str, index: usize) -> bool {
+        (self.0)(word, index)
+    }
+
+    fn address(&self) -> *const () {
+        self.0 as *const ()
+    }
+}
+
+impl core::fmt::Debug for Hyphenator {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Hyphenator").field(&self.address()).finish()
+    }
+}
+
+impl PartialEq for Hyphenator {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.address() == other.address()
+    }
+}
+
+impl Eq for Hyphenator {}
+
+impl core::hash::Hash for Hyphenator {
+    #[inline]
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.address().hash(state);
+    }
+}
+
 /// Styling options of a [`TextBox`].
 ///
 /// `TextBoxStyle` contains the font, foreground and background `PixelColor`, line spacing,
@@ -259,17 +810,105 @@ pub struct TextBoxStyle {
     /// Line height.
     pub line_height: LineHeight,
 
+    /// Returns the line height of each line, by the index of the line. `None` uses
+    /// [`line_height`](Self::line_height) for every line.
+    pub line_heights: Option<fn(line: u32) -> u32>,
+
     /// Paragraph spacing.
     pub paragraph_spacing: u32,
 
+    /// True to include the leading below the last line in the measured text height.
+    pub include_trailing_leading: bool,
+
     /// Desired column width for tabs
     pub tab_size: TabSize,
 
+    /// Fill settings of tabs. `None` leaves tabs empty.
+    pub tab_leader: Option<TabLeader>,
+
     /// True to render leading spaces
     pub leading_spaces: bool,
 
     /// True to render trailing spaces
     pub trailing_spaces: bool,
+
+    /// Markers drawn over whitespace characters. `None` disables the markers.
+    pub whitespace_style: Option<WhitespaceStyle>,
+
+    /// True to fill the unused area of each line with the background color.
+    pub pad_lines: bool,
+
+    /// Kerning adjustment in pixels between two adjacent characters of a word.
+    pub kerning: Option<fn(prev: char, next: char) -> i8>,
+
+    /// Returns whether a long word may be hyphenated before a byte index. `None` disables
+    /// automatic hyphenation.
+    pub hyphenation: Option<Hyphenator>,
+
+    /// Horizontal scroll offset in pixels. `Some` disables word wrapping.
+    pub horizontal_scroll: Option<u32>,
+
+    /// Intensity of the rendered text. 255 draws the original colors, lower values dim them.
+    pub intensity: u8,
+
+    /// True to stretch non-breaking spaces in justified lines.
+    pub justify_nbsp: bool,
+
+    /// True to justify lines that contain tab characters.
+    pub justify_tabbed_lines: bool,
+
+    /// True to let punctuation at the end of justified lines hang past the right edge.
+    pub hanging_punctuation: bool,
+
+    /// Width in pixels that is kept free after the last word of the text.
+    pub reserve_caret_width: u32,
+
+    /// Maximum number of characters in a word. Longer words are split. `None` disables splitting.
+    pub max_word_length: Option<u32>,
+
+    /// Maximum number of consecutive newlines. Extra newlines are ignored. `None` keeps every
+    /// newline, and 0 works like 1.
+    pub max_consecutive_newlines: Option<u32>,
+
+    /// Character classes used to split the text into words.
+    pub parser_config: ParserConfig,
+
+    /// Characters after which words wider than a line are preferably broken. `None` breaks long
+    /// words at the last fitting character. Not used with [`LineBreak::SpacesOnly`].
+    pub weak_break_chars: Option<&'static str>,
+
+    /// Line breaking strategy.
+    pub line_break: LineBreak,
+
+    /// Case transformation of the displayed text.
+    pub text_transform: TextTransform,
+
+    /// Display of control characters.
+    pub control_characters: ControlCharacters,
+
+    /// Space between the edges of the text box and the text.
+    pub padding: Padding,
+
+    /// Marker drawn in a hanging indent before the first line of each paragraph.
+    pub list_marker: Option<&'static str>,
+
+    /// True to wrap a word that doesn't fit into the last displayed line, instead of breaking it.
+    pub avoid_broken_last_word: bool,
+
+    /// Maximum number of displayed lines. `None` displays as many lines as fit into the text box.
+    pub max_lines: Option<NonZeroU32>,
+
+    /// Number of characters to draw. `None` draws every character.
+    pub reveal_chars: Option<usize>,
+
+    /// Grid columns of tab separated cells. `None` uses tab stops.
+    pub columns: Option<&'static [GridColumn]>,
+
+    /// Scale of the column widths, set by [`rescaled`](Self::rescaled).
+    pub(crate) column_scale: Scale,
+
+    /// Anchor of the tab stops in lines that are not left aligned.
+    pub tab_anchor: TabAnchor,
 }
 
 impl TextBoxStyle {
@@ -322,6 +961,165 @@ impl TextBoxStyle {
     }
 }
 
+impl TextBoxStyle {
+    /// Returns the width used to lay out lines in a text box of the given width.
+    pub(crate) const fn layout_width(&self, box_width: u32, marker_width: u32) -> u32 {
+        self.line_layout_width(self.text_width(box_width, marker_width))
+    }
+
+    /// Returns the width of the text area in a text box of the given width.
+    const fn text_width(&self, box_width: u32, marker_width: u32) -> u32 {
+        let width = self
+            .padding
+            .inner_width(box_width)
+            .saturating_sub(marker_width);
+
+        if width > MAX_TEXT_WIDTH {
+            MAX_TEXT_WIDTH
+        } else {
+            width
+        }
+    }
+
+    /// Returns the width of the column the text is laid out in, in a text box of the given width.
+    ///
+    /// With [`LineBreak::Balanced`], this is the narrowest width at which the text has as many
+    /// lines as in the full width, given by `line_count`.
+    pub(crate) fn balanced_width(
+        &self,
+        box_width: u32,
+        mut line_count: impl FnMut(u32) -> u32,
+    ) -> u32 {
+        if self.line_break != LineBreak::Balanced || self.horizontal_scroll.is_some() {
+            return box_width;
+        }
+
+        let lines = line_count(box_width);
+        if !(2..=LineBreak::MAX_BALANCED_LINES).contains(&lines) {
+            return box_width;
+        }
+
+        // The narrowest column is at least 1/lines of the text area wide.
+        let inner_width = self.padding.inner_width(box_width);
+        let mut narrowest = box_width - inner_width + inner_width / lines;
+        let mut widest = box_width;
+        while narrowest < widest {
+            let width = narrowest + (widest - narrowest) / 2;
+            if line_count(width) <= lines {
+                widest = width;
+            } else {
+                narrowest = width + 1;
+            }
+        }
+
+        widest
+    }
+
+    /// Returns whether the line with the given index is after the last line allowed by
+    /// `max_lines`.
+    pub(crate) fn is_past_max_lines(&self, line_index: u32) -> bool {
+        self.max_lines
+            .map_or(false, |max_lines| line_index >= max_lines.get())
+    }
+
+    /// Returns the width of the list marker.
+    pub(crate) fn list_marker_width(&self, measure: impl FnOnce(&str) -> u32) -> u32 {
+        self.list_marker.map_or(0, measure)
+    }
+
+    /// Returns the width used to lay out a line that fills the given width.
+    pub(crate) const fn line_layout_width(&self, line_width: u32) -> u32 {
+        match self.horizontal_scroll {
+            Some(_) => UNBOUNDED_LINE_WIDTH,
+            None => line_width,
+        }
+    }
+
+    /// Returns the area of the text box that the text is rendered into.
+    ///
+    /// The list marker is drawn in the `marker_width` wide gutter left of this area.
+    pub(crate) fn text_area(&self, bounds: Rectangle, marker_width: u32) -> Rectangle {
+        // The text area doesn't start outside of the text box, even if the padding is larger.
+        let left = self
+            .padding
+            .left
+            .saturating_add(marker_width)
+            .min(bounds.size.width);
+        let top = self.padding.top.min(bounds.size.height);
+
+        Rectangle::new(
+            Point::new(
+                bounds.top_left.x.saturating_add(left.saturating_as()),
+                bounds.top_left.y.saturating_add(top.saturating_as()),
+            ),
+            Size::new(
+                self.text_width(bounds.size.width, marker_width),
+                self.padding.inner_height(bounds.size.height),
+            ),
+        )
+    }
+
+    /// Creates a parser that respects the maximum word length setting.
+    pub(crate) fn parser<'a, C: PixelColor>(&self, text: &'a str) -> Parser<'a, C> {
+        let spaces_only = self.line_break == LineBreak::SpacesOnly;
+        let parser = match self.max_word_length {
+            Some(length) if !spaces_only => Parser::parse_max_word_len(text, length as usize),
+            _ => Parser::parse(text),
+        };
+
+        let parser = parser
+            .with_config(self.parser_config)
+            .break_at_soft_hyphens(!spaces_only);
+
+        match self.max_consecutive_newlines {
+            Some(max) => parser.max_consecutive_newlines(max as usize),
+            None => parser,
+        }
+    }
+}
+
+/// Ratio used to convert pixel values between two character styles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Scale {
+    from: u32,
+    to: u32,
+}
+
+impl Scale {
+    /// Scale that keeps every value unchanged.
+    pub const IDENTITY: Self = Self::new(1, 1);
+
+    pub const fn new(from: u32, to: u32) -> Self {
+        Self { from, to }
+    }
+
+    /// Returns the scale that applies `self`, then `next`.
+    pub fn then(self, next: Self) -> Self {
+        if next.from == 0 {
+            self
+        } else if self.from == 0 {
+            next
+        } else {
+            Self::new(
+                self.from.saturating_mul(next.from),
+                self.to.saturating_mul(next.to),
+            )
+        }
+    }
+
+    /// Scales a pixel value, rounding to the nearest integer.
+    pub fn apply(self, value: u32) -> u32 {
+        if self.from == 0 {
+            return value;
+        }
+
+        let from = self.from as u64;
+        let scaled = (value as u64 * self.to as u64 + from / 2) / from;
+
+        scaled.min(u32::MAX as u64) as u32
+    }
+}
+
 /// Information about a line.
 #[derive(Debug, Copy, Clone)]
 #[must_use]
@@ -337,6 +1135,9 @@ pub(crate) struct LineMeasurement {
 
     /// Number of spaces in the current line.
     pub space_count: u32,
+
+    /// Whether the line contains a tab character.
+    pub has_tab: bool,
 }
 
 impl LineMeasurement {
@@ -354,7 +1155,12 @@ impl LineMeasurement {
 
 struct MeasureLineElementHandler<'a, S> {
     style: &'a S,
+    kerning: Option<fn(char, char) -> i8>,
     trailing_spaces: bool,
+    justify_nbsp: bool,
+    justify_tabbed_lines: bool,
+    extra_space_chars: &'static [char],
+    has_tab: bool,
     cursor: u32,
     pos: u32,
     right: u32,
@@ -364,7 +1170,10 @@ struct MeasureLineElementHandler<'a, S> {
 
 impl<'a, S> MeasureLineElementHandler<'a, S> {
     fn space_count(&self) -> u32 {
-        if self.trailing_spaces {
+        if self.has_tab && !self.justify_tabbed_lines {
+            // Without stretchable spaces, the line is rendered left aligned.
+            0
+        } else if self.trailing_spaces {
             self.partial_space_count
         } else {
             self.space_count
@@ -385,19 +1194,29 @@ impl<'a, S: TextRenderer> ElementHandler for MeasureLineElementHandler<'a, S> {
     type Color = S::Color;
 
     fn measure(&self, st: &str) -> u32 {
-        str_width(self.style, st)
+        str_width_kerned(self.style, st, self.kerning)
     }
 
-    fn whitespace(&mut self, _st: &str, count: u32, width: u32) -> Result<(), Self::Error> {
-        self.cursor += width;
+    fn whitespace(&mut self, st: &str, count: u32, width: u32) -> Result<(), Self::Error> {
+        self.cursor = self.cursor.saturating_add(width);
         self.pos = self.pos.max(self.cursor);
-        self.partial_space_count += count;
+        self.has_tab |= st == "\t";
+        // Extra space characters are not stretched, so they don't count as spaces.
+        let extra_space = st
+            .chars()
+            .next()
+            .map_or(false, |c| self.extra_space_chars.contains(&c));
+        if (self.justify_nbsp || st != "\u{a0}") && !extra_space {
+            self.partial_space_count = self.partial_space_count.saturating_add(count);
+        }
 
         Ok(())
     }
 
     fn printed_characters(&mut self, str: &str, width: Option<u32>) -> Result<(), Self::Error> {
-        self.cursor += width.unwrap_or_else(|| self.measure(str));
+        self.cursor = self
+            .cursor
+            .saturating_add(width.unwrap_or_else(|| self.measure(str)));
         self.pos = self.pos.max(self.cursor);
         self.right = self.pos;
         self.space_count = self.partial_space_count;
@@ -406,7 +1225,11 @@ impl<'a, S: TextRenderer> ElementHandler for MeasureLineElementHandler<'a, S> {
     }
 
     fn move_cursor(&mut self, by: i32) -> Result<(), Self::Error> {
-        self.cursor = (self.cursor as i32 + by) as u32;
+        self.cursor = if by < 0 {
+            self.cursor.saturating_sub(by.unsigned_abs())
+        } else {
+            self.cursor.saturating_add(by as u32)
+        };
 
         Ok(())
     }
@@ -433,19 +1256,51 @@ impl TextBoxStyle {
         M: Plugin<'a, S::Color>,
         S::Color: From<Rgb888>,
     {
-        let cursor = LineCursor::new(max_line_width, self.tab_size.into_pixels(character_style));
-
-        let mut iter = LineElementParser::new(
-            parser,
-            plugin,
-            cursor,
-            SpaceConfig::new(str_width(character_style, " "), None),
-            self,
-        );
-
-        let mut handler = MeasureLineElementHandler {
-            style: character_style,
-            trailing_spaces: self.trailing_spaces,
+        self.measure_line_impl(plugin, character_style, parser, max_line_width, false, 0)
+    }
+
+    /// Measures a line, optionally wrapping words that don't fit instead of breaking them.
+    ///
+    /// The line starts `start_x` pixels from its left edge. If `start_x` is not zero, the line is
+    /// not considered empty, so a word that doesn't fit is wrapped instead of broken.
+    pub(crate) fn measure_line_impl<'a, S, M>(
+        &self,
+        plugin: &PluginWrapper<'a, M, S::Color>,
+        character_style: &S,
+        parser: &mut Parser<'a, S::Color>,
+        max_line_width: u32,
+        avoid_broken_word: bool,
+        start_x: u32,
+    ) -> LineMeasurement
+    where
+        S: TextRenderer,
+        M: Plugin<'a, S::Color>,
+        S::Color: From<Rgb888>,
+    {
+        let mut cursor =
+            LineCursor::new(max_line_width, self.tab_size.into_pixels(character_style));
+        if start_x > 0 {
+            cursor.move_cursor_forward(start_x.min(max_line_width)).ok();
+        }
+
+        let mut iter = LineElementParser::new(
+            parser,
+            plugin,
+            cursor,
+            SpaceConfig::new(str_width(character_style, " ")),
+            self,
+        );
+        iter.avoid_broken_word = avoid_broken_word;
+        iter.empty = start_x == 0;
+
+        let mut handler = MeasureLineElementHandler {
+            style: character_style,
+            kerning: self.kerning,
+            trailing_spaces: self.trailing_spaces,
+            justify_nbsp: self.justify_nbsp,
+            justify_tabbed_lines: self.justify_tabbed_lines,
+            extra_space_chars: self.parser_config.extra_space_chars,
+            has_tab: false,
 
             cursor: 0,
             pos: 0,
@@ -453,18 +1308,164 @@ impl TextBoxStyle {
             partial_space_count: 0,
             space_count: 0,
         };
-        let last_token = iter.process(&mut handler).unwrap();
+        let last_token = iter.process_infallible(&mut handler);
 
         LineMeasurement {
             max_line_width,
-            width: handler.right(),
+            // Hanging punctuation doesn't count toward the width of the line.
+            width: handler.right().saturating_sub(iter.hang),
             space_count: handler.space_count(),
             line_end_type: last_token,
+            has_tab: handler.has_tab,
+        }
+    }
+
+    /// Lays out text and passes the resulting elements to a custom [`ElementHandler`].
+    ///
+    /// The text is broken into lines of at most `max_width` pixels, using the same rules as the
+    /// [`TextBox`] does. The handler measures the text, receives the elements of each line, and
+    /// an [`end_line`] event at the end of every line. The elements are positioned as in a left
+    /// aligned text box; vertical layout is left to the handler.
+    ///
+    /// This function can be used to implement custom measurements or to export text to other
+    /// formats.
+    ///
+    /// [`TextBox`]: crate::TextBox
+    /// [`end_line`]: ElementHandler::end_line
+    ///
+    /// # Example: counting lines
+    ///
+    /// ```rust
+    /// # use core::convert::Infallible;
+    /// # use embedded_graphics::pixelcolor::BinaryColor;
+    /// use embedded_text::{style::TextBoxStyle, ElementHandler, LineEndType};
+    ///
+    /// struct LineCounter(u32);
+    ///
+    /// impl ElementHandler for LineCounter {
+    ///     type Error = Infallible;
+    ///     type Color = BinaryColor;
+    ///
+    ///     fn measure(&self, st: &str) -> u32 {
+    ///         st.chars().count() as u32
+    ///     }
+    ///
+    ///     fn end_line(&mut self, _end: LineEndType) -> Result<(), Self::Error> {
+    ///         self.0 += 1;
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut counter = LineCounter(0);
+    /// TextBoxStyle::default()
+    ///     .process_elements("Hello, World!", 7, &mut counter)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(counter.0, 2);
+    /// ```
+    #[inline]
+    pub fn process_elements<E>(
+        &self,
+        text: &str,
+        max_width: u32,
+        handler: &mut E,
+    ) -> Result<(), E::Error>
+    where
+        E: ElementHandler,
+        E::Color: From<Rgb888>,
+    {
+        self.process_elements_impl(
+            PluginWrapper::new(NoPlugin::new()),
+            text,
+            max_width,
+            handler,
+        )
+    }
+
+    pub(crate) fn process_elements_impl<'a, M, E>(
+        &self,
+        plugin: PluginWrapper<'a, M, E::Color>,
+        text: &'a str,
+        max_width: u32,
+        handler: &mut E,
+    ) -> Result<(), E::Error>
+    where
+        E: ElementHandler,
+        E::Color: From<Rgb888>,
+        M: Plugin<'a, E::Color>,
+    {
+        let marker_width = self.list_marker_width(|m| handler.measure(m));
+        let box_width = self.balanced_width(max_width, |width| {
+            let mut counter = LineCounter {
+                handler: &*handler,
+                lines: 0,
+            };
+            self.process_lines(
+                plugin.clone(),
+                text,
+                self.layout_width(width, marker_width),
+                &mut counter,
+            )
+            .unwrap_or_else(|e| match e {});
+
+            counter.lines
+        });
+
+        self.process_lines(
+            plugin,
+            text,
+            self.layout_width(box_width, marker_width),
+            handler,
+        )
+    }
+
+    /// Lays out the text in lines of the given width, and reports the elements to `handler`.
+    fn process_lines<'a, M, E>(
+        &self,
+        plugin: PluginWrapper<'a, M, E::Color>,
+        text: &'a str,
+        max_width: u32,
+        handler: &mut E,
+    ) -> Result<(), E::Error>
+    where
+        E: ElementHandler,
+        E::Color: From<Rgb888>,
+        M: Plugin<'a, E::Color>,
+    {
+        plugin.set_state(ProcessingState::Measure);
+
+        let space_width = handler.measure(" ");
+        let tab_width = match self.tab_size {
+            TabSize::Pixels(px) => px as u32,
+            TabSize::Spaces(n) => n as u32 * space_width,
+        };
+
+        let mut parser = self.parser(text);
+        loop {
+            plugin.new_line();
+
+            let mut iter = LineElementParser::new(
+                &mut parser,
+                &plugin,
+                LineCursor::new(max_width, tab_width),
+                SpaceConfig::new(space_width),
+                self,
+            );
+            let end = iter.process(handler)?;
+
+            handler.end_line(end)?;
+            if end == LineEndType::EndOfText {
+                return Ok(());
+            }
         }
     }
 
     /// Measures text height when rendered using a given width.
     ///
+    /// The height of `n` lines is `(n - 1) * line_height + font_height`, plus the paragraph
+    /// spacing. The leading below the last line is only included if
+    /// [`include_trailing_leading`](TextBoxStyle::include_trailing_leading) is set.
+    ///
     /// # Example: measure height of text when rendered using a 6x9 MonoFont and 72px width.
     ///
     /// ```rust
@@ -509,266 +1510,2156 @@ impl TextBoxStyle {
         self.measure_text_height_impl(plugin, character_style, text, max_width)
     }
 
-    pub(crate) fn measure_text_height_impl<'a, S, M>(
+    /// Measures the size of the text when it is wrapped to the given width.
+    ///
+    /// Returns the width of the widest line and the height of the text in a single pass. The
+    /// height is the same as the one returned by [`measure_text_height`]. Spaces that are not
+    /// rendered at the end of a line are not included in the width. Like the height, the width
+    /// doesn't include the padding and the list marker.
+    ///
+    /// [`measure_text_height`]: Self::measure_text_height
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// #     prelude::*,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// // "Hello " / "World"
+    /// let size = style.measure_size(&character_style, "Hello World", 60);
+    /// assert_eq!(size, Size::new(5 * 6, 2 * 9));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn measure_size<S>(&self, character_style: &S, text: &str, max_width: u32) -> Size
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let measured = self.measure_text_limited(
+            PluginWrapper::new(NoPlugin::new()),
+            character_style,
+            text,
+            max_width,
+            u32::MAX,
+            0,
+        );
+
+        Size::new(measured.width, measured.height)
+    }
+
+    /// Measures the height of text that starts after other content on its first line.
+    ///
+    /// Works like [`measure_text_height`], but the first line starts `start_x` pixels from the
+    /// left edge, so it has less room than the following lines. A word that doesn't fit into the
+    /// rest of the first line is wrapped to the second line. The first line is always included in
+    /// the height, even if it has no room for any text.
+    ///
+    /// This can be used to lay out text that continues after an inline element.
+    ///
+    /// [`measure_text_height`]: Self::measure_text_height
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// assert_eq!(style.measure_text_height_from(&character_style, "Hello", 60, 0), 9);
+    ///
+    /// // Only 4 characters fit after the inline element, so the word moves to the next line.
+    /// assert_eq!(style.measure_text_height_from(&character_style, "Hello", 60, 36), 18);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn measure_text_height_from<S>(
         &self,
-        plugin: PluginWrapper<'a, M, S::Color>,
         character_style: &S,
-        text: &'a str,
+        text: &str,
         max_width: u32,
+        start_x: u32,
     ) -> u32
     where
         S: TextRenderer,
-        M: Plugin<'a, S::Color>,
         S::Color: From<Rgb888>,
     {
-        let mut parser = Parser::parse(text);
-        let base_line_height = character_style.line_height();
-        let line_height = self.line_height.to_absolute(base_line_height);
-        let mut height = base_line_height;
-
-        plugin.set_state(ProcessingState::Measure);
+        self.measure_text_height_limited(
+            PluginWrapper::new(NoPlugin::new()),
+            character_style,
+            text,
+            max_width,
+            u32::MAX,
+            start_x,
+        )
+    }
 
-        let mut prev_end = LineEndType::EndOfText;
+    /// Measures the height of the text, and reserves room for one more line after it.
+    ///
+    /// The returned height is the height of the text as if it ended with a newline character.
+    /// This can be used to size input fields, so that a new line can be started without
+    /// resizing the field.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// assert_eq!(style.measure_text_reserve_line(&character_style, "Hello", 60), 18);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn measure_text_reserve_line<S>(
+        &self,
+        character_style: &S,
+        text: &str,
+        max_width: u32,
+    ) -> u32
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let measured = self.measure_text_limited(
+            PluginWrapper::new(NoPlugin::new()),
+            character_style,
+            text,
+            max_width,
+            u32::MAX,
+            0,
+        );
+        let line_height = self.line_height_at(character_style.line_height(), measured.lines - 1);
 
-        loop {
-            plugin.new_line();
-            let lm = self.measure_line(&plugin, character_style, &mut parser, max_width);
+        measured
+            .height
+            .saturating_add(line_height)
+            .saturating_add(self.paragraph_spacing)
+    }
 
-            if prev_end == LineEndType::LineBreak && !lm.is_empty() {
-                height += line_height;
+    /// Returns the index of the last line that starts at most `y` pixels below the top of the
+    /// text.
+    #[cfg(feature = "widget")]
+    pub(crate) fn line_index_at<S>(
+        &self,
+        character_style: &S,
+        text: &str,
+        max_width: u32,
+        y: u32,
+    ) -> u32
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let mut lines: u32 = 0;
+        let mut prev_end = None;
+        for line in LineWalker::new(self, character_style, text, max_width) {
+            if starts_line(prev_end, &line) {
+                if line.y > y {
+                    break;
+                }
+                lines += 1;
             }
+            prev_end = Some(line.end_type);
+        }
+
+        lines.saturating_sub(1)
+    }
 
-            match lm.line_end_type {
-                LineEndType::CarriageReturn | LineEndType::LineBreak => {}
-                LineEndType::NewLine => height += line_height + self.paragraph_spacing,
-                LineEndType::EndOfText => return height,
+    /// Returns the byte range of the `index`th line of the text.
+    ///
+    /// The text is laid out only up to the requested line, so this can be used to seek into long
+    /// texts. The range starts at the first byte of the line and ends where the next line starts,
+    /// so it includes the whitespace the line was wrapped at. Returns `None` if the text has fewer
+    /// lines.
+    ///
+    /// Lines are counted the same way as in [`TextBox::line_count`] and [`clip_at_height`]: text
+    /// after a carriage return overwrites the current line, so it is part of the same range.
+    ///
+    /// [`TextBox::line_count`]: crate::TextBox::line_count
+    /// [`clip_at_height`]: Self::clip_at_height
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// let text = "Hello, World!";
+    /// assert_eq!(style.line_at(&character_style, text, 60, 1), Some(7..13));
+    /// assert_eq!(style.line_at(&character_style, text, 60, 2), None);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn line_at<S>(
+        &self,
+        character_style: &S,
+        text: &str,
+        max_width: u32,
+        index: usize,
+    ) -> Option<Range<usize>>
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let mut lines = 0;
+        let mut prev_end = None;
+        let mut start = None;
+        for line in LineWalker::new(self, character_style, text, max_width) {
+            if starts_line(prev_end, &line) {
+                if let Some(start) = start {
+                    return Some(start..line.offset);
+                }
+                if lines == index {
+                    start = Some(line.offset);
+                }
+                lines += 1;
             }
-            prev_end = lm.line_end_type;
+            prev_end = Some(line.end_type);
         }
+
+        start.map(|start| start..text.len())
     }
-}
 
-#[cfg(test)]
-mod test {
-    use crate::{
-        alignment::*,
-        parser::Parser,
-        plugin::{NoPlugin, PluginWrapper},
-        style::{builder::TextBoxStyleBuilder, TextBoxStyle},
-    };
-    use embedded_graphics::{
-        mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder},
-        pixelcolor::BinaryColor,
-        text::{renderer::TextRenderer, LineHeight},
-    };
+    /// Returns the number of lines that fit into `max_height`, and the byte offset of the first
+    /// character that doesn't fit.
+    ///
+    /// The text is laid out only up to the first line that doesn't fit, so this can be used to cut
+    /// a long text into pages, or to show a teaser of it. The rest of the text can be displayed
+    /// starting at the returned offset. If the whole text fits, the offset is the length of the
+    /// text. Lines after a carriage return are not counted, and [`max_lines`] is ignored.
+    ///
+    /// [`max_lines`]: Self::max_lines
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// let text = "Hello, World!";
+    /// assert_eq!(style.clip_at_height(&character_style, text, 60, 9), (1, 7));
+    /// assert_eq!(&text[7..], "World!");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn clip_at_height<S>(
+        &self,
+        character_style: &S,
+        text: &str,
+        max_width: u32,
+        max_height: u32,
+    ) -> (u32, usize)
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let base_line_height = character_style.line_height();
 
-    #[test]
-    fn no_infinite_loop() {
-        let character_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X9)
-            .text_color(BinaryColor::On)
-            .build();
+        let mut lines = 0;
+        let mut prev_end = None;
+        for line in LineWalker::new(self, character_style, text, max_width) {
+            if starts_line(prev_end, &line) {
+                // Like in `measure_text_height`, the leading of the last line is only included if
+                // `include_trailing_leading` is set.
+                let bottom = if self.include_trailing_leading {
+                    self.line_height_at(base_line_height, lines)
+                        .max(base_line_height)
+                } else {
+                    base_line_height
+                };
+                if line.y.saturating_add(bottom) > max_height {
+                    return (lines, line.offset);
+                }
+
+                lines += 1;
+            }
+            prev_end = Some(line.end_type);
+        }
 
-        let _ = TextBoxStyleBuilder::new()
-            .build()
-            .measure_text_height(&character_style, "a", 5);
+        (lines, text.len())
     }
 
-    #[test]
-    fn test_measure_height() {
-        let data = [
-            // (text; max width in characters; number of expected lines)
-            ("", 0, 1),
-            (" ", 6, 1),
-            ("\r", 6, 1),
-            ("\n", 6, 2),
-            ("\n ", 6, 2),
-            ("word", 4 * 6, 1),   // exact fit into 1 line
-            ("word\n", 4 * 6, 2), // newline
-            ("word", 4 * 6 - 1, 2),
-            ("word", 2 * 6, 2),      // exact fit into 2 lines
-            ("word word", 4 * 6, 2), // exact fit into 2 lines
-            ("word\n", 2 * 6, 3),
-            ("word\nnext", 50, 2),
-            ("word\n\nnext", 50, 3),
-            ("word\n  \nnext", 50, 3),
-            ("verylongword", 50, 2),
-            ("some verylongword", 50, 3),
-            ("1 23456 12345 61234 561", 36, 5),
-            ("    Word      ", 36, 2),
-            ("\rcr", 36, 1),
-            ("cr\r", 36, 1),
-            ("cr\rcr", 36, 1),
-            ("Longer\r", 36, 1),
-            ("Longer\rnowrap", 36, 1),
-        ];
-
-        let character_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X9)
-            .text_color(BinaryColor::On)
-            .build();
+    /// Returns the line height of the `line`th line of the text.
+    pub(crate) fn line_height_at(&self, base_line_height: u32, line: u32) -> u32 {
+        match self.line_heights {
+            Some(line_heights) => line_heights(line),
+            None => line_height_pixels(self.line_height, base_line_height),
+        }
+    }
+
+    /// Returns whether the text fits into the given size.
+    ///
+    /// Unlike comparing the result of [`measure_text_height`] to the height, this function stops
+    /// processing the text as soon as its height exceeds `size.height`.
+    ///
+    /// [`measure_text_height`]: Self::measure_text_height
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// #     prelude::*,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// assert!(style.fits_in(&character_style, "Hello, World!", Size::new(48, 18)));
+    /// assert!(!style.fits_in(&character_style, "Hello, World!", Size::new(48, 9)));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn fits_in<S>(&self, character_style: &S, text: &str, size: Size) -> bool
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let max_height = self.padding.inner_height(size.height);
+        let height = self.measure_text_height_limited(
+            PluginWrapper::new(NoPlugin::new()),
+            character_style,
+            text,
+            size.width,
+            max_height,
+            0,
+        );
+
+        height <= max_height
+    }
+
+    /// Returns a copy of this style adapted to a different character style.
+    ///
+    /// Options that are given in pixels are scaled so that they keep their size relative to the
+    /// text. Horizontal options are scaled by the ratio of the width of a space character, vertical
+    /// options by the ratio of the line heights of the two character styles. Options given in
+    /// relative units, like [`LineHeight::Percent`] or [`TabSize::Spaces`], are kept unchanged.
+    ///
+    /// The widths of the grid [`columns`] are scaled when the text is laid out, the column
+    /// definitions are not modified. The [`kerning`] function is not modified.
+    ///
+    /// [`columns`]: Self::columns
+    /// [`kerning`]: Self::kerning
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::{TabSize, TextBoxStyleBuilder};
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::{FONT_10X20, FONT_6X10}, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let small = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    /// let large = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+    ///
+    /// let style = TextBoxStyleBuilder::new()
+    ///     .paragraph_spacing(5)
+    ///     .tab_size(TabSize::Pixels(24))
+    ///     .build();
+    ///
+    /// let rescaled = style.rescaled(&small, &large);
+    ///
+    /// assert_eq!(rescaled.paragraph_spacing, 10);
+    /// assert_eq!(rescaled.tab_size, TabSize::Pixels(40));
+    /// ```
+    #[inline]
+    pub fn rescaled<S1, S2>(&self, from: &S1, to: &S2) -> TextBoxStyle
+    where
+        S1: TextRenderer,
+        S2: TextRenderer,
+    {
+        let horizontal = Scale::new(str_width(from, " "), str_width(to, " "));
+        let vertical = Scale::new(from.line_height(), to.line_height());
+
+        let mut style = *self;
+
+        if let LineHeight::Pixels(pixels) = style.line_height {
+            style.line_height = LineHeight::Pixels(vertical.apply(pixels));
+        }
+        style.paragraph_spacing = vertical.apply(style.paragraph_spacing);
+
+        if let TabSize::Pixels(pixels) = style.tab_size {
+            let pixels = horizontal.apply(pixels as u32).min(u16::MAX as u32) as u16;
+            style.tab_size = TabSize::Pixels(pixels);
+        }
+        style.reserve_caret_width = horizontal.apply(style.reserve_caret_width);
+        style.horizontal_scroll = style
+            .horizontal_scroll
+            .map(|scroll| horizontal.apply(scroll));
+        if style.columns.is_some() {
+            style.column_scale = style.column_scale.then(horizontal);
+        }
+
+        style.padding = Padding {
+            left: horizontal.apply(style.padding.left),
+            top: vertical.apply(style.padding.top),
+            right: horizontal.apply(style.padding.right),
+            bottom: vertical.apply(style.padding.bottom),
+        };
+
+        style
+    }
+
+    /// Returns where a word would be broken at a soft hyphen, if at all.
+    ///
+    /// The word is placed at the start of a line that has `width` pixels of available space. If
+    /// the line would end at one of the soft hyphen (`\u{AD}`) characters of the word, this
+    /// function returns the byte index of that soft hyphen. Returns `None` if the word fits into
+    /// the available space, or if it would be broken in some other way, e.g. because the part
+    /// before the first soft hyphen doesn't fit.
+    ///
+    /// Note that the break may be taken even if the hyphen character itself does not fit. In this
+    /// case, the hyphen is displayed at the start of the next line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// // "sam-" fits into 5 characters, but "sample" doesn't.
+    /// assert_eq!(style.soft_hyphen_break(&character_style, "sam\u{AD}ple", 30), Some(3));
+    ///
+    /// // "sample" fits into 6 characters.
+    /// assert_eq!(style.soft_hyphen_break(&character_style, "sam\u{AD}ple", 36), None);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn soft_hyphen_break<S>(&self, character_style: &S, word: &str, width: u32) -> Option<usize>
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let plugin = PluginWrapper::new(NoPlugin::new());
+        plugin.set_state(ProcessingState::Measure);
+
+        let mut parser = Parser::parse(word);
+        let lm = self.measure_line(&plugin, character_style, &mut parser, width);
+
+        if lm.line_end_type != LineEndType::LineBreak {
+            return None;
+        }
+
+        // The parser is positioned after the last token that was processed or peeked. A line
+        // broken at a soft hyphen always stops right after the soft hyphen character.
+        let consumed = &word[..word.len() - parser.as_str().len()];
+
+        consumed
+            .strip_suffix(SPEC_CHAR_SHY)
+            .map(|before| before.len())
+    }
+
+    /// Measures the height of the first `n` paragraphs of the text when rendered using a given
+    /// width.
+    ///
+    /// Paragraphs are separated by one or more blank lines. A line is blank if it contains only
+    /// whitespace characters. Returns the height of the paragraphs and the byte offset in `text`
+    /// where the `n`th paragraph ends, not including the newline that ends it.
+    ///
+    /// # Example: measure a teaser of the first two paragraphs.
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// let text = "First paragraph.\n\nSecond paragraph.\n\nThird paragraph.";
+    /// let (height, end) = style.measure_paragraphs(&character_style, text, 120, 2);
+    ///
+    /// assert_eq!(&text[..end], "First paragraph.\n\nSecond paragraph.");
+    /// assert_eq!(height, 3 * 9);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn measure_paragraphs<S>(
+        &self,
+        character_style: &S,
+        text: &str,
+        max_width: u32,
+        n: usize,
+    ) -> (u32, usize)
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        if n == 0 {
+            return (0, 0);
+        }
+
+        let end = paragraphs_end(text, n, self.parser_config);
+        let height = self.measure_text_height(character_style, &text[..end], max_width);
+
+        (height, end)
+    }
+
+    /// Measures every paragraph of the text when rendered using a given width.
+    ///
+    /// Unlike [`measure_paragraphs`], which looks for blank lines, this function uses the same
+    /// definition of paragraphs as the text box: every newline character ends a paragraph. The
+    /// paragraphs are wrapped the same way as in the text box, and the newline characters are not
+    /// included in the text of the paragraphs.
+    ///
+    /// An empty paragraph, for example between two consecutive newlines, is rendered as a single
+    /// empty line, so it is reported as one line that is as high as the font. A text that ends in a
+    /// newline ends with an empty paragraph.
+    ///
+    /// The text is laid out once, like in the text box, so [`line_heights`] is indexed from the
+    /// start of the text, [`max_lines`] limits the lines of the whole text, and newlines dropped
+    /// by [`max_consecutive_newlines`] don't start new paragraphs. The height of a paragraph
+    /// doesn't include the [`paragraph_spacing`], and the leading below its last line is only
+    /// included if [`include_trailing_leading`] is set.
+    ///
+    /// [`measure_paragraphs`]: Self::measure_paragraphs
+    /// [`paragraph_spacing`]: Self::paragraph_spacing
+    /// [`line_heights`]: Self::line_heights
+    /// [`max_lines`]: Self::max_lines
+    /// [`max_consecutive_newlines`]: Self::max_consecutive_newlines
+    /// [`include_trailing_leading`]: Self::include_trailing_leading
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// let text = "Hi!\n\nHow are you doing?";
+    /// let mut paragraphs = style.paragraphs(&character_style, text, 60);
+    ///
+    /// let first = paragraphs.next().unwrap();
+    /// assert_eq!((first.text, first.line_count, first.height), ("Hi!", 1, 9));
+    ///
+    /// let empty = paragraphs.next().unwrap();
+    /// assert_eq!((empty.text, empty.line_count, empty.height), ("", 1, 9));
+    ///
+    /// let last = paragraphs.next().unwrap();
+    /// assert_eq!((last.text, last.line_count, last.height), ("How are you doing?", 2, 18));
+    ///
+    /// assert!(paragraphs.next().is_none());
+    /// ```
+    #[inline]
+    pub fn paragraphs<'a, S>(
+        &'a self,
+        character_style: &'a S,
+        text: &'a str,
+        max_width: u32,
+    ) -> impl Iterator<Item = ParagraphMetrics<'a>> + 'a
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let base_line_height = character_style.line_height();
+        let max_lines = match self.max_lines {
+            Some(max_lines) => max_lines.get(),
+            None => u32::MAX,
+        };
+
+        let mut lines = LineWalker::new(self, character_style, text, max_width).peekable();
+        let mut line_count = 0;
+        let mut prev_end = None;
+        let mut done = false;
+
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            let start = lines.peek()?.offset;
+            let mut end = text.len();
+            let mut first = None;
+            let mut last = (0, 0);
+            let mut count = 0;
+            while let Some(line) = lines.next() {
+                if starts_line(prev_end, &line) {
+                    if line_count == max_lines {
+                        done = true;
+                        end = line.offset;
+                        break;
+                    }
+                    first.get_or_insert(line.y);
+                    last = (line.y, line_count);
+                    line_count += 1;
+                    count += 1;
+                }
+                prev_end = Some(line.end_type);
+
+                match line.end_type {
+                    LineEndType::NewLine => {
+                        end = lines.peek().map_or(text.len(), |next| next.offset);
+                        break;
+                    }
+                    LineEndType::EndOfText => done = true,
+                    _ => {}
+                }
+            }
+
+            let first = first?;
+            let (last_y, last_index) = last;
+            let bottom = if self.include_trailing_leading {
+                self.line_height_at(base_line_height, last_index)
+                    .max(base_line_height)
+            } else {
+                base_line_height
+            };
+
+            Some(ParagraphMetrics {
+                // Newlines dropped by `max_consecutive_newlines` are skipped at the start of the
+                // next line.
+                text: text[start..end].trim_matches('\n'),
+                height: last_y.saturating_add(bottom).saturating_sub(first),
+                line_count: count,
+            })
+        })
+    }
+
+    /// Returns the width of the widest line of the text, if lines are only broken at newlines.
+    ///
+    /// The returned value is the smallest width of a text box that displays the text without
+    /// wrapping, including the padding and the list marker.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// # };
+    /// #
+    /// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    /// let style = TextBoxStyle::default();
+    ///
+    /// assert_eq!(style.natural_width(&character_style, "ab\ncdef"), 4 * 6);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn natural_width<S>(&self, character_style: &S, text: &str) -> u32
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let plugin = PluginWrapper::new(NoPlugin::new());
+        plugin.set_state(ProcessingState::Measure);
+
+        let mut parser = self.parser(text);
+        let mut width = 0;
+        loop {
+            plugin.new_line();
+            let lm = self.measure_line(&plugin, character_style, &mut parser, UNBOUNDED_LINE_WIDTH);
+            width = width.max(lm.width);
+
+            if lm.line_end_type == LineEndType::EndOfText {
+                break;
+            }
+        }
+
+        width
+            .saturating_add(self.list_marker_width(|m| str_width(character_style, m)))
+            .saturating_add(self.padding.left)
+            .saturating_add(self.padding.right)
+    }
+
+    /// Selects the first character style that fits the text into the given bounds.
+    ///
+    /// The text is measured using each candidate character style in order. Returns the index of
+    /// the first candidate whose text height fits into the height of `bounds`, together with the
+    /// size of the text when rendered using that candidate. If none of the candidates fit, the
+    /// last one is returned. Returns `None` if there are no candidates.
+    ///
+    /// The returned width is the width of the widest line, including the padding and the list
+    /// marker. The text is parsed once, and measuring a candidate stops as soon as the text is
+    /// known to overflow `bounds`, so rejected candidates only process the part of the text that
+    /// fits.
+    ///
+    /// # Example: use a larger font if the text fits.
+    ///
+    /// ```rust
+    /// # use embedded_text::style::TextBoxStyle;
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::{FONT_6X10, FONT_9X15}, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// #     prelude::*,
+    /// #     primitives::Rectangle,
+    /// # };
+    /// #
+    /// let candidates = [
+    ///     MonoTextStyle::new(&FONT_9X15, BinaryColor::On),
+    ///     MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+    /// ];
+    /// let bounds = Rectangle::new(Point::zero(), Size::new(72, 20));
+    ///
+    /// let style = TextBoxStyle::default();
+    ///
+    /// let fitting = style.first_fitting(candidates, "Short", bounds);
+    /// assert_eq!(fitting, Some((0, Size::new(45, 15))));
+    ///
+    /// let fitting = style.first_fitting(candidates, "A bit longer text", bounds);
+    /// assert_eq!(fitting, Some((1, Size::new(72, 20))));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn first_fitting<S, const N: usize>(
+        &self,
+        candidates: [S; N],
+        text: &str,
+        bounds: Rectangle,
+    ) -> Option<(usize, Size)>
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let max_width = bounds.size.width;
+        let max_height = self.padding.inner_height(bounds.size.height);
+        let parser = self.parser(text);
+
+        let mut result = None;
+        for (index, character_style) in candidates.iter().enumerate() {
+            let last = index + 1 == N;
+            let limit = if last { u32::MAX } else { max_height };
+
+            let measured = self.measure_parsed_limited(
+                PluginWrapper::new(NoPlugin::new()),
+                character_style,
+                parser.clone(),
+                max_width,
+                limit,
+                0,
+            );
+
+            let width = measured
+                .width
+                .saturating_add(self.list_marker_width(|m| str_width(character_style, m)))
+                .saturating_add(self.padding.left)
+                .saturating_add(self.padding.right);
+            result = Some((
+                index,
+                Size::new(width, self.padding.outer_height(measured.height)),
+            ));
+            if measured.height <= max_height {
+                break;
+            }
+        }
+
+        result
+    }
+
+    pub(crate) fn measure_text_height_impl<'a, S, M>(
+        &self,
+        plugin: PluginWrapper<'a, M, S::Color>,
+        character_style: &S,
+        text: &'a str,
+        max_width: u32,
+    ) -> u32
+    where
+        S: TextRenderer,
+        M: Plugin<'a, S::Color>,
+        S::Color: From<Rgb888>,
+    {
+        self.measure_text_height_limited(plugin, character_style, text, max_width, u32::MAX, 0)
+    }
+
+    pub(crate) fn measure_line_count_impl<'a, S, M>(
+        &self,
+        plugin: PluginWrapper<'a, M, S::Color>,
+        character_style: &S,
+        text: &'a str,
+        max_width: u32,
+    ) -> u32
+    where
+        S: TextRenderer,
+        M: Plugin<'a, S::Color>,
+        S::Color: From<Rgb888>,
+    {
+        let style = TextBoxStyle {
+            max_lines: None,
+            ..*self
+        };
+
+        style
+            .measure_text_limited(plugin, character_style, text, max_width, u32::MAX, 0)
+            .lines
+    }
+
+    /// Measures text height, but stops as soon as the height exceeds `max_height`.
+    ///
+    /// The first line starts `start_x` pixels from the left edge of the text.
+    fn measure_text_height_limited<'a, S, M>(
+        &self,
+        plugin: PluginWrapper<'a, M, S::Color>,
+        character_style: &S,
+        text: &'a str,
+        max_width: u32,
+        max_height: u32,
+        start_x: u32,
+    ) -> u32
+    where
+        S: TextRenderer,
+        M: Plugin<'a, S::Color>,
+        S::Color: From<Rgb888>,
+    {
+        self.measure_text_limited(
+            plugin,
+            character_style,
+            text,
+            max_width,
+            max_height,
+            start_x,
+        )
+        .height
+    }
+
+    /// Measures text line by line, until the height exceeds `max_height`.
+    fn measure_text_limited<'a, S, M>(
+        &self,
+        plugin: PluginWrapper<'a, M, S::Color>,
+        character_style: &S,
+        text: &'a str,
+        max_width: u32,
+        max_height: u32,
+        start_x: u32,
+    ) -> TextHeight
+    where
+        S: TextRenderer,
+        M: Plugin<'a, S::Color>,
+        S::Color: From<Rgb888>,
+    {
+        self.measure_parsed_limited(
+            plugin,
+            character_style,
+            self.parser(text),
+            max_width,
+            max_height,
+            start_x,
+        )
+    }
+
+    /// Measures the text of `parser`, but stops as soon as the height exceeds `max_height`.
+    fn measure_parsed_limited<'a, S, M>(
+        &self,
+        plugin: PluginWrapper<'a, M, S::Color>,
+        character_style: &S,
+        mut parser: Parser<'a, S::Color>,
+        max_width: u32,
+        max_height: u32,
+        start_x: u32,
+    ) -> TextHeight
+    where
+        S: TextRenderer,
+        M: Plugin<'a, S::Color>,
+        S::Color: From<Rgb888>,
+    {
+        let marker_width = self.list_marker_width(|m| str_width(character_style, m));
+        let max_width = self.layout_width(max_width, marker_width);
+        let mut height = TextHeight::new(self, character_style);
+
+        plugin.set_state(ProcessingState::Measure);
+
+        let mut start_x = start_x;
+        loop {
+            plugin.new_line();
+            let lm = self.measure_line_impl(
+                &plugin,
+                character_style,
+                &mut parser,
+                max_width,
+                false,
+                start_x,
+            );
+            start_x = 0;
+
+            if height.add_line(lm) || height.height > max_height {
+                return height;
+            }
+        }
+    }
+}
+
+/// The measurements of a paragraph of text.
+///
+/// Returned by [`TextBoxStyle::paragraphs`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ParagraphMetrics<'a> {
+    /// The text of the paragraph, without the newline character that ends it.
+    pub text: &'a str,
+
+    /// The height of the paragraph in pixels.
+    pub height: u32,
+
+    /// The number of lines the paragraph is wrapped into.
+    pub line_count: u32,
+}
+
+/// Accumulates the height of text that is measured line by line.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TextHeight {
+    /// The height of the lines measured so far.
+    pub height: u32,
+
+    /// The width of the widest line measured so far.
+    pub width: u32,
+    base_line_height: u32,
+    line_height: LineHeight,
+    line_heights: Option<fn(line: u32) -> u32>,
+    paragraph_spacing: u32,
+    include_trailing_leading: bool,
+    prev_end: LineEndType,
+
+    /// The number of lines included in the height, and the maximum number of lines.
+    lines: u32,
+    max_lines: u32,
+}
+
+impl TextHeight {
+    pub fn new(style: &TextBoxStyle, character_style: &impl TextRenderer) -> Self {
+        let base_line_height = character_style.line_height();
+
+        Self {
+            height: base_line_height,
+            width: 0,
+            base_line_height,
+            line_height: style.line_height,
+            line_heights: style.line_heights,
+            paragraph_spacing: style.paragraph_spacing,
+            include_trailing_leading: style.include_trailing_leading,
+            prev_end: LineEndType::EndOfText,
+            lines: 1,
+            max_lines: match style.max_lines {
+                Some(max_lines) => max_lines.get(),
+                None => u32::MAX,
+            },
+        }
+    }
+
+    /// Adds a measured line. Returns `true` if this was the last line of the text, or the last
+    /// line allowed by `max_lines`.
+    pub fn add_line(&mut self, lm: LineMeasurement) -> bool {
+        if self.prev_end == LineEndType::LineBreak && !lm.is_empty() {
+            if self.lines == self.max_lines {
+                return self.finish();
+            }
+            self.height = self.height.saturating_add(self.line_height());
+            self.lines += 1;
+        }
+        self.width = self.width.max(lm.width);
+
+        match lm.line_end_type {
+            LineEndType::CarriageReturn | LineEndType::LineBreak => {}
+            LineEndType::NewLine if self.lines == self.max_lines => return self.finish(),
+            LineEndType::NewLine => {
+                self.height = self
+                    .height
+                    .saturating_add(self.line_height())
+                    .saturating_add(self.paragraph_spacing);
+                self.lines += 1;
+            }
+            LineEndType::EndOfText => return self.finish(),
+        }
+        self.prev_end = lm.line_end_type;
+
+        false
+    }
+
+    /// Adds the last line of the text.
+    fn finish(&mut self) -> bool {
+        if self.include_trailing_leading {
+            let trailing_leading = self.line_height().saturating_sub(self.base_line_height);
+            self.height = self.height.saturating_add(trailing_leading);
+        }
+
+        true
+    }
+
+    /// Returns the line height of the last measured line.
+    fn line_height(&self) -> u32 {
+        match self.line_heights {
+            Some(line_heights) => line_heights(self.lines - 1),
+            None => line_height_pixels(self.line_height, self.base_line_height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{cell::Cell, num::NonZeroU32};
+
+    use crate::{
+        alignment::*,
+        parser::Parser,
+        plugin::{NoPlugin, PluginWrapper},
+        style::{
+            builder::TextBoxStyleBuilder, BuildError, GridColumn, HeightMode, LayoutSnapshot,
+            LineBreak, Padding, ParserConfig, TabSize, TextBoxStyle, TextTransform,
+        },
+        utils::{
+            str_width,
+            test::{size_for, Counting},
+        },
+        TextBox,
+    };
+    use embedded_graphics::{
+        geometry::{Point, Size},
+        mock_display::MockDisplay,
+        mono_font::{
+            ascii::{FONT_6X9, FONT_9X15},
+            MonoTextStyle, MonoTextStyleBuilder,
+        },
+        pixelcolor::BinaryColor,
+        primitives::Rectangle,
+        text::{renderer::TextRenderer, LineHeight},
+        Drawable,
+    };
+
+    #[test]
+    fn no_infinite_loop() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let _ = TextBoxStyleBuilder::new()
+            .build()
+            .measure_text_height(&character_style, "a", 5);
+    }
+
+    #[test]
+    fn test_measure_height() {
+        let data = [
+            // (text; max width in characters; number of expected lines)
+            ("", 0, 1),
+            (" ", 6, 1),
+            ("\r", 6, 1),
+            ("\n", 6, 2),
+            ("\n ", 6, 2),
+            ("word", 4 * 6, 1),   // exact fit into 1 line
+            ("word\n", 4 * 6, 2), // newline
+            ("word", 4 * 6 - 1, 2),
+            ("word", 2 * 6, 2),      // exact fit into 2 lines
+            ("word word", 4 * 6, 2), // exact fit into 2 lines
+            ("word\n", 2 * 6, 3),
+            ("word\nnext", 50, 2),
+            ("word\n\nnext", 50, 3),
+            ("word\n  \nnext", 50, 3),
+            ("verylongword", 50, 2),
+            ("some verylongword", 50, 3),
+            ("1 23456 12345 61234 561", 36, 5),
+            ("    Word      ", 36, 2),
+            ("\rcr", 36, 1),
+            ("cr\r", 36, 1),
+            ("cr\rcr", 36, 1),
+            ("Longer\r", 36, 1),
+            ("Longer\rnowrap", 36, 1),
+        ];
+
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
 
         let style = TextBoxStyle::with_paragraph_spacing(2);
 
-        for (i, (text, width, expected_n_lines)) in data.iter().enumerate() {
-            let height = style.measure_text_height(&character_style, text, *width);
-            let expected_height = *expected_n_lines * character_style.line_height()
-                + (text.chars().filter(|&c| c == '\n').count() as u32) * style.paragraph_spacing;
-            assert_eq!(
-                height,
-                expected_height,
-                r#"#{}: Height of {:?} is {} but is expected to be {}"#,
-                i,
-                text.replace('\r', "\\r").replace('\n', "\\n"),
-                height,
-                expected_height
-            );
+        for (i, (text, width, expected_n_lines)) in data.iter().enumerate() {
+            let height = style.measure_text_height(&character_style, text, *width);
+            let expected_height = *expected_n_lines * character_style.line_height()
+                + (text.chars().filter(|&c| c == '\n').count() as u32) * style.paragraph_spacing;
+            assert_eq!(
+                height,
+                expected_height,
+                r#"#{}: Height of {:?} is {} but is expected to be {}"#,
+                i,
+                text.replace('\r', "\\r").replace('\n', "\\n"),
+                height,
+                expected_height
+            );
+        }
+    }
+
+    #[test]
+    fn test_measure_height_ignored_spaces() {
+        let data = [
+            ("", 0, 1),
+            (" ", 0, 1),
+            (" ", 6, 1),
+            ("\n ", 6, 2),
+            ("word\n  \nnext", 50, 3),
+            ("    Word      ", 36, 1),
+        ];
+
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Center)
+            .build();
+
+        for (i, (text, width, expected_n_lines)) in data.iter().enumerate() {
+            let height = style.measure_text_height(&character_style, text, *width);
+            let expected_height = *expected_n_lines * character_style.line_height();
+            assert_eq!(
+                height, expected_height,
+                r#"#{}: Height of "{}" is {} but is expected to be {}"#,
+                i, text, height, expected_height
+            );
+        }
+    }
+
+    #[test]
+    fn test_measure_line() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Center)
+            .build();
+
+        let mut text = Parser::parse("123 45 67");
+
+        let mut plugin = PluginWrapper::new(NoPlugin::new());
+        let lm = style.measure_line(
+            &mut plugin,
+            &character_style,
+            &mut text,
+            6 * FONT_6X9.character_size.width,
+        );
+        assert_eq!(lm.width, 6 * FONT_6X9.character_size.width);
+    }
+
+    #[test]
+    fn test_measure_line_counts_nbsp() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Center)
+            .build();
+
+        let mut text = Parser::parse("123\u{A0}45");
+
+        let mut plugin = PluginWrapper::new(NoPlugin::new());
+        let lm = style.measure_line(
+            &mut plugin,
+            &character_style,
+            &mut text,
+            5 * FONT_6X9.character_size.width,
+        );
+        assert_eq!(lm.width, 5 * FONT_6X9.character_size.width);
+    }
+
+    #[test]
+    fn test_measure_height_nbsp() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Center)
+            .build();
+        let text = "123\u{A0}45 123";
+
+        let height =
+            style.measure_text_height(&character_style, text, 5 * FONT_6X9.character_size.width);
+        assert_eq!(height, 2 * character_style.line_height());
+
+        // bug discovered while using the interactive example
+        let style = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Left)
+            .build();
+
+        let text = "embedded-text also\u{A0}supports non-breaking spaces.";
+
+        let height = style.measure_text_height(&character_style, text, 79);
+        assert_eq!(height, 4 * character_style.line_height());
+    }
+
+    #[test]
+    fn height_with_line_spacing() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new()
+            .line_height(LineHeight::Pixels(11))
+            .build();
+
+        let height = style.measure_text_height(
+            &character_style,
+            "Lorem Ipsum is simply dummy text of the printing and typesetting industry.",
+            72,
+        );
+
+        assert_eq!(height, 6 * 11 + 9);
+    }
+
+    #[test]
+    fn soft_hyphenated_line_width_includes_hyphen_width() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new()
+            .line_height(LineHeight::Pixels(11))
+            .build();
+
+        let mut plugin = PluginWrapper::new(NoPlugin::new());
+        let lm = style.measure_line(
+            &mut plugin,
+            &character_style,
+            &mut Parser::parse("soft\u{AD}hyphen"),
+            50,
+        );
+
+        assert_eq!(lm.width, 30);
+    }
+
+    fn kerning_av(prev: char, next: char) -> i8 {
+        match (prev, next) {
+            ('A', 'V') => -2,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn kerning_reduces_measured_width() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let style = TextBoxStyleBuilder::new().kerning(kerning_av).build();
+
+        let plugin = PluginWrapper::new(NoPlugin::new());
+        let lm = style.measure_line(&plugin, &character_style, &mut Parser::parse("AVA"), 50);
+
+        assert_eq!(lm.width, 3 * 6 - 2);
+    }
+
+    #[test]
+    fn kerning_moves_wrap_point() {
+        let character_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::On)
+            .build();
+
+        // "AV AV" is 30px wide without kerning and 26px wide with kerning.
+        let plain = TextBoxStyleBuilder::new().build();
+        let kerned = TextBoxStyleBuilder::new().kerning(kerning_av).build();
+
+        assert_eq!(plain.measure_text_height(&character_style, "AV AV", 26), 18);
+        assert_eq!(kerned.measure_text_height(&character_style, "AV AV", 26), 9);
+        assert_eq!(
+            kerned.measure_text_height(&character_style, "AV AV", 25),
+            18
+        );
+
+        // A word that doesn't fit in an empty line is broken after the same number of characters
+        // as its kerned width allows.
+        assert_eq!(kerned.measure_text_height(&character_style, "AVAV", 22), 9);
+        assert_eq!(plain.measure_text_height(&character_style, "AVAV", 22), 18);
+    }
+
+    #[test]
+    fn measure_text_height_from_start_offset() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyle::default();
+        let measure =
+            |text, start_x| style.measure_text_height_from(&character_style, text, 60, start_x);
+
+        assert_eq!(measure("Hello", 0), 9);
+        assert_eq!(measure("Hello", 30), 9);
+        assert_eq!(measure("Hello", 36), 18);
+        assert_eq!(measure("Hello", 100), 18);
+
+        // Only the first line is shortened.
+        assert_eq!(measure("Hello World", 30), 18);
+        assert_eq!(measure("Hello World", 0), 18);
+        assert_eq!(measure("Hi World", 0), 9);
+
+        // Words longer than a line are broken on the next line.
+        assert_eq!(measure("HelloWorldHello", 0), 18);
+        assert_eq!(measure("HelloWorldHello", 6), 27);
+    }
+
+    #[test]
+    fn reserved_line_is_measured() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .line_height(LineHeight::Pixels(11))
+            .paragraph_spacing(3)
+            .build();
+
+        for text in [
+            "",
+            "Hello",
+            "Hello World",
+            "Hello\n",
+            "a\n\nb",
+            "trailing   ",
+            "a\rb",
+        ] {
+            let height = style.measure_text_height(&character_style, text, 36);
+            let reserved = style.measure_text_reserve_line(&character_style, text, 36);
+
+            assert_eq!(reserved, height + 11 + 3, "{:?}", text);
+            assert_eq!(
+                reserved,
+                style.measure_text_height(&character_style, &format!("{}\n", text), 36),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn text_transform_is_measured() {
+        fn kerning(prev: char, next: char) -> i8 {
+            match (prev, next) {
+                ('A', 'B') => -2,
+                _ => 0,
+            }
+        }
+
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = |transform| {
+            TextBoxStyleBuilder::new()
+                .kerning(kerning)
+                .text_transform(transform)
+                .build()
+        };
+
+        // The kerning only applies to the uppercase pair, so "ab" is 10px wide when uppercased.
+        for (transform, width) in [(TextTransform::None, 12), (TextTransform::Upper, 10)] {
+            let lm = style(transform).measure_line(
+                &PluginWrapper::new(NoPlugin::new()),
+                &character_style,
+                &mut Parser::parse("ab"),
+                60,
+            );
+            assert_eq!(lm.width, width);
+        }
+
+        // "ab ab" only fits into a 26px wide line when the kerning is applied.
+        assert_eq!(
+            style(TextTransform::None).measure_text_height(&character_style, "ab ab", 26),
+            18
+        );
+        assert_eq!(
+            style(TextTransform::Upper).measure_text_height(&character_style, "ab ab", 26),
+            9
+        );
+    }
+
+    #[test]
+    fn reserved_caret_width_wraps_last_word() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let plain = TextBoxStyle::default();
+        let reserved = TextBoxStyleBuilder::new().reserve_caret_width(1).build();
+
+        // "foo bar" fills 42px exactly.
+        assert_eq!(
+            plain.measure_text_height(&character_style, "foo bar", 42),
+            9
+        );
+        assert_eq!(
+            reserved.measure_text_height(&character_style, "foo bar", 42),
+            18
+        );
+        assert_eq!(
+            reserved.measure_text_height(&character_style, "foo bar", 43),
+            9
+        );
+
+        // Only the last word needs room for the caret.
+        assert_eq!(
+            reserved.measure_text_height(&character_style, "foo bar b", 42),
+            18
+        );
+
+        // A word that is alone in its line is not wrapped.
+        assert_eq!(
+            reserved.measure_text_height(&character_style, "foobar", 36),
+            9
+        );
+    }
+
+    #[test]
+    fn max_word_length_splits_long_words() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let plain = TextBoxStyle::default();
+        let limited = TextBoxStyleBuilder::new().max_word_length(5).build();
+
+        // 8 characters fit in a line.
+        let width = size_for(&FONT_6X9, 8, 1).width;
+
+        // "ab" / "01234567" / "89"
+        assert_eq!(
+            plain.measure_text_height(&character_style, "ab 0123456789", width),
+            27
+        );
+        // "ab 01234" / "56789"
+        assert_eq!(
+            limited.measure_text_height(&character_style, "ab 0123456789", width),
+            18
+        );
+    }
+
+    #[test]
+    fn consecutive_newlines_are_collapsed() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .max_consecutive_newlines(2)
+            .build();
+
+        assert_eq!(
+            TextBoxStyle::default().measure_text_height(&character_style, "a\n\n\n\nb", 60),
+            45
+        );
+        assert_eq!(
+            style.measure_text_height(&character_style, "a\n\n\n\nb", 60),
+            27
+        );
+
+        // A limit of 0 keeps the paragraphs apart.
+        let style = TextBoxStyleBuilder::new()
+            .max_consecutive_newlines(0)
+            .build();
+        assert_eq!(
+            style.measure_text_height(&character_style, "a\n\n\n\nb", 60),
+            18
+        );
+    }
+
+    #[test]
+    fn paragraph_metrics_add_up_to_text_height() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let text = "The quick brown fox\n\njumps over the lazy dog\n";
+        let width = size_for(&FONT_6X9, 10, 1).width;
+        let style = TextBoxStyleBuilder::new().paragraph_spacing(2).build();
+
+        let paragraphs = style
+            .paragraphs(&character_style, text, width)
+            .map(|p| (p.text, p.line_count, p.height))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paragraphs,
+            [
+                ("The quick brown fox", 2, 2 * 9),
+                ("", 1, 9),
+                ("jumps over the lazy dog", 3, 3 * 9),
+                ("", 1, 9),
+            ]
+        );
+
+        // Consecutive paragraphs are separated by the paragraph spacing.
+        let total = paragraphs.iter().map(|p| p.2).sum::<u32>() + 3 * style.paragraph_spacing;
+        assert_eq!(
+            total,
+            style.measure_text_height(&character_style, text, width)
+        );
+    }
+
+    #[test]
+    fn reserved_line_uses_line_heights() {
+        fn line_heights(line: u32) -> u32 {
+            if line == 0 {
+                18
+            } else {
+                9
+            }
         }
+
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .line_heights(line_heights)
+            .build();
+
+        assert_eq!(
+            style.measure_text_reserve_line(&character_style, "aaa", 60),
+            27
+        );
+        assert_eq!(
+            style.measure_text_reserve_line(&character_style, "aaa\nbbb", 60),
+            18 + 9 + 9
+        );
     }
 
     #[test]
-    fn test_measure_height_ignored_spaces() {
-        let data = [
-            ("", 0, 1),
-            (" ", 0, 1),
-            (" ", 6, 1),
-            ("\n ", 6, 2),
-            ("word\n  \nnext", 50, 3),
-            ("    Word      ", 36, 1),
-        ];
+    fn paragraphs_follow_text_box_layout() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let width = size_for(&FONT_6X9, 10, 1).width;
 
-        let character_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X9)
-            .text_color(BinaryColor::On)
+        let style = TextBoxStyleBuilder::new()
+            .max_consecutive_newlines(1)
             .build();
+        let paragraphs = style
+            .paragraphs(&character_style, "a\n\n\n\nb", width)
+            .map(|p| (p.text, p.line_count))
+            .collect::<Vec<_>>();
+        assert_eq!(paragraphs, [("a", 1), ("b", 1)]);
 
         let style = TextBoxStyleBuilder::new()
-            .alignment(HorizontalAlignment::Center)
+            .max_consecutive_newlines(2)
+            .build();
+        let paragraphs = style
+            .paragraphs(&character_style, "a\n\n\n\nb", width)
+            .map(|p| (p.text, p.line_count))
+            .collect::<Vec<_>>();
+        assert_eq!(paragraphs, [("a", 1), ("", 1), ("b", 1)]);
+
+        // Line heights are indexed from the start of the text.
+        fn line_heights(line: u32) -> u32 {
+            if line == 0 {
+                18
+            } else {
+                12
+            }
+        }
+        let style = TextBoxStyleBuilder::new()
+            .line_heights(line_heights)
+            .include_trailing_leading(true)
             .build();
+        let paragraphs = style
+            .paragraphs(&character_style, "aaa\nbbb ccc ddd", 24)
+            .map(|p| (p.text, p.line_count, p.height))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paragraphs,
+            [("aaa", 1, 18), ("bbb ccc ddd", 3, 12 + 12 + 12)]
+        );
 
-        for (i, (text, width, expected_n_lines)) in data.iter().enumerate() {
-            let height = style.measure_text_height(&character_style, text, *width);
-            let expected_height = *expected_n_lines * character_style.line_height();
+        // The maximum number of lines applies to the whole text.
+        let style = TextBoxStyleBuilder::new()
+            .max_lines(NonZeroU32::new(3).unwrap())
+            .build();
+        let paragraphs = style
+            .paragraphs(&character_style, "aaa\nbbb ccc ddd\neee", 24)
+            .map(|p| (p.text, p.line_count, p.height))
+            .collect::<Vec<_>>();
+        assert_eq!(paragraphs, [("aaa", 1, 9), ("bbb ccc ", 2, 18)]);
+    }
+
+    #[test]
+    fn measured_size_is_widest_line_and_height() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let text = "The quick brown fox  jumps over\nthe lazy dog";
+        let width = size_for(&FONT_6X9, 10, 1).width;
+
+        // "The quick " / "brown fox  " / "jumps over" / "the lazy " / "dog"
+        let style = TextBoxStyle::default();
+        assert_eq!(
+            style.measure_size(&character_style, text, width),
+            Size::new(
+                10 * 6,
+                style.measure_text_height(&character_style, text, width)
+            )
+        );
+        assert_eq!(
+            style.measure_size(&character_style, text, width).height,
+            5 * 9
+        );
+
+        // Rendered trailing spaces are included in the width, unless they are cut off.
+        let spaces = TextBoxStyleBuilder::new().trailing_spaces(true).build();
+        assert_eq!(
+            spaces.measure_size(&character_style, "ab  cd", size_for(&FONT_6X9, 3, 1).width),
+            Size::new(3 * 6, 2 * 9)
+        );
+
+        let narrow = "ab cd\nefg";
+        assert_eq!(
+            style.measure_size(&character_style, narrow, width),
+            Size::new(5 * 6, 2 * 9)
+        );
+        assert_eq!(
+            style.measure_size(&character_style, "", width),
+            Size::new(0, 9)
+        );
+    }
+
+    #[test]
+    fn extra_space_chars_separate_words() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new()
+            .parser_config(ParserConfig::new(&['\u{b7}'], &[]))
+            .build();
+        let width = size_for(&FONT_6X9, 4, 1).width;
+
+        // "12\u{b7}3" / "4\u{b7}56"
+        assert_eq!(
+            TextBoxStyle::default().measure_text_height(
+                &character_style,
+                "12\u{b7}34\u{b7}56",
+                width
+            ),
+            18
+        );
+        // "12" / "34" / "56"
+        assert_eq!(
+            style.measure_text_height(&character_style, "12\u{b7}34\u{b7}56", width),
+            27
+        );
+    }
+
+    #[test]
+    fn long_words_are_broken_after_weak_break_chars() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let plain = TextBoxStyle::default();
+        let weak = TextBoxStyleBuilder::new().weak_break_chars("_=.,/").build();
+
+        let line_starts = |style: &TextBoxStyle, text: &str, chars: u32| {
+            let width = size_for(&FONT_6X9, chars, 1).width;
+            let snapshot = LayoutSnapshot::<8>::capture(style, &character_style, text, width);
+
+            (
+                snapshot.lines().map(|line| line.offset).collect::<Vec<_>>(),
+                style.measure_text_height(&character_style, text, width),
+            )
+        };
+
+        let config = "temperature_sensor_outdoor_north=23.4";
+        assert_eq!(line_starts(&plain, config, 10), (vec![0, 10, 20, 30], 36));
+        // "temperatur" has no weak break, so it is broken at the last fitting character.
+        assert_eq!(line_starts(&weak, config, 10), (vec![0, 10, 19, 27], 36));
+        assert_eq!(line_starts(&weak, config, 15), (vec![0, 12, 27], 27));
+        assert_eq!(line_starts(&weak, config, 40), (vec![0], 9));
+
+        let url = "http://example.com/a/b";
+        assert_eq!(line_starts(&plain, url, 10), (vec![0, 10, 20], 27));
+        assert_eq!(line_starts(&weak, url, 10), (vec![0, 7, 15], 27));
+        assert_eq!(line_starts(&weak, url, 20), (vec![0, 19], 18));
+
+        // Weak break characters don't wrap words that fit into a line.
+        assert_eq!(line_starts(&weak, "ab a.b", 5), (vec![0, 3], 18));
+    }
+
+    #[test]
+    fn tabs_are_measured_to_the_next_tab_stop() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        for (tab_size, width) in [(TabSize::Spaces(4), 30), (TabSize::Pixels(10), 16)] {
+            let style = TextBoxStyleBuilder::new().tab_size(tab_size).build();
+
+            let snapshot = LayoutSnapshot::<2>::capture(&style, &character_style, "a\tb", 60);
+            assert_eq!(snapshot.line(0).map(|line| line.width), Some(width));
+
+            // "b" is wrapped if the expanded tab doesn't leave room for it.
             assert_eq!(
-                height, expected_height,
-                r#"#{}: Height of "{}" is {} but is expected to be {}"#,
-                i, text, height, expected_height
+                style.measure_text_height(&character_style, "a\tb", width),
+                9
+            );
+            assert_eq!(
+                style.measure_text_height(&character_style, "a\tb", width - 1),
+                18
             );
         }
     }
 
     #[test]
-    fn test_measure_line() {
-        let character_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X9)
-            .text_color(BinaryColor::On)
+    fn balanced_lines_have_similar_widths() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+
+        let greedy = TextBoxStyle::default();
+        let balanced = TextBoxStyleBuilder::new()
+            .line_break(LineBreak::Balanced)
             .build();
 
+        let widths = |style: &TextBoxStyle, text: &str| {
+            let width = size_for(&FONT_6X9, 20, 1).width;
+            let snapshot = LayoutSnapshot::<8>::capture(style, &character_style, text, width);
+
+            (
+                snapshot
+                    .lines()
+                    .map(|line| line.width / 6)
+                    .collect::<Vec<_>>(),
+                style.measure_text_height(&character_style, text, width),
+            )
+        };
+
+        let heading = "The quick brown fox jumps over";
+        assert_eq!(widths(&greedy, heading), (vec![19, 10], 18));
+        assert_eq!(widths(&balanced, heading), (vec![15, 14], 18));
+
+        // Single lines and longer texts are not balanced.
+        assert_eq!(widths(&balanced, "The quick"), (vec![9], 9));
+        let text = "The quick brown fox jumps over the lazy dog and the quick brown cat";
+        assert_eq!(widths(&balanced, text), widths(&greedy, text));
+    }
+
+    #[test]
+    fn rescaled_style_keeps_relative_sizes() {
+        let small = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let large = MonoTextStyle::new(&FONT_9X15, BinaryColor::On);
+
         let style = TextBoxStyleBuilder::new()
-            .alignment(HorizontalAlignment::Center)
+            .line_height(LineHeight::Pixels(12))
+            .paragraph_spacing(3)
+            .tab_size(TabSize::Pixels(24))
+            .reserve_caret_width(2)
+            .no_wrap_horizontal_scroll(10)
+            .padding(Padding::new(2, 3, 4, 6))
             .build();
 
-        let mut text = Parser::parse("123 45 67");
+        let rescaled = style.rescaled(&small, &large);
+        assert_eq!(rescaled.padding, Padding::new(3, 5, 6, 10));
+        assert_eq!(rescaled.line_height, LineHeight::Pixels(20));
+        assert_eq!(rescaled.paragraph_spacing, 5);
+        assert_eq!(rescaled.tab_size, TabSize::Pixels(36));
+        assert_eq!(rescaled.reserve_caret_width, 3);
+        assert_eq!(rescaled.horizontal_scroll, Some(15));
+
+        // Relative units are not changed.
+        let relative = TextBoxStyleBuilder::new()
+            .line_height(LineHeight::Percent(120))
+            .tab_size(TabSize::Spaces(2))
+            .build();
+        assert_eq!(relative.rescaled(&small, &large), relative);
+    }
 
-        let mut plugin = PluginWrapper::new(NoPlugin::new());
-        let lm = style.measure_line(
-            &mut plugin,
-            &character_style,
-            &mut text,
-            6 * FONT_6X9.character_size.width,
+    #[test]
+    fn rescaled_style_scales_grid_columns() {
+        static SMALL_COLUMNS: [GridColumn; 2] = [
+            GridColumn::new(20, HorizontalAlignment::Left),
+            GridColumn::new(12, HorizontalAlignment::Right),
+        ];
+        static LARGE_COLUMNS: [GridColumn; 2] = [
+            GridColumn::new(30, HorizontalAlignment::Left),
+            GridColumn::new(18, HorizontalAlignment::Right),
+        ];
+
+        let small = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let large = MonoTextStyle::new(&FONT_9X15, BinaryColor::On);
+
+        let draw = |style| {
+            let mut display = MockDisplay::new();
+            TextBox::with_textbox_style(
+                "a\tb",
+                Rectangle::new(Point::zero(), size_for(&FONT_9X15, 6, 1)),
+                large,
+                style,
+            )
+            .draw(&mut display)
+            .unwrap();
+            display
+        };
+
+        let rescaled = TextBoxStyleBuilder::new()
+            .columns(&SMALL_COLUMNS)
+            .build()
+            .rescaled(&small, &large);
+        let expected = TextBoxStyleBuilder::new().columns(&LARGE_COLUMNS).build();
+
+        draw(rescaled).assert_eq(&draw(expected));
+    }
+
+    #[test]
+    fn rescaled_text_box_applies_height_mode() {
+        let small = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let large = MonoTextStyle::new(&FONT_9X15, BinaryColor::On);
+
+        let mut text_box = TextBox::with_height_mode(
+            "foo bar",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 4, 1)),
+            small,
+            HeightMode::FitToText,
         );
-        assert_eq!(lm.width, 6 * FONT_6X9.character_size.width);
+        text_box.set_vertical_offset(-3);
+        assert_eq!(text_box.bounds.size.height, 18);
+
+        let rescaled = text_box.rescaled(
+            Rectangle::new(Point::new(10, 10), size_for(&FONT_9X15, 4, 1)),
+            large,
+        );
+        assert_eq!(
+            rescaled.bounds,
+            Rectangle::new(Point::new(10, 10), Size::new(36, 30))
+        );
+        assert_eq!(rescaled.vertical_offset, -5);
     }
 
     #[test]
-    fn test_measure_line_counts_nbsp() {
-        let character_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X9)
-            .text_color(BinaryColor::On)
-            .build();
+    fn process_elements_with_custom_handler() {
+        use crate::{ElementHandler, LineEndType};
+        use core::convert::Infallible;
+
+        struct Recorder<'a> {
+            character_style: MonoTextStyle<'a, BinaryColor>,
+            events: std::vec::Vec<(&'static str, u32)>,
+            words: std::string::String,
+        }
 
-        let style = TextBoxStyleBuilder::new()
-            .alignment(HorizontalAlignment::Center)
-            .build();
+        impl ElementHandler for Recorder<'_> {
+            type Error = Infallible;
+            type Color = BinaryColor;
 
-        let mut text = Parser::parse("123\u{A0}45");
+            fn measure(&self, st: &str) -> u32 {
+                str_width(&self.character_style, st)
+            }
 
-        let mut plugin = PluginWrapper::new(NoPlugin::new());
-        let lm = style.measure_line(
-            &mut plugin,
-            &character_style,
-            &mut text,
-            5 * FONT_6X9.character_size.width,
+            fn whitespace(&mut self, _st: &str, _count: u32, width: u32) -> Result<(), Infallible> {
+                self.events.push(("space", width));
+                Ok(())
+            }
+
+            fn printed_characters(
+                &mut self,
+                st: &str,
+                width: Option<u32>,
+            ) -> Result<(), Infallible> {
+                self.words.push_str(st);
+                self.events
+                    .push(("text", width.unwrap_or_else(|| self.measure(st))));
+                Ok(())
+            }
+
+            fn end_line(&mut self, end: LineEndType) -> Result<(), Infallible> {
+                self.words.push('|');
+                self.events.push((
+                    match end {
+                        LineEndType::LineBreak => "wrap",
+                        LineEndType::NewLine => "newline",
+                        LineEndType::CarriageReturn => "cr",
+                        LineEndType::EndOfText => "end",
+                    },
+                    0,
+                ));
+                Ok(())
+            }
+        }
+
+        let mut recorder = Recorder {
+            character_style: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            events: std::vec::Vec::new(),
+            words: std::string::String::new(),
+        };
+
+        TextBoxStyle::default()
+            .process_elements(
+                "foo bar\nbaz",
+                size_for(&FONT_6X9, 5, 1).width,
+                &mut recorder,
+            )
+            .unwrap();
+
+        assert_eq!(recorder.words, "foo|bar|baz|");
+        assert_eq!(
+            recorder.events,
+            [
+                ("text", 18),
+                // Trailing spaces are not rendered.
+                ("space", 0),
+                ("wrap", 0),
+                ("text", 18),
+                ("space", 0),
+                ("newline", 0),
+                ("text", 18),
+                ("end", 0),
+            ]
         );
-        assert_eq!(lm.width, 5 * FONT_6X9.character_size.width);
+
+        recorder.words.clear();
+        TextBoxStyleBuilder::new()
+            .line_break(LineBreak::Balanced)
+            .build()
+            .process_elements(
+                "The quick brown fox jumps over",
+                size_for(&FONT_6X9, 20, 1).width,
+                &mut recorder,
+            )
+            .unwrap();
+
+        assert_eq!(recorder.words, "Thequickbrown|foxjumpsover|");
     }
 
     #[test]
-    fn test_measure_height_nbsp() {
-        let character_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X9)
-            .text_color(BinaryColor::On)
-            .build();
+    fn fits_in_stops_early() {
+        let text = "word ".repeat(1000);
+
+        let calls = Cell::new(0);
+        let chars = Cell::new(0);
+        let character_style = Counting {
+            inner: MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            calls: &calls,
+            chars: &chars,
+        };
+        let style = TextBoxStyle::default();
+
+        assert!(!style.fits_in(&character_style, &text, size_for(&FONT_6X9, 10, 1)));
+
+        // Only the first two lines are measured.
+        assert!(chars.get() < 100, "{} characters measured", chars.get());
+
+        assert!(style.fits_in(&character_style, "word word", size_for(&FONT_6X9, 10, 1)));
+        assert!(!style.fits_in(&character_style, "word word", size_for(&FONT_6X9, 8, 1)));
+    }
+
+    #[test]
+    fn natural_width_is_width_of_widest_line() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyle::default();
+
+        assert_eq!(style.natural_width(&character_style, ""), 0);
+        assert_eq!(style.natural_width(&character_style, "ab\ncdef"), 24);
+        assert_eq!(style.natural_width(&character_style, "cdef\nab"), 24);
+        assert_eq!(style.natural_width(&character_style, "a long line"), 66);
+
+        // The text fits into a text box of its natural width without wrapping.
+        let text = "ab cd\nef";
+        let width = style.natural_width(&character_style, text);
+        assert_eq!(style.measure_text_height(&character_style, text, width), 18);
+        assert_eq!(
+            style.measure_text_height(&character_style, text, width - 1),
+            27
+        );
 
         let style = TextBoxStyleBuilder::new()
-            .alignment(HorizontalAlignment::Center)
+            .padding(Padding::horizontal(3, 4))
             .build();
-        let text = "123\u{A0}45 123";
+        assert_eq!(style.natural_width(&character_style, "ab\ncdef"), 24 + 7);
+    }
 
-        let height =
-            style.measure_text_height(&character_style, text, 5 * FONT_6X9.character_size.width);
-        assert_eq!(height, 2 * character_style.line_height());
+    #[test]
+    fn disabled_leading_and_trailing_spaces_trim_lines() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let untrimmed = TextBoxStyleBuilder::new()
+            .leading_spaces(true)
+            .trailing_spaces(true)
+            .build();
+        let trimmed = TextBoxStyleBuilder::new()
+            .leading_spaces(false)
+            .trailing_spaces(false)
+            .build();
+
+        assert_eq!(untrimmed.natural_width(&character_style, "  hi  "), 6 * 6);
+        assert_eq!(trimmed.natural_width(&character_style, "  hi  "), 2 * 6);
+        assert_eq!(
+            trimmed.natural_width(&character_style, "  hi  "),
+            trimmed.natural_width(&character_style, "hi")
+        );
+
+        // Interior spaces are kept, and every line is trimmed.
+        assert_eq!(
+            untrimmed.natural_width(&character_style, " a  b \n c "),
+            6 * 6
+        );
+        assert_eq!(
+            trimmed.natural_width(&character_style, " a  b \n c "),
+            4 * 6
+        );
+        assert_eq!(trimmed.natural_width(&character_style, "\t a b\t "), 3 * 6);
+    }
+
+    #[test]
+    fn first_fitting_falls_back_to_last() {
+        let candidates = [
+            MonoTextStyle::new(&FONT_9X15, BinaryColor::On),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        ];
+        let bounds = Rectangle::new(Point::zero(), Size::new(36, 15));
+        let style = TextBoxStyle::default();
+
+        assert_eq!(
+            style.first_fitting(candidates, "four", bounds),
+            Some((0, Size::new(36, 15)))
+        );
+        assert_eq!(
+            style.first_fitting(candidates, "abcdef", bounds),
+            Some((1, Size::new(36, 9)))
+        );
+        assert_eq!(
+            style.first_fitting(candidates, "much longer text", bounds),
+            Some((1, Size::new(36, 27)))
+        );
+        assert_eq!(
+            style.first_fitting(candidates, "ab cd", bounds),
+            Some((1, Size::new(30, 9)))
+        );
 
-        // bug discovered while using the interactive example
         let style = TextBoxStyleBuilder::new()
-            .alignment(HorizontalAlignment::Left)
+            .padding(Padding::horizontal(2, 3))
             .build();
+        assert_eq!(
+            style.first_fitting(candidates, "ab", bounds),
+            Some((0, Size::new(18 + 5, 15)))
+        );
 
-        let text = "embedded-text also\u{A0}supports non-breaking spaces.";
-
-        let height = style.measure_text_height(&character_style, text, 79);
-        assert_eq!(height, 4 * character_style.line_height());
+        let none: [MonoTextStyle<'_, BinaryColor>; 0] = [];
+        assert_eq!(style.first_fitting(none, "ab", bounds), None);
     }
 
     #[test]
-    fn height_with_line_spacing() {
+    fn measure_first_paragraphs() {
         let character_style = MonoTextStyleBuilder::new()
             .font(&FONT_6X9)
             .text_color(BinaryColor::On)
             .build();
 
-        let style = TextBoxStyleBuilder::new()
-            .line_height(LineHeight::Pixels(11))
-            .build();
+        let style = TextBoxStyle::with_paragraph_spacing(2);
 
-        let height = style.measure_text_height(
-            &character_style,
-            "Lorem Ipsum is simply dummy text of the printing and typesetting industry.",
-            72,
-        );
+        // Lines are at most 5 characters wide.
+        let text = "one two\n\nthree four five\n  \nsix";
 
-        assert_eq!(height, 6 * 11 + 9);
+        let (height, end) = style.measure_paragraphs(&character_style, text, 30, 2);
+        assert_eq!(&text[..end], "one two\n\nthree four five");
+        // 2 + 1 (blank) + 3 lines, 2 paragraph spacings
+        assert_eq!(height, 6 * 9 + 2 * 2);
+
+        let (height, end) = style.measure_paragraphs(&character_style, text, 30, 1);
+        assert_eq!(&text[..end], "one two");
+        assert_eq!(height, 2 * 9);
+
+        assert_eq!(
+            style.measure_paragraphs(&character_style, text, 30, 3),
+            (
+                style.measure_text_height(&character_style, text, 30),
+                text.len()
+            )
+        );
     }
 
     #[test]
-    fn soft_hyphenated_line_width_includes_hyphen_width() {
+    fn soft_hyphen_break_decision() {
         let character_style = MonoTextStyleBuilder::new()
             .font(&FONT_6X9)
             .text_color(BinaryColor::On)
             .build();
 
-        let style = TextBoxStyleBuilder::new()
-            .line_height(LineHeight::Pixels(11))
-            .build();
+        let style = TextBoxStyle::default();
+        let word = "sam\u{AD}ple";
 
-        let mut plugin = PluginWrapper::new(NoPlugin::new());
-        let lm = style.measure_line(
-            &mut plugin,
-            &character_style,
-            &mut Parser::parse("soft\u{AD}hyphen"),
-            50,
+        // The whole word fits
+        assert_eq!(style.soft_hyphen_break(&character_style, word, 6 * 6), None);
+        assert_eq!(style.soft_hyphen_break(&character_style, word, 100), None);
+
+        // "sam-" fits
+        assert_eq!(
+            style.soft_hyphen_break(&character_style, word, 5 * 6),
+            Some(3)
+        );
+        assert_eq!(
+            style.soft_hyphen_break(&character_style, word, 4 * 6),
+            Some(3)
         );
 
-        assert_eq!(lm.width, 30);
+        // "sam" fits, but the hyphen is moved to the next line
+        assert_eq!(
+            style.soft_hyphen_break(&character_style, word, 3 * 6),
+            Some(3)
+        );
+
+        // "sam" doesn't fit, the word is broken inside the first part
+        assert_eq!(style.soft_hyphen_break(&character_style, word, 2 * 6), None);
+
+        // No soft hyphen
+        assert_eq!(
+            style.soft_hyphen_break(&character_style, "sample", 5 * 6),
+            None
+        );
+    }
+
+    #[test]
+    fn try_build_reports_conflicts() {
+        assert_eq!(
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Justified)
+                .no_wrap_horizontal_scroll(10)
+                .try_build(),
+            Err(BuildError::JustifiedWithoutWrapping)
+        );
+        assert_eq!(
+            TextBoxStyleBuilder::new()
+                .height_mode(HeightMode::FitToText)
+                .vertical_alignment(VerticalAlignment::Bottom)
+                .try_build(),
+            Err(BuildError::VerticalAlignmentWithFitToText)
+        );
+        assert_eq!(
+            TextBoxStyleBuilder::new().intensity(0).try_build(),
+            Err(BuildError::ZeroIntensity)
+        );
+        assert_eq!(
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Justified)
+                .hanging_punctuation(true)
+                .try_build(),
+            Err(BuildError::HangingPunctuationWithoutPadding)
+        );
+        assert_eq!(
+            TextBoxStyleBuilder::new()
+                .max_consecutive_newlines(0)
+                .try_build(),
+            Err(BuildError::ZeroConsecutiveNewlines)
+        );
+    }
+
+    #[test]
+    fn try_build_accepts_valid_style() {
+        let builder = TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Center)
+            .height_mode(HeightMode::FitToText)
+            .hanging_punctuation(true)
+            .padding(Padding::new(0, 0, 6, 0))
+            .max_consecutive_newlines(1);
+
+        assert_eq!(builder.try_build(), Ok(builder.build()));
     }
 }
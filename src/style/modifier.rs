@@ -0,0 +1,451 @@
+//! Partial text box styles.
+
+use core::num::NonZeroU32;
+
+use embedded_graphics::text::LineHeight;
+
+use crate::{
+    alignment::{HorizontalAlignment, VerticalAlignment},
+    style::{
+        ControlCharacters, GridColumn, HeightMode, Hyphenator, LineBreak, Padding, ParserConfig,
+        Scale, TabAnchor, TabLeader, TabSize, TextBoxStyle, TextTransform, WhitespaceStyle,
+    },
+};
+
+/// A set of changes to a [`TextBoxStyle`].
+///
+/// Every field mirrors the field of [`TextBoxStyle`] with the same name. Fields that are `None`
+/// leave the style unchanged, so a modifier can describe a reusable role, like a title or a
+/// caption, that only differs from a base style in a few options. Options that are optional in
+/// the style themselves are wrapped twice, so `Some(None)` disables them.
+///
+/// Modifiers are applied to a builder with [`TextBoxStyleBuilder::merge`], or to an existing style
+/// with [`TextBoxStyle::modified`].
+///
+/// [`TextBoxStyleBuilder::merge`]: crate::style::TextBoxStyleBuilder::merge
+///
+/// # Example
+///
+/// ```rust
+/// use embedded_text::{
+///     alignment::HorizontalAlignment,
+///     style::{TextBoxStyleBuilder, TextBoxStyleModifier},
+/// };
+///
+/// const TITLE: TextBoxStyleModifier = TextBoxStyleModifier {
+///     alignment: Some(HorizontalAlignment::Center),
+///     paragraph_spacing: Some(4),
+///     ..TextBoxStyleModifier::new()
+/// };
+///
+/// let base = TextBoxStyleBuilder::new().paragraph_spacing(2);
+///
+/// let body = base.build();
+/// let title = base.merge(&TITLE).build();
+///
+/// assert_eq!(title.alignment, HorizontalAlignment::Center);
+/// assert_eq!(title.paragraph_spacing, 4);
+/// assert_eq!(title.line_height, body.line_height);
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[must_use]
+pub struct TextBoxStyleModifier {
+    /// Overrides [`TextBoxStyle::alignment`].
+    pub alignment: Option<HorizontalAlignment>,
+
+    /// Overrides [`TextBoxStyle::vertical_alignment`].
+    pub vertical_alignment: Option<VerticalAlignment>,
+
+    /// Overrides [`TextBoxStyle::height_mode`].
+    pub height_mode: Option<HeightMode>,
+
+    /// Overrides [`TextBoxStyle::line_height`].
+    pub line_height: Option<LineHeight>,
+
+    /// Overrides [`TextBoxStyle::line_heights`].
+    #[allow(clippy::type_complexity)]
+    pub line_heights: Option<Option<fn(line: u32) -> u32>>,
+
+    /// Overrides [`TextBoxStyle::paragraph_spacing`].
+    pub paragraph_spacing: Option<u32>,
+
+    /// Overrides [`TextBoxStyle::include_trailing_leading`].
+    pub include_trailing_leading: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::tab_size`].
+    pub tab_size: Option<TabSize>,
+
+    /// Overrides [`TextBoxStyle::tab_leader`].
+    pub tab_leader: Option<Option<TabLeader>>,
+
+    /// Overrides [`TextBoxStyle::leading_spaces`].
+    pub leading_spaces: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::trailing_spaces`].
+    pub trailing_spaces: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::whitespace_style`].
+    pub whitespace_style: Option<Option<WhitespaceStyle>>,
+
+    /// Overrides [`TextBoxStyle::pad_lines`].
+    pub pad_lines: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::kerning`].
+    #[allow(clippy::type_complexity)]
+    pub kerning: Option<Option<fn(prev: char, next: char) -> i8>>,
+
+    /// Overrides [`TextBoxStyle::hyphenation`].
+    pub hyphenation: Option<Option<Hyphenator>>,
+
+    /// Overrides [`TextBoxStyle::horizontal_scroll`].
+    pub horizontal_scroll: Option<Option<u32>>,
+
+    /// Overrides [`TextBoxStyle::intensity`].
+    pub intensity: Option<u8>,
+
+    /// Overrides [`TextBoxStyle::justify_nbsp`].
+    pub justify_nbsp: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::justify_tabbed_lines`].
+    pub justify_tabbed_lines: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::hanging_punctuation`].
+    pub hanging_punctuation: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::reserve_caret_width`].
+    pub reserve_caret_width: Option<u32>,
+
+    /// Overrides [`TextBoxStyle::max_word_length`].
+    pub max_word_length: Option<Option<u32>>,
+
+    /// Overrides [`TextBoxStyle::max_consecutive_newlines`].
+    pub max_consecutive_newlines: Option<Option<u32>>,
+
+    /// Overrides [`TextBoxStyle::parser_config`].
+    pub parser_config: Option<ParserConfig>,
+
+    /// Overrides [`TextBoxStyle::weak_break_chars`].
+    pub weak_break_chars: Option<Option<&'static str>>,
+
+    /// Overrides [`TextBoxStyle::line_break`].
+    pub line_break: Option<LineBreak>,
+
+    /// Overrides [`TextBoxStyle::text_transform`].
+    pub text_transform: Option<TextTransform>,
+
+    /// Overrides [`TextBoxStyle::control_characters`].
+    pub control_characters: Option<ControlCharacters>,
+
+    /// Overrides [`TextBoxStyle::padding`].
+    pub padding: Option<Padding>,
+
+    /// Overrides [`TextBoxStyle::list_marker`].
+    pub list_marker: Option<Option<&'static str>>,
+
+    /// Overrides [`TextBoxStyle::avoid_broken_last_word`].
+    pub avoid_broken_last_word: Option<bool>,
+
+    /// Overrides [`TextBoxStyle::max_lines`].
+    pub max_lines: Option<Option<NonZeroU32>>,
+
+    /// Overrides [`TextBoxStyle::reveal_chars`].
+    pub reveal_chars: Option<Option<usize>>,
+
+    /// Overrides [`TextBoxStyle::columns`].
+    pub columns: Option<Option<&'static [GridColumn]>>,
+
+    /// Overrides [`TextBoxStyle::tab_anchor`].
+    pub tab_anchor: Option<TabAnchor>,
+}
+
+impl TextBoxStyleModifier {
+    /// Creates a modifier that doesn't change any option.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            alignment: None,
+            vertical_alignment: None,
+            height_mode: None,
+            line_height: None,
+            line_heights: None,
+            paragraph_spacing: None,
+            include_trailing_leading: None,
+            tab_size: None,
+            tab_leader: None,
+            leading_spaces: None,
+            trailing_spaces: None,
+            whitespace_style: None,
+            pad_lines: None,
+            kerning: None,
+            hyphenation: None,
+            horizontal_scroll: None,
+            intensity: None,
+            justify_nbsp: None,
+            justify_tabbed_lines: None,
+            hanging_punctuation: None,
+            reserve_caret_width: None,
+            max_word_length: None,
+            max_consecutive_newlines: None,
+            parser_config: None,
+            weak_break_chars: None,
+            line_break: None,
+            text_transform: None,
+            control_characters: None,
+            padding: None,
+            list_marker: None,
+            avoid_broken_last_word: None,
+            max_lines: None,
+            reveal_chars: None,
+            columns: None,
+            tab_anchor: None,
+        }
+    }
+}
+
+impl TextBoxStyle {
+    /// Returns a copy of the style with the options set in `modifier` replaced.
+    ///
+    /// Unlike the builder, changing the alignment doesn't change the
+    /// [`leading_spaces`](Self::leading_spaces) and [`trailing_spaces`](Self::trailing_spaces)
+    /// options of a built style.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use embedded_text::style::{TextBoxStyle, TextBoxStyleModifier};
+    ///
+    /// let style = TextBoxStyle::default();
+    /// let dimmed = style.modified(&TextBoxStyleModifier {
+    ///     intensity: Some(128),
+    ///     ..TextBoxStyleModifier::new()
+    /// });
+    ///
+    /// assert_eq!(dimmed.intensity, 128);
+    /// assert_eq!(dimmed.alignment, style.alignment);
+    /// ```
+    #[inline]
+    pub fn modified(&self, modifier: &TextBoxStyleModifier) -> TextBoxStyle {
+        // Destructuring makes sure that every option of the style has a modifier.
+        let TextBoxStyleModifier {
+            alignment,
+            vertical_alignment,
+            height_mode,
+            line_height,
+            line_heights,
+            paragraph_spacing,
+            include_trailing_leading,
+            tab_size,
+            tab_leader,
+            leading_spaces,
+            trailing_spaces,
+            whitespace_style,
+            pad_lines,
+            kerning,
+            hyphenation,
+            horizontal_scroll,
+            intensity,
+            justify_nbsp,
+            justify_tabbed_lines,
+            hanging_punctuation,
+            reserve_caret_width,
+            max_word_length,
+            max_consecutive_newlines,
+            parser_config,
+            weak_break_chars,
+            line_break,
+            text_transform,
+            control_characters,
+            padding,
+            list_marker,
+            avoid_broken_last_word,
+            max_lines,
+            reveal_chars,
+            columns,
+            tab_anchor,
+        } = *modifier;
+
+        let TextBoxStyle {
+            alignment: base_alignment,
+            vertical_alignment: base_vertical_alignment,
+            height_mode: base_height_mode,
+            line_height: base_line_height,
+            line_heights: base_line_heights,
+            paragraph_spacing: base_paragraph_spacing,
+            include_trailing_leading: base_include_trailing_leading,
+            tab_size: base_tab_size,
+            tab_leader: base_tab_leader,
+            leading_spaces: base_leading_spaces,
+            trailing_spaces: base_trailing_spaces,
+            whitespace_style: base_whitespace_style,
+            pad_lines: base_pad_lines,
+            kerning: base_kerning,
+            hyphenation: base_hyphenation,
+            horizontal_scroll: base_horizontal_scroll,
+            intensity: base_intensity,
+            justify_nbsp: base_justify_nbsp,
+            justify_tabbed_lines: base_justify_tabbed_lines,
+            hanging_punctuation: base_hanging_punctuation,
+            reserve_caret_width: base_reserve_caret_width,
+            max_word_length: base_max_word_length,
+            max_consecutive_newlines: base_max_consecutive_newlines,
+            parser_config: base_parser_config,
+            weak_break_chars: base_weak_break_chars,
+            line_break: base_line_break,
+            text_transform: base_text_transform,
+            control_characters: base_control_characters,
+            padding: base_padding,
+            list_marker: base_list_marker,
+            avoid_broken_last_word: base_avoid_broken_last_word,
+            max_lines: base_max_lines,
+            reveal_chars: base_reveal_chars,
+            columns: base_columns,
+            column_scale: base_column_scale,
+            tab_anchor: base_tab_anchor,
+        } = *self;
+
+        TextBoxStyle {
+            alignment: alignment.unwrap_or(base_alignment),
+            vertical_alignment: vertical_alignment.unwrap_or(base_vertical_alignment),
+            height_mode: height_mode.unwrap_or(base_height_mode),
+            line_height: line_height.unwrap_or(base_line_height),
+            line_heights: line_heights.unwrap_or(base_line_heights),
+            paragraph_spacing: paragraph_spacing.unwrap_or(base_paragraph_spacing),
+            include_trailing_leading: include_trailing_leading
+                .unwrap_or(base_include_trailing_leading),
+            tab_size: tab_size.unwrap_or(base_tab_size),
+            tab_leader: tab_leader.unwrap_or(base_tab_leader),
+            leading_spaces: leading_spaces.unwrap_or(base_leading_spaces),
+            trailing_spaces: trailing_spaces.unwrap_or(base_trailing_spaces),
+            whitespace_style: whitespace_style.unwrap_or(base_whitespace_style),
+            pad_lines: pad_lines.unwrap_or(base_pad_lines),
+            kerning: kerning.unwrap_or(base_kerning),
+            hyphenation: hyphenation.unwrap_or(base_hyphenation),
+            horizontal_scroll: horizontal_scroll.unwrap_or(base_horizontal_scroll),
+            intensity: intensity.unwrap_or(base_intensity),
+            justify_nbsp: justify_nbsp.unwrap_or(base_justify_nbsp),
+            justify_tabbed_lines: justify_tabbed_lines.unwrap_or(base_justify_tabbed_lines),
+            hanging_punctuation: hanging_punctuation.unwrap_or(base_hanging_punctuation),
+            reserve_caret_width: reserve_caret_width.unwrap_or(base_reserve_caret_width),
+            max_word_length: max_word_length.unwrap_or(base_max_word_length),
+            max_consecutive_newlines: max_consecutive_newlines
+                .unwrap_or(base_max_consecutive_newlines),
+            parser_config: parser_config.unwrap_or(base_parser_config),
+            weak_break_chars: weak_break_chars.unwrap_or(base_weak_break_chars),
+            line_break: line_break.unwrap_or(base_line_break),
+            text_transform: text_transform.unwrap_or(base_text_transform),
+            control_characters: control_characters.unwrap_or(base_control_characters),
+            padding: padding.unwrap_or(base_padding),
+            list_marker: list_marker.unwrap_or(base_list_marker),
+            avoid_broken_last_word: avoid_broken_last_word.unwrap_or(base_avoid_broken_last_word),
+            max_lines: max_lines.unwrap_or(base_max_lines),
+            reveal_chars: reveal_chars.unwrap_or(base_reveal_chars),
+            columns: columns.unwrap_or(base_columns),
+            column_scale: match columns {
+                Some(_) => Scale::IDENTITY,
+                None => base_column_scale,
+            },
+            tab_anchor: tab_anchor.unwrap_or(base_tab_anchor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::num::NonZeroU32;
+
+    use crate::{
+        alignment::{HorizontalAlignment, VerticalAlignment},
+        style::{
+            HeightMode, LineBreak, Padding, TabSize, TextBoxStyle, TextBoxStyleBuilder,
+            TextBoxStyleModifier, TextTransform,
+        },
+    };
+
+    fn base() -> TextBoxStyle {
+        TextBoxStyleBuilder::new()
+            .alignment(HorizontalAlignment::Right)
+            .vertical_alignment(VerticalAlignment::Middle)
+            .height_mode(HeightMode::FitToText)
+            .paragraph_spacing(3)
+            .tab_size(TabSize::Pixels(10))
+            .intensity(100)
+            .max_word_length(8)
+            .weak_break_chars("_")
+            .line_break(LineBreak::Balanced)
+            .text_transform(TextTransform::Upper)
+            .padding(Padding::new(1, 2, 3, 4))
+            .list_marker("-")
+            .max_lines(NonZeroU32::new(2).unwrap())
+            .reveal_chars(5)
+            .build()
+    }
+
+    #[test]
+    fn unset_fields_keep_existing_values() {
+        let style = base();
+
+        assert_eq!(style.modified(&TextBoxStyleModifier::new()), style);
+        assert_eq!(
+            TextBoxStyleBuilder::new()
+                .merge(&TextBoxStyleModifier::default())
+                .build(),
+            TextBoxStyle::default()
+        );
+    }
+
+    #[test]
+    fn set_fields_are_replaced() {
+        let modifier = TextBoxStyleModifier {
+            paragraph_spacing: Some(0),
+            list_marker: Some(None),
+            reveal_chars: Some(Some(1)),
+            ..TextBoxStyleModifier::new()
+        };
+
+        let style = base();
+        let modified = style.modified(&modifier);
+
+        assert_eq!(
+            modified,
+            TextBoxStyle {
+                paragraph_spacing: 0,
+                list_marker: None,
+                reveal_chars: Some(1),
+                ..style
+            }
+        );
+    }
+
+    #[test]
+    fn merged_builder_matches_direct_builder() {
+        let title = TextBoxStyleModifier {
+            alignment: Some(HorizontalAlignment::Center),
+            trailing_spaces: Some(true),
+            max_lines: Some(None),
+            ..TextBoxStyleModifier::new()
+        };
+
+        let builder = TextBoxStyleBuilder::new()
+            .paragraph_spacing(2)
+            .max_lines(NonZeroU32::new(1).unwrap());
+
+        assert_eq!(
+            builder.merge(&title).build(),
+            TextBoxStyleBuilder::new()
+                .paragraph_spacing(2)
+                .alignment(HorizontalAlignment::Center)
+                .trailing_spaces(true)
+                .build()
+        );
+
+        // Options set later override the modifier.
+        assert_eq!(
+            builder
+                .merge(&title)
+                .alignment(HorizontalAlignment::Left)
+                .build()
+                .alignment,
+            HorizontalAlignment::Left
+        );
+    }
+}
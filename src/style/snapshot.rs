@@ -0,0 +1,447 @@
+//! Fixed capacity layout snapshots.
+//!
+//! A [`LayoutSnapshot`] records the position of every line of a laid out text, without
+//! allocating. The recorded lines can be compared to a later snapshot of a modified text to find
+//! the lines that need to be redrawn, or used to map byte offsets to lines.
+
+use embedded_graphics::{pixelcolor::Rgb888, text::renderer::TextRenderer};
+
+use crate::{
+    parser::Parser,
+    plugin::{NoPlugin, PluginWrapper, ProcessingState},
+    rendering::line_iter::LineEndType,
+    style::TextBoxStyle,
+    utils::str_width,
+};
+
+/// The layout of a single line.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct LineRecord {
+    /// Byte offset of the start of the line in the text.
+    pub offset: usize,
+
+    /// Width of the line in pixels.
+    pub width: u32,
+
+    /// Vertical position of the top of the line, relative to the top of the text.
+    pub y: u32,
+
+    /// The reason the line ended.
+    pub end_type: LineEndType,
+}
+
+/// The layout of the first `N` lines of a text.
+///
+/// Lines are laid out the same way [`TextBox`] lays them out, with the text aligned to the top.
+/// If the text has more than `N` lines, the rest of the lines are not recorded.
+///
+/// # Example
+///
+/// ```rust
+/// # use embedded_graphics::{
+/// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+/// #     pixelcolor::BinaryColor,
+/// # };
+/// use embedded_text::style::{LayoutSnapshot, TextBoxStyle};
+///
+/// let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+/// let style = TextBoxStyle::default();
+///
+/// let snapshot = LayoutSnapshot::<4>::capture(&style, &character_style, "Hello, World!", 60);
+///
+/// let lines = snapshot.lines().map(|line| (line.offset, line.y)).collect::<Vec<_>>();
+/// assert_eq!(lines, [(0, 0), (7, 9)]);
+/// ```
+///
+/// [`TextBox`]: crate::TextBox
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[must_use]
+pub struct LayoutSnapshot<const N: usize> {
+    lines: [Option<LineRecord>; N],
+    len: usize,
+    complete: bool,
+}
+
+impl<const N: usize> LayoutSnapshot<N> {
+    /// Lays out `text` using a given width, and records its lines.
+    #[inline]
+    pub fn capture<S>(style: &TextBoxStyle, character_style: &S, text: &str, max_width: u32) -> Self
+    where
+        S: TextRenderer,
+        S::Color: From<Rgb888>,
+    {
+        let mut snapshot = Self {
+            lines: [None; N],
+            len: 0,
+            complete: true,
+        };
+
+        for line in LineWalker::new(style, character_style, text, max_width) {
+            if !snapshot.push(line) {
+                snapshot.complete = false;
+                break;
+            }
+        }
+
+        snapshot
+    }
+
+    /// Returns the recorded lines.
+    #[inline]
+    pub fn lines(&self) -> impl Iterator<Item = &LineRecord> + '_ {
+        self.lines[..self.len].iter().flatten()
+    }
+
+    /// Returns the `index`th recorded line.
+    #[inline]
+    #[must_use]
+    pub fn line(&self, index: usize) -> Option<&LineRecord> {
+        self.lines[..self.len].get(index)?.as_ref()
+    }
+
+    /// Returns the number of recorded lines.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no lines were recorded.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if every line of the text was recorded.
+    #[inline]
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Returns the index of the recorded line that contains the byte offset `offset`.
+    ///
+    /// Offsets past the start of the last recorded line belong to the last line.
+    #[inline]
+    #[must_use]
+    pub fn line_at_offset(&self, offset: usize) -> Option<usize> {
+        self.lines[..self.len]
+            .iter()
+            .rposition(|line| matches!(line, Some(line) if line.offset <= offset))
+    }
+
+    /// Records a line. Returns `false` if the snapshot is full.
+    fn push(&mut self, line: LineRecord) -> bool {
+        match self.lines.get_mut(self.len) {
+            Some(slot) => {
+                *slot = Some(line);
+                self.len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns whether `line` starts a new line of the text box. `prev_end` is the end type of the
+/// previous line, `None` for the first line of the text.
+///
+/// This is the definition of a line used by every function that counts or indexes lines. Lines
+/// after a carriage return overwrite the previous line, and an empty line after a line break
+/// doesn't take up space, like in [`TextHeight`].
+///
+/// [`TextHeight`]: super::TextHeight
+pub(crate) fn starts_line(prev_end: Option<LineEndType>, line: &LineRecord) -> bool {
+    match prev_end {
+        None | Some(LineEndType::NewLine) | Some(LineEndType::EndOfText) => true,
+        Some(LineEndType::CarriageReturn) => false,
+        Some(LineEndType::LineBreak) => line.width != 0,
+    }
+}
+
+/// Iterator that lays out a text line by line, without drawing it.
+pub(crate) struct LineWalker<'a, 'b, S>
+where
+    S: TextRenderer,
+{
+    style: &'b TextBoxStyle,
+    character_style: &'b S,
+    text: &'a str,
+    parser: Parser<'a, S::Color>,
+    plugin: PluginWrapper<'a, NoPlugin<S::Color>, S::Color>,
+    max_width: u32,
+    base_line_height: u32,
+    /// The index of the current line.
+    line: u32,
+    y: u32,
+    done: bool,
+}
+
+impl<'a, 'b, S> LineWalker<'a, 'b, S>
+where
+    S: TextRenderer,
+    S::Color: From<Rgb888>,
+{
+    pub(crate) fn new(
+        style: &'b TextBoxStyle,
+        character_style: &'b S,
+        text: &'a str,
+        max_width: u32,
+    ) -> Self {
+        let plugin = PluginWrapper::new(NoPlugin::<S::Color>::new());
+        plugin.set_state(ProcessingState::Measure);
+
+        let max_width = style.balanced_width(max_width, |width| {
+            style.measure_line_count_impl(plugin.clone(), character_style, text, width)
+        });
+
+        Self {
+            style,
+            character_style,
+            text,
+            parser: style.parser(text),
+            plugin,
+            max_width: style.layout_width(
+                max_width,
+                style.list_marker_width(|m| str_width(character_style, m)),
+            ),
+            base_line_height: character_style.line_height(),
+            line: 0,
+            y: 0,
+            done: false,
+        }
+    }
+
+    /// Moves to the next line, with extra spacing below the current one.
+    fn next_line(&mut self, spacing: u32) {
+        let line_height = self.style.line_height_at(self.base_line_height, self.line);
+
+        self.y = self.y.saturating_add(line_height).saturating_add(spacing);
+        self.line = self.line.saturating_add(1);
+    }
+}
+
+impl<'a, 'b, S> Iterator for LineWalker<'a, 'b, S>
+where
+    S: TextRenderer,
+    S::Color: From<Rgb888>,
+{
+    type Item = LineRecord;
+
+    fn next(&mut self) -> Option<LineRecord> {
+        if self.done {
+            return None;
+        }
+
+        // The token that didn't fit into the previous line starts this one.
+        let offset = self
+            .plugin
+            .peeked_str()
+            .and_then(|peeked| (peeked.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize))
+            .unwrap_or_else(|| self.text.len() - self.parser.as_str().len());
+
+        self.plugin.new_line();
+        let lm = self.style.measure_line(
+            &self.plugin,
+            self.character_style,
+            &mut self.parser,
+            self.max_width,
+        );
+
+        let line = LineRecord {
+            offset,
+            width: lm.width,
+            y: self.y,
+            end_type: lm.line_end_type,
+        };
+
+        match lm.line_end_type {
+            LineEndType::EndOfText => self.done = true,
+            LineEndType::CarriageReturn => {}
+            LineEndType::LineBreak => self.next_line(0),
+            LineEndType::NewLine => self.next_line(self.style.paragraph_spacing),
+        }
+
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        geometry::{Point, Size},
+        mono_font::{ascii::FONT_6X9, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        primitives::Rectangle,
+    };
+
+    use crate::{
+        style::{LayoutSnapshot, LineHeight, LineRecord, TextBoxStyle, TextBoxStyleBuilder},
+        LineEndType, TextBox,
+    };
+
+    #[test]
+    fn snapshot_records_lines() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new().paragraph_spacing(2).build();
+
+        let snapshot =
+            LayoutSnapshot::<8>::capture(&style, &character_style, "Hello World\nfoo\rbar", 36);
+
+        assert!(snapshot.is_complete());
+        assert_eq!(
+            snapshot.lines().copied().collect::<Vec<_>>(),
+            [
+                LineRecord {
+                    offset: 0,
+                    width: 30,
+                    y: 0,
+                    end_type: LineEndType::LineBreak,
+                },
+                LineRecord {
+                    offset: 6,
+                    width: 30,
+                    y: 9,
+                    end_type: LineEndType::NewLine,
+                },
+                LineRecord {
+                    offset: 12,
+                    width: 18,
+                    y: 20,
+                    end_type: LineEndType::CarriageReturn,
+                },
+                LineRecord {
+                    offset: 16,
+                    width: 18,
+                    y: 20,
+                    end_type: LineEndType::EndOfText,
+                },
+            ]
+        );
+
+        assert_eq!(snapshot.line_at_offset(0), Some(0));
+        assert_eq!(snapshot.line_at_offset(8), Some(1));
+        assert_eq!(snapshot.line_at_offset(100), Some(3));
+    }
+
+    #[test]
+    fn snapshot_stops_recording_when_full() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyle::default();
+
+        let snapshot = LayoutSnapshot::<2>::capture(&style, &character_style, "a\nb\nc", 60);
+
+        assert!(!snapshot.is_complete());
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.line(1).map(|line| line.offset), Some(2));
+        assert_eq!(snapshot.line(2), None);
+
+        let empty = LayoutSnapshot::<0>::capture(&style, &character_style, "a", 60);
+        assert!(empty.is_empty());
+        assert!(!empty.is_complete());
+        assert_eq!(empty.line_at_offset(0), None);
+    }
+
+    #[test]
+    fn clip_at_height_returns_offset_to_resume_from() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new().paragraph_spacing(2).build();
+        let text = "The quick brown fox\njumps over the lazy dog";
+
+        // "The quick " / "brown fox" / "jumps over " / "the lazy " / "dog"
+        let (lines, offset) = style.clip_at_height(&character_style, text, 60, 20);
+        assert_eq!(lines, 2);
+        assert_eq!(&text[offset..], "jumps over the lazy dog");
+
+        // The paragraph spacing pushes the third line below 27 pixels.
+        assert_eq!(
+            style.clip_at_height(&character_style, text, 60, 28),
+            (2, 20)
+        );
+        assert_eq!(
+            style.clip_at_height(&character_style, text, 60, 29),
+            (3, 31)
+        );
+
+        assert_eq!(style.clip_at_height(&character_style, text, 60, 5), (0, 0));
+        assert_eq!(
+            style.clip_at_height(&character_style, text, 60, 100),
+            (5, text.len())
+        );
+
+        // Overwritten lines are not counted.
+        assert_eq!(
+            style.clip_at_height(&character_style, "first\rsecond\nthird", 60, 9),
+            (1, 13)
+        );
+    }
+
+    #[test]
+    fn clip_at_height_fits_measured_height() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let text = "aaa bbb ccc";
+
+        for include_trailing_leading in [false, true] {
+            let style = TextBoxStyleBuilder::new()
+                .line_height(LineHeight::Percent(150))
+                .include_trailing_leading(include_trailing_leading)
+                .build();
+
+            let height = style.measure_text_height(&character_style, text, 18);
+            assert_eq!(height, if include_trailing_leading { 39 } else { 35 });
+            assert_eq!(
+                style.clip_at_height(&character_style, text, 18, height),
+                (3, text.len())
+            );
+            assert_eq!(
+                style.clip_at_height(&character_style, text, 18, height - 1),
+                (2, 8)
+            );
+        }
+    }
+
+    #[test]
+    fn line_at_matches_line_count() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let style = TextBoxStyleBuilder::new().paragraph_spacing(2).build();
+        let text = "Some words that wrap\ninto lines\rand a longword_that_is_broken";
+
+        for width in [36, 60, 90] {
+            let line_count = TextBox::with_textbox_style(
+                text,
+                Rectangle::new(Point::zero(), Size::new(width, 0)),
+                character_style,
+                style,
+            )
+            .line_count();
+
+            // The ranges of the lines cover the text without gaps.
+            let mut end = 0;
+            for index in 0..line_count as usize {
+                let range = style.line_at(&character_style, text, width, index).unwrap();
+                assert_eq!(range.start, end, "width {width}, line {index}");
+                end = range.end;
+            }
+            assert_eq!(end, text.len());
+            assert_eq!(
+                style.line_at(&character_style, text, width, line_count as usize),
+                None
+            );
+
+            // `clip_at_height` counts the same lines.
+            assert_eq!(
+                style.clip_at_height(&character_style, text, width, u32::MAX),
+                (line_count, text.len())
+            );
+        }
+
+        // The text after the carriage return overwrites "into lines".
+        assert_eq!(style.line_at(&character_style, text, 90, 2), Some(21..38));
+
+        // "Some " / "words " / "that "
+        assert_eq!(style.line_at(&character_style, text, 36, 0), Some(0..5));
+        assert_eq!(style.line_at(&character_style, text, 36, 1), Some(5..11));
+        assert_eq!(style.line_at(&character_style, text, 36, 2), Some(11..16));
+    }
+}
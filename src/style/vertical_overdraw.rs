@@ -30,9 +30,9 @@ impl VerticalOverdraw {
             }
 
             VerticalOverdraw::Hidden => {
-                let offset_top = (cursor.top_left().y - cursor.y).saturating_as::<u32>();
-                let offset_bottom =
-                    line_height - (cursor.y - cursor.bottom()).saturating_as::<u32>();
+                let offset_top = cursor.top_left().y.saturating_sub(cursor.y).saturating_as();
+                let offset_bottom = line_height
+                    .saturating_sub(cursor.y.saturating_sub(cursor.bottom()).saturating_as());
 
                 offset_top..offset_bottom
             }
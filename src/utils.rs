@@ -1,35 +1,471 @@
 //! Misc utilities
 
 use embedded_graphics::{
-    prelude::Point,
-    text::{renderer::TextRenderer, Baseline},
+    prelude::{Point, Size},
+    primitives::Rectangle,
+    text::{renderer::TextRenderer, Baseline, LineHeight},
 };
 
+use crate::{
+    rendering::cursor::UNBOUNDED_LINE_WIDTH,
+    style::{ControlCharacters, TextTransform},
+};
+
+/// Returns the part of `area` that can be used as a clipping area.
+///
+/// The corners of rectangles that are wider or taller than `i32::MAX` can't be represented, so
+/// huge areas are limited to a size that is still much larger than any display.
+///
+/// Areas that are empty in one dimension are made empty in both: embedded-graphics crops
+/// contiguous fills to such clipping areas incorrectly, which panics on overflow.
+pub fn clip_area(area: Rectangle) -> Rectangle {
+    let size = if area.size.width == 0 || area.size.height == 0 {
+        Size::zero()
+    } else {
+        area.size
+            .component_min(Size::new_equal(UNBOUNDED_LINE_WIDTH))
+    };
+
+    Rectangle::new(area.top_left, size)
+}
+
+/// Returns the line height in pixels.
+///
+/// Unlike [`LineHeight::to_absolute`], this saturates instead of overflowing for large percentages.
+pub fn line_height_pixels(line_height: LineHeight, base_line_height: u32) -> u32 {
+    match line_height {
+        LineHeight::Pixels(px) => px,
+        LineHeight::Percent(percent) => {
+            (base_line_height as u64 * percent as u64 / 100).min(u32::MAX as u64) as u32
+        }
+    }
+}
+
+/// Returns whether a character is drawn without advancing the cursor.
+///
+/// Variation selectors and combining enclosing marks modify the preceding character, so they
+/// don't have a width of their own. The zero-width no-break space, also used as a byte order mark,
+/// is not a word separator, so it behaves like an invisible part of the word it is in. Directional
+/// isolate marks only change the display order of the text around them. Soft hyphens that are not
+/// used to break a line are invisible, too.
+pub fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{feff}'
+            | '\u{ad}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{fe00}'..='\u{fe0f}'
+            | '\u{e0100}'..='\u{e01ef}'
+            | '\u{0488}'..='\u{0489}'
+            | '\u{1abe}'
+            | '\u{20dd}'..='\u{20e0}'
+            | '\u{20e2}'..='\u{20e4}'
+            | '\u{a670}'..='\u{a672}'
+    )
+}
+
+/// Returns whether a character is a C0 control character.
+pub fn is_control(c: char) -> bool {
+    c < '\u{20}'
+}
+
+/// Iterator over the user-perceived characters of a string and their byte offsets.
+///
+/// With the `grapheme` feature, combining marks, variation selectors, emoji modifiers,
+/// zero-width joiner sequences and regional indicator pairs are kept together with the character
+/// they belong to. Without the feature, every `char` is a separate item.
+#[derive(Clone, Debug)]
+pub struct ClusterIndices<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Iterator for ClusterIndices<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.text.get(self.offset..)?;
+        let first = rest.chars().next()?;
+
+        let len = cluster_len(rest, first);
+        let item = (self.offset, &rest[..len]);
+        self.offset += len;
+
+        Some(item)
+    }
+}
+
+/// Returns an iterator over the user-perceived characters of a string.
+pub fn cluster_indices(text: &str) -> ClusterIndices<'_> {
+    ClusterIndices { text, offset: 0 }
+}
+
+#[cfg(not(feature = "grapheme"))]
+fn cluster_len(_text: &str, first: char) -> usize {
+    first.len_utf8()
+}
+
+/// Returns the length of the grapheme cluster at the start of `text`, in bytes.
+#[cfg(feature = "grapheme")]
+fn cluster_len(text: &str, first: char) -> usize {
+    const ZWJ: char = '\u{200d}';
+
+    let is_regional_indicator = |c: char| ('\u{1f1e6}'..='\u{1f1ff}').contains(&c);
+    let extends = |c: char| {
+        is_zero_width(c)
+            || c == ZWJ
+            || matches!(
+                c,
+                '\u{0300}'..='\u{036f}'
+                    | '\u{1ab0}'..='\u{1aff}'
+                    | '\u{1dc0}'..='\u{1dff}'
+                    | '\u{20d0}'..='\u{20ff}'
+                    | '\u{fe20}'..='\u{fe2f}'
+                    | '\u{1f3fb}'..='\u{1f3ff}'
+            )
+    };
+
+    let mut len = first.len_utf8();
+    let mut prev = first;
+    let mut regional_indicators = is_regional_indicator(first) as u32;
+    for c in text[len..].chars() {
+        let joined = if prev == ZWJ {
+            true
+        } else if is_regional_indicator(c) {
+            regional_indicators == 1 && is_regional_indicator(prev)
+        } else {
+            extends(c)
+        };
+
+        if !joined {
+            break;
+        }
+
+        regional_indicators += is_regional_indicator(c) as u32;
+        len += c.len_utf8();
+        prev = c;
+    }
+
+    len
+}
+
+/// Returns the length of `text` starting at `part`, if `part` is a slice of `text`.
+pub fn remaining_len_from(text: &str, part: &str) -> Option<usize> {
+    // pointer arithmetic to get the offset of `part` relative to the text
+    let start = (part.as_ptr() as usize).checked_sub(text.as_ptr() as usize)?;
+
+    text.get(start..).map(str::len)
+}
+
 /// Measure the width of a piece of string.
 pub fn str_width(renderer: &impl TextRenderer, s: &str) -> u32 {
-    renderer
-        .measure_string(s, Point::zero(), Baseline::Top)
-        .next_position
-        .x as u32
+    s.split(is_zero_width)
+        .map(|segment| {
+            renderer
+                .measure_string(segment, Point::zero(), Baseline::Top)
+                .next_position
+                .x as u32
+        })
+        .sum()
+}
+
+/// Returns the kerning adjustment between two adjacent characters.
+///
+/// Kerning is not applied next to whitespace characters.
+pub fn kerning_adjustment(kerning: Option<fn(char, char) -> i8>, prev: char, next: char) -> i32 {
+    match kerning {
+        Some(kerning) if !prev.is_whitespace() && !next.is_whitespace() => {
+            kerning(prev, next) as i32
+        }
+        _ => 0,
+    }
+}
+
+/// Measure the width of a piece of string, including kerning adjustments.
+pub fn str_width_kerned(
+    renderer: &impl TextRenderer,
+    s: &str,
+    kerning: Option<fn(char, char) -> i8>,
+) -> u32 {
+    let width = str_width(renderer, s);
+
+    if kerning.is_none() {
+        return width;
+    }
+
+    let mut adjustment = 0;
+    let mut chars = s.chars().filter(|c| !is_zero_width(*c));
+    if let Some(mut prev) = chars.next() {
+        for c in chars {
+            adjustment += kerning_adjustment(kerning, prev, c);
+            prev = c;
+        }
+    }
+
+    (width as i32 + adjustment).max(0) as u32
+}
+
+/// Measure the width of a piece of string as it is displayed, after mapping control characters
+/// and transforming the case of the characters, including kerning adjustments.
+pub fn str_width_displayed(
+    renderer: &impl TextRenderer,
+    s: &str,
+    kerning: Option<fn(char, char) -> i8>,
+    text_transform: TextTransform,
+    control_characters: ControlCharacters,
+) -> u32 {
+    if text_transform == TextTransform::None
+        && (control_characters == ControlCharacters::Keep || !s.contains(is_control))
+    {
+        return str_width_kerned(renderer, s, kerning);
+    }
+
+    // Characters are measured one by one, runs of characters also include the spacing between
+    // adjacent characters.
+    let spacing = str_width(renderer, "  ") as i32 - 2 * str_width(renderer, " ") as i32;
+    let mut buffer = [0; 4];
+    let mut width = 0;
+    let mut prev = None;
+    let mut in_run = false;
+    for c in s.chars() {
+        if is_zero_width(c) {
+            in_run = false;
+            continue;
+        }
+        for c in control_characters.apply(c) {
+            let c = text_transform.apply(c);
+            if in_run {
+                width += spacing;
+            }
+            if let Some(prev) = prev {
+                width += kerning_adjustment(kerning, prev, c);
+            }
+            width += str_width(renderer, c.encode_utf8(&mut buffer)) as i32;
+
+            prev = Some(c);
+            in_run = true;
+        }
+    }
+
+    width.max(0) as u32
 }
 
 #[cfg(test)]
 pub mod test {
+    use core::cell::Cell;
+
     use embedded_graphics::{
         mono_font::{ascii::FONT_6X9, MonoFont, MonoTextStyle},
         pixelcolor::BinaryColor,
-        prelude::Size,
+        prelude::*,
+        text::{
+            renderer::{CharacterStyle, TextMetrics, TextRenderer},
+            Baseline,
+        },
     };
 
-    use super::str_width;
+    use super::{cluster_indices, str_width, str_width_displayed, str_width_kerned};
+    use crate::style::{ControlCharacters, TextTransform};
 
     pub fn size_for(font: &MonoFont, chars: u32, lines: u32) -> Size {
         font.character_size.x_axis() * chars + font.character_size.y_axis() * lines
     }
 
+    /// Character style that counts how many characters it is asked to measure.
+    pub struct Counting<'a> {
+        pub inner: MonoTextStyle<'a, BinaryColor>,
+        pub calls: &'a Cell<usize>,
+        pub chars: &'a Cell<usize>,
+    }
+
+    impl TextRenderer for Counting<'_> {
+        type Color = BinaryColor;
+
+        fn draw_string<D>(
+            &self,
+            text: &str,
+            position: Point,
+            baseline: Baseline,
+            target: &mut D,
+        ) -> Result<Point, D::Error>
+        where
+            D: DrawTarget<Color = Self::Color>,
+        {
+            self.inner.draw_string(text, position, baseline, target)
+        }
+
+        fn draw_whitespace<D>(
+            &self,
+            width: u32,
+            position: Point,
+            baseline: Baseline,
+            target: &mut D,
+        ) -> Result<Point, D::Error>
+        where
+            D: DrawTarget<Color = Self::Color>,
+        {
+            self.inner
+                .draw_whitespace(width, position, baseline, target)
+        }
+
+        fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+            self.calls.set(self.calls.get() + 1);
+            self.chars.set(self.chars.get() + text.chars().count());
+            self.inner.measure_string(text, position, baseline)
+        }
+
+        fn line_height(&self) -> u32 {
+            self.inner.line_height()
+        }
+    }
+
+    /// Character style that counts how many strings it draws.
+    #[derive(Clone)]
+    pub struct CountingDraws<'a> {
+        pub inner: MonoTextStyle<'a, BinaryColor>,
+        pub draws: &'a Cell<usize>,
+    }
+
+    impl TextRenderer for CountingDraws<'_> {
+        type Color = BinaryColor;
+
+        fn draw_string<D>(
+            &self,
+            text: &str,
+            position: Point,
+            baseline: Baseline,
+            target: &mut D,
+        ) -> Result<Point, D::Error>
+        where
+            D: DrawTarget<Color = Self::Color>,
+        {
+            self.draws.set(self.draws.get() + 1);
+            self.inner.draw_string(text, position, baseline, target)
+        }
+
+        fn draw_whitespace<D>(
+            &self,
+            width: u32,
+            position: Point,
+            baseline: Baseline,
+            target: &mut D,
+        ) -> Result<Point, D::Error>
+        where
+            D: DrawTarget<Color = Self::Color>,
+        {
+            self.inner
+                .draw_whitespace(width, position, baseline, target)
+        }
+
+        fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+            self.inner.measure_string(text, position, baseline)
+        }
+
+        fn line_height(&self) -> u32 {
+            self.inner.line_height()
+        }
+    }
+
+    impl CharacterStyle for CountingDraws<'_> {
+        type Color = BinaryColor;
+
+        fn set_text_color(&mut self, text_color: Option<Self::Color>) {
+            self.inner.set_text_color(text_color);
+        }
+    }
+
     #[test]
     fn width_of_nbsp_is_single_space() {
         let renderer = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
         assert_eq!(str_width(&renderer, " "), str_width(&renderer, "\u{a0}"));
     }
+
+    #[test]
+    fn zero_width_characters_are_not_measured() {
+        let renderer = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        assert_eq!(str_width(&renderer, "A"), str_width(&renderer, "A\u{fe0f}"));
+        assert_eq!(
+            str_width(&renderer, "AB"),
+            str_width(&renderer, "A\u{20dd}B")
+        );
+        assert_eq!(
+            str_width(&renderer, "AB"),
+            str_width(&renderer, "\u{feff}A\u{feff}B")
+        );
+    }
+
+    #[test]
+    fn kerning_adjusts_width() {
+        fn kerning(prev: char, next: char) -> i8 {
+            match (prev, next) {
+                ('A', 'V') => -2,
+                _ => 0,
+            }
+        }
+
+        let renderer = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        assert_eq!(str_width_kerned(&renderer, "AVA", Some(kerning)), 16);
+        assert_eq!(str_width_kerned(&renderer, "A V", Some(kerning)), 18);
+        assert_eq!(str_width_kerned(&renderer, "AVA", None), 18);
+    }
+
+    #[test]
+    fn displayed_width_measures_displayed_characters() {
+        fn kerning(prev: char, next: char) -> i8 {
+            match (prev, next) {
+                ('A', 'V') => -2,
+                _ => 0,
+            }
+        }
+
+        let renderer = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let width = |s, transform, control_characters| {
+            str_width_displayed(&renderer, s, Some(kerning), transform, control_characters)
+        };
+
+        assert_eq!(
+            width("av", TextTransform::Upper, ControlCharacters::Keep),
+            10
+        );
+        assert_eq!(
+            width("av", TextTransform::None, ControlCharacters::Keep),
+            12
+        );
+        assert_eq!(
+            width("a\u{1}", TextTransform::None, ControlCharacters::Caret),
+            18
+        );
+        assert_eq!(
+            width("a\u{1}", TextTransform::None, ControlCharacters::Ignore),
+            6
+        );
+    }
+
+    #[test]
+    fn clusters_without_grapheme_feature_are_chars() {
+        let clusters = cluster_indices("ab\u{301}").collect::<Vec<_>>();
+
+        if cfg!(feature = "grapheme") {
+            assert_eq!(clusters, [(0, "a"), (1, "b\u{301}")]);
+        } else {
+            assert_eq!(clusters, [(0, "a"), (1, "b"), (2, "\u{301}")]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "grapheme")]
+    fn grapheme_clusters() {
+        let clusters = |s| cluster_indices(s).map(|(_, c)| c).collect::<Vec<_>>();
+
+        assert_eq!(clusters("e\u{301}x"), ["e\u{301}", "x"]);
+        assert_eq!(clusters("\u{2764}\u{fe0f}!"), ["\u{2764}\u{fe0f}", "!"]);
+        assert_eq!(
+            clusters("\u{1f469}\u{200d}\u{1f467}a"),
+            ["\u{1f469}\u{200d}\u{1f467}", "a"]
+        );
+        assert_eq!(
+            clusters("\u{1f1ed}\u{1f1fa}\u{1f1e9}\u{1f1ea}"),
+            ["\u{1f1ed}\u{1f1fa}", "\u{1f1e9}\u{1f1ea}"]
+        );
+    }
 }
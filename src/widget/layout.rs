@@ -0,0 +1,533 @@
+//! Layout queries implemented as a plugin.
+//!
+//! The text box reports every rendered piece of text to its plugins. The probe maps these pieces
+//! back to byte offsets in the source text, which is enough to locate the caret and to find the
+//! character closest to a point.
+
+use core::{convert::Infallible, marker::PhantomData, ops::Range};
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Point, Size},
+    pixelcolor::PixelColor,
+    primitives::Rectangle,
+    text::{renderer::TextRenderer, Baseline},
+    Pixel,
+};
+
+use crate::{
+    plugin::Plugin,
+    style::{ControlCharacters, ParserConfig, TextBoxStyle, TextTransform},
+    utils::{cluster_indices, is_control, is_zero_width, kerning_adjustment, str_width_displayed},
+};
+
+/// The settings of the text box style that change how characters are displayed.
+#[derive(Clone, Copy)]
+pub(crate) struct CharDisplay {
+    kerning: Option<fn(char, char) -> i8>,
+    text_transform: TextTransform,
+    control_characters: ControlCharacters,
+}
+
+impl CharDisplay {
+    pub const fn new(style: &TextBoxStyle) -> Self {
+        Self {
+            kerning: style.kerning,
+            text_transform: style.text_transform,
+            control_characters: style.control_characters,
+        }
+    }
+
+    /// Returns the characters displayed in place of a character.
+    fn chars(self, c: char) -> impl Iterator<Item = char> {
+        let text_transform = self.text_transform;
+        self.control_characters
+            .apply(c)
+            .filter(|c| !is_zero_width(*c))
+            .map(move |c| text_transform.apply(c))
+    }
+
+    /// Returns the horizontal position of the character boundary at `idx`, relative to the start
+    /// of `text` when displayed using `renderer`.
+    ///
+    /// The position includes the kerning adjustment between the characters around the boundary,
+    /// so it is where the character after the boundary is drawn.
+    pub fn boundary(self, renderer: &impl TextRenderer, text: &str, idx: usize) -> i32 {
+        let (before, after) = text.split_at(idx);
+        let width = str_width_displayed(
+            renderer,
+            before,
+            self.kerning,
+            self.text_transform,
+            self.control_characters,
+        ) as i32;
+
+        let prev = before.chars().rev().find_map(|c| self.chars(c).last());
+        let next = after.chars().find_map(|c| self.chars(c).next());
+        match (prev, next) {
+            (Some(prev), Some(next)) => width + kerning_adjustment(self.kerning, prev, next),
+            _ => width,
+        }
+    }
+
+    /// Draws `text` the way the text box displays it, and returns the position after the last
+    /// character.
+    pub fn draw<T, D>(
+        self,
+        renderer: &T,
+        text: &str,
+        mut position: Point,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        T: TextRenderer,
+        D: DrawTarget<Color = T::Color>,
+    {
+        if self.kerning.is_none()
+            && self.text_transform == TextTransform::None
+            && (self.control_characters == ControlCharacters::Keep || !text.contains(is_control))
+            && !text.contains(is_zero_width)
+        {
+            return renderer.draw_string(text, position, Baseline::Top, target);
+        }
+
+        let mut prev = None;
+        let mut buffer = [0; 4];
+        for c in text.chars().flat_map(|c| self.chars(c)) {
+            if let Some(prev) = prev {
+                position.x += kerning_adjustment(self.kerning, prev, c);
+            }
+            prev = Some(c);
+
+            position = renderer.draw_string(
+                c.encode_utf8(&mut buffer),
+                position,
+                Baseline::Top,
+                target,
+            )?;
+        }
+
+        Ok(position)
+    }
+}
+
+/// Returns whether a rendered piece of text only contains whitespace.
+fn is_whitespace(fragment: &str) -> bool {
+    fragment.chars().all(char::is_whitespace)
+}
+
+/// Returns whether `c` is part of a word, using the word segmentation of the parser.
+///
+/// Non-breaking spaces join words, and soft hyphens join the parts of a hyphenated word.
+fn is_word_char(c: char, config: &ParserConfig) -> bool {
+    if config.extra_word_chars.contains(&c) {
+        return true;
+    }
+
+    (!c.is_whitespace() || c == '\u{a0}')
+        && c != '\u{200b}'
+        && !config.extra_space_chars.contains(&c)
+}
+
+/// Maps pieces of rendered text to byte ranges in the source text.
+#[derive(Clone)]
+pub(crate) struct SourceMap<'a> {
+    text: &'a str,
+    consumed: usize,
+}
+
+impl<'a> SourceMap<'a> {
+    pub const fn new(text: &'a str) -> Self {
+        Self { text, consumed: 0 }
+    }
+
+    /// Returns the source range of a rendered piece of text.
+    ///
+    /// Most pieces are slices of the source text. Some whitespace characters are rendered using
+    /// string literals, these are matched with the next occurrence in the source text.
+    pub fn resolve(&mut self, fragment: &str) -> Option<Range<usize>> {
+        let range = match self.slice_range(fragment) {
+            Some(range) => range,
+            None if matches!(fragment, "\n" | "\r" | "\t" | "\u{a0}") => {
+                let start = self.consumed + self.text.get(self.consumed..)?.find(fragment)?;
+                start..start + fragment.len()
+            }
+            None => return None,
+        };
+
+        self.consumed = range.end;
+
+        Some(range)
+    }
+
+    fn slice_range(&self, fragment: &str) -> Option<Range<usize>> {
+        if fragment.is_empty() {
+            return None;
+        }
+
+        let start = (fragment.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize)?;
+        let end = start + fragment.len();
+
+        if end <= self.text.len() {
+            Some(start..end)
+        } else {
+            None
+        }
+    }
+}
+
+/// Calls `f` with the byte offset and horizontal position of every character boundary in a
+/// rendered piece of text. Grapheme clusters are not split if the `grapheme` feature is enabled.
+///
+/// Positions are measured on the displayed characters, including kerning adjustments.
+pub(crate) fn for_each_boundary(
+    renderer: &impl TextRenderer,
+    display: CharDisplay,
+    fragment: &str,
+    range: Range<usize>,
+    bounds: Rectangle,
+    mut f: impl FnMut(usize, i32),
+) {
+    let left = bounds.top_left.x;
+    let width = bounds.size.width as i32;
+
+    if range.len() != fragment.len() {
+        // The fragment is a replacement of a single source character.
+        f(range.start, left);
+        f(range.end, left + width);
+        return;
+    }
+
+    let count = cluster_indices(fragment).count() as i32;
+    let whitespace = is_whitespace(fragment);
+    for (i, (idx, _)) in cluster_indices(fragment).enumerate() {
+        let x = if whitespace {
+            // Whitespace may be stretched or hidden, so distribute the rendered width.
+            width * i as i32 / count
+        } else {
+            display.boundary(renderer, fragment, idx)
+        };
+        f(range.start + idx, left + x);
+    }
+    f(range.end, left + width);
+}
+
+/// Selects a row of text by the position of the top of the row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Row {
+    /// The last row that starts at or above the given coordinate.
+    At(i32),
+
+    /// The last row that starts above the given coordinate.
+    Above(i32),
+
+    /// The first row that starts below the given coordinate.
+    Below(i32),
+}
+
+impl Row {
+    fn accepts(self, top: i32) -> bool {
+        match self {
+            Row::At(y) => top <= y,
+            Row::Above(y) => top < y,
+            Row::Below(y) => top > y,
+        }
+    }
+}
+
+/// The question a layout pass answers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Query {
+    /// Where is the caret drawn when it is placed at the given byte offset?
+    Caret(usize),
+
+    /// Which character boundary is closest to the given horizontal position in the given row?
+    HitTest(i32, Row),
+}
+
+/// Plugin that answers a single [`Query`] while the text is rendered.
+#[derive(Clone)]
+pub(crate) struct LayoutProbe<'a> {
+    source: SourceMap<'a>,
+    display: CharDisplay,
+    query: Query,
+    after_newline: bool,
+    last_end: Option<Point>,
+    row_top: Option<i32>,
+    distance: u32,
+    offset: Option<usize>,
+    position: Option<Point>,
+}
+
+impl<'a> LayoutProbe<'a> {
+    pub const fn new(text: &'a str, display: CharDisplay, query: Query) -> Self {
+        Self {
+            source: SourceMap::new(text),
+            display,
+            query,
+            after_newline: false,
+            last_end: None,
+            row_top: None,
+            distance: u32::MAX,
+            offset: None,
+            position: None,
+        }
+    }
+
+    /// Returns the answer to a [`Query::Caret`] query.
+    pub fn position(&self) -> Point {
+        self.position.unwrap_or_default()
+    }
+
+    /// Returns the answer to a [`Query::HitTest`] query, or `None` if no row was selected.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    fn locate_caret(
+        &mut self,
+        offset: usize,
+        renderer: &impl TextRenderer,
+        fragment: &str,
+        range: Range<usize>,
+        bounds: Rectangle,
+    ) {
+        if range.start > offset {
+            // The caret is in a part of the text that wasn't rendered.
+            self.position = Some(match self.last_end {
+                Some(end) if !self.after_newline => end,
+                _ => bounds.top_left,
+            });
+            return;
+        }
+
+        if range.contains(&offset) {
+            let mut position = bounds.top_left;
+            for_each_boundary(renderer, self.display, fragment, range, bounds, |idx, x| {
+                if idx == offset {
+                    position.x = x;
+                }
+            });
+            self.position = Some(position);
+            return;
+        }
+
+        self.after_newline = matches!(fragment, "\n" | "\r");
+        self.last_end = Some(bounds.top_left + Point::new(bounds.size.width as i32, 0));
+    }
+
+    fn hit_test(
+        &mut self,
+        x: i32,
+        row: Row,
+        renderer: &impl TextRenderer,
+        fragment: &str,
+        range: Range<usize>,
+        bounds: Rectangle,
+    ) {
+        let top = bounds.top_left.y;
+        if !row.accepts(top) {
+            return;
+        }
+
+        match (row, self.row_top) {
+            // Only the first accepted row counts.
+            (Row::Below(_), Some(row_top)) if row_top != top => return,
+            (_, Some(row_top)) if row_top == top => {}
+            _ => {
+                self.row_top = Some(top);
+                self.distance = u32::MAX;
+            }
+        }
+
+        let is_newline = matches!(fragment, "\n" | "\r");
+        let mut consider = |idx: usize, boundary_x: i32| {
+            let distance = (boundary_x as i64 - x as i64)
+                .unsigned_abs()
+                .min(u32::MAX as u64) as u32;
+            if distance < self.distance {
+                self.distance = distance;
+                self.offset = Some(idx);
+            }
+        };
+
+        if is_newline {
+            consider(range.start, bounds.top_left.x);
+        } else {
+            for_each_boundary(renderer, self.display, fragment, range, bounds, consider);
+        }
+    }
+}
+
+impl<'a, C: PixelColor> Plugin<'a, C> for LayoutProbe<'a> {
+    fn post_render<T, D>(
+        &mut self,
+        _draw_target: &mut D,
+        character_style: &T,
+        text: Option<&str>,
+        bounds: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        T: TextRenderer<Color = C>,
+        D: DrawTarget<Color = C>,
+    {
+        if self.position.is_some() {
+            return Ok(());
+        }
+
+        let text_len = self.source.text.len();
+        let (fragment, range) = match text {
+            Some(fragment) => match self.source.resolve(fragment) {
+                Some(range) => (fragment, range),
+                None => return Ok(()),
+            },
+            // End of text
+            None => ("", text_len..text_len),
+        };
+
+        match self.query {
+            Query::Caret(_) if text.is_none() => self.position = Some(bounds.top_left),
+            Query::Caret(offset) => {
+                self.locate_caret(offset, character_style, fragment, range, bounds)
+            }
+            Query::HitTest(x, row) => {
+                self.hit_test(x, row, character_style, fragment, range, bounds)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Plugin that finds the word displayed at a point.
+///
+/// Consecutive pieces of a word that are rendered in the same row are merged into a single area.
+#[derive(Clone)]
+pub(crate) struct WordProbe<'a> {
+    source: SourceMap<'a>,
+    config: ParserConfig,
+    point: Point,
+    /// The source range and the area of the word pieces in the current row.
+    run: Option<(Range<usize>, Rectangle)>,
+    found: bool,
+    done: bool,
+}
+
+impl<'a> WordProbe<'a> {
+    pub const fn new(text: &'a str, config: ParserConfig, point: Point) -> Self {
+        Self {
+            source: SourceMap::new(text),
+            config,
+            point,
+            run: None,
+            found: false,
+            done: false,
+        }
+    }
+
+    /// Returns the source range of the whole word at the point, and the area of its displayed
+    /// part in the row of the point.
+    pub fn word(&self) -> Option<(Range<usize>, Rectangle)> {
+        let (range, area) = self.run.clone().filter(|_| self.found)?;
+
+        let text = self.source.text;
+        let is_word_char = |c| is_word_char(c, &self.config);
+        let start = text[..range.start]
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_word_char(c))
+            .last()
+            .map_or(range.start, |(idx, _)| idx);
+        let end = text[range.end..]
+            .char_indices()
+            .find(|&(_, c)| !is_word_char(c))
+            .map_or(text.len(), |(idx, _)| range.end + idx);
+
+        Some((start..end, area))
+    }
+
+    fn add_fragment(&mut self, fragment: &str, range: Range<usize>, bounds: Rectangle) {
+        let in_word = fragment.chars().all(|c| is_word_char(c, &self.config));
+
+        self.run = match self.run.take() {
+            Some((run, area))
+                if in_word
+                    && run.end == range.start
+                    && area.top_left.y == bounds.top_left.y
+                    && area.top_left.x + area.size.width as i32 == bounds.top_left.x =>
+            {
+                let size = Size::new(area.size.width + bounds.size.width, area.size.height);
+                Some((run.start..range.end, Rectangle::new(area.top_left, size)))
+            }
+            run if self.found => {
+                // The word under the point has ended.
+                self.done = true;
+                run
+            }
+            _ if in_word => Some((range, bounds)),
+            _ => None,
+        };
+
+        if !self.done && self.run.is_some() && bounds.contains(self.point) && in_word {
+            self.found = true;
+        }
+    }
+}
+
+impl<'a, C: PixelColor> Plugin<'a, C> for WordProbe<'a> {
+    fn post_render<T, D>(
+        &mut self,
+        _draw_target: &mut D,
+        _character_style: &T,
+        text: Option<&str>,
+        bounds: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        T: TextRenderer<Color = C>,
+        D: DrawTarget<Color = C>,
+    {
+        if self.done {
+            return Ok(());
+        }
+
+        let fragment = match text {
+            Some(fragment) => fragment,
+            None => {
+                self.done = self.found;
+                return Ok(());
+            }
+        };
+        if let Some(range) = self.source.resolve(fragment) {
+            self.add_fragment(fragment, range, bounds);
+        }
+
+        Ok(())
+    }
+}
+
+/// Draw target that discards everything, used for layout passes.
+pub(crate) struct NullDrawTarget<C>(PhantomData<C>);
+
+impl<C> NullDrawTarget<C> {
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C> OriginDimensions for NullDrawTarget<C> {
+    fn size(&self) -> Size {
+        Size::new(u32::MAX >> 2, u32::MAX >> 2)
+    }
+}
+
+impl<C: PixelColor> DrawTarget for NullDrawTarget<C> {
+    type Color = C;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        Ok(())
+    }
+}
@@ -0,0 +1,475 @@
+//! Scrolling log view.
+
+use az::SaturatingAs;
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::Rgb888,
+    primitives::Rectangle,
+    text::renderer::{CharacterStyle, TextRenderer},
+    Drawable,
+};
+
+use crate::{
+    alignment::VerticalAlignment,
+    style::{HeightMode, Padding, TextBoxStyle, VerticalOverdraw},
+    TextBox,
+};
+
+/// A line of text stored in a fixed size buffer.
+#[derive(Clone, Copy, Debug)]
+struct LogLine<const LEN: usize> {
+    bytes: [u8; LEN],
+    len: usize,
+}
+
+impl<const LEN: usize> LogLine<LEN> {
+    const EMPTY: Self = Self {
+        bytes: [0; LEN],
+        len: 0,
+    };
+
+    /// Stores the longest prefix of the first line of `text` that fits into the buffer.
+    fn set(&mut self, text: &str) {
+        let text = text.split('\n').next().unwrap_or_default();
+        let mut len = text.len().min(LEN);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        self.bytes[..len].copy_from_slice(&text.as_bytes()[..len]);
+        self.len = len;
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+/// A console-like text view that appends lines at the bottom.
+///
+/// `LogView` keeps the last `LINES` lines, each truncated to `LINE_LEN` bytes, so its memory use
+/// is fixed. Every line is displayed in a single row of the bounding box, without wrapping. New
+/// lines fill the box from the top, and once it is full, the content scrolls up by one row for
+/// every new line.
+///
+/// [`push_line`] only draws the new line, as long as the box isn't full. After that, it redraws
+/// the visible lines, while [`push_line_scrolled`] lets the display move the existing content up,
+/// for example using a hardware blit, and only draws the new line. Lines that scrolled out of the
+/// box are only needed for redrawing, so `LINES` is usually the number of visible rows, plus
+/// any history that should be kept.
+///
+/// The row of a new line is cleared using the background color, so the character style doesn't
+/// need a background color.
+///
+/// # Example
+///
+/// ```rust
+/// use embedded_graphics::{
+///     mock_display::MockDisplay,
+///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+///     pixelcolor::BinaryColor,
+///     prelude::*,
+///     primitives::Rectangle,
+/// };
+/// use embedded_text::widget::LogView;
+///
+/// let mut log = LogView::<_, 4, 16>::new(
+///     Rectangle::new(Point::zero(), Size::new(60, 27)),
+///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+///     BinaryColor::Off,
+/// );
+///
+/// let mut display = MockDisplay::new();
+/// display.set_allow_overdraw(true);
+/// log.push_line(&mut display, "Booting...").unwrap();
+/// log.push_line(&mut display, "Ready.").unwrap();
+///
+/// assert_eq!(log.lines().collect::<Vec<_>>(), ["Booting...", "Ready."]);
+/// ```
+///
+/// [`push_line`]: LogView::push_line
+/// [`push_line_scrolled`]: LogView::push_line_scrolled
+#[derive(Clone, Debug)]
+pub struct LogView<S, const LINES: usize, const LINE_LEN: usize>
+where
+    S: TextRenderer,
+{
+    bounds: Rectangle,
+    character_style: S,
+    style: TextBoxStyle,
+    background_color: S::Color,
+    lines: [LogLine<LINE_LEN>; LINES],
+    /// Index of the oldest stored line.
+    first: usize,
+    /// Number of stored lines.
+    len: usize,
+    /// Number of rows that display a line.
+    rows: u32,
+}
+
+impl<S, const LINES: usize, const LINE_LEN: usize> LogView<S, LINES, LINE_LEN>
+where
+    S: TextRenderer<Color = <S as CharacterStyle>::Color> + CharacterStyle,
+    <S as CharacterStyle>::Color: From<Rgb888>,
+{
+    /// Creates a new, empty log view.
+    #[inline]
+    pub fn new(
+        bounds: Rectangle,
+        character_style: S,
+        background_color: <S as TextRenderer>::Color,
+    ) -> Self {
+        Self {
+            bounds,
+            character_style,
+            style: Self::line_style(TextBoxStyle::default()),
+            background_color,
+            lines: [LogLine::EMPTY; LINES],
+            first: 0,
+            len: 0,
+            rows: 0,
+        }
+    }
+
+    /// Sets the text box style used to draw the lines.
+    ///
+    /// The vertical alignment, the height mode and the padding of the style are ignored, and
+    /// lines are never wrapped.
+    #[inline]
+    #[must_use]
+    pub fn with_textbox_style(mut self, style: TextBoxStyle) -> Self {
+        self.style = Self::line_style(style);
+
+        self
+    }
+
+    /// Returns the stored lines, from the oldest to the newest.
+    #[inline]
+    pub fn lines(&self) -> impl Iterator<Item = &str> + '_ {
+        (0..self.len).map(move |i| self.lines[(self.first + i) % LINES].as_str())
+    }
+
+    /// Appends a line and draws it.
+    ///
+    /// Only the first line of `text` is stored, truncated to `LINE_LEN` bytes. If the box is full,
+    /// the visible lines are redrawn one row higher.
+    #[inline]
+    pub fn push_line<D>(&mut self, display: &mut D, text: &str) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = <S as CharacterStyle>::Color>,
+    {
+        if self.store(text) {
+            self.draw(display)
+        } else {
+            self.draw_row(display, self.rows - 1, self.newest())
+        }
+    }
+
+    /// Appends a line and draws it, moving the existing content up with `scroll` if the box is
+    /// full.
+    ///
+    /// `scroll` is called with the display, the area of the text and the number of pixels the
+    /// content of the area needs to be moved up by. Only the new line is drawn after that. If the
+    /// style sets [`line_heights`], the rows can't be moved by a single distance, so the visible
+    /// lines are redrawn instead.
+    ///
+    /// [`line_heights`]: TextBoxStyle::line_heights
+    #[inline]
+    pub fn push_line_scrolled<D>(
+        &mut self,
+        display: &mut D,
+        text: &str,
+        scroll: impl FnOnce(&mut D, Rectangle, u32) -> Result<(), D::Error>,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = <S as CharacterStyle>::Color>,
+    {
+        if self.store(text) {
+            if self.rows == 0 {
+                return Ok(());
+            }
+            if self.style.line_heights.is_some() {
+                // Rows of different heights can't be moved up by a single distance.
+                return self.draw(display);
+            }
+            scroll(display, self.bounds, self.line_spacing(0))?;
+        }
+
+        self.draw_row(display, self.rows - 1, self.newest())
+    }
+
+    /// Stores a new line. Returns `true` if the displayed lines need to move up to make room for
+    /// it.
+    fn store(&mut self, text: &str) -> bool {
+        if LINES > 0 {
+            if self.len < LINES {
+                self.len += 1;
+            } else {
+                self.first = (self.first + 1) % LINES;
+            }
+            self.lines[(self.first + self.len - 1) % LINES].set(text);
+        }
+
+        let scrolled = self.rows == self.visible_rows();
+        if !scrolled {
+            self.rows += 1;
+        }
+
+        scrolled
+    }
+
+    fn newest(&self) -> &str {
+        self.lines().last().unwrap_or_default()
+    }
+
+    /// Returns the number of rows that fit into the bounding box.
+    fn visible_rows(&self) -> u32 {
+        let height = self.bounds.size.height;
+        let line_height = self.character_style.line_height();
+
+        let mut rows = 0;
+        let mut top = 0u32;
+        while top.saturating_add(line_height) <= height && rows < u32::MAX {
+            top = top.saturating_add(self.line_spacing(rows));
+            rows += 1;
+        }
+
+        rows
+    }
+
+    /// Returns the distance between the tops of a row and the next one.
+    fn line_spacing(&self, row: u32) -> u32 {
+        self.style
+            .line_height_at(self.character_style.line_height(), row)
+            .max(1)
+    }
+
+    /// Returns the top of a row, relative to the top of the bounding box.
+    fn row_top(&self, row: u32) -> u32 {
+        (0..row).fold(0, |top: u32, row| {
+            top.saturating_add(self.line_spacing(row))
+        })
+    }
+
+    /// Clears a row and draws a line into it.
+    fn draw_row<D>(&self, display: &mut D, row: u32, text: &str) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = <S as CharacterStyle>::Color>,
+    {
+        let area = Rectangle::new(
+            self.bounds.top_left + Point::new(0, self.row_top(row).saturating_as::<i32>()),
+            Size::new(self.bounds.size.width, self.character_style.line_height()),
+        );
+
+        display.fill_solid(&area, self.background_color)?;
+        TextBox::with_textbox_style(text, area, self.character_style.clone(), self.style)
+            .draw(display)?;
+
+        Ok(())
+    }
+
+    fn line_style(mut style: TextBoxStyle) -> TextBoxStyle {
+        style.vertical_alignment = VerticalAlignment::Top;
+        style.height_mode = HeightMode::Exact(VerticalOverdraw::Hidden);
+        style.padding = Padding::new(0, 0, 0, 0);
+        style.horizontal_scroll = Some(style.horizontal_scroll.unwrap_or(0));
+
+        style
+    }
+}
+
+impl<S, const LINES: usize, const LINE_LEN: usize> Drawable for LogView<S, LINES, LINE_LEN>
+where
+    S: TextRenderer<Color = <S as CharacterStyle>::Color> + CharacterStyle,
+    <S as CharacterStyle>::Color: From<Rgb888>,
+{
+    type Color = <S as CharacterStyle>::Color;
+    type Output = ();
+
+    /// Draws every row that displays a line.
+    ///
+    /// Rows of lines that are no longer stored are cleared.
+    #[inline]
+    fn draw<D>(&self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let missing = self.rows.saturating_sub(self.len.saturating_as());
+        let stored = self.len - (self.rows - missing) as usize;
+
+        for row in 0..missing {
+            self.draw_row(display, row, "")?;
+        }
+        for (row, line) in (missing..self.rows).zip(self.lines().skip(stored)) {
+            self.draw_row(display, row, line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+        text::{Baseline, Text},
+    };
+
+    use crate::{style::TextBoxStyleBuilder, utils::test::size_for, widget::LogView};
+
+    type Log<const LINES: usize> = LogView<MonoTextStyle<'static, BinaryColor>, LINES, 8>;
+
+    fn log<const LINES: usize>() -> Log<LINES> {
+        LogView::new(
+            Rectangle::new(Point::new(0, 1), size_for(&FONT_6X9, 5, 3)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            BinaryColor::Off,
+        )
+    }
+
+    fn new_display() -> MockDisplay<BinaryColor> {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+
+        display
+    }
+
+    /// Draws the given rows, the same way `LogView` draws them.
+    fn rows(lines: &[&str]) -> MockDisplay<BinaryColor> {
+        let mut display = new_display();
+        let log = log::<1>();
+        for (row, line) in lines.iter().enumerate() {
+            log.draw_row(&mut display, row as u32, line).unwrap();
+        }
+
+        display
+    }
+
+    #[test]
+    fn push_draws_only_the_new_line() {
+        let mut log = log::<3>();
+        let mut display = new_display();
+        log.push_line(&mut display, "first").unwrap();
+        display.assert_eq(&rows(&["first"]));
+
+        let mut display = new_display();
+        log.push_line(&mut display, "second").unwrap();
+
+        let mut expected = new_display();
+        log.draw_row(&mut expected, 1, "second").unwrap();
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn lines_are_truncated() {
+        let mut log = log::<2>();
+        let mut display = new_display();
+        log.push_line(&mut display, "a\nb").unwrap();
+        log.push_line(&mut display, "ééééé").unwrap();
+
+        assert_eq!(log.lines().collect::<Vec<_>>(), ["a", "éééé"]);
+    }
+
+    #[test]
+    fn full_log_redraws_visible_lines() {
+        let mut log = log::<4>();
+        let mut display = new_display();
+        for line in ["a", "b", "c"] {
+            log.push_line(&mut display, line).unwrap();
+        }
+
+        let mut display = new_display();
+        log.push_line(&mut display, "d").unwrap();
+
+        display.assert_eq(&rows(&["b", "c", "d"]));
+        assert_eq!(log.lines().collect::<Vec<_>>(), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn redraw_clears_rows_without_history() {
+        let mut log = log::<2>();
+        let mut display = new_display();
+        for line in ["a", "b", "c", "d"] {
+            log.push_line(&mut display, line).unwrap();
+        }
+
+        let mut display = new_display();
+        log.draw(&mut display).unwrap();
+
+        display.assert_eq(&rows(&["", "c", "d"]));
+    }
+
+    #[test]
+    fn rows_use_line_heights() {
+        fn line_heights(line: u32) -> u32 {
+            if line == 0 {
+                12
+            } else {
+                9
+            }
+        }
+
+        let style = TextBoxStyleBuilder::new()
+            .line_heights(line_heights)
+            .build();
+        let mut log = log::<4>().with_textbox_style(style);
+        assert_eq!(log.visible_rows(), 2);
+
+        let mut display = new_display();
+        for line in ["a", "b", "c"] {
+            log.push_line(&mut display, line).unwrap();
+        }
+
+        let mut expected = new_display();
+        for (text, y) in [("b", 1), ("c", 1 + 12)] {
+            let area = Rectangle::new(Point::new(0, y), size_for(&FONT_6X9, 5, 1));
+            expected.fill_solid(&area, BinaryColor::Off).unwrap();
+            Text::with_baseline(
+                text,
+                area.top_left,
+                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                Baseline::Top,
+            )
+            .draw(&mut expected)
+            .unwrap();
+        }
+        display.assert_eq(&expected);
+
+        // Rows of different heights are redrawn instead of scrolled.
+        let mut display = new_display();
+        log.push_line_scrolled(&mut display, "d", |_, _, _| unreachable!())
+            .unwrap();
+        assert_eq!(log.lines().collect::<Vec<_>>(), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn scrolled_push_draws_only_the_new_line() {
+        let mut log = log::<1>();
+        let mut display = new_display();
+        for line in ["a", "b", "c"] {
+            log.push_line(&mut display, line).unwrap();
+        }
+
+        let mut scrolled = None;
+        let mut display = new_display();
+        log.push_line_scrolled(&mut display, "d", |_, area, by| {
+            scrolled = Some((area, by));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            scrolled,
+            Some((Rectangle::new(Point::new(0, 1), Size::new(30, 27)), 9))
+        );
+
+        let mut expected = new_display();
+        log.draw_row(&mut expected, 2, "d").unwrap();
+        display.assert_eq(&expected);
+    }
+}
@@ -0,0 +1,989 @@
+//! Reusable widgets built on top of [`TextBox`].
+//!
+//! Apart from splitting text into characters, the widgets in this module only use the public API
+//! of the crate, including the [`Plugin`] interface. Using them requires enabling the `widget`
+//! crate feature.
+//!
+//! [`TextBox`]: crate::TextBox
+//! [`Plugin`]: crate::plugin::Plugin
+
+mod layout;
+mod log;
+
+use core::ops::Range;
+
+use embedded_graphics::{
+    draw_target::{DrawTarget, DrawTargetExt},
+    geometry::{Point, Size},
+    pixelcolor::{PixelColor, Rgb888},
+    primitives::{Line, Primitive, PrimitiveStyle, Rectangle},
+    text::{
+        renderer::{CharacterStyle, TextRenderer},
+        Baseline,
+    },
+    Drawable,
+};
+use object_chain::ChainElement;
+
+use crate::{
+    alignment::VerticalAlignment,
+    plugin::Plugin,
+    style::{HeightMode, TextBoxStyle},
+    utils::{clip_area, cluster_indices},
+    widget::layout::{
+        for_each_boundary, CharDisplay, LayoutProbe, NullDrawTarget, Query, Row, SourceMap,
+        WordProbe,
+    },
+    TextBox,
+};
+
+pub use self::log::LogView;
+
+/// Caret movement commands of the [`EditorView`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// Move to the previous character.
+    ///
+    /// With the `grapheme` feature, the caret moves over whole grapheme clusters.
+    Left,
+
+    /// Move to the next character.
+    ///
+    /// With the `grapheme` feature, the caret moves over whole grapheme clusters.
+    Right,
+
+    /// Move to the previous visual line, keeping the horizontal position.
+    Up,
+
+    /// Move to the next visual line, keeping the horizontal position.
+    Down,
+
+    /// Move to the start of the visual line.
+    Home,
+
+    /// Move to the end of the visual line.
+    End,
+}
+
+/// Colors used to draw the caret and the selection of an [`EditorView`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EditorStyle<C> {
+    /// Color of the caret.
+    pub caret_color: C,
+
+    /// Text color of the selected text.
+    pub selection_text_color: C,
+
+    /// Background color of the selected text.
+    pub selection_background_color: C,
+}
+
+impl<C> EditorStyle<C>
+where
+    C: PixelColor,
+{
+    /// Creates a new editor style.
+    #[inline]
+    pub const fn new(
+        caret_color: C,
+        selection_text_color: C,
+        selection_background_color: C,
+    ) -> Self {
+        Self {
+            caret_color,
+            selection_text_color,
+            selection_background_color,
+        }
+    }
+}
+
+/// Caret, selection and scroll state of an [`EditorView`].
+///
+/// The state is separate from the view, so it can be kept while the edited text is modified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EditorState {
+    caret: usize,
+    anchor: Option<usize>,
+    scroll: i32,
+    preferred_x: Option<i32>,
+}
+
+impl EditorState {
+    /// Creates a new state with the caret at the start of the text.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            caret: 0,
+            anchor: None,
+            scroll: 0,
+            preferred_x: None,
+        }
+    }
+
+    /// Returns the byte offset of the caret.
+    #[inline]
+    pub const fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Returns the selected byte range, or `None` if nothing is selected.
+    #[inline]
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        let range = anchor.min(self.caret)..anchor.max(self.caret);
+
+        if range.is_empty() {
+            None
+        } else {
+            Some(range)
+        }
+    }
+
+    /// Moves the caret to a byte offset and clears the selection.
+    #[inline]
+    pub fn set_caret(&mut self, offset: usize) {
+        self.caret = offset;
+        self.anchor = None;
+        self.preferred_x = None;
+    }
+
+    /// Selects a byte range. The caret is moved to the end of the range.
+    #[inline]
+    pub fn select(&mut self, range: Range<usize>) {
+        self.anchor = Some(range.start);
+        self.caret = range.end;
+        self.preferred_x = None;
+    }
+
+    /// Returns the vertical scroll offset in pixels.
+    #[inline]
+    pub const fn scroll(&self) -> i32 {
+        self.scroll
+    }
+}
+
+/// A text view with a caret and an optional selection.
+///
+/// `EditorView` renders text using a [`TextBox`] and handles caret movement based on how the
+/// text box lays out the text, so moving up and down follows the wrapped lines. The view doesn't
+/// own or modify the text: edit your own buffer using the byte offsets returned by
+/// [`EditorView::state`], then create a new view for the modified text.
+///
+/// The text is always aligned to the top of the bounding box, and the view scrolls vertically to
+/// keep the caret visible. The caret moves by characters.
+///
+/// # Example
+///
+/// ```rust
+/// use embedded_graphics::{
+///     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+///     pixelcolor::BinaryColor,
+///     prelude::*,
+///     primitives::Rectangle,
+/// };
+/// use embedded_text::widget::{EditorStyle, EditorView, Key};
+///
+/// let mut editor = EditorView::new(
+///     "Hello, World!",
+///     Rectangle::new(Point::zero(), Size::new(60, 30)),
+///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+///     EditorStyle {
+///         caret_color: BinaryColor::On,
+///         selection_text_color: BinaryColor::Off,
+///         selection_background_color: BinaryColor::On,
+///     },
+/// );
+///
+/// // The text is wrapped after "Hello, ". Select the comma at the end of the first line.
+/// editor.handle_key(Key::End);
+/// editor.handle_key_selecting(Key::Left);
+///
+/// assert_eq!(editor.state().caret(), 5);
+/// assert_eq!(editor.state().selection(), Some(5..6));
+/// ```
+///
+/// [`TextBox`]: crate::TextBox
+#[derive(Clone, Debug)]
+pub struct EditorView<'a, S>
+where
+    S: TextRenderer,
+{
+    text: &'a str,
+    bounds: Rectangle,
+    character_style: S,
+    style: TextBoxStyle,
+    editor_style: EditorStyle<S::Color>,
+    state: EditorState,
+}
+
+impl<'a, S> EditorView<'a, S>
+where
+    S: TextRenderer<Color = <S as CharacterStyle>::Color> + CharacterStyle,
+    <S as CharacterStyle>::Color: From<Rgb888>,
+{
+    /// Creates a new editor view with the caret at the start of the text.
+    #[inline]
+    pub fn new(
+        text: &'a str,
+        bounds: Rectangle,
+        character_style: S,
+        editor_style: EditorStyle<<S as TextRenderer>::Color>,
+    ) -> Self {
+        Self {
+            text,
+            bounds,
+            character_style,
+            style: TextBoxStyle::default(),
+            editor_style,
+            state: EditorState::new(),
+        }
+    }
+
+    /// Sets the text box style used to lay out the text.
+    #[inline]
+    #[must_use]
+    pub fn with_textbox_style(mut self, style: TextBoxStyle) -> Self {
+        self.style = style;
+        self.style.vertical_alignment = VerticalAlignment::Top;
+        self.style.horizontal_scroll = None;
+
+        self
+    }
+
+    /// Restores a previously saved state. The caret and the selection are limited to the text.
+    #[inline]
+    #[must_use]
+    pub fn with_state(mut self, state: EditorState) -> Self {
+        self.state = state;
+        self.state.caret = self.char_boundary(state.caret);
+        self.state.anchor = state.anchor.map(|anchor| self.char_boundary(anchor));
+
+        self
+    }
+
+    /// Returns the caret, selection and scroll state.
+    #[inline]
+    pub const fn state(&self) -> EditorState {
+        self.state
+    }
+
+    /// Moves the caret and clears the selection.
+    #[inline]
+    pub fn handle_key(&mut self, key: Key) {
+        self.move_caret(key, false);
+    }
+
+    /// Moves the caret and extends the selection.
+    #[inline]
+    pub fn handle_key_selecting(&mut self, key: Key) {
+        self.move_caret(key, true);
+    }
+
+    /// Moves the caret to the character closest to a point in screen coordinates.
+    #[inline]
+    pub fn click(&mut self, point: Point) {
+        let offset = self.offset_at(point);
+        self.state.set_caret(offset);
+        self.scroll_to_caret();
+    }
+
+    /// Returns the byte offset of the character boundary closest to a point in screen coordinates.
+    #[inline]
+    pub fn offset_at(&self, point: Point) -> usize {
+        let point = self.to_text_space(point);
+
+        self.hit_test(point.x, Row::At(point.y)).unwrap_or(0)
+    }
+
+    /// Returns the top of the caret in screen coordinates.
+    ///
+    /// The caret may be placed at the end of the text. If the text ends with a newline, the caret
+    /// is then at the start of a new, empty line.
+    #[inline]
+    pub fn caret_position(&self) -> Point {
+        self.layout_caret(self.state.caret)
+            + self.bounds.top_left
+            + Point::new(0, self.state.scroll)
+    }
+
+    fn move_caret(&mut self, key: Key, selecting: bool) {
+        let caret = self.state.caret;
+        let selection = self.state.selection();
+
+        let anchor = if selecting {
+            Some(self.state.anchor.unwrap_or(caret))
+        } else {
+            None
+        };
+
+        let mut preferred_x = None;
+        let target = match (key, selection) {
+            (Key::Left, Some(selection)) if !selecting => selection.start,
+            (Key::Right, Some(selection)) if !selecting => selection.end,
+            (Key::Left, _) => cluster_indices(&self.text[..caret])
+                .last()
+                .map_or(0, |(idx, _)| idx),
+            (Key::Right, _) => cluster_indices(&self.text[caret..])
+                .next()
+                .map_or(caret, |(_, cluster)| caret + cluster.len()),
+            (Key::Up, _) | (Key::Down, _) => {
+                let position = self.layout_caret(caret);
+                let x = self.state.preferred_x.unwrap_or(position.x);
+                preferred_x = Some(x);
+
+                if key == Key::Up {
+                    self.hit_test(x, Row::Above(position.y)).unwrap_or(0)
+                } else {
+                    self.hit_test(x, Row::Below(position.y))
+                        .unwrap_or(self.text.len())
+                }
+            }
+            (Key::Home, _) | (Key::End, _) => {
+                let position = self.layout_caret(caret);
+                let x = if key == Key::Home { i32::MIN } else { i32::MAX };
+
+                self.hit_test(x, Row::At(position.y)).unwrap_or(caret)
+            }
+        };
+
+        self.state.caret = target;
+        self.state.anchor = anchor;
+        self.state.preferred_x = preferred_x;
+
+        self.scroll_to_caret();
+    }
+
+    /// Scrolls the view vertically so that the caret is visible.
+    fn scroll_to_caret(&mut self) {
+        let top = self.layout_caret(self.state.caret).y;
+        let bottom = top + self.line_height(top);
+        let height = self.bounds.size.height as i32;
+
+        if top < -self.state.scroll {
+            self.state.scroll = -top;
+        } else if bottom > height - self.state.scroll {
+            self.state.scroll = height - bottom;
+        }
+    }
+
+    /// Returns the height of the line that starts at `top`, relative to the top of the unscrolled
+    /// text.
+    fn line_height(&self, top: i32) -> i32 {
+        let y = top.saturating_sub(self.style.padding.top as i32).max(0) as u32;
+        let line =
+            self.style
+                .line_index_at(&self.character_style, self.text, self.bounds.size.width, y);
+
+        self.style
+            .line_height_at(self.character_style.line_height(), line) as i32
+    }
+
+    fn char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        offset
+    }
+
+    fn to_text_space(&self, point: Point) -> Point {
+        point - self.bounds.top_left - Point::new(0, self.state.scroll)
+    }
+
+    /// Lays out the text starting at the origin, with unlimited height.
+    fn run_layout(&self, query: Query) -> LayoutProbe<'a> {
+        let mut style = self.style;
+        style.height_mode = HeightMode::FitToText;
+
+        let text_box = TextBox::with_textbox_style(
+            self.text,
+            Rectangle::new(Point::zero(), Size::new(self.bounds.size.width, 0)),
+            self.character_style.clone(),
+            style,
+        )
+        .add_plugin(LayoutProbe::new(
+            self.text,
+            CharDisplay::new(&self.style),
+            query,
+        ));
+
+        text_box.draw(&mut NullDrawTarget::new()).ok();
+
+        text_box.take_plugins().pop().0
+    }
+
+    /// Returns the top of the caret placed at `offset`, relative to the top left corner of the
+    /// unscrolled text.
+    fn layout_caret(&self, offset: usize) -> Point {
+        self.run_layout(Query::Caret(offset)).position()
+    }
+
+    fn hit_test(&self, x: i32, row: Row) -> Option<usize> {
+        self.run_layout(Query::HitTest(x, row)).offset()
+    }
+}
+
+impl<'a, S> Drawable for EditorView<'a, S>
+where
+    S: TextRenderer<Color = <S as CharacterStyle>::Color> + CharacterStyle,
+    <S as CharacterStyle>::Color: From<Rgb888>,
+{
+    type Color = <S as CharacterStyle>::Color;
+    type Output = ();
+
+    #[inline]
+    fn draw<D>(&self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let mut text_box = TextBox::with_textbox_style(
+            self.text,
+            self.bounds,
+            self.character_style.clone(),
+            self.style,
+        );
+        text_box.set_vertical_offset(self.state.scroll);
+        text_box.draw_with_selection(
+            display,
+            self.state.selection().unwrap_or(0..0),
+            self.editor_style.selection_background_color,
+            self.editor_style.selection_text_color,
+        )?;
+
+        let top = self.caret_position();
+        let left = (top.x - 1).max(self.bounds.top_left.x);
+        let bottom = top.y + self.character_style.line_height() as i32 - 1;
+
+        Line::new(Point::new(left, top.y), Point::new(left, bottom))
+            .into_styled(PrimitiveStyle::with_stroke(
+                self.editor_style.caret_color,
+                1,
+            ))
+            .draw(&mut display.clipped(&clip_area(self.bounds)))
+    }
+}
+
+impl<'a, S> TextBox<'a, S>
+where
+    S: TextRenderer<Color = <S as CharacterStyle>::Color> + CharacterStyle,
+    <S as CharacterStyle>::Color: From<Rgb888>,
+{
+    /// Draws the text box with a highlighted selection.
+    ///
+    /// `range` is a byte range of the text. The selected parts of every line are drawn over the
+    /// text using `text_color_on_selection`, on a `highlight_color` background. Selections can
+    /// span multiple lines, and start or end in the middle of a line.
+    ///
+    /// Returns the part of the text that was not drawn, like [`draw`] does.
+    ///
+    /// [`draw`]: Drawable::draw
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_graphics::{
+    /// #     mock_display::MockDisplay,
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// #     prelude::*,
+    /// #     primitives::Rectangle,
+    /// # };
+    /// # let mut display = MockDisplay::new();
+    /// # display.set_allow_overdraw(true);
+    /// use embedded_text::TextBox;
+    ///
+    /// TextBox::new(
+    ///     "Hello, World!",
+    ///     Rectangle::new(Point::zero(), Size::new(60, 30)),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// )
+    /// .draw_with_selection(&mut display, 7..12, BinaryColor::On, BinaryColor::Off)?;
+    /// # Ok::<(), core::convert::Infallible>(())
+    /// ```
+    #[inline]
+    pub fn draw_with_selection<D>(
+        &self,
+        display: &mut D,
+        range: Range<usize>,
+        highlight_color: <S as CharacterStyle>::Color,
+        text_color_on_selection: <S as CharacterStyle>::Color,
+    ) -> Result<&'a str, D::Error>
+    where
+        D: DrawTarget<Color = <S as CharacterStyle>::Color>,
+    {
+        let mut selection_style = self.character_style.clone();
+        selection_style.set_text_color(Some(text_color_on_selection));
+        selection_style.set_background_color(Some(highlight_color));
+
+        self.clone()
+            .add_plugin(SelectionPlugin {
+                source: SourceMap::new(self.text),
+                display: CharDisplay::new(&self.style),
+                selection: range,
+                style: selection_style,
+            })
+            .draw(display)
+    }
+
+    /// Returns the word displayed at a point.
+    ///
+    /// Words are separated the same way the text box separates them for wrapping, except that
+    /// the parts of a word that are joined by a soft hyphen are a single word. Words joined by
+    /// non-breaking spaces are also a single word.
+    ///
+    /// Returns the byte range of the whole word, and the area of the part of the word that is
+    /// displayed in the line at the point. If the word is wrapped, the area only covers the part
+    /// of the word in that line. Returns `None` if there is no word at the point, for example on
+    /// whitespace or outside of the text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use embedded_graphics::{
+    /// #     mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    /// #     pixelcolor::BinaryColor,
+    /// #     prelude::*,
+    /// #     primitives::Rectangle,
+    /// # };
+    /// use embedded_text::TextBox;
+    ///
+    /// let text_box = TextBox::new(
+    ///     "Hello, World!",
+    ///     Rectangle::new(Point::zero(), Size::new(60, 30)),
+    ///     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+    /// );
+    ///
+    /// let (range, area) = text_box.word_at(Point::new(10, 12)).unwrap();
+    /// assert_eq!(&text_box.text[range], "World!");
+    /// assert_eq!(area, Rectangle::new(Point::new(0, 9), Size::new(36, 9)));
+    ///
+    /// assert_eq!(text_box.word_at(Point::new(50, 4)), None);
+    /// ```
+    #[inline]
+    pub fn word_at(&self, point: Point) -> Option<(Range<usize>, Rectangle)> {
+        if !clip_area(self.bounds).contains(point) {
+            return None;
+        }
+
+        let text_box =
+            self.clone()
+                .add_plugin(WordProbe::new(self.text, self.style.parser_config, point));
+        text_box.draw(&mut NullDrawTarget::new()).ok();
+
+        text_box.take_plugins().pop().0.word()
+    }
+}
+
+/// Redraws the selected parts of the text using the selection colors.
+#[derive(Clone)]
+struct SelectionPlugin<'a, S> {
+    source: SourceMap<'a>,
+    display: CharDisplay,
+    selection: Range<usize>,
+    style: S,
+}
+
+impl<'a, S> Plugin<'a, S::Color> for SelectionPlugin<'a, S>
+where
+    S: TextRenderer + Clone,
+{
+    fn post_render<T, D>(
+        &mut self,
+        draw_target: &mut D,
+        _character_style: &T,
+        text: Option<&str>,
+        bounds: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        T: TextRenderer<Color = S::Color>,
+        D: DrawTarget<Color = S::Color>,
+    {
+        let fragment = match text {
+            Some(fragment) => fragment,
+            None => return Ok(()),
+        };
+        let range = match self.source.resolve(fragment) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        let start = range.start.max(self.selection.start);
+        let end = range.end.min(self.selection.end);
+        if start >= end || range.len() != fragment.len() {
+            return Ok(());
+        }
+
+        let mut left = bounds.top_left.x;
+        let mut right = left;
+        for_each_boundary(
+            &self.style,
+            self.display,
+            fragment,
+            range.clone(),
+            bounds,
+            |idx, x| {
+                if idx == start {
+                    left = x;
+                }
+                if idx == end {
+                    right = x;
+                }
+            },
+        );
+
+        let position = Point::new(left, bounds.top_left.y);
+        let selected = &fragment[start - range.start..end - range.start];
+        if selected.chars().all(char::is_whitespace) {
+            // Whitespace may be stretched, so highlight the rendered area.
+            self.style.draw_whitespace(
+                (right - left).max(0) as u32,
+                position,
+                Baseline::Top,
+                draw_target,
+            )?;
+        } else {
+            self.display
+                .draw(&self.style, selected, position, draw_target)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X9, MonoTextStyle, MonoTextStyleBuilder},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        primitives::Rectangle,
+        text::{Baseline, Text},
+    };
+
+    use crate::{
+        style::{TextBoxStyleBuilder, TextTransform},
+        utils::test::size_for,
+        widget::{EditorState, EditorStyle, EditorView, Key},
+        TextBox,
+    };
+
+    fn editor(
+        text: &str,
+        columns: u32,
+        rows: u32,
+    ) -> EditorView<'_, MonoTextStyle<'_, BinaryColor>> {
+        EditorView::new(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, columns, rows)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+            EditorStyle::new(BinaryColor::On, BinaryColor::Off, BinaryColor::On),
+        )
+    }
+
+    fn caret_after(editor: &mut EditorView<'_, MonoTextStyle<'_, BinaryColor>>, key: Key) -> usize {
+        editor.handle_key(key);
+        editor.state().caret()
+    }
+
+    #[test]
+    fn caret_moves_across_wrapped_lines() {
+        // Wrapped as "Hello " / "world " / "foo"
+        let mut editor = editor("Hello world foo", 6, 3);
+        let mut state = EditorState::new();
+        state.set_caret(2);
+        editor = editor.with_state(state);
+
+        assert_eq!(caret_after(&mut editor, Key::Down), 8);
+        assert_eq!(caret_after(&mut editor, Key::Down), 14);
+        assert_eq!(caret_after(&mut editor, Key::Down), 15);
+        assert_eq!(caret_after(&mut editor, Key::Up), 8);
+        assert_eq!(caret_after(&mut editor, Key::Up), 2);
+        assert_eq!(caret_after(&mut editor, Key::Up), 0);
+    }
+
+    #[test]
+    fn caret_moves_along_wrapped_lines() {
+        let mut editor = editor("Hello world foo", 6, 3);
+
+        assert_eq!(caret_after(&mut editor, Key::End), 5);
+        assert_eq!(caret_after(&mut editor, Key::Right), 6);
+        assert_eq!(editor.caret_position(), Point::new(0, 9));
+        assert_eq!(caret_after(&mut editor, Key::End), 11);
+        assert_eq!(caret_after(&mut editor, Key::Home), 6);
+        assert_eq!(caret_after(&mut editor, Key::Left), 5);
+        assert_eq!(editor.caret_position(), Point::new(30, 0));
+    }
+
+    #[test]
+    fn vertical_movement_keeps_column() {
+        let mut editor = editor("Hello\nab\nworld", 6, 3);
+        let mut state = EditorState::new();
+        state.set_caret(4);
+        editor = editor.with_state(state);
+
+        assert_eq!(caret_after(&mut editor, Key::Down), 8);
+        assert_eq!(caret_after(&mut editor, Key::Down), 13);
+        assert_eq!(caret_after(&mut editor, Key::Up), 8);
+        assert_eq!(caret_after(&mut editor, Key::Up), 4);
+    }
+
+    #[test]
+    fn selection_collapses() {
+        let mut editor = editor("Hello", 6, 1);
+
+        editor.handle_key_selecting(Key::Right);
+        editor.handle_key_selecting(Key::Right);
+        assert_eq!(editor.state().selection(), Some(0..2));
+
+        assert_eq!(caret_after(&mut editor, Key::Left), 0);
+        assert_eq!(editor.state().selection(), None);
+    }
+
+    #[test]
+    fn scrolls_to_caret() {
+        let mut editor = editor("a\nb\nc", 1, 2);
+
+        editor.handle_key(Key::Down);
+        assert_eq!(editor.state().scroll(), 0);
+        editor.handle_key(Key::Down);
+        assert_eq!(editor.state().scroll(), -9);
+        assert_eq!(editor.caret_position(), Point::new(0, 9));
+    }
+
+    #[test]
+    fn scrolls_to_caret_with_line_heights() {
+        fn line_heights(line: u32) -> u32 {
+            if line == 0 {
+                9
+            } else {
+                14
+            }
+        }
+
+        let style = TextBoxStyleBuilder::new()
+            .line_heights(line_heights)
+            .build();
+        let mut editor = editor("a\nb\nc", 1, 2).with_textbox_style(style);
+
+        // The second line is 14 pixels high, so it doesn't fit into the 18 pixel high box.
+        editor.handle_key(Key::Down);
+        assert_eq!(editor.state().scroll(), 18 - (9 + 14));
+    }
+
+    #[test]
+    fn draws_caret_and_selection() {
+        let mut editor = editor("ab", 3, 1);
+        editor.handle_key(Key::End);
+        editor.handle_key_selecting(Key::Left);
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        editor.draw(&mut display).unwrap();
+
+        // "b" is selected, the caret is drawn in front of it.
+        display.assert_pattern(&[
+            "     #######",
+            "     ##.####",
+            "     ##.####",
+            "  #####...##",
+            " #  ###.##.#",
+            " #  ###.##.#",
+            "  #####...##",
+            "     #######",
+            "     #######",
+        ]);
+    }
+
+    #[test]
+    fn selection_spans_lines() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let text_box = TextBox::new(
+            "abc def",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 2)),
+            character_style,
+        );
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        text_box
+            .draw_with_selection(&mut display, 1..6, BinaryColor::On, BinaryColor::Off)
+            .unwrap();
+
+        // "bc" is selected at the end of the first line, and "de" at the start of the second.
+        let selection_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::Off)
+            .background_color(BinaryColor::On)
+            .build();
+
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        text_box.draw(&mut expected).unwrap();
+        Text::with_baseline("bc", Point::new(6, 0), selection_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Text::with_baseline("de", Point::new(0, 9), selection_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn selection_is_drawn_like_the_text() {
+        let character_style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let text_box = TextBox::with_textbox_style(
+            "abc",
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 1)),
+            character_style,
+            TextBoxStyleBuilder::new()
+                .text_transform(TextTransform::Upper)
+                .kerning(|_, _| -1)
+                .build(),
+        );
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        text_box
+            .draw_with_selection(&mut display, 1..3, BinaryColor::On, BinaryColor::Off)
+            .unwrap();
+
+        // The selection is uppercase, and kerned the same way as the text under it.
+        let selection_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X9)
+            .text_color(BinaryColor::Off)
+            .background_color(BinaryColor::On)
+            .build();
+
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        text_box.draw(&mut expected).unwrap();
+        Text::with_baseline("B", Point::new(5, 0), selection_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+        Text::with_baseline("C", Point::new(10, 0), selection_style, Baseline::Top)
+            .draw(&mut expected)
+            .unwrap();
+
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn caret_follows_kerning() {
+        let mut editor = editor("abc", 3, 1)
+            .with_textbox_style(TextBoxStyleBuilder::new().kerning(|_, _| -1).build());
+
+        editor.handle_key(Key::Right);
+        editor.handle_key(Key::Right);
+        assert_eq!(editor.caret_position(), Point::new(10, 0));
+
+        editor.click(Point::new(6, 4));
+        assert_eq!(editor.state().caret(), 1);
+    }
+
+    #[test]
+    fn hit_test_trailing_spaces_at_wrap_point() {
+        // "abc" and the three spaces fill the first line exactly.
+        let text = "abc   def";
+        let with_trailing_spaces = |render| {
+            editor(text, 6, 2)
+                .with_textbox_style(TextBoxStyleBuilder::new().trailing_spaces(render).build())
+        };
+
+        let rendered = with_trailing_spaces(true);
+        assert_eq!(rendered.offset_at(Point::new(31, 4)), 5);
+        assert_eq!(rendered.offset_at(Point::new(35, 4)), 6);
+
+        // Hidden spaces have no width, so the click lands after "abc".
+        let hidden = with_trailing_spaces(false);
+        assert_eq!(hidden.offset_at(Point::new(31, 4)), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "grapheme")]
+    fn caret_skips_grapheme_clusters() {
+        let mut editor = editor("e\u{301}x", 6, 1);
+
+        assert_eq!(caret_after(&mut editor, Key::Right), 3);
+        assert_eq!(caret_after(&mut editor, Key::Left), 0);
+    }
+
+    #[test]
+    fn caret_at_end_of_text() {
+        let at_end = |text: &str| {
+            let mut state = EditorState::new();
+            state.set_caret(text.len());
+            let editor = editor(text, 6, 3).with_state(state);
+
+            // Click right of the end of the caret's line.
+            let caret = editor.caret_position();
+            (caret, editor.offset_at(caret + Point::new(30, 4)))
+        };
+
+        assert_eq!(at_end("abc"), (Point::new(18, 0), 3));
+        // The caret is on a new, empty line.
+        assert_eq!(at_end("abc\n"), (Point::new(0, 9), 4));
+        assert_eq!(at_end("abc\n\n"), (Point::new(0, 18), 5));
+        assert_eq!(at_end("\n"), (Point::new(0, 9), 1));
+        assert_eq!(at_end(""), (Point::zero(), 0));
+
+        // The empty last line can be reached using the keyboard.
+        let mut editor = editor("abc\n", 6, 3);
+        assert_eq!(caret_after(&mut editor, Key::Down), 4);
+        assert_eq!(caret_after(&mut editor, Key::Up), 0);
+        assert_eq!(caret_after(&mut editor, Key::End), 3);
+        assert_eq!(caret_after(&mut editor, Key::Right), 4);
+        assert_eq!(editor.caret_position(), Point::new(0, 9));
+    }
+
+    #[test]
+    fn word_at_point_covers_wrapped_word() {
+        let text = "see extraordinary, ok";
+        let text_box = TextBox::new(
+            text,
+            Rectangle::new(Point::new(2, 3), size_for(&FONT_6X9, 10, 4)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        );
+        let word = |x, y| text_box.word_at(Point::new(x, y));
+
+        // "see" / "extraordin" / "ary, ok"
+        let first_part = Rectangle::new(Point::new(2, 12), size_for(&FONT_6X9, 10, 1));
+        let second_part = Rectangle::new(Point::new(2, 21), size_for(&FONT_6X9, 4, 1));
+        assert_eq!(word(32, 16), Some((4..18, first_part)));
+        assert_eq!(word(2, 12), Some((4..18, first_part)));
+        assert_eq!(word(61, 20), Some((4..18, first_part)));
+        assert_eq!(word(2, 21), Some((4..18, second_part)));
+        assert_eq!(word(25, 29), Some((4..18, second_part)));
+        assert_eq!(&text[4..18], "extraordinary,");
+
+        assert_eq!(word(3, 4).map(|(range, _)| range), Some(0..3));
+        assert_eq!(word(33, 25).map(|(range, _)| range), Some(19..21));
+
+        // Whitespace and the empty parts of the text box.
+        assert_eq!(word(28, 25), None);
+        assert_eq!(word(50, 4), None);
+        assert_eq!(word(2, 35), None);
+        assert_eq!(word(0, 0), None);
+    }
+
+    #[test]
+    fn word_at_point_joins_nbsp() {
+        let text = "a\u{a0}b c";
+        let text_box = TextBox::new(
+            text,
+            Rectangle::new(Point::zero(), size_for(&FONT_6X9, 10, 1)),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        );
+
+        let area = Rectangle::new(Point::zero(), size_for(&FONT_6X9, 3, 1));
+        assert_eq!(text_box.word_at(Point::new(1, 1)), Some((0..4, area)));
+        assert_eq!(text_box.word_at(Point::new(8, 1)), Some((0..4, area)));
+        assert_eq!(text_box.word_at(Point::new(17, 8)), Some((0..4, area)));
+        assert_eq!(
+            text_box.word_at(Point::new(25, 1)).map(|(range, _)| range),
+            Some(5..6)
+        );
+    }
+}